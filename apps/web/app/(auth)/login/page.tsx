@@ -3,6 +3,7 @@
 import { signInWithPopup, signInWithRedirect, getRedirectResult } from 'firebase/auth';
 import { auth, googleProvider } from '@/lib/firebase';
 import { useAuth } from '@/lib/auth-context';
+import { useI18n } from '@/lib/i18n-context';
 import { useRouter } from 'next/navigation';
 import { useState, useEffect, useCallback, useRef } from 'react';
 
@@ -13,6 +14,7 @@ export default function LoginPage() {
   const [error, setError] = useState<string | null>(null);
   const [tauriMode, setTauriMode] = useState<TauriMode>('web');
   const { user, loading: authLoading } = useAuth();
+  const { language } = useI18n();
   const router = useRouter();
   const unlistenRef = useRef<(() => void) | null>(null);
 
@@ -70,43 +72,33 @@ export default function LoginPage() {
   // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
-  // Android Tauri: 딥링크로 돌아온 인증 데이터 처리
+  // Tauri 모바일: noah://auth-callback 딥링크로 돌아온 인증 데이터 처리 (해석은 Rust 라우터).
+  // 데스크톱은 PKCE 루프백(`oauth-callback`)으로만 로그인한다 — 아무 웹 페이지나 이 링크로 세션을 심을 수 있다
   useEffect(() => {
     if (tauriMode !== 'mobile') return;
 
     let cancelled = false;
 
     (async () => {
-      try {
-        const { onOpenUrl } = await import('@tauri-apps/plugin-deep-link');
-        const unlisten = await onOpenUrl((urls: string[]) => {
-          if (cancelled) return;
-          for (const url of urls) {
-            try {
-              // noah://auth-callback#data=base64encodedJSON
-              const hashPart = url.split('#')[1];
-              if (!hashPart) continue;
-              const params = new URLSearchParams(hashPart);
-              const data = params.get('data');
-              if (!data) continue;
-
-              const userData = JSON.parse(atob(data));
-              if (!userData || !userData.uid) continue;
-
-              const apiKey = process.env.NEXT_PUBLIC_FIREBASE_API_KEY || '';
-              const storageKey = `firebase:authUser:${apiKey}:[DEFAULT]`;
-              localStorage.setItem(storageKey, JSON.stringify(userData));
-              window.location.href = '/my-day';
-              return;
-            } catch {
-              // skip invalid URLs
-            }
-          }
-        });
-        unlistenRef.current = unlisten;
-      } catch {
-        // deep-link not available
-      }
+      const { subscribeDeepLinks } = await import('@/lib/deep-link');
+      const unlisten = await subscribeDeepLinks(async (event) => {
+        if (cancelled || event.route !== 'authCallback') return;
+        try {
+          const userData = JSON.parse(atob(event.data));
+          if (!userData || !userData.uid) return;
+
+          // Firebase persistence 키로 보관소에 저장 → 다음 페이지 로드에서 세션 복원
+          const apiKey = process.env.NEXT_PUBLIC_FIREBASE_API_KEY || '';
+          const storageKey = `firebase:authUser:${apiKey}:[DEFAULT]`;
+          const { tokenStorePut } = await import('@/lib/token-store');
+          await tokenStorePut(storageKey, { value: JSON.stringify(userData) });
+          window.location.href = '/my-day';
+        } catch {
+          // 잘못된 페이로드는 무시
+        }
+      }, ['authCallback']);
+      if (cancelled) unlisten();
+      else unlistenRef.current = unlisten;
     })();
 
     return () => {
@@ -126,7 +118,7 @@ export default function LoginPage() {
     };
   }, []);
 
-  // Tauri Desktop: 시스템 브라우저 인증 → 로컬 서버 → localStorage 주입
+  // Tauri Desktop: 시스템 브라우저 → Google (PKCE) → 로컬 서버에서 코드 교환 → signInWithCredential
   const handleTauriDesktopLogin = useCallback(async () => {
     setLoading(true);
     setError(null);
@@ -135,47 +127,55 @@ export default function LoginPage() {
       const { invoke } = await import('@tauri-apps/api/core');
       const { open } = await import('@tauri-apps/plugin-shell');
       const { listen } = await import('@tauri-apps/api/event');
+      const { GoogleAuthProvider, signInWithCredential } = await import('firebase/auth');
+
+      const session = await invoke<{ id: string; port: number }>('start_oauth', {
+        request: {
+          provider: 'google',
+          clientId: process.env.NEXT_PUBLIC_GOOGLE_DESKTOP_CLIENT_ID || '',
+          clientSecret: process.env.NEXT_PUBLIC_GOOGLE_DESKTOP_CLIENT_SECRET || null,
+          locale: language,
+        },
+      });
 
-      const port = await invoke<number>('start_oauth_server');
-
-      const unlisten = await listen<string>('oauth-callback', async (event) => {
-        unlisten();
+      const unlisteners: (() => void)[] = [];
+      const cleanup = () => {
+        unlisteners.forEach((fn) => fn());
         unlistenRef.current = null;
+      };
+
+      unlisteners.push(await listen<{ idToken: string; accessToken: string }>('oauth-callback', async (event) => {
+        cleanup();
         try {
-          const userData = JSON.parse(event.payload);
-          if (!userData || !userData.uid) {
-            setError('인증 정보를 받지 못했습니다. 다시 시도해주세요.');
-            setLoading(false);
-            return;
-          }
-          const apiKey = process.env.NEXT_PUBLIC_FIREBASE_API_KEY || '';
-          const storageKey = `firebase:authUser:${apiKey}:[DEFAULT]`;
-          localStorage.setItem(storageKey, JSON.stringify(userData));
+          const credential = GoogleAuthProvider.credential(event.payload.idToken, event.payload.accessToken);
+          await signInWithCredential(auth, credential);
           window.location.href = '/my-day';
         } catch (err) {
           const message = err instanceof Error ? err.message : '로그인에 실패했습니다';
           setError(message);
           setLoading(false);
         }
-      });
-      unlistenRef.current = unlisten;
-
-      const params = new URLSearchParams({
-        apiKey: process.env.NEXT_PUBLIC_FIREBASE_API_KEY || '',
-        authDomain: process.env.NEXT_PUBLIC_FIREBASE_AUTH_DOMAIN || '',
-        projectId: process.env.NEXT_PUBLIC_FIREBASE_PROJECT_ID || '',
-      });
-
-      await open(`http://localhost:${port}/login?${params.toString()}`);
+      }));
+      unlisteners.push(await listen<{ provider: string; reason: string; message: string }>('oauth-error', (event) => {
+        if (event.payload.provider !== 'google') return;
+        cleanup();
+        setError(event.payload.reason === 'access_denied'
+          ? '로그인이 취소되었습니다.'
+          : `로그인 실패: ${event.payload.message}`);
+        setLoading(false);
+      }));
+      unlisteners.push(await listen<{ sessionId: string }>('oauth-timeout', (event) => {
+        if (event.payload.sessionId !== session.id) return;
+        cleanup();
+        setError('로그인 시간이 초과되었습니다. 다시 시도해주세요.');
+        setLoading(false);
+      }));
+      unlistenRef.current = () => {
+        cleanup();
+        invoke('cancel_oauth_session', { id: session.id }).catch(() => {});
+      };
 
-      setTimeout(() => {
-        if (unlistenRef.current) {
-          unlistenRef.current();
-          unlistenRef.current = null;
-          setError('로그인 시간이 초과되었습니다. 다시 시도해주세요.');
-          setLoading(false);
-        }
-      }, 120000);
+      await open(`http://127.0.0.1:${session.port}/login`);
 
     } catch (err) {
       const message = err instanceof Error ? err.message : '로그인에 실패했습니다';
@@ -183,7 +183,7 @@ export default function LoginPage() {
       setError(`데스크톱 로그인 오류: ${message}`);
       setLoading(false);
     }
-  }, []);
+  }, [language]);
 
   // Tauri Mobile: Firebase Hosting의 mobile-auth.html 열기
   // signInWithRedirect → Google 인증 → noah:// 딥링크로 앱 복귀
@@ -4,6 +4,7 @@ import { useState, useEffect, useCallback, useRef } from 'react';
 import { useAuth } from '@/lib/auth-context';
 import { useI18n } from '@/lib/i18n-context';
 import { useDataStore } from '@/lib/data-store';
+import { notify } from '@/lib/notify';
 import {
   addCalendarEvent,
   updateCalendarEvent,
@@ -395,12 +396,7 @@ export default function CalendarPage() {
     if (upcoming.length === 0) return;
     markNotificationShown();
     const label = upcoming.some(e => e.date === todayDate) ? t('calendar.todaySchedule') : t('calendar.tomorrowSchedule');
-    try {
-      new Notification(label, {
-        body: upcoming.slice(0, 3).map(e => e.title).join(', '),
-        icon: '/icon-192.png',
-      });
-    } catch { /* ignore */ }
+    notify('calendar', label, upcoming.slice(0, 3).map(e => e.title).join(', '));
   }, [settings.notifications, storeEvents]);
 
   // GCal helpers
@@ -428,9 +424,9 @@ export default function CalendarPage() {
   const loadGCalEvents = useCallback(async (year: number, month: number, directToken?: string) => {
     setGcalLoading(true); setGcalError(null);
     try {
-      let token = directToken ?? getStoredGCalToken();
+      let token = directToken ?? await getStoredGCalToken();
       if (!token) {
-        // sessionStorage에 없으면 Firebase 재인증으로 갱신 시도
+        // 저장된 토큰이 없거나 만료됐으면 Firebase 재인증으로 갱신 시도
         try {
           token = await refreshGCalToken();
           setGcalToken(token);
@@ -529,11 +525,7 @@ export default function CalendarPage() {
         let isMobile = false;
         try { const { type } = await import('@tauri-apps/plugin-os'); isMobile = type() === 'android' || type() === 'ios'; } catch { }
         if (isMobile) { await connectGoogleCalendarRedirect(); setGcalLoading(false); return; }
-        const token = await connectGoogleCalendarDesktop({
-          apiKey: process.env.NEXT_PUBLIC_FIREBASE_API_KEY ?? '',
-          authDomain: process.env.NEXT_PUBLIC_FIREBASE_AUTH_DOMAIN ?? '',
-          projectId: process.env.NEXT_PUBLIC_FIREBASE_PROJECT_ID ?? '',
-        });
+        const token = await connectGoogleCalendarDesktop(language);
         setGcalToken(token);
         saveGCalToken(token);
         markGCalConnected(true);
@@ -3,6 +3,22 @@
 import { useState, useRef, useCallback, useEffect } from 'react';
 import FloatingAIBar from '@/components/ai/FloatingAIBar';
 import { pickSaveFolder, openSaveFolder, getSavedFolderPath } from '@/lib/tauri-download';
+import {
+  cancelDownload,
+  enqueueDownload,
+  getDownloadSettings,
+  listDownloads,
+  pauseDownload,
+  probeDownload,
+  removeDownload,
+  resumeDownload,
+  retryDownload,
+  setDownloadSettings,
+  subscribeDownloadProgress,
+  type DownloadJob,
+  type DownloadProgress,
+  type DownloadState,
+} from '@/lib/downloads';
 
 type Quality = 'best' | '2160p' | '1440p' | '1080p' | '720p' | '480p' | '360p' | 'audio';
 
@@ -33,18 +49,37 @@ function formatDuration(sec: number): string {
   return `${m}:${String(s).padStart(2, '0')}`;
 }
 
+const JOB_STATE_LABEL: Record<DownloadState, string> = {
+  queued: '대기 중',
+  running: '받는 중',
+  paused: '일시 정지',
+  completed: '완료',
+  failed: '실패',
+  cancelled: '취소됨',
+};
+
+const POSTPROCESSOR_LABEL: Record<string, string> = {
+  Merger: '영상과 소리 합치는 중',
+  ExtractAudio: 'MP3로 변환 중',
+  MoveFiles: '파일 옮기는 중',
+};
+
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`;
+}
+
+/** 작업마다 남기는 출력 줄 수 */
+const MAX_LOG_LINES = 500;
+
 function formatViews(n: number): string {
   if (n >= 1_000_000) return `${(n / 1_000_000).toFixed(1)}M`;
   if (n >= 1_000) return `${(n / 1_000).toFixed(0)}K`;
   return String(n);
 }
 
-// ── 사이드카 래퍼 ────────────────────────────────────────────────────────────
-async function getSidecar(args: string[]) {
-  const { Command } = await import('@tauri-apps/plugin-shell');
-  return Command.sidecar('binaries/yt-dlp', args);
-}
-
 export default function DownloaderPage() {
   const [url, setUrl] = useState('');
   const [quality, setQuality] = useState<Quality>('best');
@@ -56,95 +91,129 @@ export default function DownloaderPage() {
   }, []);
   const [status, setStatus] = useState<'idle' | 'analyzing' | 'ready' | 'downloading' | 'done' | 'error'>('idle');
   const [videoInfo, setVideoInfo] = useState<VideoInfo | null>(null);
-  const [logs, setLogs] = useState<string[]>([]);
   const [errorMsg, setErrorMsg] = useState('');
   const logsEndRef = useRef<HTMLDivElement>(null);
 
-  const addLog = useCallback((line: string) => {
-    setLogs(prev => [...prev, line]);
-    setTimeout(() => logsEndRef.current?.scrollIntoView({ behavior: 'smooth' }), 50);
+  // ── 다운로드 대기열 (Rust가 yt-dlp를 띄운다) ───────────────────────────────
+  const [jobs, setJobs] = useState<DownloadJob[]>([]);
+  const [jobLogs, setJobLogs] = useState<Record<string, string[]>>({});
+  const [jobProgress, setJobProgress] = useState<Record<string, DownloadProgress>>({});
+  const [activeJob, setActiveJob] = useState<string | null>(null);
+  const [concurrency, setConcurrency] = useState(2);
+  const activeJobRef = useRef<string | null>(null);
+  activeJobRef.current = activeJob;
+  const logs = activeJob ? jobLogs[activeJob] ?? [] : [];
+  const progress = activeJob ? jobProgress[activeJob] : undefined;
+
+  const addLog = useCallback((jobId: string, line: string) => {
+    setJobLogs(prev => ({ ...prev, [jobId]: [...(prev[jobId] ?? []), line].slice(-MAX_LOG_LINES) }));
+    if (jobId === activeJobRef.current) {
+      setTimeout(() => logsEndRef.current?.scrollIntoView({ behavior: 'smooth' }), 50);
+    }
+  }, []);
+
+  const refreshJobs = useCallback(() => {
+    listDownloads().then(setJobs).catch(e => console.warn('[downloads]', e));
   }, []);
 
+  useEffect(() => {
+    refreshJobs();
+    getDownloadSettings().then(s => setConcurrency(s.concurrency)).catch(() => {});
+    return subscribeDownloadProgress((event) => {
+      if (event.progress) {
+        const progress = event.progress;
+        setJobProgress(prev => ({ ...prev, [event.jobId]: progress }));
+        return;
+      }
+      if (event.line) {
+        addLog(event.jobId, event.line);
+        return;
+      }
+      if (event.state !== 'running') {
+        setJobProgress(prev => {
+          const next = { ...prev };
+          delete next[event.jobId];
+          return next;
+        });
+      }
+      refreshJobs();
+      if (event.jobId !== activeJobRef.current) return;
+      if (event.state === 'running' || event.state === 'queued') setStatus('downloading');
+      if (event.state === 'completed') {
+        setStatus('done');
+        addLog(event.jobId, '✅ 다운로드 완료!');
+      }
+      if (event.state === 'failed') {
+        setErrorMsg(event.error ?? '다운로드 실패');
+        setStatus('error');
+        addLog(event.jobId, `❌ ${event.error ?? '다운로드 실패'}`);
+      }
+      if (event.state === 'paused' || event.state === 'cancelled') {
+        setStatus('ready');
+        addLog(event.jobId, `⏸ ${JOB_STATE_LABEL[event.state]}`);
+      }
+    });
+  }, [addLog, refreshJobs]);
+
   // ── 영상 정보 가져오기 ─────────────────────────────────────────────────────
   const handleAnalyze = useCallback(async () => {
     const trimmed = url.trim();
     if (!trimmed) return;
     setStatus('analyzing');
     setVideoInfo(null);
-    setLogs([]);
+    setActiveJob(null);
     setErrorMsg('');
 
     try {
-      const cmd = await getSidecar([
-        '--dump-json',
-        '--no-playlist',
-        '--no-warnings',
-        trimmed,
-      ]);
-
-      // execute() 반환값에서 stdout 직접 읽기 (이벤트 방식 X)
-      const output = await cmd.execute();
-
-      if (output.code !== 0 || !output.stdout.trim()) {
-        const errDetail = output.stderr?.trim() || `종료 코드 ${output.code}`;
-        setErrorMsg(`yt-dlp 실패: ${errDetail}`);
-        setStatus('error');
-        return;
-      }
-
-      const info = JSON.parse(output.stdout) as VideoInfo;
+      // yt-dlp는 Rust가 띄운다 (웹뷰에는 실행 권한이 없다)
+      const info = await probeDownload<VideoInfo>(trimmed);
       setVideoInfo(info);
       setStatus('ready');
     } catch (e: unknown) {
       const msg = e instanceof Error ? e.message : String(e);
-      setErrorMsg(msg);
+      setErrorMsg(`yt-dlp 실패: ${msg}`);
       setStatus('error');
     }
   }, [url]);
 
-  // ── 다운로드 (spawn + 실시간 로그) ─────────────────────────────────────────
+  // ── 다운로드 (대기열에 넣는다 — 페이지를 떠나도 계속 받는다) ──────────────
   const handleDownload = useCallback(async () => {
     const trimmed = url.trim();
     if (!trimmed) return;
-    setStatus('downloading');
-    setLogs(['⬇️ 다운로드를 시작합니다...']);
     setErrorMsg('');
 
     try {
       const selected = QUALITY_OPTIONS.find(q => q.id === quality)!;
-      const outputTemplate = `${outputPath}/%(title)s.%(ext)s`;
-
-      const args: string[] = quality === 'audio'
-        ? [trimmed, '-x', '--audio-format', 'mp3', '-o', outputTemplate]
-        : [trimmed, '-f', selected.arg, '--merge-output-format', 'mp4', '-o', outputTemplate];
-
-      const cmd = await getSidecar(args);
-
-      // spawn()으로 실시간 로그 수신
-      await new Promise<void>((resolve, reject) => {
-        cmd.stdout.on('data', (line: string) => addLog(line));
-        cmd.stderr.on('data', (line: string) => addLog(`[info] ${line}`));
-        cmd.on('close', (data: { code: number | null }) => {
-          if (data.code === 0) {
-            setStatus('done');
-            addLog('✅ 다운로드 완료!');
-            resolve();
-          } else {
-            setStatus('error');
-            reject(new Error(`종료 코드: ${data.code}`));
-          }
-        });
-        cmd.on('error', (err: string) => reject(new Error(err)));
-        cmd.spawn().catch(reject);
+      const job = await enqueueDownload({
+        url: trimmed,
+        format: quality === 'audio' ? null : selected.arg,
+        audioOnly: quality === 'audio',
+        outputDir: outputPath,
       });
+      setActiveJob(job.id);
+      setStatus('downloading');
+      addLog(job.id, '⬇️ 대기열에 추가했습니다...');
+      refreshJobs();
     } catch (e: unknown) {
       const msg = e instanceof Error ? e.message : String(e);
-      if (status !== 'done') {
-        setErrorMsg(msg);
-        setStatus('error');
-      }
+      setErrorMsg(msg);
+      setStatus('error');
     }
-  }, [url, quality, outputPath, addLog, status]);
+  }, [url, quality, outputPath, addLog, refreshJobs]);
+
+  const handleConcurrency = async (value: number) => {
+    setConcurrency(value);
+    await setDownloadSettings({ concurrency: value }).catch(e => setErrorMsg(String(e)));
+  };
+
+  const handleJobAction = async (action: (id: string) => Promise<unknown>, id: string) => {
+    try {
+      await action(id);
+    } catch (e: unknown) {
+      setErrorMsg(e instanceof Error ? e.message : String(e));
+    }
+    refreshJobs();
+  };
 
   const handlePaste = async () => {
     try {
@@ -156,7 +225,7 @@ export default function DownloaderPage() {
   const handleReset = () => {
     setStatus('idle');
     setVideoInfo(null);
-    setLogs([]);
+    setActiveJob(null);
     setErrorMsg('');
     setUrl('');
   };
@@ -315,6 +384,45 @@ export default function DownloaderPage() {
           </div>
         )}
 
+        {/* 진행 상황 */}
+        {status === 'downloading' && progress && (
+          <div className="bg-background-card border border-border rounded-2xl p-5 space-y-2">
+            <div className="flex items-center justify-between text-xs">
+              <span className="font-semibold text-text-secondary">
+                {progress.phase === 'postprocessing'
+                  ? POSTPROCESSOR_LABEL[progress.postprocessor ?? ''] ?? `후처리 중 (${progress.postprocessor ?? '...'})`
+                  : progress.phase === 'downloaded' ? '파일 하나 받음' : '받는 중'}
+              </span>
+              {progress.phase !== 'postprocessing' && progress.percent !== null && (
+                <span className="font-mono text-text-primary">{progress.percent.toFixed(1)}%</span>
+              )}
+            </div>
+            <div className="h-2 bg-border/40 rounded-full overflow-hidden">
+              {progress.phase === 'postprocessing' || progress.percent === null ? (
+                <div className="h-full w-1/3 bg-[#e94560]/70 rounded-full animate-pulse" />
+              ) : (
+                <div className="h-full bg-[#e94560] rounded-full transition-[width] duration-200" style={{ width: `${progress.percent}%` }} />
+              )}
+            </div>
+            {progress.phase !== 'postprocessing' && (
+              <div className="flex flex-wrap gap-x-4 gap-y-1 text-[10px] text-text-muted font-mono">
+                {progress.downloadedBytes !== null && (
+                  <span>
+                    {formatBytes(progress.downloadedBytes)}
+                    {progress.totalBytes !== null && ` / ${progress.totalIsEstimate ? '~' : ''}${formatBytes(progress.totalBytes)}`}
+                  </span>
+                )}
+                {progress.speed !== null && <span>{formatBytes(Math.round(progress.speed))}/s</span>}
+                {progress.eta !== null && <span>남은 시간 {formatDuration(progress.eta)}</span>}
+                {progress.fragmentCount !== null && <span>조각 {progress.fragmentIndex ?? 0}/{progress.fragmentCount}</span>}
+              </div>
+            )}
+            {progress.filename && (
+              <p className="text-[10px] text-text-muted font-mono truncate" title={progress.filename}>{progress.filename}</p>
+            )}
+          </div>
+        )}
+
         {/* 실시간 로그 */}
         {logs.length > 0 && (
           <div className="bg-background-card border border-border rounded-2xl p-5 space-y-3">
@@ -339,7 +447,7 @@ export default function DownloaderPage() {
                   </button>
                 )}
                 <button
-                  onClick={() => setLogs([])}
+                  onClick={() => activeJob && setJobLogs(prev => ({ ...prev, [activeJob]: [] }))}
                   className="text-[10px] px-2.5 py-1 border border-border rounded-lg text-text-muted hover:text-[#e94560] hover:border-[#e94560]/40 transition-colors"
                 >
                   지우기
@@ -362,6 +470,69 @@ export default function DownloaderPage() {
           </div>
         )}
 
+        {/* 다운로드 대기열 */}
+        {jobs.length > 0 && (
+          <div className="bg-background-card border border-border rounded-2xl p-5 space-y-3">
+            <div className="flex items-center justify-between">
+              <span className="text-xs font-semibold text-text-secondary">대기열</span>
+              <label className="flex items-center gap-2 text-[10px] text-text-muted">
+                동시에
+                <select
+                  value={concurrency}
+                  onChange={e => handleConcurrency(Number(e.target.value))}
+                  className="px-2 py-1 bg-background border border-border rounded-lg text-xs text-text-primary"
+                >
+                  {[1, 2, 3, 4, 5, 6, 7, 8].map(n => <option key={n} value={n}>{n}개</option>)}
+                </select>
+              </label>
+            </div>
+            <div className="space-y-1.5">
+              {jobs.map(job => (
+                <div
+                  key={job.id}
+                  onClick={() => setActiveJob(job.id)}
+                  className={`flex items-center gap-2 px-3 py-2 rounded-xl border cursor-pointer transition-colors ${
+                    job.id === activeJob ? 'border-[#e94560]/60 bg-[#e94560]/5' : 'border-border hover:bg-border/20'
+                  }`}
+                >
+                  <span className="flex-1 min-w-0 text-xs font-mono text-text-primary truncate" title={job.file ?? undefined}>
+                    {job.file?.split(/[\\/]/).pop() || job.url}
+                  </span>
+                  {job.state === 'running' && jobProgress[job.id]?.percent != null && (
+                    <span className="text-[10px] font-mono text-text-muted">{jobProgress[job.id].percent!.toFixed(0)}%</span>
+                  )}
+                  {job.state === 'completed' && job.size != null && (
+                    <span className="text-[10px] font-mono text-text-muted">{formatBytes(job.size)}</span>
+                  )}
+                  <span className={`text-[10px] font-semibold ${
+                    job.state === 'completed' ? 'text-green-400' :
+                    job.state === 'failed' ? 'text-red-400' :
+                    job.state === 'running' ? 'text-yellow-400' : 'text-text-muted'
+                  }`} title={job.error ?? undefined}>
+                    {JOB_STATE_LABEL[job.state]}
+                  </span>
+                  <div className="flex items-center gap-1" onClick={e => e.stopPropagation()}>
+                    {(job.state === 'queued' || job.state === 'running') && (
+                      <button onClick={() => handleJobAction(pauseDownload, job.id)} className="text-[10px] px-2 py-0.5 border border-border rounded-lg text-text-muted hover:text-text-primary">일시 정지</button>
+                    )}
+                    {job.state === 'paused' && (
+                      <button onClick={() => handleJobAction(resumeDownload, job.id)} className="text-[10px] px-2 py-0.5 border border-border rounded-lg text-text-muted hover:text-text-primary">이어 받기</button>
+                    )}
+                    {(job.state === 'failed' || job.state === 'cancelled') && (
+                      <button onClick={() => handleJobAction(retryDownload, job.id)} className="text-[10px] px-2 py-0.5 border border-border rounded-lg text-text-muted hover:text-text-primary">다시 받기</button>
+                    )}
+                    {(job.state === 'queued' || job.state === 'running' || job.state === 'paused') ? (
+                      <button onClick={() => handleJobAction(cancelDownload, job.id)} className="text-[10px] px-2 py-0.5 border border-border rounded-lg text-text-muted hover:text-[#e94560]">취소</button>
+                    ) : (
+                      <button onClick={() => handleJobAction(removeDownload, job.id)} className="text-[10px] px-2 py-0.5 border border-border rounded-lg text-text-muted hover:text-[#e94560]">지우기</button>
+                    )}
+                  </div>
+                </div>
+              ))}
+            </div>
+          </div>
+        )}
+
         {/* 지원 사이트 (idle 상태에서만) */}
         {status === 'idle' && !url.trim() && (
           <div className="bg-background-card border border-border rounded-2xl p-5">
@@ -8,6 +8,7 @@ import { requestNotificationPermission } from '@/lib/use-reminders';
 import NoahAIButton from '@/components/ai/NoahAIButton';
 import { PomodoroProvider } from '@/lib/pomodoro-context';
 import PomodoroFloatingWidget from '@/components/pomodoro/PomodoroFloatingWidget';
+import DeepLinkRouter from '@/components/layout/DeepLinkRouter';
 
 export default function DashboardLayout({
   children,
@@ -87,6 +88,7 @@ export default function DashboardLayout({
 
       {/* NoahAIButton 비활성화 — 채팅 패널 대신 페이지별 FloatingAIBar 사용 */}
       <PomodoroFloatingWidget />
+      <DeepLinkRouter />
     </div>
     </PomodoroProvider>
   );
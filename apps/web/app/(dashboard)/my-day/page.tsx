@@ -12,6 +12,7 @@ import {
 import { useTaskReminders } from '@/lib/use-reminders';
 import { deleteAttachmentsFromStorage } from '@/lib/attachment-store';
 import { useDataStore } from '@/lib/data-store';
+import { isLocalDbAvailable } from '@/lib/local-db';
 import FloatingAIBar, { type SlashCommand } from '@/components/ai/FloatingAIBar';
 import { detectCrossPageAction, crossPageContext, handleCrossPageResult } from '@/lib/cross-page-ai';
 import TaskDetailPanel from '@/components/task/TaskDetailPanel';
@@ -302,8 +303,8 @@ export default function MyDayPage() {
     const completedDate = newStatus === 'completed' ? selectedDate : null;
     await updateTask(user.uid, task.id, { status: newStatus, completedDate });
 
-    // 반복 규칙이 있고 완료됐을 때 다음 반복 할일 자동 생성
-    if (newStatus === 'completed' && task.recurrence_rule) {
+    // 반복 규칙이 있고 완료됐을 때 다음 반복 할일 자동 생성 (데스크톱은 로컬 DB가 완료와 함께 만든다)
+    if (newStatus === 'completed' && task.recurrence_rule && !isLocalDbAvailable()) {
       const baseDate = getTaskCreatedDate(task);
       const nextDateStr = calcNextOccurrence(baseDate, task.recurrence_rule);
       // until 날짜 초과 시 생성 안 함
@@ -52,6 +52,8 @@ function TasksContent() {
   const [adding, setAdding] = useState(false);
   const [showCompleted, setShowCompleted] = useState(true);
   const [showWeeklyReview, setShowWeeklyReview] = useState(false);
+  // noah://add-task 딥링크로 채워진 할 일 (사용자가 확인해야 추가된다)
+  const [linkDraft, setLinkDraft] = useState<{ title: string; due: string | null } | null>(null);
 
   const [dragSrcIdx, setDragSrcIdx] = useState<number | null>(null);
   const [dragOverIdx, setDragOverIdx] = useState<number | null>(null);
@@ -73,6 +75,11 @@ function TasksContent() {
   useEffect(() => {
     const listParam = searchParams.get('list');
     if (listParam) setFilterList(listParam);
+    // noah://task/<id> 딥링크
+    const taskParam = searchParams.get('task');
+    if (taskParam) setSelectedTaskId(taskParam);
+    const addParam = searchParams.get('add');
+    if (addParam !== null) setLinkDraft({ title: addParam, due: searchParams.get('due') });
   }, [searchParams]);
 
   // 스토어 → 로컬 tasks (정렬 유지)
@@ -237,6 +244,30 @@ function TasksContent() {
     }
   };
 
+  const closeLinkDraft = () => {
+    setLinkDraft(null);
+    router.replace('/tasks');
+  };
+
+  const handleAddLinkDraft = async () => {
+    if (!linkDraft || !linkDraft.title.trim() || !user || adding) return;
+    setAdding(true);
+    const title = linkDraft.title.trim();
+    const maxOrder = tasks.reduce((m, t) => Math.max(m, t.order ?? 0), 0);
+    try {
+      await addTaskDB(user.uid, {
+        title, status: 'todo', priority: newTaskPriority,
+        starred: false, listId: newTaskList || lists[0]?.id || '',
+        myDay: false, tags: parseTags(title), order: maxOrder + 1000,
+        dueDate: linkDraft.due,
+        createdDate: (() => { const d = new Date(); return `${d.getFullYear()}-${String(d.getMonth() + 1).padStart(2, '0')}-${String(d.getDate()).padStart(2, '0')}`; })(),
+      });
+      closeLinkDraft();
+    } catch { /* ignore */ } finally {
+      setAdding(false);
+    }
+  };
+
   const handlePanelUpdate = async (updates: Partial<TaskData>) => {
     if (!user || !selectedTaskId) return;
     const finalUpdates = { ...updates };
@@ -589,6 +620,38 @@ function TasksContent() {
           onClose={() => setShowWeeklyReview(false)}
         />
       )}
+
+      {linkDraft && (
+        <div className="fixed inset-0 bg-black/60 backdrop-blur-sm z-50 flex items-center justify-center p-4">
+          <div className="bg-background-card border border-border rounded-2xl shadow-2xl w-full max-w-md p-6 space-y-4">
+            <div>
+              <h2 className="text-sm font-bold text-text-primary">링크로 할 일 추가</h2>
+              <p className="text-[10px] text-text-muted mt-1">다른 앱이나 웹 페이지가 연 링크입니다. 내용을 확인하고 추가하세요.</p>
+            </div>
+            <input
+              type="text"
+              value={linkDraft.title}
+              onChange={(e) => setLinkDraft({ ...linkDraft, title: e.target.value })}
+              onKeyDown={(e) => e.key === 'Enter' && handleAddLinkDraft()}
+              autoFocus
+              className="w-full px-4 py-3 bg-background border border-border rounded-xl text-sm text-text-primary focus:outline-none focus:border-[#e94560]"
+            />
+            {linkDraft.due && <p className="text-xs text-text-secondary">📅 {linkDraft.due}</p>}
+            <div className="flex justify-end gap-2">
+              <button onClick={closeLinkDraft} className="px-4 py-2 text-xs text-text-muted hover:text-text-primary transition-colors">
+                {t('common.cancel')}
+              </button>
+              <button
+                onClick={handleAddLinkDraft}
+                disabled={adding || !linkDraft.title.trim()}
+                className="px-4 py-2 bg-[#e94560] hover:bg-[#ff5a7a] text-white font-semibold rounded-xl text-xs transition-colors disabled:opacity-50"
+              >
+                {adding ? '...' : t('common.add')}
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
     </div>
   );
 }
@@ -0,0 +1,65 @@
+'use client';
+
+import { useEffect, useRef } from 'react';
+import { useRouter } from 'next/navigation';
+import { useAuth } from '@/lib/auth-context';
+import { usePomodoroContext } from '@/lib/pomodoro-context';
+import { subscribeDeepLinks, type DeepLinkEvent } from '@/lib/deep-link';
+
+/**
+ * 로그인 후 대시보드에서 noah:// 링크 처리 (authCallback은 로그인 페이지 담당).
+ * 링크는 아무 웹 페이지나 앱이 열 수 있으므로 이동만 한다 — 할 일 추가도 확인 창을 거친다
+ */
+export default function DeepLinkRouter() {
+  const router = useRouter();
+  const { user } = useAuth();
+  const pomodoro = usePomodoroContext();
+
+  // 구독은 한 번만 — 최신 값은 ref로 읽는다
+  const latest = useRef({ router, user, pomodoro });
+  latest.current = { router, user, pomodoro };
+
+  useEffect(() => {
+    const handle = async (event: DeepLinkEvent) => {
+      const { router, user, pomodoro } = latest.current;
+      switch (event.route) {
+        case 'openTask':
+          router.push(`/tasks?task=${encodeURIComponent(event.id)}`);
+          break;
+        case 'openNote':
+          router.push(`/notes?note=${encodeURIComponent(event.id)}`);
+          break;
+        case 'addTask': {
+          if (!user) return;
+          // 할 일 페이지가 채워진 추가 창을 띄운다
+          const params = new URLSearchParams({ add: event.title });
+          if (event.due) params.set('due', event.due);
+          router.push(`/tasks?${params}`);
+          break;
+        }
+        case 'startPomodoro':
+          pomodoro.start(event.minutes ?? undefined);
+          router.push('/pomodoro');
+          break;
+        case 'openMyDay':
+          router.push('/my-day');
+          break;
+        default:
+          break;
+      }
+    };
+
+    let unlisten: (() => void) | null = null;
+    let cancelled = false;
+    subscribeDeepLinks(handle, ['openTask', 'openNote', 'addTask', 'startPomodoro', 'openMyDay']).then((fn) => {
+      if (cancelled) fn();
+      else unlisten = fn;
+    });
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  return null;
+}
@@ -4,6 +4,8 @@ import { useState, useEffect, useRef, useCallback } from 'react';
 import { useRouter } from 'next/navigation';
 import { useAuth } from '@/lib/auth-context';
 import { useI18n } from '@/lib/i18n-context';
+import { notify } from '@/lib/notify';
+import { cacheTimebox } from '@/lib/agenda';
 import {
   getTimebox, saveTimebox, updateTask, addNote as addNoteDB,
   getUserSettings,
@@ -31,16 +33,7 @@ function getInitialInterval(): number {
 }
 
 async function trySystemNotification(title: string, body: string) {
-  try {
-    const mod = await import('@tauri-apps/plugin-notification');
-    if (await mod.isPermissionGranted()) {
-      await mod.sendNotification({ title, body });
-      return;
-    }
-  } catch {}
-  if (typeof window !== 'undefined' && 'Notification' in window && Notification.permission === 'granted') {
-    new Notification(title, { body });
-  }
+  await notify('timebox', title, body);
 }
 
 async function requestNotificationPermission(): Promise<boolean> {
@@ -129,6 +122,7 @@ export default function TimeboxPlanner({ date, tasks }: Props) {
     setLoading(true);
     getTimebox(user.uid, date).then((data) => {
       setSlots(data.slots || {});
+      cacheTimebox(user.uid, date, data.slots || {});
       setSlotAlarms(data.slotAlarms || {});
       setBrainDump(data.brainDump || '');
       setLoading(false);
@@ -188,6 +182,7 @@ export default function TimeboxPlanner({ date, tasks }: Props) {
     clearTimeout(saveTimer.current);
     saveTimer.current = setTimeout(() => {
       if (!user) return;
+      cacheTimebox(user.uid, date, s);
       saveTimebox(user.uid, date, {
         slots: s,
         slotAlarms: a,
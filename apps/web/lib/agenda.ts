@@ -0,0 +1,47 @@
+// 아침 일정 요약 — 데스크톱에서 Rust가 정한 시각에 로컬 DB로 요약 알림을 띄운다 (누르면 My Day).
+// 타임박스는 로컬 DB에 없어서 웹뷰가 읽고 저장할 때 `cacheTimebox`로 넘겨 둔다.
+import { isTauriRuntime } from './token-store';
+
+export interface AgendaTask {
+  id: string;
+  title: string;
+  dueDate: string | null;
+}
+
+export interface Agenda {
+  /** YYYY-MM-DD */
+  date: string;
+  overdue: AgendaTask[];
+  dueToday: AgendaTask[];
+  myDay: AgendaTask[];
+  timebox: { time: string; text: string }[];
+}
+
+export interface AgendaSettings {
+  enabled: boolean;
+  /** "HH:MM" */
+  time: string;
+}
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+/** 웹에서는 아무것도 하지 않는다 */
+export function cacheTimebox(uid: string, date: string, slots: Record<string, string>): void {
+  if (!isTauriRuntime()) return;
+  invoke('cache_timebox', { uid, date, slots }).catch((e) => console.warn('[agenda] timebox', e));
+}
+
+export function getAgenda(uid: string, date?: string): Promise<Agenda> {
+  return invoke('get_agenda', { uid, date: date ?? null });
+}
+
+export function getAgendaSettings(): Promise<AgendaSettings> {
+  return invoke('get_agenda_settings');
+}
+
+export function setAgendaSettings(settings: AgendaSettings): Promise<void> {
+  return invoke('set_agenda_settings', { settings });
+}
@@ -0,0 +1,93 @@
+// 데스크톱 로컬 백업 — Rust가 로컬 DB 전체를 압축(선택적으로 암호화)해 백업 폴더에 쓴다.
+// 예약 백업 결과는 `backup-status` 이벤트로, 실패는 시스템 알림으로도 온다.
+import { isTauriRuntime } from './token-store';
+import type { LocalCollection } from './local-db';
+
+export type BackupKind = 'scheduled' | 'manual' | 'pre_restore';
+
+/** missing: 지워진 문서만 / revert: 고친 문서도 되돌림 / exact: 백업 뒤에 만든 문서까지 지움 */
+export type RestoreMode = 'missing' | 'revert' | 'exact';
+
+export interface BackupInfo {
+  id: string;
+  path: string;
+  size: number;
+  createdAt: number;
+  kind: BackupKind;
+  encrypted: boolean;
+  counts: Partial<Record<LocalCollection, number>>;
+  accounts: number;
+}
+
+export interface BackupSettings {
+  enabled: boolean;
+  /** 절대 경로. null이면 앱 데이터 폴더의 backups */
+  folder: string | null;
+  encrypted: boolean;
+  intervalHours: number;
+  keepDaily: number;
+  keepWeekly: number;
+}
+
+export interface BackupStatus {
+  settings: BackupSettings;
+  folder: string;
+  passphraseSet: boolean;
+  lastBackupAt: number | null;
+  nextBackupAt: number | null;
+  lastError: string | null;
+  /** 로그인 시 자동 실행. 꺼져 있으면 앱을 켜 둔 동안에만 예약 백업이 돈다 */
+  autostart?: boolean | null;
+}
+
+export interface RestoreSummary {
+  restored: number;
+  reverted: number;
+  removed: number;
+  /** 복원 직전 상태를 담은 백업 */
+  safetyBackup: BackupInfo;
+}
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+/** 암호가 필요하거나 틀렸을 때 Rust 오류 문자열의 접두어 */
+export function isPassphraseError(error: unknown): boolean {
+  const message = String(error);
+  return message.startsWith('passphrase_required') || message.startsWith('wrong_passphrase');
+}
+
+export function createBackup(): Promise<BackupInfo> {
+  return invoke('create_backup');
+}
+
+export function listBackups(): Promise<BackupInfo[]> {
+  return isTauriRuntime() ? invoke('list_backups') : Promise.resolve([]);
+}
+
+export function verifyBackup(id: string, passphrase?: string): Promise<BackupInfo> {
+  return invoke('verify_backup', { id, passphrase: passphrase ?? null });
+}
+
+export function restoreBackup(id: string, uid: string, mode: RestoreMode, passphrase?: string): Promise<RestoreSummary> {
+  return invoke('restore_backup', { id, uid, mode, passphrase: passphrase ?? null });
+}
+
+export function getBackupStatus(): Promise<BackupStatus> {
+  return invoke('backup_status');
+}
+
+/** passphrase를 주면 백업 암호를 바꾼다. 암호화를 끄면 저장된 암호는 지워진다 */
+export function setBackupSettings(settings: BackupSettings, passphrase?: string): Promise<void> {
+  return invoke('set_backup_settings', { settings, passphrase: passphrase ?? null });
+}
+
+export function subscribeBackupStatus(handler: (status: BackupStatus) => void): () => void {
+  if (!isTauriRuntime()) return () => {};
+  const unlisten = import('@tauri-apps/api/event').then(({ listen }) =>
+    listen<BackupStatus>('backup-status', (e) => handler(e.payload)),
+  );
+  return () => { unlisten.then((u) => u()); };
+}
@@ -5,15 +5,22 @@
  * - onSnapshot 리스너를 앱 전체에서 단 1회 설정
  * - 모든 페이지가 동일한 인메모리 데이터를 공유 (페이지 이동 시 재요청 없음)
  * - Firestore 쓰기 직후 로컬 캐시에서 즉시 반영 (오프라인 캐시 모드)
+ * - 데스크톱(Tauri)은 로컬 DB로 먼저 그리고, 서버 스냅샷을 로컬 DB에 미러링
  */
 
 import {
   createContext, useContext, useEffect, useState, useRef,
   type ReactNode,
 } from 'react';
-import { collection, query, orderBy, onSnapshot } from 'firebase/firestore';
+import { collection, query, orderBy, onSnapshot, type QuerySnapshot } from 'firebase/firestore';
 import { db } from './firebase';
 import { useAuth } from './auth-context';
+import {
+  isLocalDbAvailable, mirrorSnapshot, getLocalTasks, getLocalLists, getLocalNotes, getLocalFolders, getLocalMindmaps,
+  type LocalCollection,
+} from './local-db';
+import { startNativeSync, subscribeLocalDbChanges } from './sync';
+import { startReminderActions } from './reminders';
 import type { TaskData, ListData, NoteData, FolderData, MindMapData, CalendarEvent } from './firestore';
 
 interface DataStore {
@@ -101,11 +108,73 @@ export function DataStoreProvider({ children }: { children: ReactNode }) {
     const total = 6;
     const markLoaded = () => { if (++loadedCount >= total) setLoading(false); };
 
+    // 로컬 DB 우선: 스냅샷이 오기 전까지 로컬 데이터로 화면을 그린다
+    const localFirst = isLocalDbAvailable();
+    const received = new Set<LocalCollection>();
+    const seeded = new Set<LocalCollection>();
+    let cancelled = false;
+
+    // 로컬에 먼저 쓴 변경은 서버 스냅샷보다 먼저 화면에 반영
+    const localLoaders: Record<LocalCollection, () => Promise<void>> = {
+      tasks: () => getLocalTasks(uid).then(setTasks),
+      lists: () => getLocalLists(uid).then(setLists),
+      notes: () => getLocalNotes(uid).then(setNotes),
+      folders: () => getLocalFolders(uid).then(setFolders),
+      mindmaps: () => getLocalMindmaps(uid).then(setMindmaps),
+    };
+
+    function applySnapshot<T>(name: LocalCollection, snap: QuerySnapshot, set: (docs: T[]) => void) {
+      const docs = snap.docs.map((d) => ({ id: d.id, ...d.data() } as T));
+      if (!localFirst) {
+        set(docs);
+        return;
+      }
+      // 데스크톱에서는 로컬 DB가 기준 — 캐시 스냅샷은 무시하고, 서버 스냅샷은 로컬에 반영한 뒤
+      // 아직 올라가지 않은 변경까지 합쳐진 로컬 데이터로 다시 그린다
+      if (snap.metadata.fromCache) return;
+      received.add(name);
+      mirrorSnapshot(uid, name, docs as Array<{ id?: string }>)
+        .then(() => { if (!cancelled) return localLoaders[name](); })
+        .catch((e) => {
+          console.warn('[local-db] mirror failed', name, e);
+          if (!cancelled) set(docs);
+        });
+    }
+
+    if (localFirst) {
+      const seed = <T,>(name: LocalCollection, load: Promise<T[]>, set: (docs: T[]) => void) =>
+        load
+          .then((docs) => {
+            if (cancelled || received.has(name) || docs.length === 0) return;
+            seeded.add(name);
+            set(docs);
+          })
+          .catch((e) => console.warn('[local-db] load failed', name, e));
+      Promise.all([
+        seed('tasks', getLocalTasks(uid), setTasks),
+        seed('lists', getLocalLists(uid), setLists),
+        seed('notes', getLocalNotes(uid), setNotes),
+        seed('folders', getLocalFolders(uid), setFolders),
+        seed('mindmaps', getLocalMindmaps(uid), setMindmaps),
+      ]).then(() => { if (!cancelled && seeded.size > 0) setLoading(false); });
+    }
+
+    const localUnsubs = localFirst
+      ? [
+          startNativeSync(),
+          startReminderActions(),
+          subscribeLocalDbChanges((e) => {
+            if (cancelled || e.uid !== uid) return;
+            localLoaders[e.collection]().catch((err) => console.warn('[local-db] reload failed', e.collection, err));
+          }),
+        ]
+      : [];
+
     // Tasks — 생성순 desc
     const unsubTasks = onSnapshot(
       query(collection(db, 'users', uid, 'tasks'), orderBy('createdAt', 'desc')),
       (snap) => {
-        setTasks(snap.docs.map((d) => ({ id: d.id, ...d.data() } as TaskData)));
+        applySnapshot<TaskData>('tasks', snap, setTasks);
         markLoaded();
       },
       () => markLoaded() // error: 권한 문제 시 로딩 해제
@@ -115,7 +184,7 @@ export function DataStoreProvider({ children }: { children: ReactNode }) {
     const unsubLists = onSnapshot(
       collection(db, 'users', uid, 'lists'),
       (snap) => {
-        setLists(snap.docs.map((d) => ({ id: d.id, ...d.data() } as ListData)));
+        applySnapshot<ListData>('lists', snap, setLists);
         markLoaded();
       },
       () => markLoaded()
@@ -125,7 +194,7 @@ export function DataStoreProvider({ children }: { children: ReactNode }) {
     const unsubNotes = onSnapshot(
       query(collection(db, 'users', uid, 'notes'), orderBy('createdAt', 'desc')),
       (snap) => {
-        setNotes(snap.docs.map((d) => ({ id: d.id, ...d.data() } as NoteData)));
+        applySnapshot<NoteData>('notes', snap, setNotes);
         markLoaded();
       },
       () => markLoaded()
@@ -135,7 +204,7 @@ export function DataStoreProvider({ children }: { children: ReactNode }) {
     const unsubFolders = onSnapshot(
       collection(db, 'users', uid, 'folders'),
       (snap) => {
-        setFolders(snap.docs.map((d) => ({ id: d.id, ...d.data() } as FolderData)));
+        applySnapshot<FolderData>('folders', snap, setFolders);
         markLoaded();
       },
       () => markLoaded()
@@ -145,7 +214,7 @@ export function DataStoreProvider({ children }: { children: ReactNode }) {
     const unsubMindmaps = onSnapshot(
       query(collection(db, 'users', uid, 'mindmaps'), orderBy('createdAt', 'desc')),
       (snap) => {
-        setMindmaps(snap.docs.map((d) => ({ id: d.id, ...d.data() } as MindMapData)));
+        applySnapshot<MindMapData>('mindmaps', snap, setMindmaps);
         markLoaded();
       },
       () => markLoaded()
@@ -164,6 +233,8 @@ export function DataStoreProvider({ children }: { children: ReactNode }) {
     unsubsRef.current = [unsubTasks, unsubLists, unsubNotes, unsubFolders, unsubMindmaps, unsubCalEvents];
 
     return () => {
+      cancelled = true;
+      localUnsubs.forEach((u) => u());
       unsubsRef.current.forEach((u) => u());
       unsubsRef.current = [];
     };
@@ -0,0 +1,41 @@
+// noah:// 딥링크 — Rust 라우터가 해석한 `deep-link` 이벤트 구독
+export type DeepLinkRoute =
+  | { route: 'authCallback'; data: string }
+  | { route: 'openTask'; id: string }
+  | { route: 'openNote'; id: string }
+  | { route: 'addTask'; title: string; due: string | null }
+  | { route: 'startPomodoro'; minutes: number | null }
+  | { route: 'openMyDay' };
+
+export type DeepLinkEvent = DeepLinkRoute & {
+  url: string;
+  source: 'cold_start' | 'running' | 'second_instance' | 'notification';
+};
+
+export type DeepLinkRouteName = DeepLinkRoute['route'];
+
+/**
+ * `deep-link` 이벤트를 구독하고, 콜드 스타트로 대기 중이던 링크도 꺼내 처리한다.
+ * routes를 주면 해당 라우트만 받는다 (나머지 대기 링크는 다른 구독자 몫으로 남는다).
+ * Tauri가 아니면 아무것도 하지 않는다. 반환값은 구독 해제 함수
+ */
+export async function subscribeDeepLinks(
+  handler: (event: DeepLinkEvent) => void,
+  routes?: DeepLinkRouteName[],
+): Promise<() => void> {
+  if (typeof window === 'undefined' || !('__TAURI_INTERNALS__' in window)) return () => {};
+  const { listen } = await import('@tauri-apps/api/event');
+  const { invoke } = await import('@tauri-apps/api/core');
+  const accepts = (e: DeepLinkEvent) => !routes || routes.includes(e.route);
+
+  const unlisten = await listen<DeepLinkEvent>('deep-link', (event) => {
+    if (accepts(event.payload)) handler(event.payload);
+  });
+  try {
+    const pending = await invoke<DeepLinkEvent[]>('take_pending_deep_links', { routes: routes ?? null });
+    pending.forEach(handler);
+  } catch {
+    // 명령이 없는 구버전 셸
+  }
+  return unlisten;
+}
@@ -0,0 +1,119 @@
+// 미디어 다운로드 대기열 — 데스크톱에서 Rust가 yt-dlp 사이드카를 띄우고 작업을 로컬 DB에 둔다.
+// 페이지를 떠나도 계속 받고, 상태와 출력 줄은 `download-progress` 이벤트로 온다 (작업 id별).
+// 진행 줄은 Rust가 읽어 `progress`로 보낸다 (약 0.25초마다, 단계가 바뀌면 바로).
+import { isTauriRuntime } from './token-store';
+
+export type DownloadState = 'queued' | 'running' | 'paused' | 'completed' | 'failed' | 'cancelled';
+
+export interface DownloadRequest {
+  url: string;
+  /** yt-dlp `-f` 형식 (없으면 최고 화질) */
+  format?: string | null;
+  /** mp3로 소리만 */
+  audioOnly?: boolean;
+  outputDir: string;
+}
+
+export interface DownloadJob extends DownloadRequest {
+  id: string;
+  state: DownloadState;
+  /** 실행한 횟수 (다시 받기, 이어 받기 포함) */
+  attempts: number;
+  error?: string | null;
+  /** 받은 파일 (병합/추출 뒤의 것, 끝난 뒤) */
+  file?: string | null;
+  /** 받은 파일 크기 (bytes) */
+  size?: number | null;
+  createdAt: number;
+  updatedAt: number;
+}
+
+export type ProgressPhase = 'downloading' | 'downloaded' | 'postprocessing';
+
+export interface DownloadProgress {
+  phase: ProgressPhase;
+  /** 지금 받는 파일의 0..=100 */
+  percent: number | null;
+  downloadedBytes: number | null;
+  totalBytes: number | null;
+  /** totalBytes가 추정치 (조각으로 받는 스트림) */
+  totalIsEstimate: boolean;
+  /** bytes/s */
+  speed: number | null;
+  /** 초 */
+  eta: number | null;
+  fragmentIndex: number | null;
+  fragmentCount: number | null;
+  /** 후처리기 이름 (Merger, ExtractAudio ...) */
+  postprocessor: string | null;
+  filename: string | null;
+}
+
+export interface DownloadEvent {
+  jobId: string;
+  state: DownloadState;
+  /** yt-dlp 출력 한 줄 (진행 줄이거나 상태만 바뀌었으면 없음) */
+  line?: string;
+  progress?: DownloadProgress;
+  error?: string;
+}
+
+export interface DownloadSettings {
+  /** 동시에 받는 수 (1..=8) */
+  concurrency: number;
+}
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+/** 받기 전에 영상 정보 (yt-dlp `--dump-json` 결과) */
+export function probeDownload<T = Record<string, unknown>>(url: string): Promise<T> {
+  return invoke('probe_download', { url });
+}
+
+export function enqueueDownload(request: DownloadRequest): Promise<DownloadJob> {
+  return invoke('enqueue_download', { request });
+}
+
+export function listDownloads(): Promise<DownloadJob[]> {
+  return invoke('list_downloads');
+}
+
+export function pauseDownload(id: string): Promise<DownloadJob> {
+  return invoke('pause_download', { id });
+}
+
+export function resumeDownload(id: string): Promise<DownloadJob> {
+  return invoke('resume_download', { id });
+}
+
+export function cancelDownload(id: string): Promise<DownloadJob> {
+  return invoke('cancel_download', { id });
+}
+
+export function retryDownload(id: string): Promise<DownloadJob> {
+  return invoke('retry_download', { id });
+}
+
+/** 목록에서 지운다 (받은 파일은 그대로) */
+export function removeDownload(id: string): Promise<boolean> {
+  return invoke('remove_download', { id });
+}
+
+export function getDownloadSettings(): Promise<DownloadSettings> {
+  return invoke('get_download_settings');
+}
+
+export function setDownloadSettings(settings: DownloadSettings): Promise<void> {
+  return invoke('set_download_settings', { settings });
+}
+
+export function subscribeDownloadProgress(handler: (event: DownloadEvent) => void): () => void {
+  if (!isTauriRuntime()) return () => {};
+  const unlisten = import('@tauri-apps/api/event').then(({ listen }) =>
+    listen<DownloadEvent>('download-progress', (e) => handler(e.payload)),
+  );
+  return () => { unlisten.then((u) => u()); };
+}
@@ -3,6 +3,7 @@ import { initializeAuth, getAuth, browserLocalPersistence, browserPopupRedirectR
 import { getFirestore } from 'firebase/firestore';
 import { getStorage } from 'firebase/storage';
 import { getFunctions } from 'firebase/functions';
+import { isTauriRuntime, tauriVaultPersistence } from './token-store';
 
 const firebaseConfig = {
   apiKey: process.env.NEXT_PUBLIC_FIREBASE_API_KEY,
@@ -17,11 +18,14 @@ const firebaseConfig = {
 // Initialize Firebase (prevent duplicate initialization)
 const app = getApps().length === 0 ? initializeApp(firebaseConfig) : getApps()[0];
 
-// Use browserLocalPersistence (localStorage) for Tauri compatibility
+// Tauri: 네이티브 보관소에 세션 저장 (기존 localStorage 세션은 Firebase가 보관소로 옮긴다)
+// 웹: browserLocalPersistence (localStorage)
 export const auth = (() => {
   try {
     return initializeAuth(app, {
-      persistence: browserLocalPersistence,
+      persistence: isTauriRuntime()
+        ? [tauriVaultPersistence, browserLocalPersistence]
+        : browserLocalPersistence,
       popupRedirectResolver: browserPopupRedirectResolver,
     });
   } catch {
@@ -16,6 +16,14 @@ import {
   writeBatch,
 } from 'firebase/firestore';
 import { db } from './firebase';
+import {
+  isLocalDbAvailable,
+  addLocalTask, updateLocalTask, deleteLocalTask,
+  addLocalNote, updateLocalNote, deleteLocalNote,
+  addLocalList, updateLocalList, deleteLocalList,
+  addLocalFolder, updateLocalFolder, deleteLocalFolder,
+  addLocalMindmap, updateLocalMindmap, deleteLocalMindmap,
+} from './local-db';
 
 // ============================================================================
 // Simple in-memory cache to avoid redundant Firestore reads
@@ -66,6 +74,24 @@ export interface RecurrenceRule {
   freq: 'daily' | 'weekly' | 'monthly' | 'yearly';
   interval?: number;
   until?: string;
+  /** 주간 반복 요일 (0 = 월요일) */
+  byweekday?: number[];
+  bymonthday?: number;
+  /** RFC 5545 규칙 (RRULE/EXDATE/RDATE 줄). 있으면 위 필드보다 우선 — `lib/recurrence.ts`로 펼친다 */
+  rrule?: string;
+  /** 회차별 예외. 키는 원래 날짜 (RECURRENCE-ID, YYYY-MM-DD) */
+  overrides?: Record<string, OccurrenceOverride>;
+  /** 이 회차에 제목/우선순위 예외가 걸려 있을 때 반복 본래의 값 */
+  series_title?: string;
+  series_priority?: TaskData['priority'];
+}
+
+export interface OccurrenceOverride {
+  skipped?: boolean;
+  /** 옮긴 날짜 (YYYY-MM-DD) */
+  start?: string;
+  title?: string;
+  priority?: TaskData['priority'];
 }
 
 export interface TaskData {
@@ -87,6 +113,12 @@ export interface TaskData {
   createdDate?: string | null;
   linkedNoteIds?: string[];
   recurrence_rule?: RecurrenceRule | null;
+  /** 반복으로 만들어진 할 일이면 반복의 첫 할 일 id */
+  recurrence_parent_id?: string | null;
+  /** 반복 회차의 원래 날짜 (RECURRENCE-ID). 옮긴 회차면 dueDate와 다르다 */
+  recurrence_id?: string | null;
+  /** 데스크톱 앱이 기록하는 필드별 수정 시계 (동시 편집 병합용) */
+  fieldClocks?: Record<string, string>;
   createdAt?: Timestamp;
   updatedAt?: Timestamp;
 }
@@ -209,6 +241,7 @@ export async function getMyDayTasks(uid: string): Promise<TaskData[]> {
 export async function addTask(uid: string, task: Omit<TaskData, 'id' | 'createdAt' | 'updatedAt'>): Promise<string> {
   invalidateCache(uid, 'tasks');
   invalidateCache(uid, 'myDayTasks');
+  if (isLocalDbAvailable()) return addLocalTask(uid, task);
   const docRef = await addDoc(tasksRef(uid), {
     ...task,
     createdAt: serverTimestamp(),
@@ -220,6 +253,7 @@ export async function addTask(uid: string, task: Omit<TaskData, 'id' | 'createdA
 export async function updateTask(uid: string, taskId: string, updates: Partial<TaskData>): Promise<void> {
   invalidateCache(uid, 'tasks');
   invalidateCache(uid, 'myDayTasks');
+  if (isLocalDbAvailable()) return updateLocalTask(uid, taskId, updates);
   await updateDoc(doc(db, 'users', uid, 'tasks', taskId), {
     ...updates,
     updatedAt: serverTimestamp(),
@@ -229,6 +263,7 @@ export async function updateTask(uid: string, taskId: string, updates: Partial<T
 export async function deleteTask(uid: string, taskId: string): Promise<void> {
   invalidateCache(uid, 'tasks');
   invalidateCache(uid, 'myDayTasks');
+  if (isLocalDbAvailable()) return deleteLocalTask(uid, taskId);
   await deleteDoc(doc(db, 'users', uid, 'tasks', taskId));
 }
 
@@ -252,6 +287,7 @@ export async function getNotes(uid: string): Promise<NoteData[]> {
 
 export async function addNote(uid: string, note: Omit<NoteData, 'id' | 'createdAt' | 'updatedAt'>): Promise<string> {
   invalidateCache(uid, 'notes');
+  if (isLocalDbAvailable()) return addLocalNote(uid, note);
   const docRef = await addDoc(notesRef(uid), {
     ...note,
     createdAt: serverTimestamp(),
@@ -261,6 +297,7 @@ export async function addNote(uid: string, note: Omit<NoteData, 'id' | 'createdA
 }
 
 export async function updateNote(uid: string, noteId: string, updates: Partial<NoteData>): Promise<void> {
+  if (isLocalDbAvailable()) return updateLocalNote(uid, noteId, updates);
   await updateDoc(doc(db, 'users', uid, 'notes', noteId), {
     ...updates,
     updatedAt: serverTimestamp(),
@@ -270,12 +307,14 @@ export async function updateNote(uid: string, noteId: string, updates: Partial<N
 /** Permanent delete note (legacy — prefer softDeleteNote) */
 export async function deleteNote(uid: string, noteId: string): Promise<void> {
   invalidateCache(uid, 'notes');
+  if (isLocalDbAvailable()) return deleteLocalNote(uid, noteId);
   await deleteDoc(doc(db, 'users', uid, 'notes', noteId));
 }
 
 /** Soft delete — move note to trash */
 export async function softDeleteNote(uid: string, noteId: string, currentFolderId: string | null): Promise<void> {
   invalidateCache(uid, 'notes');
+  if (isLocalDbAvailable()) return updateLocalNote(uid, noteId, { deleted: true, deletedAt: new Date().toISOString(), originalFolderId: currentFolderId });
   await updateDoc(doc(db, 'users', uid, 'notes', noteId), {
     deleted: true,
     deletedAt: new Date().toISOString(),
@@ -288,6 +327,9 @@ export async function softDeleteNote(uid: string, noteId: string, currentFolderI
 export async function restoreNote(uid: string, noteId: string, originalFolderId: string | null, existingFolderIds: string[]): Promise<void> {
   invalidateCache(uid, 'notes');
   const targetFolderId = originalFolderId && existingFolderIds.includes(originalFolderId) ? originalFolderId : null;
+  if (isLocalDbAvailable()) {
+    return updateLocalNote(uid, noteId, { deleted: false, deletedAt: null, originalFolderId: null, folderId: targetFolderId });
+  }
   await updateDoc(doc(db, 'users', uid, 'notes', noteId), {
     deleted: false,
     deletedAt: null,
@@ -300,12 +342,17 @@ export async function restoreNote(uid: string, noteId: string, originalFolderId:
 /** Permanent delete note */
 export async function permanentDeleteNote(uid: string, noteId: string): Promise<void> {
   invalidateCache(uid, 'notes');
+  if (isLocalDbAvailable()) return deleteLocalNote(uid, noteId);
   await deleteDoc(doc(db, 'users', uid, 'notes', noteId));
 }
 
 /** Empty trash — permanently delete all trashed notes */
 export async function emptyNoteTrash(uid: string, trashedNoteIds: string[]): Promise<void> {
   invalidateCache(uid, 'notes');
+  if (isLocalDbAvailable()) {
+    for (const id of trashedNoteIds) await deleteLocalNote(uid, id);
+    return;
+  }
   const batch = writeBatch(db);
   for (const id of trashedNoteIds) {
     batch.delete(doc(db, 'users', uid, 'notes', id));
@@ -332,6 +379,7 @@ export async function getLists(uid: string): Promise<ListData[]> {
 
 export async function updateList(uid: string, listId: string, updates: Partial<ListData>): Promise<void> {
   invalidateCache(uid, 'lists');
+  if (isLocalDbAvailable()) return updateLocalList(uid, listId, updates);
   await updateDoc(doc(db, 'users', uid, 'lists', listId), {
     ...updates,
   });
@@ -339,6 +387,7 @@ export async function updateList(uid: string, listId: string, updates: Partial<L
 
 export async function addList(uid: string, list: Omit<ListData, 'id' | 'createdAt'>): Promise<string> {
   invalidateCache(uid, 'lists');
+  if (isLocalDbAvailable()) return addLocalList(uid, list);
   const docRef = await addDoc(listsRef(uid), {
     ...list,
     createdAt: serverTimestamp(),
@@ -348,6 +397,7 @@ export async function addList(uid: string, list: Omit<ListData, 'id' | 'createdA
 
 export async function deleteList(uid: string, listId: string): Promise<void> {
   invalidateCache(uid, 'lists');
+  if (isLocalDbAvailable()) return deleteLocalList(uid, listId);
   await deleteDoc(doc(db, 'users', uid, 'lists', listId));
 }
 
@@ -370,6 +420,7 @@ export async function getFolders(uid: string): Promise<FolderData[]> {
 
 export async function addFolder(uid: string, folder: Omit<FolderData, 'id' | 'createdAt'>): Promise<string> {
   invalidateCache(uid, 'folders');
+  if (isLocalDbAvailable()) return addLocalFolder(uid, folder);
   const docRef = await addDoc(foldersRef(uid), {
     ...folder,
     createdAt: serverTimestamp(),
@@ -379,12 +430,14 @@ export async function addFolder(uid: string, folder: Omit<FolderData, 'id' | 'cr
 
 export async function updateFolder(uid: string, folderId: string, updates: Partial<FolderData>): Promise<void> {
   invalidateCache(uid, 'folders');
+  if (isLocalDbAvailable()) return updateLocalFolder(uid, folderId, updates);
   await updateDoc(doc(db, 'users', uid, 'folders', folderId), { ...updates });
 }
 
 /** 소프트 삭제 — 휴지통으로 이동 */
 export async function deleteFolder(uid: string, folderId: string): Promise<void> {
   invalidateCache(uid, 'folders');
+  if (isLocalDbAvailable()) return updateLocalFolder(uid, folderId, { deleted: true, deletedAt: new Date().toISOString() });
   await updateDoc(doc(db, 'users', uid, 'folders', folderId), {
     deleted: true,
     deletedAt: new Date().toISOString(),
@@ -394,6 +447,7 @@ export async function deleteFolder(uid: string, folderId: string): Promise<void>
 /** 휴지통에서 복원 */
 export async function restoreFolder(uid: string, folderId: string): Promise<void> {
   invalidateCache(uid, 'folders');
+  if (isLocalDbAvailable()) return updateLocalFolder(uid, folderId, { deleted: false, deletedAt: null });
   await updateDoc(doc(db, 'users', uid, 'folders', folderId), {
     deleted: false,
     deletedAt: null,
@@ -403,6 +457,7 @@ export async function restoreFolder(uid: string, folderId: string): Promise<void
 /** 영구 삭제 */
 export async function permanentDeleteFolder(uid: string, folderId: string): Promise<void> {
   invalidateCache(uid, 'folders');
+  if (isLocalDbAvailable()) return deleteLocalFolder(uid, folderId);
   await deleteDoc(doc(db, 'users', uid, 'folders', folderId));
 }
 
@@ -416,6 +471,7 @@ function mindmapsRef(uid: string) {
 
 export async function addMindmap(uid: string, data: Omit<MindMapData, 'id' | 'createdAt' | 'updatedAt'>): Promise<string> {
   invalidateCache(uid, 'mindmaps');
+  if (isLocalDbAvailable()) return addLocalMindmap(uid, data);
   const docRef = await addDoc(mindmapsRef(uid), {
     ...data,
     createdAt: serverTimestamp(),
@@ -425,6 +481,7 @@ export async function addMindmap(uid: string, data: Omit<MindMapData, 'id' | 'cr
 }
 
 export async function updateMindmap(uid: string, id: string, updates: Partial<MindMapData>): Promise<void> {
+  if (isLocalDbAvailable()) return updateLocalMindmap(uid, id, updates);
   await updateDoc(doc(db, 'users', uid, 'mindmaps', id), {
     ...updates,
     updatedAt: serverTimestamp(),
@@ -433,6 +490,7 @@ export async function updateMindmap(uid: string, id: string, updates: Partial<Mi
 
 export async function deleteMindmap(uid: string, id: string): Promise<void> {
   invalidateCache(uid, 'mindmaps');
+  if (isLocalDbAvailable()) return deleteLocalMindmap(uid, id);
   await deleteDoc(doc(db, 'users', uid, 'mindmaps', id));
 }
 
@@ -1,27 +1,60 @@
 import { getFunctions, httpsCallable } from 'firebase/functions';
 import { getFirestore, doc, setDoc } from 'firebase/firestore';
 import { auth } from './firebase';
+import { isTauriRuntime, tokenStoreDelete, tokenStorePut } from './token-store';
 
 // ── 서버 기반 OAuth (Cloud Function) ──────────────────────────────────────────
 // refresh token을 Firestore에 서버에서 저장하므로 영구 연동 가능
 
 const CONNECTED_KEY = 'gcal_connected';
 const TOKEN_SESSION_KEY = 'gcal_access_token';
+/** Tauri 토큰 보관소 키 (Rust OAuth 프로바이더 id와 같음) */
+const VAULT_KEY = 'google-calendar';
+/** Google access token 수명 (실제 1시간, 여유를 둠) */
+const ACCESS_TOKEN_TTL_MS = 55 * 60 * 1000;
 
-/** sessionStorage에 access token 저장 (세션 내 지속) */
+/** access token 저장 — Tauri는 네이티브 보관소, 웹은 sessionStorage (세션 내 지속) */
 export function saveGCalToken(token: string) {
-  if (typeof window !== 'undefined') sessionStorage.setItem(TOKEN_SESSION_KEY, token);
+  if (typeof window === 'undefined') return;
+  if (isTauriRuntime()) {
+    tokenStorePut(VAULT_KEY, { value: token, expiresAt: Date.now() + ACCESS_TOKEN_TTL_MS }).catch(() => {});
+    return;
+  }
+  sessionStorage.setItem(TOKEN_SESSION_KEY, token);
 }
 
-/** sessionStorage에서 access token 조회 */
-export function getStoredGCalToken(): string | null {
+/**
+ * 유효한 access token 조회 (없거나 만료됐으면 null).
+ * Tauri는 만료가 가까우면 네이티브에서 refresh token으로 갱신해 돌려준다
+ */
+export async function getStoredGCalToken(): Promise<string | null> {
   if (typeof window === 'undefined') return null;
+  if (isTauriRuntime()) {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      const token = await invoke<{ accessToken: string; expiresAt: number | null }>('get_valid_gcal_token', {
+        request: {
+          clientId: process.env.NEXT_PUBLIC_GOOGLE_DESKTOP_CLIENT_ID || '',
+          clientSecret: process.env.NEXT_PUBLIC_GOOGLE_DESKTOP_CLIENT_SECRET || null,
+        },
+      });
+      return token.accessToken;
+    } catch {
+      // not_connected / reconnect_required / refresh_failed → 호출 측이 재연결 처리
+      return null;
+    }
+  }
   return sessionStorage.getItem(TOKEN_SESSION_KEY);
 }
 
-/** sessionStorage에서 access token 삭제 */
+/** access token 삭제 */
 export function clearGCalToken() {
-  if (typeof window !== 'undefined') sessionStorage.removeItem(TOKEN_SESSION_KEY);
+  if (typeof window === 'undefined') return;
+  if (isTauriRuntime()) {
+    tokenStoreDelete(VAULT_KEY).catch(() => {});
+    return;
+  }
+  sessionStorage.removeItem(TOKEN_SESSION_KEY);
 }
 
 async function setGCalConnectedFirestore(connected: boolean) {
@@ -167,48 +200,49 @@ export async function disconnectGoogleCalendar(): Promise<void> {
   } catch { /* ignore */ }
 }
 
-// ── Tauri Desktop (기존 방식 유지 — 로컬 OAuth 서버) ─────────────────────────
+// ── Tauri Desktop (로컬 OAuth 서버, PKCE) ─────────────────────────────────────
 
-export async function connectGoogleCalendarDesktop(params: {
-  apiKey: string;
-  authDomain: string;
-  projectId: string;
-}): Promise<string> {
+/** locale: 브라우저에 뜨는 완료/실패 페이지 언어 */
+export async function connectGoogleCalendarDesktop(locale?: string): Promise<string> {
   const { invoke } = await import('@tauri-apps/api/core');
   const { open } = await import('@tauri-apps/plugin-shell');
   const { listen } = await import('@tauri-apps/api/event');
 
-  const port = await invoke<number>('start_gcal_oauth_server');
+  const session = await invoke<{ id: string; port: number }>('start_oauth', {
+    request: {
+      provider: 'google-calendar',
+      clientId: process.env.NEXT_PUBLIC_GOOGLE_DESKTOP_CLIENT_ID || '',
+      clientSecret: process.env.NEXT_PUBLIC_GOOGLE_DESKTOP_CLIENT_SECRET || null,
+      locale: locale ?? null,
+    },
+  });
 
   return new Promise<string>((resolve, reject) => {
-    const url = new URL(`http://localhost:${port}/`);
-    url.searchParams.set('apiKey', params.apiKey);
-    url.searchParams.set('authDomain', params.authDomain);
-    url.searchParams.set('projectId', params.projectId);
 
-    let unlisten: (() => void) | null = null;
+    const unlisteners: (() => void)[] = [];
+    const cleanup = () => unlisteners.forEach((fn) => fn());
 
-    const timeout = setTimeout(() => {
-      if (unlisten) unlisten();
+    // 만료는 Rust 세션이 관리한다 (oauth-timeout)
+    listen<{ sessionId: string }>('oauth-timeout', (event) => {
+      if (event.payload.sessionId !== session.id) return;
+      cleanup();
       reject(new Error('timeout'));
-    }, 120000);
-
-    listen<string>('gcal-oauth-callback', (event) => {
-      clearTimeout(timeout);
-      if (unlisten) unlisten();
-      try {
-        const data = JSON.parse(event.payload);
-        const token = data.accessToken;
-        if (!token) { reject(new Error('no_token')); return; }
-        markGCalConnected(true);
-        setGCalConnectedFirestore(true);
-        resolve(token);
-      } catch {
-        reject(new Error('parse_failed'));
-      }
-    }).then(fn => { unlisten = fn; });
-
-    open(url.toString());
+    }).then(fn => { unlisteners.push(fn); });
+
+    listen<{ accessToken: string }>('gcal-oauth-callback', (event) => {
+      cleanup();
+      markGCalConnected(true);
+      setGCalConnectedFirestore(true);
+      resolve(event.payload.accessToken);
+    }).then(fn => { unlisteners.push(fn); });
+
+    listen<{ provider: string; reason: string; message: string }>('oauth-error', (event) => {
+      if (event.payload.provider !== 'google-calendar') return;
+      cleanup();
+      reject(new Error(event.payload.reason));
+    }).then(fn => { unlisteners.push(fn); });
+
+    open(`http://127.0.0.1:${session.port}/login`);
   });
 }
 
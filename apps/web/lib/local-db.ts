@@ -0,0 +1,130 @@
+// Tauri 로컬 DB (SQLite) — lib/firestore.ts와 같은 모양의 CRUD.
+// 로컬에서는 createdAt/updatedAt을 epoch ms로 저장하므로 주고받을 때 Timestamp로 바꾼다.
+import { Timestamp } from 'firebase/firestore';
+import { isTauriRuntime } from './token-store';
+import type { TaskData, NoteData, NoteBlock, ListData, FolderData, MindMapData } from './firestore';
+
+export type LocalCollection = 'tasks' | 'notes' | 'lists' | 'folders' | 'mindmaps';
+
+type Stored<T> = Omit<T, 'createdAt' | 'updatedAt'> & { createdAt?: number; updatedAt?: number };
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+function toMillis(value: unknown): number | undefined {
+  if (typeof value === 'number') return value;
+  if (value && typeof (value as Timestamp).toMillis === 'function') return (value as Timestamp).toMillis();
+  return undefined; // 대기 중인 serverTimestamp() 등
+}
+
+function toStored<T extends object>(doc: T): Stored<T> {
+  const { createdAt, updatedAt, ...rest } = doc as T & { createdAt?: unknown; updatedAt?: unknown };
+  return { ...rest, createdAt: toMillis(createdAt), updatedAt: toMillis(updatedAt) } as Stored<T>;
+}
+
+function fromStored<T>(doc: Stored<T>): T {
+  const { createdAt, updatedAt, ...rest } = doc;
+  return {
+    ...rest,
+    ...(createdAt != null ? { createdAt: Timestamp.fromMillis(createdAt) } : {}),
+    ...(updatedAt != null ? { updatedAt: Timestamp.fromMillis(updatedAt) } : {}),
+  } as T;
+}
+
+async function list<T>(cmd: string, uid: string): Promise<T[]> {
+  const docs = await invoke<Stored<T>[]>(cmd, { uid });
+  return docs.map(fromStored);
+}
+
+export const isLocalDbAvailable = isTauriRuntime;
+
+// Tasks
+export const getLocalTasks = (uid: string) => list<TaskData>('get_tasks', uid);
+export const getLocalMyDayTasks = (uid: string) => list<TaskData>('get_my_day_tasks', uid);
+export const addLocalTask = (uid: string, task: Omit<TaskData, 'createdAt' | 'updatedAt'>) =>
+  invoke<string>('add_task', { uid, task: toStored(task) });
+export const updateLocalTask = (uid: string, taskId: string, updates: Partial<TaskData>) =>
+  invoke<void>('update_task', { uid, taskId, updates: toStored(updates) });
+export const deleteLocalTask = (uid: string, taskId: string) => invoke<void>('delete_task', { uid, taskId });
+
+// Notes
+export const getLocalNotes = (uid: string) => list<NoteData>('get_notes', uid);
+export const addLocalNote = (uid: string, note: Omit<NoteData, 'createdAt' | 'updatedAt'>) =>
+  invoke<string>('add_note', { uid, note: toStored(note) });
+export const updateLocalNote = (uid: string, noteId: string, updates: Partial<NoteData>) =>
+  invoke<void>('update_note', { uid, noteId, updates: toStored(updates) });
+export const deleteLocalNote = (uid: string, noteId: string) => invoke<void>('delete_note', { uid, noteId });
+
+// Lists
+export const getLocalLists = (uid: string) => list<ListData>('get_lists', uid);
+export const addLocalList = (uid: string, list: Omit<ListData, 'createdAt'>) =>
+  invoke<string>('add_list', { uid, list: toStored(list) });
+export const updateLocalList = (uid: string, listId: string, updates: Partial<ListData>) =>
+  invoke<void>('update_list', { uid, listId, updates: toStored(updates) });
+export const deleteLocalList = (uid: string, listId: string) => invoke<void>('delete_list', { uid, listId });
+
+// Folders
+export const getLocalFolders = (uid: string) => list<FolderData>('get_folders', uid);
+export const addLocalFolder = (uid: string, folder: Omit<FolderData, 'createdAt'>) =>
+  invoke<string>('add_folder', { uid, folder: toStored(folder) });
+export const updateLocalFolder = (uid: string, folderId: string, updates: Partial<FolderData>) =>
+  invoke<void>('update_folder', { uid, folderId, updates: toStored(updates) });
+export const deleteLocalFolder = (uid: string, folderId: string) => invoke<void>('delete_folder', { uid, folderId });
+
+// Mind Maps
+export const getLocalMindmaps = (uid: string) => list<MindMapData>('get_mindmaps', uid);
+export const addLocalMindmap = (uid: string, data: Omit<MindMapData, 'createdAt' | 'updatedAt'>) =>
+  invoke<string>('add_mindmap', { uid, data: toStored(data) });
+export const updateLocalMindmap = (uid: string, id: string, updates: Partial<MindMapData>) =>
+  invoke<void>('update_mindmap', { uid, id, updates: toStored(updates) });
+export const deleteLocalMindmap = (uid: string, id: string) => invoke<void>('delete_mindmap', { uid, id });
+
+// 전문 검색 (할 일 제목/메모/태그, 노트 제목/블록, 마인드맵 제목/노드)
+export interface SearchHit {
+  collection: 'tasks' | 'notes' | 'mindmaps';
+  id: string;
+  title: string;
+  field: 'title' | 'memo' | 'tags' | 'block' | 'node';
+  /** block이면 블록 id, node면 노드 id */
+  subId: string | null;
+  snippet: { text: string; highlight: boolean }[];
+  score: number;
+  /** 오타를 허용해 찾은 결과 */
+  fuzzy: boolean;
+}
+
+export const searchEverything = (uid: string, query: string, limit?: number) =>
+  invoke<SearchHit[]>('search_everything', { uid, query, limit });
+
+// 동시 편집 충돌 (노트 블록)
+export type ConflictKind = 'both_edited' | 'deleted_locally' | 'deleted_remotely';
+
+export interface NoteBlockConflict {
+  id: string;
+  noteId: string;
+  blockId: string;
+  kind: ConflictKind;
+  base: NoteBlock | null;
+  local: NoteBlock | null;
+  remote: NoteBlock | null;
+  createdAt: number;
+}
+
+/** custom의 block이 null이면 블록을 지운다 */
+export type ConflictResolution =
+  | { choice: 'local' }
+  | { choice: 'remote' }
+  | { choice: 'custom'; block: NoteBlock | null };
+
+export const listConflicts = (uid: string) => invoke<NoteBlockConflict[]>('list_conflicts', { uid });
+export async function resolveConflict(uid: string, conflictId: string, resolution: ConflictResolution): Promise<NoteData | null> {
+  const note = await invoke<Stored<NoteData> | null>('resolve_conflict', { uid, conflictId, resolution });
+  return note ? fromStored(note) : null;
+}
+
+/** Firestore 스냅샷을 로컬에 통째로 반영 (스키마에 맞지 않는 문서는 건너뜀) */
+export function mirrorSnapshot<T extends { id?: string }>(uid: string, collection: LocalCollection, docs: T[]): Promise<void> {
+  return invoke('local_db_replace', { uid, collection, docs: docs.map(toStored) });
+}
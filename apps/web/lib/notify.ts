@@ -0,0 +1,60 @@
+// 알림 보내기 — 데스크톱은 Rust `notify` 명령을 거쳐 방해 금지 시간/묶기/빈도 제한이 적용된다.
+// 웹에서는 브라우저 Notification API (권한이 있을 때만).
+import { isTauriRuntime } from './token-store';
+
+export type NotificationSource = 'reminder' | 'timebox' | 'pomodoro' | 'calendar' | 'agenda' | 'backup' | 'other';
+
+export type NotifyOutcome =
+  | { status: 'queued' }
+  | { status: 'deferred'; until: number }
+  | { status: 'suppressed' };
+
+export type Weekday = 'mon' | 'tue' | 'wed' | 'thu' | 'fri' | 'sat' | 'sun';
+
+export interface QuietHours {
+  days: Weekday[];
+  /** "HH:MM". end가 start보다 이르면 다음 날 end까지, 같으면 하루 종일 */
+  start: string;
+  end: string;
+}
+
+export interface NotificationPolicy {
+  quietHours: QuietHours[];
+  rateLimits: Partial<Record<NotificationSource, { max: number; perMinutes: number }>>;
+  collapseWindowMs: number;
+}
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+/** 보여 줬으면(또는 Rust가 받았으면) 결과, 보낼 수 없었으면 null */
+export async function notify(source: NotificationSource, title: string, body = ''): Promise<NotifyOutcome | null> {
+  if (typeof window === 'undefined') return null;
+  if (isTauriRuntime()) {
+    try {
+      return await invoke<NotifyOutcome>('notify', { source, title, body });
+    } catch (e) {
+      console.warn('[notify]', e);
+      return null;
+    }
+  }
+  if ('Notification' in window && Notification.permission === 'granted') {
+    try {
+      new Notification(title, { body, tag: title });
+      return { status: 'queued' };
+    } catch {
+      // 모바일 브라우저 등
+    }
+  }
+  return null;
+}
+
+export function getNotificationPolicy(): Promise<NotificationPolicy> {
+  return invoke('get_notification_policy');
+}
+
+export function setNotificationPolicy(settings: NotificationPolicy): Promise<void> {
+  return invoke('set_notification_policy', { settings });
+}
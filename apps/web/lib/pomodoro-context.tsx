@@ -41,6 +41,8 @@ interface PomodoroContextValue {
   sessions: number;
   settings: PomodoroSettings;
   toggle: () => void;
+  /** 집중 단계를 처음부터 시작. workMinutes는 이번 세션에만 적용 (설정은 그대로) */
+  start: (workMinutes?: number) => void;
   reset: () => void;
   skip: () => void;
   switchPhase: (p: PomodoroPhase) => void;
@@ -160,6 +162,12 @@ export function PomodoroProvider({ children }: { children: ReactNode }) {
 
   const toggle = useCallback(() => setIsRunning((v) => !v), []);
 
+  const start = useCallback((workMinutes?: number) => {
+    setPhase('work');
+    setSecondsLeft(workMinutes ? workMinutes * 60 : getPhaseSeconds('work', settingsRef.current));
+    setIsRunning(true);
+  }, [getPhaseSeconds]);
+
   const reset = useCallback(() => {
     setIsRunning(false);
     setSecondsLeft(getPhaseSeconds(phaseRef.current, settingsRef.current));
@@ -201,7 +209,7 @@ export function PomodoroProvider({ children }: { children: ReactNode }) {
   return (
     <PomodoroContext.Provider value={{
       phase, secondsLeft, isRunning, sessions, settings,
-      toggle, reset, skip, switchPhase, updateSettings,
+      toggle, start, reset, skip, switchPhase, updateSettings,
     }}>
       {children}
     </PomodoroContext.Provider>
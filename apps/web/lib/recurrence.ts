@@ -0,0 +1,91 @@
+// 반복 규칙 — 데스크톱에서 Rust가 RFC 5545 RRULE(+EXDATE/RDATE)을 시간대/서머타임까지 맞춰 펼친다.
+// 회차는 하루 종일이면 'YYYY-MM-DD', 시간대가 없으면 오프셋 없는 ISO, 있으면 RFC 3339 문자열.
+
+import type { OccurrenceOverride, TaskData } from './firestore';
+
+export interface RecurrenceRange {
+  /** 포함 */
+  from: string;
+  /** 제외 */
+  to: string;
+  /** 기본 1000, 최대 10000 */
+  limit?: number;
+}
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+function localTimeZone(): string | null {
+  try {
+    return Intl.DateTimeFormat().resolvedOptions().timeZone ?? null;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * @param rule 'FREQ=MONTHLY;BYDAY=-1FR' 또는 'RRULE:...\nEXDATE:...' 줄들
+ * @param start DTSTART ('2026-03-10', '2026-03-10T09:00:00', '20260310T090000' ...)
+ * @param tz IANA 시간대. 생략하면 이 기기의 시간대, null이면 시간대 없이
+ */
+export function expandRecurrence(
+  rule: string,
+  start: string,
+  range: RecurrenceRange,
+  tz: string | null = localTimeZone(),
+): Promise<string[]> {
+  return invoke('expand_recurrence', { rule, start, tz, range });
+}
+
+/** `after`(생략하면 지금) 다음 회차. 끝난 반복이면 null */
+export function nextOccurrence(
+  rule: string,
+  start: string,
+  after?: string,
+  tz: string | null = localTimeZone(),
+): Promise<string | null> {
+  return invoke('next_occurrence', { rule, start, tz, after: after ?? null });
+}
+
+/** 예외를 적용한 회차. `recurrenceId`는 원래 회차, `start`는 옮겼으면 옮긴 날 */
+export interface RecurrenceInstance {
+  recurrenceId: string;
+  start: string;
+  title?: string;
+  priority?: TaskData['priority'];
+}
+
+export type OverrideOutcome =
+  | { kind: 'recorded'; task: TaskData }
+  | { kind: 'skipped'; next: TaskData | null }
+  | { kind: 'updated'; task: TaskData };
+
+/** 로컬 DB의 반복 할 일 회차 (건너뛴 회차 빼고, 옮긴 회차는 옮긴 날에) */
+export function expandTaskOccurrences(
+  uid: string,
+  taskId: string,
+  range: RecurrenceRange,
+): Promise<RecurrenceInstance[]> {
+  return invoke('expand_task_occurrences', { uid, taskId, range });
+}
+
+/**
+ * 반복 할 일의 한 회차만 건너뛰거나 옮기거나 이름을 바꾼다. `value`가 null이면 예외를 지운다.
+ * 이 할 일 자신의 회차를 건너뛰면 할 일은 지워지고 다음 회차가 생긴다.
+ * @param recurrenceId 회차의 원래 날짜 (YYYY-MM-DD)
+ */
+export function overrideOccurrence(
+  uid: string,
+  taskId: string,
+  recurrenceId: string,
+  value: OccurrenceOverride | null,
+): Promise<OverrideOutcome> {
+  return invoke('override_occurrence', { uid, taskId, recurrenceId, value });
+}
+
+/** "이번만 건너뛰기" */
+export function skipOccurrence(uid: string, taskId: string, recurrenceId: string): Promise<OverrideOutcome> {
+  return overrideOccurrence(uid, taskId, recurrenceId, { skipped: true });
+}
@@ -0,0 +1,115 @@
+// 데스크톱 알림 예약 — Rust가 로컬 DB에 예약해 두고 때가 되면 시스템 알림을 띄운다.
+// 울린 알림은 `reminder-fired` 이벤트로도 온다 (missed: 잠자기/앱 종료로 늦게 울림).
+
+export interface ReminderPayload {
+  title: string;
+  body: string;
+}
+
+export interface Reminder {
+  taskId: string;
+  uid: string | null;
+  /** epoch ms */
+  fireAt: number;
+  payload: ReminderPayload;
+  createdAt: number;
+}
+
+export interface FiredReminder extends Reminder {
+  firedAt: number;
+  missed: boolean;
+}
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+/** 할 일 하나에 알림 하나. 다시 예약하면 덮어쓴다 */
+export function scheduleReminder(
+  taskId: string,
+  fireAt: number,
+  payload: ReminderPayload,
+  uid: string | null = null,
+): Promise<Reminder> {
+  return invoke('schedule_reminder', { taskId, fireAt, payload, uid });
+}
+
+export function cancelReminder(taskId: string): Promise<boolean> {
+  return invoke('cancel_reminder', { taskId });
+}
+
+export function listReminders(): Promise<Reminder[]> {
+  return invoke('list_reminders');
+}
+
+export function subscribeFiredReminders(handler: (fired: FiredReminder[]) => void): () => void {
+  const unlisten = import('@tauri-apps/api/event').then(({ listen }) =>
+    listen<FiredReminder[]>('reminder-fired', (e) => handler(e.payload)),
+  );
+  return () => { unlisten.then((u) => u()); };
+}
+
+export type ReminderAction = 'complete' | 'snooze_10m' | 'snooze_tomorrow' | 'open';
+
+/** Rust `notification_actions::ReminderAction`와 같은 순서/문구 */
+const REMINDER_ACTIONS: { id: ReminderAction; title: string }[] = [
+  { id: 'complete', title: '완료' },
+  { id: 'snooze_10m', title: '10분 뒤' },
+  { id: 'snooze_tomorrow', title: '내일 다시' },
+  { id: 'open', title: '열기' },
+];
+
+export interface NotificationActionEvent {
+  action: ReminderAction;
+  taskId: string;
+  uid: string | null;
+  snoozedUntil: number | null;
+  completed: boolean;
+}
+
+/** 알림 버튼 처리 (완료/미루기는 창 없이 Rust가 저장, 열기는 `deep-link` openTask로 온다) */
+export function runReminderAction(action: ReminderAction, reminder: Reminder): Promise<NotificationActionEvent> {
+  return invoke('notification_action', { action, reminder });
+}
+
+/**
+ * 모바일: `task-reminder` 버튼을 알림 플러그인에 등록하고 눌린 버튼을 Rust로 넘긴다 (링크 알림은 그 링크를 연다).
+ * 데스크톱은 Rust가 직접 처리하므로 등록이 실패해도 무시한다. 반환값으로 구독 해제
+ */
+export function startReminderActions(): () => void {
+  let stopped = false;
+  let unregister: (() => void) | null = null;
+  (async () => {
+    const { registerActionTypes, onAction } = await import('@tauri-apps/plugin-notification');
+    try {
+      await registerActionTypes([{ id: 'task-reminder', actions: REMINDER_ACTIONS.map((a) => ({ ...a, foreground: a.id === 'open' })) }]);
+    } catch {
+      return; // 데스크톱
+    }
+    const listener = await onAction((notification) => {
+      const { actionId, extra } = notification as typeof notification & { actionId?: string };
+      const reminder = extra?.reminder as Reminder | undefined;
+      const link = extra?.link as string | undefined;
+      if (link) {
+        invoke('open_notification_link', { url: link }).catch((e) => console.warn('[reminders] link', e));
+        return;
+      }
+      const action = REMINDER_ACTIONS.find((a) => a.id === actionId)?.id ?? 'open';
+      if (reminder) runReminderAction(action, reminder).catch((e) => console.warn('[reminders] action', e));
+    });
+    if (stopped) listener.unregister();
+    else unregister = () => listener.unregister();
+  })().catch((e) => console.warn('[reminders] actions', e));
+  return () => {
+    stopped = true;
+    unregister?.();
+  };
+}
+
+export function subscribeNotificationActions(handler: (event: NotificationActionEvent) => void): () => void {
+  const unlisten = import('@tauri-apps/api/event').then(({ listen }) =>
+    listen<NotificationActionEvent>('notification-action', (e) => handler(e.payload)),
+  );
+  return () => { unlisten.then((u) => u()); };
+}
@@ -0,0 +1,83 @@
+// 데스크톱 동기화 — 로컬 DB의 변경(outbox)을 Rust가 Firestore REST로 내보낸다.
+// 웹뷰는 ID 토큰만 넘겨주고, 진행 상황은 `sync-status` 이벤트로 받는다.
+import { onIdTokenChanged } from 'firebase/auth';
+import { auth, db } from './firebase';
+import { isTauriRuntime } from './token-store';
+import type { LocalCollection } from './local-db';
+
+export type SyncState = 'signed_out' | 'idle' | 'syncing' | 'backoff' | 'auth_required';
+
+export interface SyncStatus {
+  state: SyncState;
+  pending: number;
+  failed: number;
+  lastError: string | null;
+  lastSyncedAt: number | null;
+  nextRetryAt: number | null;
+}
+
+export interface LocalDbChangedEvent {
+  uid: string;
+  collection: LocalCollection;
+}
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+async function listen<T>(event: string, handler: (payload: T) => void): Promise<() => void> {
+  const { listen } = await import('@tauri-apps/api/event');
+  return listen<T>(event, (e) => handler(e.payload));
+}
+
+/**
+ * 로그인 상태/ID 토큰을 Rust 동기화에 계속 넘겨준다. 토큰이 거부되면 강제로 새로 받아 다시 넘긴다.
+ * 반환값으로 구독 해제.
+ */
+export function startNativeSync(): () => void {
+  if (!isTauriRuntime()) return () => {};
+  const projectId = db.app.options.projectId ?? '';
+
+  const push = async (force = false) => {
+    const user = auth.currentUser;
+    const credentials = user ? { uid: user.uid, idToken: await user.getIdToken(force), projectId } : null;
+    await invoke('sync_set_credentials', { credentials });
+  };
+
+  const unsubToken = onIdTokenChanged(auth, () => {
+    push().catch((e) => console.warn('[sync] credentials', e));
+  });
+  // 새 토큰도 거부되는 경우(프로젝트 설정 오류 등) 무한 반복하지 않도록 1분에 한 번만
+  let lastForcedAt = 0;
+  const unlisten = listen<SyncStatus>('sync-status', (status) => {
+    if (status.state === 'auth_required' && auth.currentUser && Date.now() - lastForcedAt > 60_000) {
+      lastForcedAt = Date.now();
+      push(true).catch((e) => console.warn('[sync] token refresh', e));
+    }
+  });
+
+  return () => {
+    unsubToken();
+    unlisten.then((u) => u());
+  };
+}
+
+export function getSyncStatus(): Promise<SyncStatus> {
+  return invoke('sync_status');
+}
+
+/** 백오프를 건너뛰고 바로 동기화. retryFailed면 거부됐던 변경도 다시 보낸다 */
+export function retrySync(retryFailed = false): Promise<void> {
+  return invoke('sync_retry', { retryFailed });
+}
+
+export function subscribeSyncStatus(handler: (status: SyncStatus) => void): () => void {
+  const unlisten = listen<SyncStatus>('sync-status', handler);
+  return () => { unlisten.then((u) => u()); };
+}
+
+export function subscribeLocalDbChanges(handler: (event: LocalDbChangedEvent) => void): () => void {
+  const unlisten = listen<LocalDbChangedEvent>('local-db-changed', handler);
+  return () => { unlisten.then((u) => u()); };
+}
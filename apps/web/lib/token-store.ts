@@ -0,0 +1,86 @@
+// Tauri 네이티브 토큰 보관소 (암호화 파일) — 웹뷰 저장소에 토큰을 두지 않는다
+import type { Persistence } from 'firebase/auth';
+
+export interface TokenEntry {
+  value: string;
+  /** 저장할 때만 사용. 조회 결과에는 담기지 않는다 */
+  refreshToken?: string | null;
+  /** unix ms */
+  expiresAt?: number | null;
+  scope?: string | null;
+  updatedAt?: number;
+}
+
+export interface TokenInfo {
+  key: string;
+  expiresAt: number | null;
+  hasRefreshToken: boolean;
+  expired: boolean;
+  updatedAt: number;
+}
+
+export function isTauriRuntime(): boolean {
+  return typeof window !== 'undefined' && ('__TAURI__' in window || '__TAURI_INTERNALS__' in window);
+}
+
+async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<T>(cmd, args);
+}
+
+export function tokenStorePut(key: string, entry: TokenEntry): Promise<void> {
+  return invoke('token_store_put', { key, entry });
+}
+
+/** 만료됐거나 minTtlSecs(기본 60초)보다 적게 남았으면 null */
+export function tokenStoreGet(key: string, minTtlSecs?: number): Promise<TokenEntry | null> {
+  return invoke('token_store_get', { key, minTtlSecs: minTtlSecs ?? null });
+}
+
+export function tokenStoreDelete(key: string): Promise<boolean> {
+  return invoke('token_store_delete', { key });
+}
+
+export function tokenStoreList(): Promise<TokenInfo[]> {
+  return invoke('token_store_list');
+}
+
+type StorageEventListener = (value: unknown) => void;
+
+/**
+ * Firebase Auth 세션(refreshToken 포함)을 localStorage 대신 보관소에 저장하는 persistence.
+ * Firebase의 내부 persistence 인터페이스(_get/_set/_remove)를 구현한다.
+ */
+class TauriVaultPersistence {
+  static type = 'LOCAL' as const;
+  readonly type = 'LOCAL' as const;
+
+  async _isAvailable(): Promise<boolean> {
+    return isTauriRuntime();
+  }
+
+  async _set(key: string, value: unknown): Promise<void> {
+    await tokenStorePut(key, { value: JSON.stringify(value) });
+  }
+
+  async _get<T>(key: string): Promise<T | null> {
+    const entry = await tokenStoreGet(key, 0);
+    if (!entry) return null;
+    try {
+      return JSON.parse(entry.value) as T;
+    } catch {
+      return null;
+    }
+  }
+
+  async _remove(key: string): Promise<void> {
+    await tokenStoreDelete(key);
+  }
+
+  // 창이 하나뿐이라 다른 탭의 변경을 감지할 필요가 없다
+  _addListener(_key: string, _listener: StorageEventListener): void {}
+
+  _removeListener(_key: string, _listener: StorageEventListener): void {}
+}
+
+export const tauriVaultPersistence = TauriVaultPersistence as unknown as Persistence;
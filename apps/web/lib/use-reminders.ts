@@ -2,6 +2,9 @@
 
 import { useEffect, useRef } from 'react';
 import { TaskData } from './firestore';
+import { auth } from './firebase';
+import { notify } from './notify';
+import { cancelReminder, scheduleReminder } from './reminders';
 
 const MAX_TIMEOUT = 2_147_483_647; // ~24.8일 (setTimeout 최대 안전 값)
 
@@ -44,36 +47,10 @@ function showInAppAlert(title: string, body: string) {
 async function fireNotification(title: string, body: string) {
   if (typeof window === 'undefined') return;
 
-  // 1) Tauri 네이티브 알림 (Windows / macOS / Android / iOS 모두 지원)
-  if (isTauriEnv()) {
-    try {
-      const { isPermissionGranted, requestPermission, sendNotification } =
-        await import('@tauri-apps/plugin-notification');
-      let granted = await isPermissionGranted();
-      if (!granted) {
-        const perm = await requestPermission();
-        granted = perm === 'granted';
-      }
-      if (granted) {
-        await sendNotification({ title, body });
-        return;
-      }
-    } catch {
-      // plugin-notification 사용 불가 시 다음 단계로 fall-through
-    }
-  }
-
-  // 2) 브라우저 Notification API (웹 전용 fallback)
-  if ('Notification' in window && Notification.permission === 'granted') {
-    try {
-      new Notification(title, { body, tag: title });
-      return;
-    } catch {
-      // fall-through
-    }
-  }
+  // 1) 데스크톱은 Rust 알림 정책을, 웹은 브라우저 Notification API를 거친다
+  if (await notify('reminder', title, body)) return;
 
-  // 3) 인앱 알림 (최후 fallback)
+  // 2) 인앱 알림 (최후 fallback)
   showInAppAlert(title, body);
 }
 
@@ -86,13 +63,32 @@ export function useTaskReminders(tasks: TaskData[]) {
   useEffect(() => {
     if (typeof window === 'undefined') return;
 
+    // 데스크톱: Rust 예약 큐에 맡긴다 (창을 닫거나 자는 동안 지난 알림도 나중에 울린다)
+    if (isTauriEnv()) {
+      const now = Date.now();
+      const uid = auth.currentUser?.uid ?? null;
+      for (const task of tasks) {
+        if (!task.id) continue;
+        // 알림을 지웠거나 완료한 할 일은 큐에서 뺀다 (지운 할 일은 Rust가 지울 때 뺀다)
+        const fireAt = task.reminder && task.status !== 'completed' ? new Date(task.reminder).getTime() : NaN;
+        // 이미 지난 알림은 울렸거나 큐에 남아 있으므로 다시 넣지 않는다
+        const request = Number.isNaN(fireAt)
+          ? cancelReminder(task.id)
+          : fireAt > now
+            ? scheduleReminder(task.id, fireAt, { title: 'AI Todo 알림', body: `⏰ ${task.title}` }, uid)
+            : null;
+        request?.catch((e) => console.warn('[reminders]', e));
+      }
+      return;
+    }
+
     timers.current.forEach(clearTimeout);
     timers.current.clear();
 
     const now = Date.now();
 
     for (const task of tasks) {
-      if (!task.reminder || !task.id) continue;
+      if (!task.reminder || !task.id || task.status === 'completed') continue;
       const fireAt = new Date(task.reminder).getTime();
       const delay = fireAt - now;
       if (delay <= 0 || delay > MAX_TIMEOUT) continue;
@@ -0,0 +1,413 @@
+//! 아침 일정 요약 (agenda digest).
+//!
+//! 정한 시각(기본 08:00)에 로컬 DB에서 오늘 할 일(지난 마감, 오늘 마감, My Day, 타임박스)을 모아 알림 하나로 보낸다.
+//! 알림을 누르면 `noah://my-day`로 My Day를 연다. 하루에 한 번만 보내고(보낸 날은 `meta`의 `agenda.last_sent`),
+//! 로그인 시 자동 실행처럼 정한 시각보다 늦게 켜져도 `CATCH_UP_HOURS` 안이면 켜자마자 보낸다.
+//! 누구의 할 일인지는 마지막으로 로그인한 계정(`agenda.uid`)이라 웹뷰가 로그인하기 전에도 보낼 수 있다.
+//! 타임박스는 로컬 DB에 미러가 없어서 웹뷰가 읽고 쓸 때 `timeboxes`에 넣어 둔 것을 쓴다.
+
+use crate::models::{TaskData, TaskStatus};
+use crate::storage::{LocalDb, StorageError};
+use crate::token_store::write_atomic;
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDate, NaiveTime, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// 정한 시각에서 이만큼 지나도록 못 보냈으면 그날은 건너뛴다
+const CATCH_UP_HOURS: i64 = 4;
+/// 잠자기/시계 변경을 이 안에 알아챈다
+const MAX_WAIT: Duration = Duration::from_secs(10 * 60);
+/// 본문에 적는 항목 수
+const BODY_ITEMS: usize = 4;
+pub const MY_DAY_LINK: &str = "noah://my-day";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgendaTask {
+    pub id: String,
+    pub title: String,
+    pub due_date: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeboxBlock {
+    /// "HH:MM"
+    pub time: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Agenda {
+    /// YYYY-MM-DD
+    pub date: String,
+    pub overdue: Vec<AgendaTask>,
+    pub due_today: Vec<AgendaTask>,
+    /// 위 둘에 없는 My Day 할 일
+    pub my_day: Vec<AgendaTask>,
+    pub timebox: Vec<TimeboxBlock>,
+}
+
+impl Agenda {
+    pub fn is_empty(&self) -> bool {
+        self.overdue.is_empty() && self.due_today.is_empty() && self.my_day.is_empty() && self.timebox.is_empty()
+    }
+
+    pub fn title(&self) -> String {
+        "오늘의 일정".to_string()
+    }
+
+    /// 개수 한 줄 + 타임박스, 지난 마감, 오늘 마감, My Day 순으로 몇 개
+    pub fn body(&self) -> String {
+        let counts = [
+            ("지난 마감", self.overdue.len()),
+            ("오늘 마감", self.due_today.len()),
+            ("My Day", self.my_day.len()),
+            ("타임박스", self.timebox.len()),
+        ];
+        let summary: Vec<String> =
+            counts.iter().filter(|(_, n)| *n > 0).map(|(label, n)| format!("{} {}", label, n)).collect();
+        let items: Vec<String> = self
+            .timebox
+            .iter()
+            .map(|b| format!("{} {}", b.time, b.text))
+            .chain(self.overdue.iter().map(|t| format!("⚠ {}", t.title)))
+            .chain(self.due_today.iter().chain(&self.my_day).map(|t| format!("• {}", t.title)))
+            .collect();
+        let mut lines = vec![summary.join(" · ")];
+        lines.extend(items.iter().take(BODY_ITEMS).cloned());
+        if items.len() > BODY_ITEMS {
+            lines.push(format!("외 {}개", items.len() - BODY_ITEMS));
+        }
+        lines.join("\n")
+    }
+}
+
+/// `dueDate`는 YYYY-MM-DD 또는 ISO 시각. 시각이면 (보통 UTC) `tz` 기준 날짜로 바꾼다
+fn due_date<Tz: TimeZone>(task: &TaskData, tz: &Tz) -> Option<NaiveDate> {
+    let due = task.due_date.as_deref()?;
+    if let Ok(at) = DateTime::parse_from_rfc3339(due) {
+        return Some(at.with_timezone(tz).date_naive());
+    }
+    NaiveDate::parse_from_str(due.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn agenda_task(task: &TaskData) -> AgendaTask {
+    AgendaTask { id: task.id.clone().unwrap_or_default(), title: task.title.clone(), due_date: task.due_date.clone() }
+}
+
+pub fn build<Tz: TimeZone>(db: &LocalDb, uid: &str, today: NaiveDate, tz: &Tz) -> Result<Agenda, StorageError> {
+    let mut tasks: Vec<TaskData> =
+        db.list::<TaskData>(uid)?.into_iter().filter(|t| t.status != TaskStatus::Completed).collect();
+    tasks.sort_by(|a, b| {
+        due_date(a, tz).cmp(&due_date(b, tz)).then(a.order.partial_cmp(&b.order).unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    let mut agenda = Agenda {
+        date: today.format("%Y-%m-%d").to_string(),
+        overdue: Vec::new(),
+        due_today: Vec::new(),
+        my_day: Vec::new(),
+        timebox: Vec::new(),
+    };
+    for task in &tasks {
+        match due_date(task, tz) {
+            Some(due) if due < today => agenda.overdue.push(agenda_task(task)),
+            Some(due) if due == today => agenda.due_today.push(agenda_task(task)),
+            _ if task.my_day => agenda.my_day.push(agenda_task(task)),
+            _ => {}
+        }
+    }
+    agenda.timebox = timebox(&db.conn(), uid, &agenda.date)?
+        .into_iter()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(time, text)| TimeboxBlock { time, text: text.trim().to_string() })
+        .collect();
+    Ok(agenda)
+}
+
+/// 웹뷰가 읽거나 저장한 타임박스 (`"HH:MM"` → 내용)
+pub(crate) fn put_timebox(
+    conn: &Connection,
+    uid: &str,
+    date: &str,
+    slots: &BTreeMap<String, String>,
+) -> Result<(), StorageError> {
+    if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+        return Err(StorageError::Invalid(format!("timebox date must be YYYY-MM-DD: {}", date)));
+    }
+    let slots = serde_json::to_string(slots).expect("timebox slots serialize");
+    conn.prepare_cached(
+        "INSERT INTO timeboxes (uid, date, slots) VALUES (?1, ?2, ?3)
+         ON CONFLICT (uid, date) DO UPDATE SET slots = ?3",
+    )?
+    .execute(params![uid, date, slots])?;
+    // 지난 날짜는 필요 없다
+    conn.prepare_cached("DELETE FROM timeboxes WHERE uid = ?1 AND date < date(?2, '-7 days')")?
+        .execute(params![uid, date])?;
+    Ok(())
+}
+
+fn timebox(conn: &Connection, uid: &str, date: &str) -> Result<BTreeMap<String, String>, StorageError> {
+    let slots: Option<String> = conn
+        .prepare_cached("SELECT slots FROM timeboxes WHERE uid = ?1 AND date = ?2")?
+        .query_row([uid, date], |row| row.get(0))
+        .optional()?;
+    Ok(slots.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default())
+}
+
+fn meta_get(conn: &Connection, key: &str) -> Result<Option<String>, StorageError> {
+    Ok(conn.prepare_cached("SELECT value FROM meta WHERE key = ?1")?.query_row([key], |row| row.get(0)).optional()?)
+}
+
+fn meta_set(conn: &Connection, key: &str, value: Option<&str>) -> Result<(), StorageError> {
+    match value {
+        Some(value) => conn
+            .prepare_cached("INSERT INTO meta (key, value) VALUES (?1, ?2) ON CONFLICT (key) DO UPDATE SET value = ?2")?
+            .execute([key, value])?,
+        None => conn.prepare_cached("DELETE FROM meta WHERE key = ?1")?.execute([key])?,
+    };
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AgendaSettings {
+    pub enabled: bool,
+    /// "HH:MM" (현지 시각)
+    pub time: String,
+}
+
+impl Default for AgendaSettings {
+    fn default() -> Self {
+        Self { enabled: true, time: "08:00".into() }
+    }
+}
+
+impl AgendaSettings {
+    fn time(&self) -> NaiveTime {
+        NaiveTime::parse_from_str(&self.time, "%H:%M")
+            .unwrap_or_else(|_| NaiveTime::from_hms_opt(8, 0, 0).expect("08:00"))
+    }
+}
+
+type DigestListener = Box<dyn Fn(&Agenda) + Send + Sync>;
+
+pub struct AgendaDigest {
+    db: Arc<LocalDb>,
+    settings_path: PathBuf,
+    state: Mutex<(AgendaSettings, bool)>,
+    signal: Condvar,
+    on_digest: DigestListener,
+}
+
+impl AgendaDigest {
+    /// `settings_path`(agenda.json)가 없거나 읽을 수 없으면 기본 설정
+    pub fn new<F>(db: Arc<LocalDb>, settings_path: PathBuf, on_digest: F) -> Self
+    where
+        F: Fn(&Agenda) + Send + Sync + 'static,
+    {
+        let settings = match std::fs::read(&settings_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                eprintln!("{} 무시: {}", settings_path.display(), e);
+                AgendaSettings::default()
+            }),
+            Err(_) => AgendaSettings::default(),
+        };
+        Self {
+            db,
+            settings_path,
+            state: Mutex::new((settings, false)),
+            signal: Condvar::new(),
+            on_digest: Box::new(on_digest),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, (AgendaSettings, bool)> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wake(&self) {
+        self.lock().1 = true;
+        self.signal.notify_all();
+    }
+
+    pub fn settings(&self) -> AgendaSettings {
+        self.lock().0.clone()
+    }
+
+    pub fn set_settings(&self, settings: AgendaSettings) -> io::Result<()> {
+        if NaiveTime::parse_from_str(&settings.time, "%H:%M").is_err() {
+            let msg = format!("agenda time must be HH:MM: {}", settings.time);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        }
+        if let Some(dir) = self.settings_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec_pretty(&settings).expect("agenda settings serialize");
+        write_atomic(&self.settings_path, &json, false)?;
+        self.lock().0 = settings;
+        self.wake();
+        Ok(())
+    }
+
+    /// 로그인한 계정 (`None`이면 로그아웃: 보내지 않는다)
+    pub fn set_uid(&self, uid: Option<&str>) -> Result<(), StorageError> {
+        meta_set(&self.db.conn(), "agenda.uid", uid)?;
+        self.wake();
+        Ok(())
+    }
+
+    pub fn uid(&self) -> Result<Option<String>, StorageError> {
+        meta_get(&self.db.conn(), "agenda.uid")
+    }
+
+    pub fn cache_timebox(&self, uid: &str, date: &str, slots: &BTreeMap<String, String>) -> Result<(), StorageError> {
+        put_timebox(&self.db.conn(), uid, date, slots)
+    }
+
+    /// 보낼 때가 됐으면 보낸다. 다음에 확인할 때까지의 시간
+    pub fn run_due<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Result<Duration, StorageError> {
+        let settings = self.settings();
+        if !settings.enabled {
+            return Ok(MAX_WAIT);
+        }
+        let local = now.naive_local();
+        let today = local.date();
+        let at = today.and_time(settings.time());
+        if local >= at && local < at + ChronoDuration::hours(CATCH_UP_HOURS) {
+            let date = today.format("%Y-%m-%d").to_string();
+            let (uid, last_sent) = {
+                let conn = self.db.conn();
+                (meta_get(&conn, "agenda.uid")?, meta_get(&conn, "agenda.last_sent")?)
+            };
+            if let (Some(uid), false) = (uid, last_sent.as_deref() == Some(date.as_str())) {
+                let agenda = build(&self.db, &uid, today, &now.timezone())?;
+                meta_set(&self.db.conn(), "agenda.last_sent", Some(&date))?;
+                if !agenda.is_empty() {
+                    (self.on_digest)(&agenda);
+                }
+            }
+        }
+        let next = if local < at { at } else { at + ChronoDuration::days(1) };
+        Ok((next - local).to_std().unwrap_or_default().min(MAX_WAIT))
+    }
+
+    /// 요약 스레드를 띄운다
+    pub fn spawn(self: &Arc<Self>) {
+        let digest = Arc::clone(self);
+        std::thread::spawn(move || loop {
+            let timeout = digest.run_due(&Local::now()).unwrap_or_else(|e| {
+                eprintln!("일정 요약 실패: {}", e);
+                MAX_WAIT
+            });
+            let mut guard = digest.lock();
+            if !guard.1 {
+                guard = digest.signal.wait_timeout(guard, timeout).unwrap_or_else(|e| e.into_inner()).0;
+            }
+            guard.1 = false;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use serde_json::json;
+
+    fn task(title: &str, due: Option<&str>, my_day: bool, status: &str) -> TaskData {
+        serde_json::from_value(json!({
+            "title": title,
+            "status": status,
+            "priority": "medium",
+            "listId": "inbox",
+            "dueDate": due,
+            "myDay": my_day,
+        }))
+        .unwrap()
+    }
+
+    fn seeded() -> Arc<LocalDb> {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        db.insert("u1", &task("보고서", Some("2026-03-09"), true, "todo")).unwrap();
+        db.insert("u1", &task("장보기", Some("2026-03-10T18:00:00.000Z"), false, "in_progress")).unwrap();
+        db.insert("u1", &task("운동", None, true, "todo")).unwrap();
+        db.insert("u1", &task("끝난 일", Some("2026-03-01"), true, "completed")).unwrap();
+        db.insert("u1", &task("다음 주", Some("2026-03-17"), false, "todo")).unwrap();
+        let slots = BTreeMap::from([("09:00".to_string(), "회의".to_string()), ("10:00".to_string(), " ".to_string())]);
+        put_timebox(&db.conn(), "u1", "2026-03-10", &slots).unwrap();
+        db
+    }
+
+    fn titles(tasks: &[AgendaTask]) -> Vec<&str> {
+        tasks.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn agenda_groups_due_overdue_my_day_and_timebox() {
+        let db = seeded();
+        let agenda = build(&db, "u1", NaiveDate::from_ymd_opt(2026, 3, 10).unwrap(), &chrono::Utc).unwrap();
+        assert_eq!(titles(&agenda.overdue), ["보고서"]);
+        assert_eq!(titles(&agenda.due_today), ["장보기"]);
+        assert_eq!(titles(&agenda.my_day), ["운동"]);
+        assert_eq!(agenda.timebox, [TimeboxBlock { time: "09:00".into(), text: "회의".into() }]);
+        assert_eq!(agenda.body(), "지난 마감 1 · 오늘 마감 1 · My Day 1 · 타임박스 1\n09:00 회의\n⚠ 보고서\n• 장보기\n• 운동");
+    }
+
+    #[test]
+    fn timestamps_are_grouped_by_local_date() {
+        let db = LocalDb::open_in_memory().unwrap();
+        // 한국 시각으로 3월 10일 08:00과 3월 11일 00:30
+        db.insert("u1", &task("아침", Some("2026-03-09T23:00:00.000Z"), false, "todo")).unwrap();
+        db.insert("u1", &task("자정 넘어", Some("2026-03-10T15:30:00Z"), false, "todo")).unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+
+        let agenda = build(&db, "u1", today, &FixedOffset::east_opt(9 * 3600).unwrap()).unwrap();
+        assert_eq!(titles(&agenda.due_today), ["아침"]);
+        assert!(agenda.overdue.is_empty());
+        let agenda = build(&db, "u1", today, &chrono::Utc).unwrap();
+        assert_eq!(titles(&agenda.overdue), ["아침"]);
+        assert_eq!(titles(&agenda.due_today), ["자정 넘어"]);
+    }
+
+    #[test]
+    fn digest_is_sent_once_a_day_within_the_catch_up_window() {
+        let db = seeded();
+        let dir = std::env::temp_dir().join(format!("noah-agenda-{}", crate::storage::new_document_id()));
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&sent);
+        let digest = AgendaDigest::new(Arc::clone(&db), dir.join("agenda.json"), move |agenda: &Agenda| {
+            sink.lock().unwrap().push(agenda.date.clone())
+        });
+        let kst = FixedOffset::east_opt(9 * 3600).unwrap();
+        let at = |d: u32, h: u32, m: u32| kst.with_ymd_and_hms(2026, 3, d, h, m, 0).unwrap();
+
+        // 로그인 전에는 보내지 않는다
+        assert_eq!(digest.run_due(&at(10, 7, 50)).unwrap(), Duration::from_secs(600));
+        digest.run_due(&at(10, 8, 0)).unwrap();
+        assert!(sent.lock().unwrap().is_empty());
+
+        // 로그인하자마자 (08:30, 아직 따라잡을 수 있음) 보내고, 같은 날 다시 보내지 않는다
+        digest.set_uid(Some("u1")).unwrap();
+        digest.run_due(&at(10, 8, 30)).unwrap();
+        digest.run_due(&at(10, 9, 0)).unwrap();
+        assert_eq!(*sent.lock().unwrap(), ["2026-03-10"]);
+
+        // 다음 날은 정한 시각에서 4시간이 지나 켜졌으면 건너뛴다
+        digest.run_due(&at(11, 12, 30)).unwrap();
+        assert_eq!(sent.lock().unwrap().len(), 1);
+
+        digest.set_settings(AgendaSettings { enabled: true, time: "13:00".into() }).unwrap();
+        digest.run_due(&at(11, 13, 0)).unwrap();
+        assert_eq!(*sent.lock().unwrap(), ["2026-03-10", "2026-03-11"]);
+        assert!(digest.set_settings(AgendaSettings { enabled: true, time: "8시".into() }).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,905 @@
+//! 로컬 데이터 백업.
+//!
+//! 로컬 DB의 모든 계정 데이터(할 일/노트/목록/폴더/마인드맵)를 JSON으로 묶어 gzip으로 압축하고,
+//! 암호를 정했으면 ChaCha20-Poly1305(키는 암호에서 PBKDF2-HMAC-SHA256)로 봉인해 백업 폴더에 파일 하나로 쓴다.
+//! 파일 머리(header)는 평문 JSON이라 암호 없이도 목록을 볼 수 있고, 암호화된 백업에서는 AEAD 추가 데이터로
+//! 묶여 있어 머리를 고치면 복원이 실패한다. 내용의 SHA-256도 머리에 있어 `verify`로 손상을 확인할 수 있다.
+//!
+//! 예약 백업은 `interval_hours`마다 만들고 보관 규칙(기본 일간 7개, 주간 4개)에 맞지 않는 예약 백업을 지운다.
+//! 직접 만든 백업과 복원 직전 자동 백업은 지우지 않는다. 암호는 토큰 보관소(`backup.passphrase`)에 둔다.
+
+use crate::models::{FolderData, ListData, MindMapData, NoteData, TaskData};
+use crate::storage::{query_docs, LocalDb, RestoreCounts, StorageError};
+use crate::token_store::{now_ms, write_atomic, TokenEntry, TokenVault};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::{Datelike, TimeZone};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 8] = b"NOAHBK1\0";
+const EXTENSION: &str = "noahbak";
+const FORMAT_VERSION: u32 = 1;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 600_000;
+/// 머리에 적힌 반복 횟수의 상한. 조작된 파일이 키 유도로 앱을 붙잡아 두지 못하게
+const MAX_PBKDF2_ITERATIONS: u32 = PBKDF2_ITERATIONS * 10;
+/// 토큰 보관소에서 백업 암호의 키
+pub const PASSPHRASE_KEY: &str = "backup.passphrase";
+/// 앱을 켠 직후에는 바로 백업하지 않는다
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+/// 예약 백업이 실패하면 이만큼 뒤에 다시
+const RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+pub enum BackupError {
+    Io(io::Error),
+    Storage(StorageError),
+    /// 백업 파일이 아니거나 손상됨
+    Corrupt(String),
+    PassphraseRequired,
+    WrongPassphrase,
+    Invalid(String),
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupError::Io(e) => write!(f, "backup i/o error: {}", e),
+            BackupError::Storage(e) => e.fmt(f),
+            BackupError::Corrupt(msg) => write!(f, "corrupt_backup: {}", msg),
+            BackupError::PassphraseRequired => write!(f, "passphrase_required: the backup is encrypted"),
+            BackupError::WrongPassphrase => write!(f, "wrong_passphrase: the backup could not be decrypted"),
+            BackupError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
+impl From<io::Error> for BackupError {
+    fn from(e: io::Error) -> Self {
+        BackupError::Io(e)
+    }
+}
+
+impl From<StorageError> for BackupError {
+    fn from(e: StorageError) -> Self {
+        BackupError::Storage(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    /// 예약 백업 (보관 규칙에 따라 지워진다)
+    Scheduled,
+    Manual,
+    /// 복원 직전의 상태
+    PreRestore,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Encryption {
+    /// 항상 `pbkdf2-sha256`
+    pub kdf: String,
+    pub iterations: u32,
+    /// base64
+    pub salt: String,
+}
+
+/// 파일 머리. 평문
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupHeader {
+    pub version: u32,
+    pub created_at: i64,
+    pub kind: BackupKind,
+    pub app_version: String,
+    /// 컬렉션별 문서 수 (모든 계정 합계)
+    pub counts: BTreeMap<String, usize>,
+    pub accounts: usize,
+    /// 압축 전 내용의 SHA-256 (hex)
+    pub sha256: String,
+    #[serde(default)]
+    pub encryption: Option<Encryption>,
+}
+
+/// `list_backups` 항목
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    /// 파일 이름
+    pub id: String,
+    pub path: String,
+    pub size: u64,
+    pub created_at: i64,
+    pub kind: BackupKind,
+    pub encrypted: bool,
+    pub counts: BTreeMap<String, usize>,
+    pub accounts: usize,
+}
+
+/// 계정 하나의 데이터
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountData {
+    pub tasks: Vec<TaskData>,
+    pub notes: Vec<NoteData>,
+    pub lists: Vec<ListData>,
+    pub folders: Vec<FolderData>,
+    pub mindmaps: Vec<MindMapData>,
+}
+
+impl AccountData {
+    fn counts(&self) -> [(&'static str, usize); 5] {
+        [
+            ("tasks", self.tasks.len()),
+            ("notes", self.notes.len()),
+            ("lists", self.lists.len()),
+            ("folders", self.folders.len()),
+            ("mindmaps", self.mindmaps.len()),
+        ]
+    }
+}
+
+/// 백업 내용. uid → 데이터
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub accounts: BTreeMap<String, AccountData>,
+}
+
+/// 로컬 DB 전체를 읽는다 (한 읽기 트랜잭션 안에서)
+pub fn export(db: &LocalDb) -> Result<Snapshot, StorageError> {
+    let conn = db.conn();
+    let tx = conn.unchecked_transaction()?;
+    let uids: Vec<String> = {
+        let mut stmt = tx.prepare(
+            "SELECT uid FROM tasks UNION SELECT uid FROM notes UNION SELECT uid FROM lists
+             UNION SELECT uid FROM folders UNION SELECT uid FROM mindmaps",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut snapshot = Snapshot::default();
+    for uid in uids {
+        let data = AccountData {
+            tasks: query_docs(&tx, &uid, "", &[])?,
+            notes: query_docs(&tx, &uid, "", &[])?,
+            lists: query_docs(&tx, &uid, "", &[])?,
+            folders: query_docs(&tx, &uid, "", &[])?,
+            mindmaps: query_docs(&tx, &uid, "", &[])?,
+        };
+        snapshot.accounts.insert(uid, data);
+    }
+    tx.commit()?;
+    Ok(snapshot)
+}
+
+/// PBKDF2-HMAC-SHA256, 32바이트 키 (RFC 8018)
+fn pbkdf2_sha256(passphrase: &[u8], salt: &[u8], iterations: u32) -> Key {
+    let mac = <Hmac<Sha256> as Mac>::new_from_slice(passphrase).expect("HMAC accepts keys of any length");
+    let mut first = mac.clone();
+    first.update(salt);
+    first.update(&1u32.to_be_bytes());
+    let mut u = first.finalize().into_bytes();
+    let mut key = u;
+    for _ in 1..iterations {
+        let mut next = mac.clone();
+        next.update(&u);
+        u = next.finalize().into_bytes();
+        key.iter_mut().zip(u.iter()).for_each(|(k, x)| *k ^= x);
+    }
+    *Key::from_slice(&key)
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut buf = [0u8; N];
+    getrandom::getrandom(&mut buf).expect("OS random source unavailable");
+    buf
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `MAGIC | 머리 길이(u32 LE) | 머리 JSON` — 암호화할 때 추가 데이터로도 쓴다
+fn header_bytes(header: &BackupHeader) -> Vec<u8> {
+    let json = serde_json::to_vec(header).expect("backup header serializes");
+    let mut out = Vec::with_capacity(MAGIC.len() + 4 + json.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    out.extend_from_slice(&json);
+    out
+}
+
+/// 백업 파일 내용을 만든다. `passphrase`가 있으면 암호화
+pub fn encode(
+    snapshot: &Snapshot,
+    kind: BackupKind,
+    created_at: i64,
+    passphrase: Option<&str>,
+    iterations: u32,
+) -> Result<Vec<u8>, BackupError> {
+    let plain = serde_json::to_vec(snapshot).map_err(|e| BackupError::Invalid(e.to_string()))?;
+    let mut counts = BTreeMap::new();
+    for data in snapshot.accounts.values() {
+        for (name, n) in data.counts() {
+            *counts.entry(name.to_string()).or_insert(0) += n;
+        }
+    }
+    let salt = random_bytes::<SALT_LEN>();
+    let header = BackupHeader {
+        version: FORMAT_VERSION,
+        created_at,
+        kind,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        counts,
+        accounts: snapshot.accounts.len(),
+        sha256: hex(&Sha256::digest(&plain)),
+        encryption: passphrase.map(|_| Encryption { kdf: "pbkdf2-sha256".into(), iterations, salt: B64.encode(salt) }),
+    };
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&plain)?;
+    let compressed = gz.finish()?;
+
+    let mut out = header_bytes(&header);
+    match passphrase {
+        None => out.extend_from_slice(&compressed),
+        Some(passphrase) => {
+            let cipher = ChaCha20Poly1305::new(&pbkdf2_sha256(passphrase.as_bytes(), &salt, iterations));
+            let nonce = random_bytes::<NONCE_LEN>();
+            let aad = out.clone();
+            let sealed = cipher
+                .encrypt(Nonce::from_slice(&nonce), Payload { msg: &compressed, aad: &aad })
+                .expect("chacha20poly1305 encryption");
+            out.extend_from_slice(&nonce);
+            out.extend_from_slice(&sealed);
+        }
+    }
+    Ok(out)
+}
+
+/// 머리와 나머지(본문)로 나눈다
+pub fn read_header(bytes: &[u8]) -> Result<(BackupHeader, usize), BackupError> {
+    let corrupt = |msg: &str| BackupError::Corrupt(msg.to_string());
+    if bytes.len() < MAGIC.len() + 4 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(corrupt("not a backup file"));
+    }
+    let len = u32::from_le_bytes(bytes[MAGIC.len()..MAGIC.len() + 4].try_into().expect("4 bytes")) as usize;
+    let end = MAGIC.len() + 4 + len;
+    let json = bytes.get(MAGIC.len() + 4..end).ok_or_else(|| corrupt("truncated header"))?;
+    let header: BackupHeader = serde_json::from_slice(json).map_err(|e| corrupt(&format!("bad header: {}", e)))?;
+    if header.version != FORMAT_VERSION {
+        return Err(BackupError::Corrupt(format!("unsupported backup version {}", header.version)));
+    }
+    Ok((header, end))
+}
+
+/// 파일 내용을 풀고 무결성을 확인한다
+pub fn decode(bytes: &[u8], passphrase: Option<&str>) -> Result<(BackupHeader, Snapshot), BackupError> {
+    let (header, body_at) = read_header(bytes)?;
+    let body = &bytes[body_at..];
+    let decrypted;
+    let compressed = match &header.encryption {
+        None => body,
+        Some(encryption) => {
+            let passphrase = passphrase.ok_or(BackupError::PassphraseRequired)?;
+            if encryption.kdf != "pbkdf2-sha256" {
+                return Err(BackupError::Corrupt(format!("unknown kdf {}", encryption.kdf)));
+            }
+            if encryption.iterations == 0 || encryption.iterations > MAX_PBKDF2_ITERATIONS {
+                return Err(BackupError::Corrupt(format!("bad kdf iterations {}", encryption.iterations)));
+            }
+            let salt = B64.decode(&encryption.salt).map_err(|_| BackupError::Corrupt("bad salt".into()))?;
+            if body.len() < NONCE_LEN {
+                return Err(BackupError::Corrupt("truncated body".into()));
+            }
+            let (nonce, sealed) = body.split_at(NONCE_LEN);
+            let cipher = ChaCha20Poly1305::new(&pbkdf2_sha256(passphrase.as_bytes(), &salt, encryption.iterations));
+            decrypted = cipher
+                .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: &bytes[..body_at] })
+                .map_err(|_| BackupError::WrongPassphrase)?;
+            &decrypted
+        }
+    };
+    let mut plain = Vec::new();
+    GzDecoder::new(compressed)
+        .read_to_end(&mut plain)
+        .map_err(|e| BackupError::Corrupt(format!("decompression failed: {}", e)))?;
+    if hex(&Sha256::digest(&plain)) != header.sha256 {
+        return Err(BackupError::Corrupt("checksum mismatch".into()));
+    }
+    let snapshot = serde_json::from_slice(&plain).map_err(|e| BackupError::Corrupt(format!("bad payload: {}", e)))?;
+    Ok((header, snapshot))
+}
+
+/// 지울 예약 백업. 가장 최근 `keep_daily`개 날짜와 `keep_weekly`개 주(ISO 주)에서 각각 가장 최근 것 하나씩을 남긴다.
+/// 예약 백업이 아닌 것은 건드리지 않는다
+pub fn expired<'a, Tz: TimeZone>(
+    backups: &'a [BackupInfo],
+    keep_daily: usize,
+    keep_weekly: usize,
+    tz: &Tz,
+) -> Vec<&'a BackupInfo> {
+    let mut scheduled: Vec<&BackupInfo> = backups.iter().filter(|b| b.kind == BackupKind::Scheduled).collect();
+    scheduled.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+    let (mut days, mut weeks) = (HashSet::new(), HashSet::new());
+    let mut expired = Vec::new();
+    for backup in scheduled {
+        let Some(at) = tz.timestamp_millis_opt(backup.created_at).single() else { continue };
+        let day = at.date_naive();
+        let week = (day.iso_week().year(), day.iso_week().week());
+        let mut keep = false;
+        if !days.contains(&day) && days.len() < keep_daily {
+            days.insert(day);
+            keep = true;
+        }
+        if !weeks.contains(&week) && weeks.len() < keep_weekly {
+            weeks.insert(week);
+            keep = true;
+        }
+        if !keep {
+            expired.push(backup);
+        }
+    }
+    expired
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupSettings {
+    /// 예약 백업 여부
+    pub enabled: bool,
+    /// 백업 폴더. 없으면 앱 데이터 폴더의 `backups`
+    pub folder: Option<String>,
+    /// 암호화 (암호는 토큰 보관소에)
+    pub encrypted: bool,
+    pub interval_hours: u32,
+    pub keep_daily: usize,
+    pub keep_weekly: usize,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self { enabled: true, folder: None, encrypted: false, interval_hours: 24, keep_daily: 7, keep_weekly: 4 }
+    }
+}
+
+/// `restore_backup`의 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    /// 지워진 문서만 되살린다
+    Missing,
+    /// 지워진 문서를 되살리고, 고친 문서도 백업 때로 되돌린다
+    Revert,
+    /// 백업 시점과 똑같이 (백업 뒤에 만든 문서는 지운다)
+    Exact,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSummary {
+    #[serde(flatten)]
+    pub counts: RestoreCounts,
+    /// 복원 직전 상태를 담은 백업
+    pub safety_backup: BackupInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupStatus {
+    pub settings: BackupSettings,
+    pub folder: String,
+    pub passphrase_set: bool,
+    pub last_backup_at: Option<i64>,
+    pub next_backup_at: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+/// 예약 백업 결과 (알림/이벤트로 내보낼 곳)
+#[derive(Debug, Clone)]
+pub enum BackupEvent {
+    Completed(BackupInfo),
+    Failed(String),
+}
+
+struct Inner {
+    settings: BackupSettings,
+    last_error: Option<String>,
+    /// 마지막 예약 백업 시도 (실패 포함)
+    last_attempt_at: Option<i64>,
+    next_run_at: Option<i64>,
+    wake: bool,
+}
+
+type EventListener = Box<dyn Fn(&BackupEvent) + Send + Sync>;
+
+pub struct Backups {
+    db: Arc<LocalDb>,
+    vault: Arc<TokenVault>,
+    settings_path: PathBuf,
+    default_folder: PathBuf,
+    iterations: u32,
+    started: Instant,
+    inner: Mutex<Inner>,
+    signal: Condvar,
+    on_event: EventListener,
+}
+
+impl Backups {
+    /// `settings_path`(backup.json)가 없거나 읽을 수 없으면 기본 설정
+    pub fn new<F>(db: Arc<LocalDb>, vault: Arc<TokenVault>, settings_path: PathBuf, default_folder: PathBuf, on_event: F) -> Self
+    where
+        F: Fn(&BackupEvent) + Send + Sync + 'static,
+    {
+        let settings = match std::fs::read(&settings_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                eprintln!("{} 무시: {}", settings_path.display(), e);
+                BackupSettings::default()
+            }),
+            Err(_) => BackupSettings::default(),
+        };
+        Self {
+            db,
+            vault,
+            settings_path,
+            default_folder,
+            iterations: PBKDF2_ITERATIONS,
+            started: Instant::now(),
+            inner: Mutex::new(Inner { settings, last_error: None, last_attempt_at: None, next_run_at: None, wake: false }),
+            signal: Condvar::new(),
+            on_event: Box::new(on_event),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn settings(&self) -> BackupSettings {
+        self.lock().settings.clone()
+    }
+
+    pub fn folder(&self) -> PathBuf {
+        self.lock().settings.folder.as_ref().map(PathBuf::from).unwrap_or_else(|| self.default_folder.clone())
+    }
+
+    fn stored_passphrase(&self) -> Option<String> {
+        self.vault.get(PASSPHRASE_KEY).map(|e| e.value).filter(|p| !p.is_empty())
+    }
+
+    /// 설정을 저장한다. `passphrase`를 주면 백업 암호를 바꾼다. 암호화를 끄면 저장된 암호를 지운다
+    pub fn set_settings(&self, settings: BackupSettings, passphrase: Option<String>) -> Result<(), BackupError> {
+        if settings.interval_hours == 0 {
+            return Err(BackupError::Invalid("intervalHours must be at least 1".into()));
+        }
+        if settings.keep_daily == 0 && settings.keep_weekly == 0 {
+            return Err(BackupError::Invalid("at least one backup must be kept".into()));
+        }
+        if let Some(folder) = &settings.folder {
+            if !Path::new(folder).is_absolute() {
+                return Err(BackupError::Invalid(format!("backup folder must be an absolute path: {}", folder)));
+            }
+        }
+        match passphrase.filter(|p| !p.is_empty()) {
+            Some(passphrase) => self.vault.put(PASSPHRASE_KEY, TokenEntry::new(passphrase))?,
+            None if settings.encrypted && self.stored_passphrase().is_none() => {
+                return Err(BackupError::PassphraseRequired);
+            }
+            None => {}
+        }
+        if !settings.encrypted {
+            self.vault.delete(PASSPHRASE_KEY)?;
+        }
+        if let Some(dir) = self.settings_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec_pretty(&settings).expect("backup settings serialize");
+        write_atomic(&self.settings_path, &json, false)?;
+        {
+            let mut inner = self.lock();
+            inner.settings = settings;
+            inner.wake = true;
+        }
+        self.signal.notify_all();
+        Ok(())
+    }
+
+    pub fn status(&self) -> BackupStatus {
+        let last_backup_at = self.list().ok().and_then(|list| list.first().map(|b| b.created_at));
+        let inner = self.lock();
+        BackupStatus {
+            settings: inner.settings.clone(),
+            folder: self.folder_of(&inner.settings).to_string_lossy().into_owned(),
+            passphrase_set: self.stored_passphrase().is_some(),
+            last_backup_at,
+            next_backup_at: if inner.settings.enabled { inner.next_run_at } else { None },
+            last_error: inner.last_error.clone(),
+        }
+    }
+
+    fn folder_of(&self, settings: &BackupSettings) -> PathBuf {
+        settings.folder.as_ref().map(PathBuf::from).unwrap_or_else(|| self.default_folder.clone())
+    }
+
+    /// 백업 폴더 안의 파일 경로. 다른 폴더를 가리키는 id는 거부한다
+    fn path_of(&self, id: &str) -> Result<PathBuf, BackupError> {
+        let valid = Path::new(id).file_name().and_then(|n| n.to_str()) == Some(id)
+            && Path::new(id).extension().and_then(|e| e.to_str()) == Some(EXTENSION);
+        if !valid {
+            return Err(BackupError::Invalid(format!("invalid backup id: {}", id)));
+        }
+        Ok(self.folder().join(id))
+    }
+
+    fn info(path: &Path, header: BackupHeader, size: u64) -> BackupInfo {
+        BackupInfo {
+            id: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            path: path.to_string_lossy().into_owned(),
+            size,
+            created_at: header.created_at,
+            kind: header.kind,
+            encrypted: header.encryption.is_some(),
+            counts: header.counts,
+            accounts: header.accounts,
+        }
+    }
+
+    /// 지금 상태를 백업한다. 암호화 설정이면 저장된 암호로
+    pub fn create(&self, kind: BackupKind) -> Result<BackupInfo, BackupError> {
+        let settings = self.settings();
+        let passphrase = if settings.encrypted {
+            Some(self.stored_passphrase().ok_or(BackupError::PassphraseRequired)?)
+        } else {
+            None
+        };
+        let snapshot = export(&self.db)?;
+        let created_at = now_ms() as i64;
+        let bytes = encode(&snapshot, kind, created_at, passphrase.as_deref(), self.iterations)?;
+
+        let folder = self.folder_of(&settings);
+        std::fs::create_dir_all(&folder)?;
+        let stamp = chrono::Utc
+            .timestamp_millis_opt(created_at)
+            .single()
+            .map(|t| t.format("%Y%m%d-%H%M%S").to_string())
+            .unwrap_or_default();
+        let mut path = folder.join(format!("noah-{}.{}", stamp, EXTENSION));
+        let mut n = 2;
+        while path.exists() {
+            path = folder.join(format!("noah-{}-{}.{}", stamp, n, EXTENSION));
+            n += 1;
+        }
+        write_atomic(&path, &bytes, true)?;
+        let (header, _) = read_header(&bytes)?;
+        Ok(Self::info(&path, header, bytes.len() as u64))
+    }
+
+    /// 백업 폴더의 백업들, 최근 것부터. 백업 파일이 아닌 것은 건너뛴다
+    pub fn list(&self) -> Result<Vec<BackupInfo>, BackupError> {
+        let entries = match std::fs::read_dir(self.folder()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut backups = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            // 머리만 읽는다
+            let Ok(mut file) = std::fs::File::open(&path) else { continue };
+            let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+            let mut head = [0u8; 12];
+            if file.read_exact(&mut head).is_err() {
+                continue;
+            }
+            let len = u32::from_le_bytes(head[8..12].try_into().expect("4 bytes")) as u64;
+            let mut bytes = head.to_vec();
+            if file.take(len).read_to_end(&mut bytes).is_err() {
+                continue;
+            }
+            if let Ok((header, _)) = read_header(&bytes) {
+                backups.push(Self::info(&path, header, size));
+            }
+        }
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+        Ok(backups)
+    }
+
+    fn read(&self, id: &str, passphrase: Option<&str>) -> Result<(BackupInfo, Snapshot), BackupError> {
+        let path = self.path_of(id)?;
+        let bytes = std::fs::read(&path)?;
+        let stored = self.stored_passphrase();
+        let (header, snapshot) = decode(&bytes, passphrase.or(stored.as_deref()))?;
+        Ok((Self::info(&path, header, bytes.len() as u64), snapshot))
+    }
+
+    /// 끝까지 풀어 보고 체크섬을 확인한다. `passphrase`가 없으면 저장된 암호로
+    pub fn verify(&self, id: &str, passphrase: Option<&str>) -> Result<BackupInfo, BackupError> {
+        self.read(id, passphrase).map(|(info, _)| info)
+    }
+
+    /// 계정 `uid`의 데이터를 백업 시점으로 되돌린다. 먼저 지금 상태를 백업해 두고, 되돌린 변경은 원격에도 반영된다
+    pub fn restore(
+        &self,
+        id: &str,
+        uid: &str,
+        passphrase: Option<&str>,
+        mode: RestoreMode,
+    ) -> Result<RestoreSummary, BackupError> {
+        let (_, mut snapshot) = self.read(id, passphrase)?;
+        let data = snapshot
+            .accounts
+            .remove(uid)
+            .ok_or_else(|| BackupError::Invalid("the backup has no data for this account".into()))?;
+        let safety_backup = self.create(BackupKind::PreRestore)?;
+
+        let (revert, exact) = (mode != RestoreMode::Missing, mode == RestoreMode::Exact);
+        // 한 트랜잭션으로: 중간에 실패하면 아무것도 되돌리지 않는다
+        let counts = self.db.restore(uid, revert, exact, |restorer| {
+            // 목록/폴더를 먼저 (할 일/노트가 가리키는 쪽)
+            restorer.docs(&data.lists)?;
+            restorer.docs(&data.folders)?;
+            restorer.docs(&data.tasks)?;
+            restorer.docs(&data.notes)?;
+            restorer.docs(&data.mindmaps)?;
+            Ok(())
+        })?;
+        Ok(RestoreSummary { counts, safety_backup })
+    }
+
+    /// 보관 규칙에 맞지 않는 예약 백업을 지운다. 지운 개수
+    pub fn prune(&self) -> Result<usize, BackupError> {
+        let settings = self.settings();
+        let backups = self.list()?;
+        let expired = expired(&backups, settings.keep_daily, settings.keep_weekly, &chrono::Local);
+        for backup in &expired {
+            std::fs::remove_file(&backup.path)?;
+        }
+        Ok(expired.len())
+    }
+
+    /// 예약 백업이 밀렸으면 만든다. 다음에 확인할 때까지의 시간 (꺼져 있으면 `None`)
+    pub fn run_due(&self) -> Option<Duration> {
+        let settings = self.settings();
+        if !settings.enabled {
+            self.lock().next_run_at = None;
+            return None;
+        }
+        let interval = Duration::from_secs(settings.interval_hours as u64 * 3600);
+        let now = now_ms() as i64;
+        let last_scheduled = self
+            .list()
+            .ok()
+            .and_then(|list| list.into_iter().find(|b| b.kind == BackupKind::Scheduled).map(|b| b.created_at));
+        let last_attempt = self.lock().last_attempt_at;
+        let due_at = match (last_scheduled, last_attempt) {
+            (_, Some(attempt)) if last_scheduled.is_none_or(|s| attempt > s) => attempt + RETRY_AFTER.as_millis() as i64,
+            (Some(last), _) => last + interval.as_millis() as i64,
+            (None, _) => now,
+        };
+        let startup_left = STARTUP_DELAY.saturating_sub(self.started.elapsed());
+        if due_at > now || !startup_left.is_zero() {
+            let wait = Duration::from_millis((due_at - now).max(0) as u64).max(startup_left);
+            self.lock().next_run_at = Some(now + wait.as_millis() as i64);
+            return Some(wait);
+        }
+
+        self.lock().last_attempt_at = Some(now);
+        let event = match self.create(BackupKind::Scheduled) {
+            Ok(info) => {
+                if let Err(e) = self.prune() {
+                    eprintln!("오래된 백업 정리 실패: {}", e);
+                }
+                let mut inner = self.lock();
+                inner.last_error = None;
+                inner.last_attempt_at = None;
+                inner.next_run_at = Some(info.created_at + interval.as_millis() as i64);
+                BackupEvent::Completed(info)
+            }
+            Err(e) => {
+                let mut inner = self.lock();
+                inner.last_error = Some(e.to_string());
+                inner.next_run_at = Some(now + RETRY_AFTER.as_millis() as i64);
+                BackupEvent::Failed(e.to_string())
+            }
+        };
+        (self.on_event)(&event);
+        let next = self.lock().next_run_at.unwrap_or(now);
+        Some(Duration::from_millis((next - now).max(0) as u64))
+    }
+
+    fn wait(&self, timeout: Option<Duration>) {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut inner = self.lock();
+        while !inner.wake {
+            match deadline {
+                None => inner = self.signal.wait(inner).unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    inner = self.signal.wait_timeout(inner, deadline - now).unwrap_or_else(|e| e.into_inner()).0;
+                }
+            }
+        }
+        inner.wake = false;
+    }
+
+    /// 예약 백업 스레드를 띄운다
+    pub fn spawn(self: &Arc<Self>) {
+        let backups = Arc::clone(self);
+        std::thread::spawn(move || loop {
+            let timeout = backups.run_due();
+            backups.wait(timeout);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("noah-backup-{}-{}", name, hex(&random_bytes::<4>())));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn task(title: &str) -> TaskData {
+        serde_json::from_value(json!({ "title": title, "status": "todo", "priority": "low", "listId": "inbox" })).unwrap()
+    }
+
+    fn backups(dir: &Path, db: &Arc<LocalDb>) -> Backups {
+        let vault = Arc::new(TokenVault::open_with_secret(dir.join("tokens.vault"), &[7u8; 32]).unwrap());
+        let mut backups =
+            Backups::new(Arc::clone(db), vault, dir.join("backup.json"), dir.join("backups"), |_: &BackupEvent| {});
+        backups.iterations = 10;
+        backups
+    }
+
+    fn info(id: &str, created_at: i64, kind: BackupKind) -> BackupInfo {
+        BackupInfo {
+            id: id.into(),
+            path: id.into(),
+            size: 0,
+            created_at,
+            kind,
+            encrypted: false,
+            counts: BTreeMap::new(),
+            accounts: 1,
+        }
+    }
+
+    #[test]
+    fn pbkdf2_matches_rfc_vectors() {
+        // 널리 쓰이는 PBKDF2-HMAC-SHA256 테스트 벡터 ("password", "salt")
+        assert_eq!(
+            hex(&pbkdf2_sha256(b"password", b"salt", 1)),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        assert_eq!(
+            hex(&pbkdf2_sha256(b"password", b"salt", 2)),
+            "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"
+        );
+    }
+
+    #[test]
+    fn encrypted_round_trip_and_tamper_detection() {
+        let mut snapshot = Snapshot::default();
+        snapshot.accounts.insert("u1".into(), AccountData { tasks: vec![task("a")], ..Default::default() });
+        let bytes = encode(&snapshot, BackupKind::Manual, 1_000, Some("비밀"), 10).unwrap();
+
+        let (header, decoded) = decode(&bytes, Some("비밀")).unwrap();
+        assert_eq!(decoded, snapshot);
+        assert_eq!(header.counts["tasks"], 1);
+        assert!(matches!(decode(&bytes, None), Err(BackupError::PassphraseRequired)));
+        assert!(matches!(decode(&bytes, Some("틀림")), Err(BackupError::WrongPassphrase)));
+
+        // 머리의 개수를 고치면 복호화가 실패한다
+        let (_, body_at) = read_header(&bytes).unwrap();
+        let mut tampered = bytes[..body_at].to_vec();
+        let pos = tampered.windows(9).position(|w| w == b"\"tasks\":1").unwrap();
+        tampered[pos + 8] = b'9';
+        tampered.extend_from_slice(&bytes[body_at..]);
+        assert!(matches!(decode(&tampered, Some("비밀")), Err(BackupError::WrongPassphrase)));
+
+        // 평문 백업은 체크섬으로
+        let mut plain = encode(&snapshot, BackupKind::Manual, 1_000, None, 10).unwrap();
+        let last = plain.len() - 10;
+        plain[last] ^= 0xff;
+        assert!(matches!(decode(&plain, None), Err(BackupError::Corrupt(_))));
+    }
+
+    #[test]
+    fn rejects_absurd_kdf_iterations() {
+        let bytes = encode(&Snapshot::default(), BackupKind::Manual, 1_000, Some("비밀"), 10).unwrap();
+        let (mut header, body_at) = read_header(&bytes).unwrap();
+        header.encryption.as_mut().unwrap().iterations = u32::MAX;
+        let json = serde_json::to_vec(&header).unwrap();
+        let mut forged = MAGIC.to_vec();
+        forged.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        forged.extend_from_slice(&json);
+        forged.extend_from_slice(&bytes[body_at..]);
+
+        // 키를 유도하기 전에 거절한다
+        assert!(matches!(decode(&forged, Some("비밀")), Err(BackupError::Corrupt(_))));
+    }
+
+    #[test]
+    fn retention_keeps_daily_and_weekly() {
+        const DAY: i64 = 86_400_000;
+        // 2026-01-05(월) 정오부터 30일 동안 하루 두 번
+        let start = 1_767_614_400_000;
+        let mut list = Vec::new();
+        for d in 0..30 {
+            for h in [0, 6] {
+                list.push(info(&format!("{}-{}", d, h), start + d * DAY + h * 3_600_000, BackupKind::Scheduled));
+            }
+        }
+        list.push(info("manual", start, BackupKind::Manual));
+        let expired: HashSet<&str> = expired(&list, 7, 4, &chrono::Utc).into_iter().map(|b| b.id.as_str()).collect();
+        let kept: Vec<&str> = list.iter().map(|b| b.id.as_str()).filter(|id| !expired.contains(id)).collect();
+        // 최근 7일 각각의 마지막 + 그 전 주들의 마지막 (일요일)
+        assert_eq!(kept, ["13-6", "20-6", "23-6", "24-6", "25-6", "26-6", "27-6", "28-6", "29-6", "manual"]);
+    }
+
+    #[test]
+    fn create_list_verify_and_restore() {
+        let dir = temp_dir("restore");
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let backups = backups(&dir, &db);
+        let keep = db.insert("u1", &task("keep")).unwrap();
+        let edit = db.insert("u1", &task("before")).unwrap();
+        db.insert("u2", &task("other account")).unwrap();
+
+        backups.set_settings(BackupSettings { encrypted: true, ..Default::default() }, Some("pw".into())).unwrap();
+        let backup = backups.create(BackupKind::Manual).unwrap();
+        assert!(backup.encrypted);
+        assert_eq!((backup.accounts, backup.counts["tasks"]), (2, 3));
+        assert_eq!(backups.list().unwrap(), vec![backup.clone()]);
+        assert!(backups.verify(&backup.id, None).is_ok());
+        assert!(matches!(backups.verify(&backup.id, Some("nope")), Err(BackupError::WrongPassphrase)));
+        assert!(matches!(backups.verify("../tokens.vault", None), Err(BackupError::Invalid(_))));
+
+        let keep_id = keep.id.unwrap();
+        let edit_id = edit.id.unwrap();
+        db.delete::<TaskData>("u1", &keep_id).unwrap();
+        db.update::<TaskData>("u1", &edit_id, &json!({ "title": "after" })).unwrap();
+        let newer = db.insert("u1", &task("newer")).unwrap().id.unwrap();
+
+        let summary = backups.restore(&backup.id, "u1", None, RestoreMode::Missing).unwrap();
+        assert_eq!((summary.counts.restored, summary.counts.reverted, summary.counts.removed), (1, 0, 0));
+        assert_eq!(summary.safety_backup.kind, BackupKind::PreRestore);
+        let restored = db.get::<TaskData>("u1", &keep_id).unwrap().unwrap();
+        assert_eq!(restored.created_at, keep.created_at);
+        assert_eq!(db.get::<TaskData>("u1", &edit_id).unwrap().unwrap().title, "after");
+
+        let summary = backups.restore(&backup.id, "u1", None, RestoreMode::Exact).unwrap();
+        assert_eq!((summary.counts.restored, summary.counts.reverted, summary.counts.removed), (0, 1, 1));
+        assert_eq!(db.get::<TaskData>("u1", &edit_id).unwrap().unwrap().title, "before");
+        assert!(db.get::<TaskData>("u1", &newer).unwrap().is_none());
+        assert_eq!(db.list::<TaskData>("u2").unwrap().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
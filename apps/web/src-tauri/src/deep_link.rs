@@ -0,0 +1,311 @@
+//! `noah://` 딥링크 라우터.
+//!
+//! URL을 타입이 있는 라우트로 해석해 `deep-link` 이벤트 하나로 내보낸다.
+//! 콜드 스타트(실행 인자/플러그인)로 들어온 링크는 웹뷰가 리스너를 달기 전이므로
+//! 대기열에 두고 `take_pending_deep_links`로 가져가게 한다.
+//!
+//! | URL | 라우트 |
+//! |---|---|
+//! | `noah://auth-callback#data=<base64 JSON>` | `authCallback` |
+//! | `noah://task/<id>` | `openTask` |
+//! | `noah://note/<id>` | `openNote` |
+//! | `noah://add-task?title=<제목>&due=<YYYY-MM-DD>` | `addTask` |
+//! | `noah://pomodoro/start?minutes=<1-180>` | `startPomodoro` |
+
+use crate::loopback::parse_query;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Mutex;
+
+pub const SCHEME: &str = "noah";
+
+const MAX_ID_LEN: usize = 128;
+const MAX_TITLE_LEN: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "route", rename_all = "camelCase")]
+pub enum DeepLinkRoute {
+    /// 모바일 로그인 브릿지가 넘겨주는 Firebase 사용자 (base64 JSON, 해석은 웹뷰가)
+    AuthCallback { data: String },
+    OpenTask { id: String },
+    OpenNote { id: String },
+    AddTask {
+        title: String,
+        /// YYYY-MM-DD
+        due: Option<String>,
+    },
+    StartPomodoro {
+        /// 집중 시간(분). 없으면 현재 설정
+        minutes: Option<u32>,
+    },
+    /// 아침 일정 요약 알림에서
+    OpenMyDay,
+}
+
+impl DeepLinkRoute {
+    /// `take_pending_deep_links`의 필터에 쓰는 이름 (serde 태그와 같음)
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeepLinkRoute::AuthCallback { .. } => "authCallback",
+            DeepLinkRoute::OpenTask { .. } => "openTask",
+            DeepLinkRoute::OpenNote { .. } => "openNote",
+            DeepLinkRoute::AddTask { .. } => "addTask",
+            DeepLinkRoute::StartPomodoro { .. } => "startPomodoro",
+            DeepLinkRoute::OpenMyDay => "openMyDay",
+        }
+    }
+}
+
+/// 링크가 들어온 경로
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeepLinkSource {
+    /// 링크로 앱이 실행됨
+    ColdStart,
+    /// 실행 중인 앱에 OS가 전달 (macOS/모바일)
+    Running,
+    /// 두 번째 실행이 인자를 넘겨줌 (Windows/Linux)
+    SecondInstance,
+    /// 알림의 열기 버튼
+    Notification,
+}
+
+/// `deep-link` 이벤트 페이로드
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkEvent {
+    pub url: String,
+    pub source: DeepLinkSource,
+    #[serde(flatten)]
+    pub route: DeepLinkRoute,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLinkError {
+    NotNoahUrl,
+    UnknownRoute(String),
+    MissingParam(&'static str),
+    InvalidParam(&'static str),
+}
+
+impl fmt::Display for DeepLinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeepLinkError::NotNoahUrl => write!(f, "not a {}:// URL", SCHEME),
+            DeepLinkError::UnknownRoute(route) => write!(f, "unknown deep link route: {}", route),
+            DeepLinkError::MissingParam(name) => write!(f, "deep link is missing {}", name),
+            DeepLinkError::InvalidParam(name) => write!(f, "deep link has an invalid {}", name),
+        }
+    }
+}
+
+impl std::error::Error for DeepLinkError {}
+
+fn strip_scheme(url: &str) -> Option<&str> {
+    let (scheme, rest) = url.trim().split_once(':')?;
+    if !scheme.eq_ignore_ascii_case(SCHEME) {
+        return None;
+    }
+    Some(rest.trim_start_matches('/'))
+}
+
+fn param(params: &[(String, String)], name: &str) -> Option<String> {
+    params.iter().find(|(k, _)| k == name).map(|(_, v)| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Firestore 문서 id (영숫자/`-`/`_`)
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_ID_LEN && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// YYYY-MM-DD, 달력상 존재하는 날짜만
+fn valid_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+        return false;
+    }
+    let nums: Option<Vec<u32>> = parts.iter().map(|p| p.parse::<u32>().ok()).collect();
+    let Some([year, month, day]) = nums.as_deref().and_then(|n| <[u32; 3]>::try_from(n).ok()) else {
+        return false;
+    };
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+/// `noah://...` URL → 라우트
+pub fn parse(url: &str) -> Result<DeepLinkRoute, DeepLinkError> {
+    let rest = strip_scheme(url).ok_or(DeepLinkError::NotNoahUrl)?;
+    let (rest, fragment) = rest.split_once('#').unwrap_or((rest, ""));
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let mut params = parse_query(query);
+    params.extend(parse_query(fragment));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match segments.as_slice() {
+        ["auth-callback"] => {
+            let data = param(&params, "data").ok_or(DeepLinkError::MissingParam("data"))?;
+            Ok(DeepLinkRoute::AuthCallback { data })
+        }
+        ["task" | "tasks", id] => {
+            let id = urlencoding::decode(id).map_err(|_| DeepLinkError::InvalidParam("id"))?;
+            if !valid_id(&id) {
+                return Err(DeepLinkError::InvalidParam("id"));
+            }
+            Ok(DeepLinkRoute::OpenTask { id: id.into_owned() })
+        }
+        ["note" | "notes", id] => {
+            let id = urlencoding::decode(id).map_err(|_| DeepLinkError::InvalidParam("id"))?;
+            if !valid_id(&id) {
+                return Err(DeepLinkError::InvalidParam("id"));
+            }
+            Ok(DeepLinkRoute::OpenNote { id: id.into_owned() })
+        }
+        ["add-task"] => {
+            let title = param(&params, "title").ok_or(DeepLinkError::MissingParam("title"))?;
+            if title.chars().count() > MAX_TITLE_LEN || title.chars().any(char::is_control) {
+                return Err(DeepLinkError::InvalidParam("title"));
+            }
+            let due = param(&params, "due");
+            if due.as_deref().is_some_and(|d| !valid_date(d)) {
+                return Err(DeepLinkError::InvalidParam("due"));
+            }
+            Ok(DeepLinkRoute::AddTask { title, due })
+        }
+        ["pomodoro"] | ["pomodoro", "start"] => {
+            let minutes = match param(&params, "minutes") {
+                Some(m) => match m.parse::<u32>() {
+                    Ok(m) if (1..=180).contains(&m) => Some(m),
+                    _ => return Err(DeepLinkError::InvalidParam("minutes")),
+                },
+                None => None,
+            };
+            Ok(DeepLinkRoute::StartPomodoro { minutes })
+        }
+        ["my-day"] => Ok(DeepLinkRoute::OpenMyDay),
+        _ => Err(DeepLinkError::UnknownRoute(path.to_string())),
+    }
+}
+
+/// 실행 인자 중 `noah:` URL만 (Windows/Linux는 OS가 링크를 인자로 넘긴다)
+pub fn links_in_args<I, S>(args: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .map(|a| a.as_ref().trim().to_string())
+        .filter(|a| strip_scheme(a).is_some())
+        .collect()
+}
+
+/// 웹뷰가 아직 듣지 않는 동안 들어온 링크
+#[derive(Default)]
+pub struct DeepLinks {
+    pending: Mutex<Vec<DeepLinkEvent>>,
+}
+
+impl DeepLinks {
+    pub fn queue(&self, event: DeepLinkEvent) {
+        if let Ok(mut pending) = self.pending.lock() {
+            // 같은 링크가 플러그인과 실행 인자 양쪽으로 들어오는 경우
+            if !pending.iter().any(|p| p.url == event.url) {
+                pending.push(event);
+            }
+        }
+    }
+
+    /// 대기 중인 링크를 꺼낸다. `routes`를 주면 해당 라우트만 꺼내고 나머지는 남긴다
+    pub fn take(&self, routes: Option<&[String]>) -> Vec<DeepLinkEvent> {
+        let Ok(mut pending) = self.pending.lock() else { return Vec::new() };
+        match routes {
+            None => std::mem::take(&mut *pending),
+            Some(routes) => {
+                let (taken, kept) = std::mem::take(&mut *pending)
+                    .into_iter()
+                    .partition(|e| routes.iter().any(|r| r == e.route.name()));
+                *pending = kept;
+                taken
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_route() {
+        assert_eq!(
+            parse("noah://auth-callback#data=eyJ1aWQiOiJ1MSJ9"),
+            Ok(DeepLinkRoute::AuthCallback { data: "eyJ1aWQiOiJ1MSJ9".into() })
+        );
+        assert_eq!(parse("noah://task/AbC123_-x"), Ok(DeepLinkRoute::OpenTask { id: "AbC123_-x".into() }));
+        assert_eq!(parse("NOAH://note/n1/"), Ok(DeepLinkRoute::OpenNote { id: "n1".into() }));
+        assert_eq!(
+            parse("noah://add-task?title=%EC%9A%B0%EC%9C%A0+%EC%82%AC%EA%B8%B0&due=2024-02-29"),
+            Ok(DeepLinkRoute::AddTask { title: "우유 사기".into(), due: Some("2024-02-29".into()) })
+        );
+        assert_eq!(
+            parse("noah:add-task?title=Call%20mom"),
+            Ok(DeepLinkRoute::AddTask { title: "Call mom".into(), due: None })
+        );
+        assert_eq!(parse("noah://pomodoro/start?minutes=50"), Ok(DeepLinkRoute::StartPomodoro { minutes: Some(50) }));
+        assert_eq!(parse("noah://pomodoro"), Ok(DeepLinkRoute::StartPomodoro { minutes: None }));
+        assert_eq!(parse("noah://my-day"), Ok(DeepLinkRoute::OpenMyDay));
+    }
+
+    #[test]
+    fn rejects_bad_links() {
+        assert_eq!(parse("https://example.com/task/1"), Err(DeepLinkError::NotNoahUrl));
+        assert_eq!(parse("noah://settings"), Err(DeepLinkError::UnknownRoute("settings".into())));
+        assert_eq!(parse("noah://task/../etc"), Err(DeepLinkError::UnknownRoute("task/../etc".into())));
+        assert_eq!(parse("noah://task/a%2Fb"), Err(DeepLinkError::InvalidParam("id")));
+        assert_eq!(parse("noah://auth-callback"), Err(DeepLinkError::MissingParam("data")));
+        assert_eq!(parse("noah://add-task?title=%20"), Err(DeepLinkError::MissingParam("title")));
+        assert_eq!(parse("noah://add-task?title=x&due=2023-02-29"), Err(DeepLinkError::InvalidParam("due")));
+        assert_eq!(parse("noah://add-task?title=x&due=tomorrow"), Err(DeepLinkError::InvalidParam("due")));
+        assert_eq!(parse("noah://add-task?title=a%0Ab"), Err(DeepLinkError::InvalidParam("title")));
+        assert_eq!(parse("noah://pomodoro/start?minutes=0"), Err(DeepLinkError::InvalidParam("minutes")));
+    }
+
+    #[test]
+    fn finds_links_in_cold_start_args() {
+        let args = ["--minimized", "noah://task/t1", "C:\\notes.txt", " NOAH://note/n1 "];
+        assert_eq!(links_in_args(args), vec!["noah://task/t1".to_string(), "NOAH://note/n1".to_string()]);
+    }
+
+    #[test]
+    fn event_is_flat_and_tagged() {
+        let event = DeepLinkEvent {
+            url: "noah://add-task?title=x".into(),
+            source: DeepLinkSource::ColdStart,
+            route: DeepLinkRoute::AddTask { title: "x".into(), due: None },
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"url":"noah://add-task?title=x","source":"cold_start","route":"addTask","title":"x","due":null})
+        );
+    }
+
+    #[test]
+    fn pending_queue_filters_by_route_and_dedupes() {
+        let links = DeepLinks::default();
+        for url in ["noah://task/t1", "noah://auth-callback#data=abc", "noah://task/t1"] {
+            links.queue(DeepLinkEvent { url: url.into(), source: DeepLinkSource::ColdStart, route: parse(url).unwrap() });
+        }
+        let auth = links.take(Some(&["authCallback".to_string()]));
+        assert_eq!(auth.len(), 1);
+        let rest = links.take(None);
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].route.name(), "openTask");
+        assert!(links.take(None).is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+//! yt-dlp 출력에서 진행 상황 읽기.
+//!
+//! 다운로드는 `--progress-template`으로 기계가 읽을 줄(`[noah:download]`, `[noah:postprocess]`)을 받는다.
+//! 템플릿을 모르는 예전 사이드카를 위해 기본 `[download]  42.0% of 10.00MiB at ...` 줄도 읽는다.
+//! 받은 파일 이름은 `Destination:`/`Merging formats into` 줄에서 따라가서 끝난 뒤 크기를 잴 수 있다.
+
+use serde::Serialize;
+
+const DOWNLOAD_TAG: &str = "[noah:download]";
+const POSTPROCESS_TAG: &str = "[noah:postprocess]";
+
+/// 다운로드 진행 템플릿. 값이 없으면 yt-dlp가 `NA`를 넣는다. 파일 이름에는 공백이 있을 수 있어 마지막에
+pub const DOWNLOAD_TEMPLATE: &str = "download:[noah:download] %(progress.status)s %(progress.downloaded_bytes)s \
+     %(progress.total_bytes)s %(progress.total_bytes_estimate)s %(progress.speed)s %(progress.eta)s \
+     %(progress.fragment_index)s %(progress.fragment_count)s %(progress.filename)s";
+/// 후처리(병합, 오디오 추출) 템플릿
+pub const POSTPROCESS_TEMPLATE: &str =
+    "postprocess:[noah:postprocess] %(progress.status)s %(progress.postprocessor)s %(info.filepath)s";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressPhase {
+    #[default]
+    Downloading,
+    /// 파일 하나를 다 받았다 (영상+소리면 다음 파일이 이어진다)
+    Downloaded,
+    /// 병합, 오디오 추출 등
+    Postprocessing,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub phase: ProgressPhase,
+    /// 지금 받는 파일의 0..=100
+    pub percent: Option<f64>,
+    pub downloaded_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+    /// `total_bytes`가 추정치 (조각으로 받는 스트림)
+    pub total_is_estimate: bool,
+    /// bytes/s
+    pub speed: Option<f64>,
+    /// 초
+    pub eta: Option<u64>,
+    pub fragment_index: Option<u32>,
+    pub fragment_count: Option<u32>,
+    /// 후처리기 이름 (`Merger`, `ExtractAudio` ...)
+    pub postprocessor: Option<String>,
+    pub filename: Option<String>,
+}
+
+/// 한 작업의 출력 줄을 차례로 받는다
+#[derive(Debug, Clone, Default)]
+pub struct ProgressParser {
+    progress: DownloadProgress,
+    /// 마지막으로 알게 된 결과 파일 (병합/추출 뒤의 것)
+    output: Option<String>,
+    /// 다 받은 파일들의 크기 합
+    finished_bytes: u64,
+}
+
+fn number(token: &str) -> Option<f64> {
+    if token == "NA" || token == "None" {
+        return None;
+    }
+    token.parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0)
+}
+
+/// `10.00MiB`, `1.5KB`, `512B`
+fn bytes(token: &str) -> Option<u64> {
+    let token = token.trim_start_matches('~');
+    let split = token.find(|c: char| c.is_ascii_alphabetic())?;
+    let (value, unit) = token.split_at(split);
+    let value: f64 = value.parse().ok()?;
+    let scale = match unit {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        "KB" | "kB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some((value * scale).round() as u64)
+}
+
+/// `01:02:03`, `02:03`, `45`
+fn seconds(token: &str) -> Option<u64> {
+    token.split(':').try_fold(0u64, |acc, part| Some(acc * 60 + part.parse::<u64>().ok()?))
+}
+
+fn percent(downloaded: Option<u64>, total: Option<u64>) -> Option<f64> {
+    match (downloaded, total) {
+        (Some(done), Some(total)) if total > 0 => Some((done as f64 / total as f64 * 1000.0).round() / 10.0),
+        _ => None,
+    }
+    .map(|p| p.min(100.0))
+}
+
+fn unquote(value: &str) -> String {
+    value.trim().trim_matches('"').to_string()
+}
+
+impl ProgressParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn progress(&self) -> &DownloadProgress {
+        &self.progress
+    }
+
+    /// 결과 파일 (병합/추출 뒤의 것)
+    pub fn output(&self) -> Option<&str> {
+        self.output.as_deref()
+    }
+
+    /// 다 받은 파일들의 크기 합 (결과 파일 크기를 잴 수 없을 때)
+    pub fn finished_bytes(&self) -> u64 {
+        self.finished_bytes
+    }
+
+    /// 진행 줄이면 바뀐 진행 상황. 다른 줄이면 파일 이름만 따라가고 `None`
+    pub fn feed(&mut self, line: &str) -> Option<&DownloadProgress> {
+        let line = line.trim_end();
+        if let Some(rest) = line.strip_prefix(DOWNLOAD_TAG) {
+            return self.template_download(rest.trim_start()).then_some(&self.progress);
+        }
+        if let Some(rest) = line.strip_prefix(POSTPROCESS_TAG) {
+            return self.template_postprocess(rest.trim_start()).then_some(&self.progress);
+        }
+        if let Some(rest) = line.strip_prefix("[download]") {
+            let rest = rest.trim();
+            if rest.contains('%') && rest.split_whitespace().next().is_some_and(|t| t.ends_with('%')) {
+                return self.legacy_download(rest).then_some(&self.progress);
+            }
+            if let Some(file) = rest.strip_prefix("Destination:") {
+                self.start_file(unquote(file));
+            } else if let Some(file) = rest.strip_suffix("has already been downloaded") {
+                self.output = Some(unquote(file));
+            }
+            return None;
+        }
+        // [Merger] Merging formats into "a.mp4", [ExtractAudio] Destination: a.mp3, [MoveFiles] Moving file "a" to "b"
+        if let Some((tag, rest)) = line.strip_prefix('[').and_then(|l| l.split_once(']')) {
+            let rest = rest.trim();
+            let file = rest
+                .strip_prefix("Merging formats into")
+                .or_else(|| rest.strip_prefix("Destination:"))
+                .or_else(|| rest.rsplit_once("\" to ").map(|(_, to)| to));
+            if let Some(file) = file {
+                self.output = Some(unquote(file));
+                self.progress.phase = ProgressPhase::Postprocessing;
+                self.progress.postprocessor = Some(tag.to_string());
+                return Some(&self.progress);
+            }
+        }
+        None
+    }
+
+    fn start_file(&mut self, file: String) {
+        self.progress = DownloadProgress { filename: Some(file.clone()), ..DownloadProgress::default() };
+        self.output = Some(file);
+    }
+
+    fn finish_file(&mut self) {
+        if self.progress.phase == ProgressPhase::Downloading {
+            self.finished_bytes += self.progress.total_bytes.or(self.progress.downloaded_bytes).unwrap_or(0);
+        }
+        self.progress.phase = ProgressPhase::Downloaded;
+        self.progress.percent = Some(100.0);
+        self.progress.eta = None;
+    }
+
+    /// `status downloaded total estimate speed eta fragment_index fragment_count filename`
+    fn template_download(&mut self, rest: &str) -> bool {
+        let mut tokens = rest.splitn(9, ' ');
+        let mut next = || tokens.next().unwrap_or("NA");
+        let status = next();
+        let values: Vec<Option<f64>> = (0..7).map(|_| number(next())).collect();
+        let filename = next().trim();
+        if !filename.is_empty() && filename != "NA" && self.progress.filename.as_deref() != Some(filename) {
+            self.start_file(filename.to_string());
+        }
+        let (exact, estimate) = (values[1].map(|n| n as u64), values[2].map(|n| n as u64));
+        let progress = &mut self.progress;
+        progress.downloaded_bytes = values[0].map(|n| n as u64);
+        progress.total_bytes = exact.or(estimate);
+        progress.total_is_estimate = exact.is_none() && estimate.is_some();
+        progress.speed = values[3];
+        progress.eta = values[4].map(|n| n as u64);
+        progress.fragment_index = values[5].map(|n| n as u32);
+        progress.fragment_count = values[6].map(|n| n as u32);
+        progress.postprocessor = None;
+        progress.percent = percent(progress.downloaded_bytes, progress.total_bytes);
+        match status {
+            "finished" => self.finish_file(),
+            "downloading" => self.progress.phase = ProgressPhase::Downloading,
+            "error" => return false,
+            _ => {}
+        }
+        true
+    }
+
+    /// `status postprocessor filepath`
+    fn template_postprocess(&mut self, rest: &str) -> bool {
+        let mut tokens = rest.splitn(3, ' ');
+        let status = tokens.next().unwrap_or_default();
+        let name = tokens.next().filter(|n| *n != "NA").map(str::to_string);
+        if let Some(file) = tokens.next().map(str::trim).filter(|f| !f.is_empty() && *f != "NA") {
+            self.output = Some(file.to_string());
+        }
+        self.progress.phase = ProgressPhase::Postprocessing;
+        self.progress.postprocessor = name;
+        self.progress.eta = None;
+        self.progress.speed = None;
+        matches!(status, "started" | "processing" | "finished")
+    }
+
+    /// `42.0% of ~  10.00MiB at  1.00MiB/s ETA 00:05 (frag 3/10)` 또는 `100% of 10.00MiB in 00:00:03 at 2.90MiB/s`
+    fn legacy_download(&mut self, rest: &str) -> bool {
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        let Some(percent) = tokens.first().and_then(|t| number(t.trim_end_matches('%'))) else {
+            return false;
+        };
+        let after = |key: &str| tokens.iter().position(|t| *t == key).and_then(|i| tokens.get(i + 1)).copied();
+        // `of ~ 10.00MiB` 또는 `of ~10.00MiB`
+        let size = tokens.iter().position(|t| *t == "of").and_then(|i| tokens.get(i + 1..));
+        let estimate = size.and_then(|rest| rest.first()).is_some_and(|t| t.starts_with('~'));
+        let total = size.and_then(|rest| rest.iter().find(|t| **t != "~")).and_then(|t| bytes(t));
+        let frag = tokens.iter().position(|t| *t == "(frag").and_then(|i| tokens.get(i + 1));
+        let frag = frag.and_then(|f| f.trim_end_matches(')').split_once('/'));
+        let progress = &mut self.progress;
+        progress.percent = Some(percent.min(100.0));
+        progress.total_bytes = total;
+        progress.total_is_estimate = estimate;
+        progress.downloaded_bytes = total.map(|t| (t as f64 * percent / 100.0).round() as u64);
+        progress.speed = after("at").and_then(|s| s.strip_suffix("/s")).and_then(bytes).map(|b| b as f64);
+        progress.eta = after("ETA").and_then(seconds);
+        progress.fragment_index = frag.and_then(|(i, _)| i.parse().ok());
+        progress.fragment_count = frag.and_then(|(_, n)| n.parse().ok());
+        progress.postprocessor = None;
+        // 다 받은 줄에는 ETA 대신 걸린 시간(`in`)이 있다
+        if percent >= 100.0 && after("in").is_some() {
+            self.finish_file();
+        } else {
+            self.progress.phase = ProgressPhase::Downloading;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(fixture: &str) -> (ProgressParser, Vec<DownloadProgress>) {
+        let mut parser = ProgressParser::new();
+        let updates = fixture.lines().filter_map(|line| parser.feed(line).cloned()).collect();
+        (parser, updates)
+    }
+
+    #[test]
+    fn template_output_for_a_merged_video() {
+        let (parser, updates) = run(include_str!("../fixtures/yt-dlp/template-merge.txt"));
+        let first = &updates[0];
+        assert_eq!(first.phase, ProgressPhase::Downloading);
+        assert_eq!((first.downloaded_bytes, first.total_bytes), (Some(1024), Some(52_428_800)));
+        assert_eq!((first.percent, first.eta, first.speed), (Some(0.0), Some(38), Some(1_379_712.5)));
+        assert_eq!(first.filename.as_deref(), Some("/home/me/Downloads/Big Buck Bunny [aqz-KE-bpKQ].f137.mp4"));
+
+        let video_done = updates.iter().find(|u| u.phase == ProgressPhase::Downloaded).unwrap();
+        assert_eq!((video_done.percent, video_done.downloaded_bytes), (Some(100.0), Some(52_428_800)));
+        // 두 번째 파일(소리)은 처음부터
+        let audio = updates.iter().find(|u| u.filename.as_deref().is_some_and(|f| f.ends_with(".f140.m4a"))).unwrap();
+        assert_eq!((audio.phase, audio.percent), (ProgressPhase::Downloading, Some(25.0)));
+
+        let last = updates.last().unwrap();
+        assert_eq!(last.phase, ProgressPhase::Postprocessing);
+        assert_eq!(last.postprocessor.as_deref(), Some("Merger"));
+        assert_eq!(parser.output(), Some("/home/me/Downloads/Big Buck Bunny [aqz-KE-bpKQ].mp4"));
+        assert_eq!(parser.finished_bytes(), 52_428_800 + 3_276_800);
+    }
+
+    #[test]
+    fn template_output_for_fragments_and_audio_extraction() {
+        let (parser, updates) = run(include_str!("../fixtures/yt-dlp/template-fragments-audio.txt"));
+        let estimate = &updates[1];
+        assert!(estimate.total_is_estimate);
+        assert_eq!((estimate.fragment_index, estimate.fragment_count), (Some(3), Some(120)));
+        assert_eq!(estimate.total_bytes, Some(10_485_760));
+        assert_eq!(estimate.eta, None);
+        let extracting = updates.iter().find(|u| u.phase == ProgressPhase::Postprocessing).unwrap();
+        assert_eq!(extracting.postprocessor.as_deref(), Some("ExtractAudio"));
+        assert_eq!(parser.output(), Some("C:\\Users\\me\\Downloads\\Live Set.mp3"));
+    }
+
+    #[test]
+    fn default_output_without_a_template() {
+        let (parser, updates) = run(include_str!("../fixtures/yt-dlp/default.txt"));
+        let first = &updates[0];
+        assert_eq!(first.percent, Some(0.0));
+        assert_eq!(first.total_bytes, Some(10_485_760));
+        assert_eq!((first.speed, first.eta), (None, None));
+        let mid = &updates[1];
+        assert_eq!((mid.percent, mid.downloaded_bytes), (Some(42.0), Some(4_404_019)));
+        assert_eq!((mid.speed, mid.eta), (Some(1_048_576.0), Some(65)));
+
+        let done = updates.iter().find(|u| u.phase == ProgressPhase::Downloaded).unwrap();
+        assert_eq!((done.percent, done.filename.as_deref()), (Some(100.0), Some("/tmp/out/clip.mp4")));
+
+        // 재생목록의 두 번째 항목은 조각으로 받는 스트림
+        let hls = updates.iter().find(|u| u.fragment_count.is_some()).unwrap();
+        assert!(hls.total_is_estimate);
+        assert_eq!(hls.total_bytes, Some(54_525_952));
+        assert_eq!((hls.fragment_index, hls.fragment_count, hls.eta), (Some(3), Some(10), Some(3_723)));
+        assert_eq!(parser.output(), Some("/tmp/out/stream.mp4"));
+        assert_eq!(parser.finished_bytes(), 10_485_760 + 54_525_952);
+    }
+
+    #[test]
+    fn units_and_garbage() {
+        assert_eq!(bytes("1.5KB"), Some(1_500));
+        assert_eq!(bytes("~2.00GiB"), Some(2_147_483_648));
+        assert_eq!(bytes("Unknown"), None);
+        assert_eq!(seconds("01:02:03"), Some(3_723));
+        assert_eq!(seconds("Unknown"), None);
+        let mut parser = ProgressParser::new();
+        assert!(parser.feed("[youtube] aqz-KE-bpKQ: Downloading webpage").is_none());
+        assert!(parser.feed("[download] 42").is_none());
+        assert!(parser.feed("").is_none());
+    }
+}
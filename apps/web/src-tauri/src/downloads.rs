@@ -0,0 +1,760 @@
+//! 미디어 다운로드 대기열 (yt-dlp 사이드카).
+//!
+//! 작업은 로컬 DB의 `downloads` 테이블에 두고 Rust 스레드가 `externalBin`의 yt-dlp를 띄운다.
+//! 페이지를 떠나도 계속 받고, 받던 중에 앱을 끄면 다음에 켤 때 대기열로 돌아가 이어 받는다 (yt-dlp는 `.part`부터).
+//! 동시에 받는 수는 downloads.json의 `concurrency`. 일시 정지와 취소는 프로세스를 죽이고, 다시 받기는 대기열 끝으로.
+//! 상태가 바뀌거나 출력 줄이 오면 `on_event`로 알린다 (`download-progress`, 작업 id별).
+//! 진행 줄은 `download_progress`가 읽어 `progress`로 보내고, 끝나면 받은 파일과 크기를 기록한다.
+
+use crate::download_progress::{DownloadProgress, ProgressParser, DOWNLOAD_TEMPLATE, POSTPROCESS_TEMPLATE};
+use crate::storage::{new_document_id, LocalDb, StorageError};
+use crate::token_store::{now_ms, write_atomic};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// 동시 다운로드 수 한도
+const MAX_CONCURRENCY: usize = 8;
+/// 대기열을 다시 보는 간격 (깨우는 신호를 놓쳐도 이 안에)
+const MAX_WAIT: Duration = Duration::from_secs(30);
+/// 화질을 고르지 않았을 때
+const DEFAULT_FORMAT: &str = "bestvideo+bestaudio/best";
+/// 진행 이벤트 최소 간격 (단계가 바뀌면 바로)
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Paused => "paused",
+            JobState::Completed => "completed",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "queued" => JobState::Queued,
+            "running" => JobState::Running,
+            "paused" => JobState::Paused,
+            "completed" => JobState::Completed,
+            "cancelled" => JobState::Cancelled,
+            _ => JobState::Failed,
+        }
+    }
+}
+
+/// 받을 것. `output_dir`의 `~`와 환경 변수는 yt-dlp가 푼다
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRequest {
+    pub url: String,
+    /// yt-dlp `-f` 형식 (없으면 최고 화질)
+    #[serde(default)]
+    pub format: Option<String>,
+    /// mp3로 소리만
+    #[serde(default)]
+    pub audio_only: bool,
+    pub output_dir: String,
+}
+
+impl DownloadRequest {
+    /// yt-dlp 인자. 진행 줄을 한 줄씩, 읽을 수 있는 모양으로 받도록 `--newline`과 `--progress-template`
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec!["--newline".to_string()];
+        for template in [DOWNLOAD_TEMPLATE, POSTPROCESS_TEMPLATE] {
+            args.extend(["--progress-template".to_string(), template.to_string()]);
+        }
+        if self.audio_only {
+            args.extend(["-x", "--audio-format", "mp3"].map(String::from));
+        } else {
+            let format = self.format.as_deref().filter(|f| !f.trim().is_empty()).unwrap_or(DEFAULT_FORMAT);
+            args.extend(["-f", format, "--merge-output-format", "mp4"].map(String::from));
+        }
+        let dir = self.output_dir.trim_end_matches(['/', '\\']);
+        args.extend(["-o".to_string(), format!("{}/%(title)s.%(ext)s", dir), "--".to_string(), self.url.clone()]);
+        args
+    }
+
+    fn validate(&self) -> Result<(), StorageError> {
+        check_url(&self.url)?;
+        if self.output_dir.trim().is_empty() {
+            return Err(StorageError::Invalid("download needs an output folder".into()));
+        }
+        Ok(())
+    }
+}
+
+fn check_url(url: &str) -> Result<(), StorageError> {
+    let trimmed = url.trim();
+    if !(trimmed.starts_with("https://") || trimmed.starts_with("http://")) {
+        return Err(StorageError::Invalid(format!("download url must be http(s): {}", url)));
+    }
+    Ok(())
+}
+
+/// 받기 전에 영상 정보만 읽는 yt-dlp 인자 (`--dump-json`)
+pub fn probe_args(url: &str) -> Result<Vec<String>, StorageError> {
+    check_url(url)?;
+    Ok(["--dump-json", "--no-playlist", "--no-warnings", "--", url.trim()].map(String::from).to_vec())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadJob {
+    pub id: String,
+    #[serde(flatten)]
+    pub request: DownloadRequest,
+    pub state: JobState,
+    /// 실행한 횟수 (다시 받기, 이어 받기 포함)
+    pub attempts: u32,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// 받은 파일 (병합/추출 뒤의 것, 끝난 뒤)
+    #[serde(default)]
+    pub file: Option<String>,
+    /// 받은 파일 크기 (bytes)
+    #[serde(default)]
+    pub size: Option<u64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// `download-progress`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadEvent {
+    pub job_id: String,
+    pub state: JobState,
+    /// yt-dlp 출력 한 줄 (진행 줄이거나 상태만 바뀌었으면 없음)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<DownloadProgress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DownloadSettings {
+    /// 동시에 받는 수 (1..=8)
+    pub concurrency: usize,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self { concurrency: 2 }
+    }
+}
+
+/// 띄운 yt-dlp에서 오는 것
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChildEvent {
+    Stdout(String),
+    Stderr(String),
+    /// 종료 코드 (시그널로 죽었으면 없음)
+    Exit(Option<i32>),
+    Error(String),
+}
+
+/// 띄운 프로세스. `events`가 닫히면 끝난 것으로 본다
+pub struct ChildProcess {
+    pub events: Receiver<ChildEvent>,
+    pub kill: Box<dyn FnOnce() + Send>,
+}
+
+const SELECT: &str = "SELECT id, request, state, attempts, error, created_at, updated_at, file, size FROM downloads";
+
+fn row_to_job(row: &Row<'_>) -> rusqlite::Result<DownloadJob> {
+    let request: String = row.get(1)?;
+    let request = serde_json::from_str(&request).unwrap_or_else(|_| DownloadRequest {
+        url: String::new(),
+        format: None,
+        audio_only: false,
+        output_dir: String::new(),
+    });
+    let state: String = row.get(2)?;
+    Ok(DownloadJob {
+        id: row.get(0)?,
+        request,
+        state: JobState::parse(&state),
+        attempts: row.get(3)?,
+        error: row.get(4)?,
+        file: row.get(7)?,
+        size: row.get::<_, Option<i64>>(8)?.map(|n| n.max(0) as u64),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+pub(crate) fn insert(conn: &Connection, job: &DownloadJob) -> Result<(), StorageError> {
+    let request = serde_json::to_string(&job.request).expect("download request serializes");
+    conn.prepare_cached(
+        "INSERT INTO downloads (id, request, state, attempts, error, file, size, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?
+    .execute(params![
+        job.id,
+        request,
+        job.state.as_str(),
+        job.attempts,
+        job.error,
+        job.file,
+        job.size.map(|n| n as i64),
+        job.created_at,
+        job.updated_at
+    ])?;
+    Ok(())
+}
+
+pub(crate) fn get(conn: &Connection, id: &str) -> Result<Option<DownloadJob>, StorageError> {
+    Ok(conn.prepare_cached(&format!("{} WHERE id = ?1", SELECT))?.query_row([id], row_to_job).optional()?)
+}
+
+/// 넣은 순서대로
+pub(crate) fn list(conn: &Connection) -> Result<Vec<DownloadJob>, StorageError> {
+    let mut stmt = conn.prepare_cached(&format!("{} ORDER BY created_at, rowid", SELECT))?;
+    let rows = stmt.query_map([], row_to_job)?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+fn set_state(conn: &Connection, id: &str, state: JobState, error: Option<&str>) -> Result<(), StorageError> {
+    conn.prepare_cached("UPDATE downloads SET state = ?2, error = ?3, updated_at = ?4 WHERE id = ?1")?
+        .execute(params![id, state.as_str(), error, now_ms() as i64])?;
+    Ok(())
+}
+
+type Launcher = Box<dyn Fn(&[String]) -> Result<ChildProcess, String> + Send + Sync>;
+type EventListener = Box<dyn Fn(&DownloadEvent) + Send + Sync>;
+
+struct Running {
+    /// 같은 작업을 멈췄다 바로 다시 받을 때 앞 프로세스의 종료를 가려낸다
+    run: u64,
+    kill: Box<dyn FnOnce() + Send>,
+}
+
+struct State {
+    settings: DownloadSettings,
+    running: HashMap<String, Running>,
+    next_run: u64,
+    wake: bool,
+}
+
+pub struct Downloads {
+    db: Arc<LocalDb>,
+    settings_path: PathBuf,
+    state: Mutex<State>,
+    signal: Condvar,
+    launch: Launcher,
+    on_event: EventListener,
+}
+
+impl Downloads {
+    /// `launch`는 yt-dlp를 인자와 함께 띄운다. `settings_path`(downloads.json)가 없으면 기본 설정
+    pub fn new<L, F>(db: Arc<LocalDb>, settings_path: PathBuf, launch: L, on_event: F) -> Self
+    where
+        L: Fn(&[String]) -> Result<ChildProcess, String> + Send + Sync + 'static,
+        F: Fn(&DownloadEvent) + Send + Sync + 'static,
+    {
+        let settings = match std::fs::read(&settings_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                eprintln!("{} 무시: {}", settings_path.display(), e);
+                DownloadSettings::default()
+            }),
+            Err(_) => DownloadSettings::default(),
+        };
+        Self {
+            db,
+            settings_path,
+            state: Mutex::new(State { settings, running: HashMap::new(), next_run: 0, wake: false }),
+            signal: Condvar::new(),
+            launch: Box::new(launch),
+            on_event: Box::new(on_event),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wake(&self) {
+        self.lock().wake = true;
+        self.signal.notify_all();
+    }
+
+    fn emit(&self, job_id: &str, state: JobState, line: Option<String>, error: Option<String>) {
+        (self.on_event)(&DownloadEvent { job_id: job_id.to_string(), state, line, progress: None, error });
+    }
+
+    fn emit_progress(&self, job_id: &str, progress: &DownloadProgress) {
+        let progress = Some(progress.clone());
+        let (state, line, error) = (JobState::Running, None, None);
+        (self.on_event)(&DownloadEvent { job_id: job_id.to_string(), state, line, progress, error });
+    }
+
+    fn job(&self, id: &str) -> Result<DownloadJob, StorageError> {
+        get(&self.db.conn(), id)?.ok_or_else(|| StorageError::Invalid(format!("no download {}", id)))
+    }
+
+    fn transition(&self, id: &str, state: JobState, error: Option<&str>) -> Result<DownloadJob, StorageError> {
+        set_state(&self.db.conn(), id, state, error)?;
+        self.emit(id, state, None, error.map(str::to_string));
+        self.job(id)
+    }
+
+    pub fn settings(&self) -> DownloadSettings {
+        self.lock().settings.clone()
+    }
+
+    pub fn set_settings(&self, settings: DownloadSettings) -> io::Result<()> {
+        if !(1..=MAX_CONCURRENCY).contains(&settings.concurrency) {
+            let msg = format!("download concurrency must be 1..={}: {}", MAX_CONCURRENCY, settings.concurrency);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        }
+        if let Some(dir) = self.settings_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec_pretty(&settings).expect("download settings serialize");
+        write_atomic(&self.settings_path, &json, false)?;
+        self.lock().settings = settings;
+        self.wake();
+        Ok(())
+    }
+
+    pub fn list(&self) -> Result<Vec<DownloadJob>, StorageError> {
+        list(&self.db.conn())
+    }
+
+    /// 대기열 끝에 넣는다
+    pub fn enqueue(&self, request: DownloadRequest) -> Result<DownloadJob, StorageError> {
+        request.validate()?;
+        let now = now_ms() as i64;
+        let job = DownloadJob {
+            id: new_document_id(),
+            request,
+            state: JobState::Queued,
+            attempts: 0,
+            error: None,
+            file: None,
+            size: None,
+            created_at: now,
+            updated_at: now,
+        };
+        insert(&self.db.conn(), &job)?;
+        self.emit(&job.id, job.state, None, None);
+        self.wake();
+        Ok(job)
+    }
+
+    /// 받던 것이면 프로세스를 죽인다. 다시 받으면 받은 데까지 이어 받는다
+    pub fn pause(&self, id: &str) -> Result<DownloadJob, StorageError> {
+        match self.job(id)?.state {
+            JobState::Queued | JobState::Running => self.stop(id, JobState::Paused),
+            _ => self.job(id),
+        }
+    }
+
+    pub fn resume(&self, id: &str) -> Result<DownloadJob, StorageError> {
+        match self.job(id)?.state {
+            JobState::Paused => self.requeue(id),
+            _ => self.job(id),
+        }
+    }
+
+    pub fn cancel(&self, id: &str) -> Result<DownloadJob, StorageError> {
+        match self.job(id)?.state {
+            JobState::Queued | JobState::Running | JobState::Paused => self.stop(id, JobState::Cancelled),
+            _ => self.job(id),
+        }
+    }
+
+    /// 실패하거나 취소한 작업을 대기열 끝으로
+    pub fn retry(&self, id: &str) -> Result<DownloadJob, StorageError> {
+        match self.job(id)?.state {
+            JobState::Failed | JobState::Cancelled => {
+                let sql = "UPDATE downloads SET created_at = ?2 WHERE id = ?1";
+                self.db.conn().prepare_cached(sql)?.execute(params![id, now_ms() as i64])?;
+                self.requeue(id)
+            }
+            _ => self.job(id),
+        }
+    }
+
+    /// 목록에서 지운다 (받는 중이면 먼저 취소한다). 받은 파일은 그대로
+    pub fn remove(&self, id: &str) -> Result<bool, StorageError> {
+        if self.job(id).is_ok_and(|job| job.state == JobState::Running) {
+            self.stop(id, JobState::Cancelled)?;
+        }
+        Ok(self.db.conn().prepare_cached("DELETE FROM downloads WHERE id = ?1")?.execute([id])? > 0)
+    }
+
+    fn requeue(&self, id: &str) -> Result<DownloadJob, StorageError> {
+        let job = self.transition(id, JobState::Queued, None)?;
+        self.wake();
+        Ok(job)
+    }
+
+    fn stop(&self, id: &str, state: JobState) -> Result<DownloadJob, StorageError> {
+        let running = self.lock().running.remove(id);
+        let job = self.transition(id, state, None)?;
+        if let Some(running) = running {
+            (running.kill)();
+        }
+        self.wake();
+        Ok(job)
+    }
+
+    /// 자리가 나는 만큼 대기열 앞에서부터 띄운다. 띄운 작업 id
+    pub fn run_pending(self: &Arc<Self>) -> Result<Vec<String>, StorageError> {
+        let mut started = Vec::new();
+        loop {
+            let free = {
+                let state = self.lock();
+                state.settings.concurrency.saturating_sub(state.running.len())
+            };
+            if free == 0 {
+                break;
+            }
+            let next = list(&self.db.conn())?.into_iter().find(|job| job.state == JobState::Queued);
+            let Some(job) = next else {
+                break;
+            };
+            started.push(job.id.clone());
+            self.start(job)?;
+        }
+        Ok(started)
+    }
+
+    fn start(self: &Arc<Self>, job: DownloadJob) -> Result<(), StorageError> {
+        self.db
+            .conn()
+            .prepare_cached(
+                "UPDATE downloads SET state = 'running', attempts = attempts + 1, error = NULL, updated_at = ?2
+                 WHERE id = ?1",
+            )?
+            .execute(params![job.id, now_ms() as i64])?;
+        self.emit(&job.id, JobState::Running, None, None);
+        let child = match (self.launch)(&job.request.args()) {
+            Ok(child) => child,
+            Err(e) => {
+                self.transition(&job.id, JobState::Failed, Some(&e))?;
+                return Ok(());
+            }
+        };
+        let run = {
+            let mut state = self.lock();
+            state.next_run += 1;
+            let run = state.next_run;
+            state.running.insert(job.id.clone(), Running { run, kill: child.kill });
+            run
+        };
+        // 띄우는 사이에 멈추거나 취소했으면 바로 죽인다
+        if self.job(&job.id)?.state != JobState::Running {
+            if let Some(running) = self.lock().running.remove(&job.id) {
+                (running.kill)();
+            }
+        }
+        let downloads = Arc::clone(self);
+        let events = child.events;
+        std::thread::spawn(move || downloads.watch(&job.id, run, events));
+        Ok(())
+    }
+
+    /// 프로세스가 끝날 때까지 출력을 넘기고, 끝나면 상태를 정한다
+    fn watch(&self, id: &str, run: u64, events: Receiver<ChildEvent>) {
+        let mut parser = ProgressParser::new();
+        let mut last_sent: Option<(Instant, DownloadProgress)> = None;
+        let mut last_error: Option<String> = None;
+        let mut code = None;
+        for event in events.iter() {
+            match event {
+                ChildEvent::Stdout(line) | ChildEvent::Stderr(line) => {
+                    if line.starts_with("ERROR:") {
+                        last_error = Some(line.trim_start_matches("ERROR:").trim().to_string());
+                    }
+                    let Some(progress) = parser.feed(&line) else {
+                        self.emit(id, JobState::Running, Some(line), None);
+                        continue;
+                    };
+                    // 진행 줄은 몇 ms마다 오므로 줄인다. 단계나 파일이 바뀐 것은 바로
+                    let due = last_sent.as_ref().is_none_or(|(at, sent)| {
+                        at.elapsed() >= PROGRESS_INTERVAL
+                            || sent.phase != progress.phase
+                            || sent.filename != progress.filename
+                            || sent.postprocessor != progress.postprocessor
+                    });
+                    if due {
+                        self.emit_progress(id, progress);
+                        last_sent = Some((Instant::now(), progress.clone()));
+                    }
+                }
+                ChildEvent::Error(e) => last_error = Some(e),
+                ChildEvent::Exit(exit) => {
+                    code = exit;
+                    break;
+                }
+            }
+        }
+        // 멈추거나 취소해서 죽인 것이면 이미 상태가 정해져 있다
+        let ours = {
+            let mut state = self.lock();
+            match state.running.get(id) {
+                Some(running) if running.run == run => state.running.remove(id).is_some(),
+                _ => false,
+            }
+        };
+        if ours {
+            let result = match code {
+                Some(0) => {
+                    self.record_output(id, &parser);
+                    self.transition(id, JobState::Completed, None)
+                }
+                _ => {
+                    let error = last_error.unwrap_or_else(|| match code {
+                        Some(code) => format!("yt-dlp exited with code {}", code),
+                        None => "yt-dlp was terminated".to_string(),
+                    });
+                    self.transition(id, JobState::Failed, Some(&error))
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("다운로드 상태 기록 실패 ({}): {}", id, e);
+            }
+        }
+        self.wake();
+    }
+
+    /// 받은 파일과 크기. 파일을 잴 수 없으면 (옮겨졌거나 이름을 못 읽었으면) 받은 바이트 합
+    fn record_output(&self, id: &str, parser: &ProgressParser) {
+        let file = parser.output().map(str::to_string);
+        let measured = file.as_deref().and_then(|f| std::fs::metadata(f).ok()).map(|m| m.len());
+        let size = measured.or(Some(parser.finished_bytes()).filter(|n| *n > 0));
+        let sql = "UPDATE downloads SET file = ?2, size = ?3 WHERE id = ?1";
+        let result = self.db.conn().prepare_cached(sql).and_then(|mut stmt| {
+            stmt.execute(params![id, file, size.map(|n| n as i64)])
+        });
+        if let Err(e) = result {
+            eprintln!("받은 파일 기록 실패 ({}): {}", id, e);
+        }
+    }
+
+    /// 앱을 끄는 동안 받던 작업을 대기열로 돌린다
+    fn recover(&self) -> Result<(), StorageError> {
+        let running = self.lock().running.keys().cloned().collect::<Vec<_>>();
+        for job in self.list()?.into_iter().filter(|job| job.state == JobState::Running) {
+            if !running.contains(&job.id) {
+                self.transition(&job.id, JobState::Queued, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 대기열 스레드를 띄운다
+    pub fn spawn(self: &Arc<Self>) {
+        if let Err(e) = self.recover() {
+            eprintln!("다운로드 대기열 복구 실패: {}", e);
+        }
+        let downloads = Arc::clone(self);
+        std::thread::spawn(move || loop {
+            if let Err(e) = downloads.run_pending() {
+                eprintln!("다운로드 시작 실패: {}", e);
+            }
+            let mut guard = downloads.lock();
+            if !guard.wake {
+                guard = downloads.signal.wait_timeout(guard, MAX_WAIT).unwrap_or_else(|e| e.into_inner()).0;
+            }
+            guard.wake = false;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download_progress::ProgressPhase;
+    use std::sync::mpsc::{channel, Sender};
+    use std::time::Instant;
+
+    /// 띄운 가짜 yt-dlp: 인자, 출력을 보낼 곳, 죽였는지
+    type Spawned = Arc<Mutex<Vec<(Vec<String>, Sender<ChildEvent>, Arc<Mutex<bool>>)>>>;
+
+    fn downloads() -> (Arc<Downloads>, Spawned, Arc<Mutex<Vec<DownloadEvent>>>) {
+        let dir = std::env::temp_dir().join(format!("noah-downloads-{}", new_document_id()));
+        let spawned: Spawned = Arc::default();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (spawn_sink, event_sink) = (Arc::clone(&spawned), Arc::clone(&events));
+        let launch = move |args: &[String]| {
+            if args.iter().any(|a| a.contains("no-sidecar")) {
+                return Err("sidecar not found".to_string());
+            }
+            let (tx, rx) = channel();
+            let killed = Arc::new(Mutex::new(false));
+            let (flag, exit) = (Arc::clone(&killed), tx.clone());
+            spawn_sink.lock().unwrap().push((args.to_vec(), tx, killed));
+            let kill = move || {
+                *flag.lock().unwrap() = true;
+                let _ = exit.send(ChildEvent::Exit(None));
+            };
+            Ok(ChildProcess { events: rx, kill: Box::new(kill) })
+        };
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let downloads = Downloads::new(db, dir.join("downloads.json"), launch, move |event: &DownloadEvent| {
+            event_sink.lock().unwrap().push(event.clone())
+        });
+        (Arc::new(downloads), spawned, events)
+    }
+
+    fn request(url: &str) -> DownloadRequest {
+        DownloadRequest { url: url.into(), format: None, audio_only: false, output_dir: "~/Downloads/".into() }
+    }
+
+    fn wait_for(downloads: &Downloads, id: &str, state: JobState) -> DownloadJob {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let job = downloads.job(id).unwrap();
+            if job.state == state || Instant::now() > deadline {
+                assert_eq!(job.state, state, "{}", id);
+                return job;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn request_args() {
+        let video = request("https://youtu.be/x");
+        assert_eq!(
+            video.args(),
+            [
+                "--newline",
+                "--progress-template",
+                DOWNLOAD_TEMPLATE,
+                "--progress-template",
+                POSTPROCESS_TEMPLATE,
+                "-f",
+                "bestvideo+bestaudio/best",
+                "--merge-output-format",
+                "mp4",
+                "-o",
+                "~/Downloads/%(title)s.%(ext)s",
+                "--",
+                "https://youtu.be/x"
+            ]
+        );
+        let audio = DownloadRequest { audio_only: true, ..video };
+        assert_eq!(audio.args()[5..8], ["-x", "--audio-format", "mp3"]);
+        assert_eq!(probe_args(" https://youtu.be/x ").unwrap()[3..], ["--", "https://youtu.be/x"]);
+        assert!(probe_args("--exec=rm").is_err());
+    }
+
+    #[test]
+    fn queue_respects_concurrency_and_reports_results() {
+        let (downloads, spawned, events) = downloads();
+        downloads.set_settings(DownloadSettings { concurrency: 1 }).unwrap();
+        assert!(downloads.set_settings(DownloadSettings { concurrency: 0 }).is_err());
+        assert!(downloads.enqueue(request("file:///etc/passwd")).is_err());
+        let a = downloads.enqueue(request("https://example.com/a")).unwrap();
+        let b = downloads.enqueue(request("https://example.com/b")).unwrap();
+
+        // 한 번에 하나
+        assert_eq!(downloads.run_pending().unwrap(), [a.id.as_str()]);
+        assert!(downloads.run_pending().unwrap().is_empty());
+        let tx = spawned.lock().unwrap()[0].1.clone();
+        tx.send(ChildEvent::Stdout("[download] Destination: /nonexistent/a.mp4".into())).unwrap();
+        for done in [512, 1024, 1536] {
+            let line = format!("[noah:download] downloading {} 2048 NA 1024.0 1 NA NA /nonexistent/a.mp4", done);
+            tx.send(ChildEvent::Stdout(line)).unwrap();
+        }
+        let line = "[noah:download] finished 2048 2048 NA NA NA NA NA /nonexistent/a.mp4";
+        tx.send(ChildEvent::Stdout(line.into())).unwrap();
+        tx.send(ChildEvent::Exit(Some(0))).unwrap();
+        let done = wait_for(&downloads, &a.id, JobState::Completed);
+        assert_eq!(done.attempts, 1);
+        // 파일을 잴 수 없으면 받은 바이트 합
+        assert_eq!((done.file.as_deref(), done.size), (Some("/nonexistent/a.mp4"), Some(2048)));
+        let sent = events.lock().unwrap().clone();
+        let lines = sent.iter().filter_map(|e| e.line.as_deref()).collect::<Vec<_>>();
+        assert_eq!(lines, ["[download] Destination: /nonexistent/a.mp4"]);
+        // 진행 줄은 줄여서 보내지만 다 받은 것은 바로
+        let progress = sent.iter().filter_map(|e| e.progress.as_ref()).collect::<Vec<_>>();
+        assert_eq!(progress.len(), 2, "{:?}", progress);
+        assert_eq!((progress[0].downloaded_bytes, progress[0].percent), (Some(512), Some(25.0)));
+        assert_eq!((progress[1].phase, progress[1].percent), (ProgressPhase::Downloaded, Some(100.0)));
+
+        assert_eq!(downloads.run_pending().unwrap(), [b.id.as_str()]);
+        let tx = spawned.lock().unwrap()[1].1.clone();
+        tx.send(ChildEvent::Stderr("ERROR: [generic] Unsupported URL: https://example.com/b".into())).unwrap();
+        tx.send(ChildEvent::Exit(Some(1))).unwrap();
+        let failed = wait_for(&downloads, &b.id, JobState::Failed);
+        assert_eq!(failed.size, None);
+        assert_eq!(failed.error.as_deref(), Some("[generic] Unsupported URL: https://example.com/b"));
+
+        // 다시 받기는 대기열 끝으로
+        assert_eq!(downloads.retry(&b.id).unwrap().state, JobState::Queued);
+        assert_eq!(downloads.run_pending().unwrap(), [b.id.as_str()]);
+        assert_eq!(downloads.job(&b.id).unwrap().attempts, 2);
+
+        // 사이드카를 못 띄우면 실패로 남고 다음 작업으로 넘어간다
+        spawned.lock().unwrap()[2].1.send(ChildEvent::Exit(Some(0))).unwrap();
+        wait_for(&downloads, &b.id, JobState::Completed);
+        let broken = downloads.enqueue(request("https://example.com/no-sidecar")).unwrap();
+        downloads.run_pending().unwrap();
+        assert_eq!(downloads.job(&broken.id).unwrap().error.as_deref(), Some("sidecar not found"));
+        assert!(events.lock().unwrap().iter().any(|e| e.job_id == broken.id && e.state == JobState::Failed));
+    }
+
+    #[test]
+    fn pause_and_cancel_kill_the_process() {
+        let (downloads, spawned, _) = downloads();
+        let a = downloads.enqueue(request("https://example.com/a")).unwrap();
+        let b = downloads.enqueue(request("https://example.com/b")).unwrap();
+        downloads.run_pending().unwrap();
+
+        assert_eq!(downloads.pause(&a.id).unwrap().state, JobState::Paused);
+        assert!(*spawned.lock().unwrap()[0].2.lock().unwrap());
+        // 죽은 프로세스의 종료가 상태를 덮지 않는다
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(downloads.job(&a.id).unwrap().state, JobState::Paused);
+        assert_eq!(downloads.resume(&a.id).unwrap().state, JobState::Queued);
+        assert_eq!(downloads.run_pending().unwrap(), [a.id.as_str()]);
+        assert_eq!(downloads.job(&a.id).unwrap().attempts, 2);
+
+        assert_eq!(downloads.cancel(&b.id).unwrap().state, JobState::Cancelled);
+        assert!(*spawned.lock().unwrap()[1].2.lock().unwrap());
+        // 끝난 작업은 멈추거나 이어 받을 수 없다
+        assert_eq!(downloads.pause(&b.id).unwrap().state, JobState::Cancelled);
+        assert_eq!(downloads.resume(&b.id).unwrap().state, JobState::Cancelled);
+
+        assert!(downloads.remove(&a.id).unwrap());
+        assert!(*spawned.lock().unwrap()[2].2.lock().unwrap());
+        assert_eq!(downloads.list().unwrap().iter().map(|j| j.id.as_str()).collect::<Vec<_>>(), [b.id.as_str()]);
+    }
+
+    #[test]
+    fn jobs_running_at_shutdown_are_queued_again() {
+        let (downloads, _, _) = downloads();
+        let a = downloads.enqueue(request("https://example.com/a")).unwrap();
+        set_state(&downloads.db.conn(), &a.id, JobState::Running, None).unwrap();
+        downloads.recover().unwrap();
+        assert_eq!(downloads.job(&a.id).unwrap().state, JobState::Queued);
+    }
+}
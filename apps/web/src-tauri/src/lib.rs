@@ -1,326 +1,1020 @@
-use tauri::Emitter;
-use std::io::{Read, Write};
-use std::net::TcpListener;
-
-const LOGIN_HTML: &str = r##"<!DOCTYPE html>
-<html lang="ko">
-<head>
-<meta charset="utf-8">
-<meta name="viewport" content="width=device-width, initial-scale=1">
-<title>NOAH - Google Login</title>
-<style>
-  * { margin: 0; padding: 0; box-sizing: border-box; }
-  body { font-family: system-ui, -apple-system, sans-serif; background: #08081a; color: #e2e8f0; display: flex; align-items: center; justify-content: center; min-height: 100vh; }
-  .card { background: #111128; border: 1px solid #1e1e3a; border-radius: 16px; padding: 48px; text-align: center; max-width: 400px; width: 90%; }
-  h1 { font-size: 28px; margin-bottom: 8px; background: linear-gradient(to right, #e2e8f0, #e94560); -webkit-background-clip: text; -webkit-text-fill-color: transparent; }
-  p { color: #94a3b8; margin-bottom: 24px; font-size: 14px; }
-  .spinner { width: 40px; height: 40px; border: 3px solid #1e1e3a; border-top-color: #e94560; border-radius: 50%; animation: spin 0.8s linear infinite; margin: 24px auto; }
-  @keyframes spin { to { transform: rotate(360deg); } }
-  .error { color: #ef4444; margin-top: 16px; font-size: 13px; }
-  .success { color: #34d399; }
-  #status { margin-top: 16px; font-size: 13px; color: #94a3b8; }
-</style>
-</head>
-<body>
-<div class="card">
-  <h1>NOAH</h1>
-  <p>Google 계정으로 로그인 중...</p>
-  <div class="spinner" id="spinner"></div>
-  <div id="status">잠시만 기다려주세요</div>
-  <div class="error" id="error"></div>
-</div>
-<script src="https://www.gstatic.com/firebasejs/10.12.0/firebase-app-compat.js"></script>
-<script src="https://www.gstatic.com/firebasejs/10.12.0/firebase-auth-compat.js"></script>
-<script>
-(async function() {
-  var params = new URLSearchParams(location.search);
-  var config = {
-    apiKey: params.get('apiKey'),
-    authDomain: params.get('authDomain'),
-    projectId: params.get('projectId'),
-  };
-  var mode = params.get('mode') || 'popup'; // 'popup' | 'redirect'
-  var statusEl = document.getElementById('status');
-  var errorEl = document.getElementById('error');
-  var spinnerEl = document.getElementById('spinner');
-
-  async function sendCallback(user, accessToken) {
-    var userData = {
-      uid: user.uid,
-      email: user.email,
-      emailVerified: user.emailVerified,
-      displayName: user.displayName,
-      isAnonymous: user.isAnonymous,
-      photoURL: user.photoURL,
-      providerData: user.providerData.map(function(p) {
-        return {
-          providerId: p.providerId,
-          uid: p.uid,
-          displayName: p.displayName,
-          email: p.email,
-          phoneNumber: p.phoneNumber,
-          photoURL: p.photoURL
-        };
-      }),
-      stsTokenManager: {
-        refreshToken: user.refreshToken,
-        accessToken: accessToken,
-        expirationTime: Date.now() + 3600 * 1000
-      },
-      createdAt: String(new Date(user.metadata.creationTime).getTime()),
-      lastLoginAt: String(new Date(user.metadata.lastSignInTime).getTime()),
-      apiKey: config.apiKey,
-      appName: '[DEFAULT]'
-    };
+pub mod agenda;
+pub mod backup;
+pub mod deep_link;
+pub mod download_progress;
+pub mod downloads;
+pub mod loopback;
+pub mod merge;
+pub mod models;
+pub mod notification_actions;
+pub mod notification_policy;
+pub mod oauth;
+pub mod oauth_pages;
+pub mod oauth_provider;
+pub mod oauth_session;
+pub mod outbox;
+pub mod recurrence;
+pub mod reminders;
+pub mod search;
+pub mod single_instance;
+pub mod storage;
+pub mod sync;
+pub mod token_refresh;
+pub mod token_store;
 
-    await fetch('/callback', {
-      method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify(userData)
-    });
+use agenda::{Agenda, AgendaDigest, AgendaSettings};
+use backup::{BackupEvent, BackupInfo, BackupKind, BackupSettings, BackupStatus, Backups, RestoreMode, RestoreSummary};
+use deep_link::{DeepLinkEvent, DeepLinkSource, DeepLinks};
+use downloads::{ChildEvent, ChildProcess, DownloadJob, DownloadRequest, DownloadSettings, Downloads};
+use loopback::{Limits, ServeOutcome};
+use merge::{Conflict, Resolution};
+use models::{FolderData, ListData, MindMapData, NoteData, OccurrenceOverride, TaskData};
+use notification_actions::{NotificationActionEvent, ReminderAction};
+use notification_policy::{Delivery, Notification, NotificationSource, Notifier, Outcome, PolicySettings};
+use oauth::{CalendarToken, OAuthErrorEvent};
+use oauth_pages::PageTemplates;
+use oauth_provider::ProviderRegistry;
+use oauth_session::{OAuthSessionInfo, OAuthSessions, OAuthTimeoutEvent};
+use recurrence::{Instance, Occurrence, Recurrence};
+use reminders::{FiredReminder, Reminder, ReminderPayload, ReminderScheduler};
+use search::SearchHit;
+use serde::{Deserialize, Serialize};
+#[cfg(desktop)]
+use single_instance::ForwardedLaunch;
+use std::sync::Arc;
+use storage::{Collection, Document, LocalDb, OverrideOutcome};
+use sync::{Credentials, SyncEngine, SyncStatus};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_shell::ShellExt;
+use token_refresh::TokenRefresher;
+use token_store::{TokenEntry, TokenInfo, TokenLookup, TokenVault};
+
+/// OAuth 세션 기본 수명 (브라우저에서 2단계 인증까지 마칠 시간)
+const OAUTH_SESSION_TIMEOUT_SECS: u64 = 300;
+
+fn oauth_limits(timeout_secs: Option<u64>) -> Limits {
+    let secs = timeout_secs.unwrap_or(OAUTH_SESSION_TIMEOUT_SECS).clamp(10, 1800);
+    Limits { lifetime: Duration::from_secs(secs), ..Limits::default() }
+}
+
+/// 세션이 수명 초과로 끝나면 `oauth-timeout`
+fn emit_on_timeout(app_handle: tauri::AppHandle) -> impl FnOnce(&OAuthSessionInfo, ServeOutcome) + Send {
+    move |info, outcome| {
+        if outcome == ServeOutcome::Expired {
+            let event = OAuthTimeoutEvent { session_id: info.id.clone(), provider: info.provider.clone() };
+            let _ = app_handle.emit("oauth-timeout", event);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StartOAuthRequest {
+    /// 레지스트리 id (google, google-calendar, microsoft-todo, notion, github, ...)
+    provider: String,
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+    /// 지정하면 프로바이더 기본 scope 대신 사용
+    #[serde(default)]
+    scopes: Option<Vec<String>>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    /// 완료/실패 페이지 언어 (ko, en, ja, es, pt, fr). 없으면 ko
+    #[serde(default)]
+    locale: Option<String>,
+}
+
+/// 모든 프로바이더 공용 OAuth 로그인 (PKCE 루프백).
+/// 반환된 포트의 `/login`을 브라우저로 열면 인가 페이지로 이동하고,
+/// 성공하면 토큰을 보관소에 저장하고 프로바이더의 이벤트, 실패하면 `oauth-error`를 emit한다
+#[tauri::command]
+fn start_oauth(
+    app_handle: tauri::AppHandle,
+    sessions: tauri::State<'_, Arc<OAuthSessions>>,
+    registry: tauri::State<'_, Arc<ProviderRegistry>>,
+    vault: tauri::State<'_, Arc<TokenVault>>,
+    pages: tauri::State<'_, Arc<PageTemplates>>,
+    request: StartOAuthRequest,
+) -> Result<OAuthSessionInfo, String> {
+    let provider = registry
+        .get(&request.provider)
+        .cloned()
+        .ok_or_else(|| format!("unknown OAuth provider: {}", request.provider))?;
+    let mut config = provider.client_config(request.client_id, request.client_secret);
+    if let Some(scopes) = request.scopes {
+        config.scopes = scopes;
+    }
 
-    spinnerEl.style.display = 'none';
-    statusEl.innerHTML = '<span class="success">&#10003; 로그인 완료!</span><br><br>이 창을 닫고 앱으로 돌아가세요.';
-    setTimeout(function() { window.close(); }, 2000);
-  }
-
-  try {
-    firebase.initializeApp(config);
-    var auth = firebase.auth();
-    var provider = new firebase.auth.GoogleAuthProvider();
-
-    if (mode === 'redirect') {
-      // 모바일: signInWithRedirect 방식
-      // 먼저 리다이렉트 결과가 있는지 확인
-      statusEl.textContent = '로그인 정보를 확인하는 중...';
-      var result = null;
-      try {
-        result = await auth.getRedirectResult();
-      } catch(e) {
-        result = null;
-      }
-
-      if (result && result.user) {
-        // 리다이렉트 후 로그인 성공
-        statusEl.textContent = '인증 정보를 앱으로 전달하는 중...';
-        var accessToken = await result.user.getIdToken(true);
-        await sendCallback(result.user, accessToken);
-      } else {
-        // 리다이렉트 시작 (Google 로그인 페이지로 이동)
-        statusEl.textContent = 'Google 로그인 페이지로 이동 중...';
-        await auth.signInWithRedirect(provider);
-        // 이후 Google 인증 완료 시 이 페이지로 다시 돌아옴
-      }
+    let callback_handle = app_handle.clone();
+    let callback_provider = provider.clone();
+    let callback_vault = Arc::clone(&vault);
+    let pages = pages.localized(request.locale.as_deref());
+    let prepared = oauth::prepare_loopback_flow(config, pages, move |result| {
+        // 토큰(특히 refresh_token)은 웹뷰가 아니라 보관소에 프로바이더 id로 둔다
+        if let Ok(tokens) = &result {
+            if let Err(e) = callback_vault.put(&callback_provider.id, TokenEntry::from_oauth(tokens)) {
+                eprintln!("{} 토큰 저장 실패: {}", callback_provider.id, e);
+            }
+        }
+        match result.and_then(|tokens| callback_provider.payload(&tokens)) {
+            Ok(payload) => {
+                let _ = callback_handle.emit(&callback_provider.event, payload);
+            }
+            Err(e) => {
+                let _ = callback_handle.emit("oauth-error", OAuthErrorEvent::new(&callback_provider.id, &e));
+            }
+        }
+    })
+    .map_err(|e| e.to_string())?;
+
+    sessions.cancel_provider(&provider.id);
+    Ok(sessions.spawn(
+        &provider.id,
+        prepared.server,
+        prepared.router,
+        oauth_limits(request.timeout_secs),
+        emit_on_timeout(app_handle),
+    ))
+}
 
+#[tauri::command]
+fn list_oauth_providers(registry: tauri::State<'_, Arc<ProviderRegistry>>) -> Vec<oauth_provider::OAuthProvider> {
+    registry.list()
+}
+
+#[tauri::command]
+fn cancel_oauth_session(sessions: tauri::State<'_, Arc<OAuthSessions>>, id: String) -> bool {
+    sessions.cancel(&id)
+}
+
+#[tauri::command]
+fn list_oauth_sessions(sessions: tauri::State<'_, Arc<OAuthSessions>>) -> Vec<OAuthSessionInfo> {
+    sessions.list()
+}
+
+/// 토큰 저장 (같은 키는 덮어씀)
+#[tauri::command]
+fn token_store_put(vault: tauri::State<'_, Arc<TokenVault>>, key: String, entry: TokenEntry) -> Result<(), String> {
+    vault.put(&key, entry).map_err(|e| e.to_string())
+}
+
+/// 유효한 토큰만 돌려준다. 만료됐거나 `min_ttl_secs`(기본 60초)보다 적게 남았으면 None.
+/// refresh_token은 네이티브 밖으로 내보내지 않는다
+#[tauri::command]
+fn token_store_get(
+    vault: tauri::State<'_, Arc<TokenVault>>,
+    key: String,
+    min_ttl_secs: Option<u64>,
+) -> Option<TokenEntry> {
+    let min_ttl = min_ttl_secs.map(Duration::from_secs).unwrap_or(token_store::DEFAULT_MIN_TTL);
+    match vault.lookup(&key, min_ttl) {
+        TokenLookup::Fresh(entry) => Some(entry.without_refresh_token()),
+        TokenLookup::Expired(_) | TokenLookup::Missing => None,
+    }
+}
+
+#[tauri::command]
+fn token_store_delete(vault: tauri::State<'_, Arc<TokenVault>>, key: String) -> Result<bool, String> {
+    vault.delete(&key).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn token_store_list(vault: tauri::State<'_, Arc<TokenVault>>) -> Vec<TokenInfo> {
+    vault.list()
+}
+
+/// 캘린더 연동 프로바이더 id. 토큰도 같은 키로 보관소에 있다
+const GCAL_PROVIDER: &str = "google-calendar";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidTokenRequest {
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+    #[serde(default)]
+    min_ttl_secs: Option<u64>,
+}
+
+/// 유효한 Google Calendar access token. 만료가 가까우면 refresh_token으로 갱신한다.
+/// 토큰 엔드포인트는 프로바이더 레지스트리(`oauth-providers.json`로 변경 가능)를 따른다.
+/// 에러는 `not_connected` / `reconnect_required` / `refresh_failed: ...`
+/// 갱신은 네트워크를 기다리므로 메인 스레드 밖에서 돈다
+#[tauri::command(async)]
+fn get_valid_gcal_token(
+    registry: tauri::State<'_, Arc<ProviderRegistry>>,
+    vault: tauri::State<'_, Arc<TokenVault>>,
+    refresher: tauri::State<'_, Arc<TokenRefresher>>,
+    request: ValidTokenRequest,
+) -> Result<CalendarToken, String> {
+    let provider = registry
+        .get(GCAL_PROVIDER)
+        .ok_or_else(|| format!("unknown OAuth provider: {}", GCAL_PROVIDER))?;
+    let config = provider.client_config(request.client_id, request.client_secret);
+    let min_ttl = request.min_ttl_secs.map(Duration::from_secs).unwrap_or(token_store::DEFAULT_MIN_TTL);
+    match refresher.valid_token(&vault, GCAL_PROVIDER, &config, min_ttl) {
+        Ok(entry) => Ok(CalendarToken { access_token: entry.value, expires_at: entry.expires_at }),
+        Err(token_refresh::RefreshError::Failed(e)) => Err(format!("refresh_failed: {}", e)),
+        Err(e) => Err(e.code().to_string()),
+    }
+}
+
+/// 딥링크 하나를 처리한다. 콜드 스타트는 웹뷰가 꺼내 갈 때까지 대기열에, 그 외에는 `deep-link` 이벤트로
+pub fn dispatch_deep_link(app_handle: &tauri::AppHandle, url: &str, source: DeepLinkSource) {
+    let route = match deep_link::parse(url) {
+        Ok(route) => route,
+        Err(e) => {
+            eprintln!("딥링크 무시 ({}): {}", url, e);
+            return;
+        }
+    };
+    let event = DeepLinkEvent { url: url.to_string(), source, route };
+    if source == DeepLinkSource::ColdStart {
+        app_handle.state::<Arc<DeepLinks>>().queue(event);
     } else {
-      // 데스크톱: signInWithPopup 방식
-      statusEl.textContent = '팝업 창에서 Google 계정을 선택해주세요';
-      var result = await auth.signInWithPopup(provider);
-      var accessToken = await result.user.getIdToken(true);
-      await sendCallback(result.user, accessToken);
+        let _ = app_handle.emit("deep-link", event);
     }
+}
 
-  } catch (err) {
-    spinnerEl.style.display = 'none';
-    statusEl.textContent = '';
-    errorEl.textContent = '로그인 실패: ' + (err.message || String(err));
-    console.error(err);
-  }
-})();
-</script>
-</body>
-</html>"##;
-
-const SUCCESS_HTML: &str = r#"{"ok":true}"#;
-
-const GCAL_HTML: &str = r##"<!DOCTYPE html>
-<html lang="ko">
-<head>
-<meta charset="utf-8">
-<meta name="viewport" content="width=device-width, initial-scale=1">
-<title>Google Calendar 연동</title>
-<style>
-  * { margin: 0; padding: 0; box-sizing: border-box; }
-  body { font-family: system-ui, -apple-system, sans-serif; background: #08081a; color: #e2e8f0; display: flex; align-items: center; justify-content: center; min-height: 100vh; }
-  .card { background: #111128; border: 1px solid #1e1e3a; border-radius: 16px; padding: 48px; text-align: center; max-width: 400px; width: 90%; }
-  h1 { font-size: 24px; margin-bottom: 8px; color: #4285F4; }
-  p { color: #94a3b8; margin-bottom: 24px; font-size: 14px; }
-  .spinner { width: 40px; height: 40px; border: 3px solid #1e1e3a; border-top-color: #4285F4; border-radius: 50%; animation: spin 0.8s linear infinite; margin: 24px auto; }
-  @keyframes spin { to { transform: rotate(360deg); } }
-  .error { color: #ef4444; margin-top: 16px; font-size: 13px; }
-  .success { color: #34d399; }
-  #status { margin-top: 16px; font-size: 13px; color: #94a3b8; }
-</style>
-</head>
-<body>
-<div class="card">
-  <h1>Google Calendar</h1>
-  <p>캘린더 읽기 권한을 허용해주세요</p>
-  <div class="spinner" id="spinner"></div>
-  <div id="status">팝업 창에서 Google 계정을 선택해주세요</div>
-  <div class="error" id="error"></div>
-</div>
-<script src="https://www.gstatic.com/firebasejs/10.12.0/firebase-app-compat.js"></script>
-<script src="https://www.gstatic.com/firebasejs/10.12.0/firebase-auth-compat.js"></script>
-<script>
-(async function() {
-  var params = new URLSearchParams(location.search);
-  var config = {
-    apiKey: params.get('apiKey'),
-    authDomain: params.get('authDomain'),
-    projectId: params.get('projectId'),
-  };
-  var statusEl = document.getElementById('status');
-  var errorEl = document.getElementById('error');
-  var spinnerEl = document.getElementById('spinner');
-  try {
-    firebase.initializeApp(config);
-    var auth = firebase.auth();
-    var provider = new firebase.auth.GoogleAuthProvider();
-    provider.addScope('https://www.googleapis.com/auth/calendar.events.readonly');
-    provider.setCustomParameters({ prompt: 'consent' });
-    var result = await auth.signInWithPopup(provider);
-    var googleAccessToken = result.credential.accessToken;
-    await fetch('/gcal-callback', {
-      method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ accessToken: googleAccessToken })
-    });
-    spinnerEl.style.display = 'none';
-    statusEl.innerHTML = '<span class="success">&#10003; 연결 완료!</span><br><br>이 창을 닫고 앱으로 돌아가세요.';
-    setTimeout(function() { window.close(); }, 2000);
-  } catch (err) {
-    spinnerEl.style.display = 'none';
-    errorEl.textContent = '연결 실패: ' + (err.message || String(err));
-  }
-})();
-</script>
-</body>
-</html>"##;
-
-fn send_response(stream: &mut std::net::TcpStream, status: &str, content_type: &str, body: &str) {
-    let response = format!(
-        "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\nAccess-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: POST, GET, OPTIONS\r\nAccess-Control-Allow-Headers: Content-Type\r\n\r\n{}",
-        status, content_type, body.len(), body
-    );
-    let _ = stream.write_all(response.as_bytes());
-    let _ = stream.flush();
-}
-
-fn read_request(stream: &mut std::net::TcpStream) -> String {
-    let mut buf = Vec::new();
-    let mut tmp = [0u8; 4096];
-
-    loop {
-        match stream.read(&mut tmp) {
-            Ok(0) => break,
-            Ok(n) => {
-                buf.extend_from_slice(&tmp[..n]);
-                let s = String::from_utf8_lossy(&buf);
-                if let Some(header_end) = s.find("\r\n\r\n") {
-                    let headers = &s[..header_end];
-                    if let Some(cl_line) = headers.lines().find(|l| l.to_lowercase().starts_with("content-length:")) {
-                        if let Ok(cl) = cl_line.split(':').nth(1).unwrap_or("0").trim().parse::<usize>() {
-                            let body_start = header_end + 4;
-                            if buf.len() >= body_start + cl {
-                                break;
-                            }
-                            continue;
-                        }
-                    }
-                    break;
-                }
-                if buf.len() > 65536 {
-                    break;
-                }
+/// 콜드 스타트로 들어와 아직 처리되지 않은 딥링크. `routes`(authCallback, openTask, ...)를 주면 해당 라우트만
+#[tauri::command]
+fn take_pending_deep_links(
+    links: tauri::State<'_, Arc<DeepLinks>>,
+    routes: Option<Vec<String>>,
+) -> Vec<DeepLinkEvent> {
+    links.take(routes.as_deref())
+}
+
+// ============================================================================
+// 로컬 DB (lib/firestore.ts의 getX/addX/updateX/deleteX와 같은 모양)
+// ============================================================================
+
+type Db<'a> = tauri::State<'a, Arc<LocalDb>>;
+
+fn db_list<T: Document>(db: &LocalDb, uid: &str) -> Result<Vec<T>, String> {
+    db.list(uid).map_err(|e| e.to_string())
+}
+
+fn db_add<T: Document>(db: &LocalDb, uid: &str, doc: T) -> Result<String, String> {
+    let added = db.insert(uid, &doc).map_err(|e| e.to_string())?;
+    Ok(storage::document_id(&added).unwrap_or_default())
+}
+
+fn db_update<T: Document>(db: &LocalDb, uid: &str, id: &str, updates: serde_json::Value) -> Result<(), String> {
+    db.update::<T>(uid, id, &updates).map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn db_delete<T: Document>(db: &LocalDb, uid: &str, id: &str) -> Result<(), String> {
+    db.delete::<T>(uid, id).map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_tasks(db: Db<'_>, uid: String) -> Result<Vec<TaskData>, String> {
+    db_list(&db, &uid)
+}
+
+#[tauri::command]
+fn get_my_day_tasks(db: Db<'_>, uid: String) -> Result<Vec<TaskData>, String> {
+    db.my_day_tasks(&uid).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn add_task(db: Db<'_>, uid: String, task: TaskData) -> Result<String, String> {
+    db_add(&db, &uid, task)
+}
+
+#[tauri::command]
+fn update_task(db: Db<'_>, uid: String, task_id: String, updates: serde_json::Value) -> Result<(), String> {
+    db_update::<TaskData>(&db, &uid, &task_id, updates)
+}
+
+#[tauri::command]
+fn delete_task(db: Db<'_>, uid: String, task_id: String) -> Result<(), String> {
+    db_delete::<TaskData>(&db, &uid, &task_id)
+}
+
+#[tauri::command]
+fn get_notes(db: Db<'_>, uid: String) -> Result<Vec<NoteData>, String> {
+    db_list(&db, &uid)
+}
+
+#[tauri::command]
+fn add_note(db: Db<'_>, uid: String, note: NoteData) -> Result<String, String> {
+    db_add(&db, &uid, note)
+}
+
+#[tauri::command]
+fn update_note(db: Db<'_>, uid: String, note_id: String, updates: serde_json::Value) -> Result<(), String> {
+    db_update::<NoteData>(&db, &uid, &note_id, updates)
+}
+
+#[tauri::command]
+fn delete_note(db: Db<'_>, uid: String, note_id: String) -> Result<(), String> {
+    db_delete::<NoteData>(&db, &uid, &note_id)
+}
+
+#[tauri::command]
+fn get_lists(db: Db<'_>, uid: String) -> Result<Vec<ListData>, String> {
+    db_list(&db, &uid)
+}
+
+#[tauri::command]
+fn add_list(db: Db<'_>, uid: String, list: ListData) -> Result<String, String> {
+    db_add(&db, &uid, list)
+}
+
+#[tauri::command]
+fn update_list(db: Db<'_>, uid: String, list_id: String, updates: serde_json::Value) -> Result<(), String> {
+    db_update::<ListData>(&db, &uid, &list_id, updates)
+}
+
+#[tauri::command]
+fn delete_list(db: Db<'_>, uid: String, list_id: String) -> Result<(), String> {
+    db_delete::<ListData>(&db, &uid, &list_id)
+}
+
+#[tauri::command]
+fn get_folders(db: Db<'_>, uid: String) -> Result<Vec<FolderData>, String> {
+    db_list(&db, &uid)
+}
+
+#[tauri::command]
+fn add_folder(db: Db<'_>, uid: String, folder: FolderData) -> Result<String, String> {
+    db_add(&db, &uid, folder)
+}
+
+#[tauri::command]
+fn update_folder(db: Db<'_>, uid: String, folder_id: String, updates: serde_json::Value) -> Result<(), String> {
+    db_update::<FolderData>(&db, &uid, &folder_id, updates)
+}
+
+#[tauri::command]
+fn delete_folder(db: Db<'_>, uid: String, folder_id: String) -> Result<(), String> {
+    db_delete::<FolderData>(&db, &uid, &folder_id)
+}
+
+#[tauri::command]
+fn get_mindmaps(db: Db<'_>, uid: String) -> Result<Vec<MindMapData>, String> {
+    db_list(&db, &uid)
+}
+
+#[tauri::command]
+fn add_mindmap(db: Db<'_>, uid: String, data: MindMapData) -> Result<String, String> {
+    db_add(&db, &uid, data)
+}
+
+#[tauri::command]
+fn update_mindmap(db: Db<'_>, uid: String, id: String, updates: serde_json::Value) -> Result<(), String> {
+    db_update::<MindMapData>(&db, &uid, &id, updates)
+}
+
+#[tauri::command]
+fn delete_mindmap(db: Db<'_>, uid: String, id: String) -> Result<(), String> {
+    db_delete::<MindMapData>(&db, &uid, &id)
+}
+
+fn replace_from_snapshot<T: Document>(db: &LocalDb, uid: &str, docs: Vec<serde_json::Value>) -> Result<(), String> {
+    let docs: Vec<T> = docs
+        .into_iter()
+        .filter_map(|doc| match serde_json::from_value(doc) {
+            Ok(doc) => Some(doc),
+            Err(e) => {
+                eprintln!("로컬 DB: {} 문서 건너뜀: {}", T::COLLECTION.table(), e);
+                None
+            }
+        })
+        .collect();
+    db.replace_all(uid, &docs).map_err(|e| e.to_string())
+}
+
+/// Firestore 스냅샷을 로컬에 그대로 반영한다. 오프라인일 때 이 데이터로 화면을 그린다.
+/// 스키마에 맞지 않는 문서 하나 때문에 나머지를 잃지 않도록 그런 문서는 건너뛴다
+#[tauri::command]
+fn local_db_replace(
+    db: Db<'_>,
+    uid: String,
+    collection: Collection,
+    docs: Vec<serde_json::Value>,
+) -> Result<(), String> {
+    match collection {
+        Collection::Tasks => replace_from_snapshot::<TaskData>(&db, &uid, docs),
+        Collection::Notes => replace_from_snapshot::<NoteData>(&db, &uid, docs),
+        Collection::Lists => replace_from_snapshot::<ListData>(&db, &uid, docs),
+        Collection::Folders => replace_from_snapshot::<FolderData>(&db, &uid, docs),
+        Collection::Mindmaps => replace_from_snapshot::<MindMapData>(&db, &uid, docs),
+    }
+}
+
+/// 할 일(제목/메모/태그), 노트(제목/블록), 마인드맵(제목/노드)을 한꺼번에 찾는다
+#[tauri::command]
+fn search_everything(db: Db<'_>, uid: String, query: String, limit: Option<usize>) -> Result<Vec<SearchHit>, String> {
+    db.search(&uid, &query, limit.unwrap_or(search::DEFAULT_LIMIT)).map_err(|e| e.to_string())
+}
+
+/// 자동으로 합치지 못한 노트 블록 충돌
+#[tauri::command]
+fn list_conflicts(db: Db<'_>, uid: String) -> Result<Vec<Conflict>, String> {
+    db.conflicts(&uid).map_err(|e| e.to_string())
+}
+
+/// 충돌 블록을 고른 쪽(`local`/`remote`/`custom`)으로 정한다. 바뀐 노트를 돌려준다
+#[tauri::command]
+fn resolve_conflict(
+    db: Db<'_>,
+    uid: String,
+    conflict_id: String,
+    resolution: Resolution,
+) -> Result<Option<NoteData>, String> {
+    db.resolve_conflict(&uid, &conflict_id, &resolution).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalDbChangedEvent {
+    uid: String,
+    collection: Collection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncCredentialsRequest {
+    uid: String,
+    /// Firebase ID 토큰 (`user.getIdToken()`)
+    id_token: String,
+    project_id: String,
+}
+
+/// 로그인/토큰 갱신 때마다 호출. `None`이면 로그아웃으로 보고 동기화를 멈춘다
+#[tauri::command]
+fn sync_set_credentials(
+    sync: tauri::State<'_, Arc<SyncEngine>>,
+    digest: tauri::State<'_, Arc<AgendaDigest>>,
+    credentials: Option<SyncCredentialsRequest>,
+) -> Result<(), String> {
+    // 일정 요약은 웹뷰 로그인 전에도 마지막 계정으로 보낸다
+    digest.set_uid(credentials.as_ref().map(|c| c.uid.as_str())).map_err(|e| e.to_string())?;
+    sync.set_credentials(credentials.map(|c| Credentials {
+        uid: c.uid,
+        id_token: c.id_token,
+        base_url: sync::documents_url(&c.project_id),
+    }))
+}
+
+#[tauri::command]
+fn sync_status(sync: tauri::State<'_, Arc<SyncEngine>>) -> SyncStatus {
+    sync.status()
+}
+
+/// 백오프를 건너뛰고 바로 동기화. `retry_failed`면 거부됐던 변경도 다시 보낸다
+#[tauri::command]
+fn sync_retry(sync: tauri::State<'_, Arc<SyncEngine>>, retry_failed: Option<bool>) -> Result<(), String> {
+    sync.retry_now(retry_failed.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// 아직 원격에 반영되지 않은 변경 (실패로 남은 것 포함)
+#[tauri::command]
+fn sync_pending(db: Db<'_>, uid: String) -> Result<Vec<outbox::Mutation>, String> {
+    outbox::list(&db.conn(), &uid).map_err(|e| e.to_string())
+}
+
+type Reminders<'a> = tauri::State<'a, Arc<ReminderScheduler>>;
+
+/// 할 일 알림을 (다시) 예약한다. `fire_at`은 epoch ms, 이미 지났으면 바로 울린다
+#[tauri::command]
+fn schedule_reminder(
+    reminders: Reminders<'_>,
+    task_id: String,
+    fire_at: i64,
+    payload: ReminderPayload,
+    uid: Option<String>,
+) -> Result<Reminder, String> {
+    reminders.schedule(&task_id, uid, fire_at, payload).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn cancel_reminder(reminders: Reminders<'_>, task_id: String) -> Result<bool, String> {
+    reminders.cancel(&task_id).map_err(|e| e.to_string())
+}
+
+/// 아직 울리지 않은 알림, 울릴 순서대로
+#[tauri::command]
+fn list_reminders(reminders: Reminders<'_>) -> Result<Vec<Reminder>, String> {
+    reminders.list().map_err(|e| e.to_string())
+}
+
+/// 울린 알림을 알림 정책에 넘기고 `reminder-fired`로도 알린다.
+/// 방해 금지 시간이면 끝나는 시각으로 다시 예약한다 (앱을 꺼도 남도록)
+fn fire_reminders(app_handle: &tauri::AppHandle, fired: &[FiredReminder]) {
+    let notifier = app_handle.state::<Arc<Notifier>>();
+    if let Some(until) = notifier.quiet_until() {
+        let reminders = app_handle.state::<Arc<ReminderScheduler>>();
+        for fired in fired {
+            let reminder = &fired.reminder;
+            if let Err(e) = reminders.schedule(&reminder.task_id, reminder.uid.clone(), until, reminder.payload.clone()) {
+                eprintln!("방해 금지 시간 뒤로 알림을 미루지 못함: {}", e);
             }
-            Err(_) => break,
         }
+        return;
     }
+    for fired in fired {
+        let payload = &fired.reminder.payload;
+        let body = if fired.missed { format!("{} (놓친 알림)", payload.body) } else { payload.body.clone() };
+        notifier.notify(Notification {
+            source: NotificationSource::Reminder,
+            title: payload.title.clone(),
+            body: body.trim().to_string(),
+            reminder: Some(fired.reminder.clone()),
+            link: None,
+        });
+    }
+    let _ = app_handle.emit("reminder-fired", fired);
+}
 
-    String::from_utf8_lossy(&buf).to_string()
+/// 정책을 통과한 알림을 띄운다. 할 일 알림 하나면 버튼을, 링크가 있는 알림 하나면 누르면 열리게 하고,
+/// 묶인 요약은 그냥 띄운다
+fn deliver_notification(app_handle: &tauri::AppHandle, delivery: &Delivery) {
+    let shown = match delivery.items.as_slice() {
+        [Notification { reminder: Some(reminder), .. }] => show_reminder(app_handle, reminder, &delivery.body),
+        [Notification { link: Some(link), .. }] => show_link(app_handle, delivery, link),
+        _ => {
+            use tauri_plugin_notification::NotificationExt;
+            app_handle
+                .notification()
+                .builder()
+                .title(&delivery.title)
+                .body(&delivery.body)
+                .show()
+                .map_err(|e| e.to_string())
+        }
+    };
+    if let Err(e) = shown {
+        eprintln!("알림을 띄우지 못함: {}", e);
+    }
 }
 
+/// 웹뷰의 알림(타임박스, 뽀모도로, 일정 ...)도 방해 금지/묶기/빈도 제한을 거친다
 #[tauri::command]
-fn start_oauth_server(app_handle: tauri::AppHandle) -> Result<u16, String> {
-    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
-    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+fn notify(
+    notifier: tauri::State<'_, Arc<Notifier>>,
+    source: NotificationSource,
+    title: String,
+    body: Option<String>,
+) -> Outcome {
+    notifier.notify(Notification { source, title, body: body.unwrap_or_default(), reminder: None, link: None })
+}
 
-    std::thread::spawn(move || {
-        for _ in 0..20 {
-            if let Ok((mut stream, _)) = listener.accept() {
-                let request = read_request(&mut stream);
+#[tauri::command]
+fn get_notification_policy(notifier: tauri::State<'_, Arc<Notifier>>) -> PolicySettings {
+    notifier.settings()
+}
 
-                let first_line = request.lines().next().unwrap_or("");
-                let method = first_line.split_whitespace().next().unwrap_or("");
-                let path = first_line.split_whitespace().nth(1).unwrap_or("/");
+#[tauri::command]
+fn set_notification_policy(notifier: tauri::State<'_, Arc<Notifier>>, settings: PolicySettings) -> Result<(), String> {
+    notifier.set_settings(settings).map_err(|e| e.to_string())
+}
 
-                if method == "OPTIONS" {
-                    send_response(&mut stream, "204 No Content", "text/plain", "");
-                    continue;
+/// Linux는 notify-rust로 직접 띄워 눌린 버튼을 받는다
+#[cfg(target_os = "linux")]
+fn show_reminder(app_handle: &tauri::AppHandle, reminder: &Reminder, body: &str) -> Result<(), String> {
+    let mut notification = notify_rust::Notification::new();
+    notification.summary(&reminder.payload.title).body(body).auto_icon();
+    if let Some(name) = &app_handle.config().product_name {
+        notification.appname(name);
+    }
+    // "default"는 본문을 눌렀을 때
+    notification.action("default", ReminderAction::Open.label());
+    for action in ReminderAction::ALL {
+        notification.action(action.id(), action.label());
+    }
+    let shown = notification.show().map_err(|e| e.to_string())?;
+    let handle = app_handle.clone();
+    let reminder = reminder.clone();
+    std::thread::spawn(move || {
+        shown.wait_for_action(|id| {
+            let action = if id == "default" { Some(ReminderAction::Open) } else { ReminderAction::parse(id) };
+            if let Some(action) = action {
+                if let Err(e) = handle_notification_action(&handle, &reminder, action) {
+                    eprintln!("알림 버튼 처리 실패: {}", e);
                 }
+            }
+        })
+    });
+    Ok(())
+}
 
-                if method == "POST" && path.starts_with("/callback") {
-                    let body = if let Some(pos) = request.find("\r\n\r\n") {
-                        request[pos + 4..].to_string()
-                    } else {
-                        String::new()
-                    };
-
-                    send_response(&mut stream, "200 OK", "application/json", SUCCESS_HTML);
-                    let _ = app_handle.emit("oauth-callback", body);
-                    break;
-                } else if path == "/favicon.ico" {
-                    send_response(&mut stream, "204 No Content", "text/plain", "");
-                } else {
-                    // /login 및 기타 GET 요청 → 로그인 페이지 서빙
-                    send_response(&mut stream, "200 OK", "text/html", LOGIN_HTML);
-                }
+/// 모바일은 웹뷰가 등록한 action type으로 버튼이 붙는다 (`extra.reminder`를 `notification_action`에 돌려준다).
+/// Windows/macOS 알림에는 버튼이 없다
+#[cfg(not(target_os = "linux"))]
+fn show_reminder(app_handle: &tauri::AppHandle, reminder: &Reminder, body: &str) -> Result<(), String> {
+    use tauri_plugin_notification::NotificationExt;
+    app_handle
+        .notification()
+        .builder()
+        .title(&reminder.payload.title)
+        .body(body)
+        .action_type_id(notification_actions::ACTION_TYPE_ID)
+        .extra("reminder", reminder)
+        .show()
+        .map_err(|e| e.to_string())
+}
+
+/// Linux는 본문을 누르면("default") 링크를 연다
+#[cfg(target_os = "linux")]
+fn show_link(app_handle: &tauri::AppHandle, delivery: &Delivery, link: &str) -> Result<(), String> {
+    let mut notification = notify_rust::Notification::new();
+    notification.summary(&delivery.title).body(&delivery.body).auto_icon();
+    if let Some(name) = &app_handle.config().product_name {
+        notification.appname(name);
+    }
+    notification.action("default", "열기");
+    let shown = notification.show().map_err(|e| e.to_string())?;
+    let handle = app_handle.clone();
+    let link = link.to_string();
+    std::thread::spawn(move || {
+        shown.wait_for_action(|id| {
+            if id == "default" {
+                open_link(&handle, &link);
             }
-        }
+        })
     });
+    Ok(())
+}
 
-    Ok(port)
+/// 모바일은 웹뷰의 `onAction`이 `extra.link`를 `open_notification_link`로 돌려준다
+#[cfg(not(target_os = "linux"))]
+fn show_link(app_handle: &tauri::AppHandle, delivery: &Delivery, link: &str) -> Result<(), String> {
+    use tauri_plugin_notification::NotificationExt;
+    app_handle
+        .notification()
+        .builder()
+        .title(&delivery.title)
+        .body(&delivery.body)
+        .extra("link", link)
+        .show()
+        .map_err(|e| e.to_string())
+}
+
+/// 알림에서 연 링크: 메인 창을 앞으로 가져오고 딥링크로 넘긴다
+fn open_link(app_handle: &tauri::AppHandle, url: &str) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    dispatch_deep_link(app_handle, url, DeepLinkSource::Notification);
 }
 
 #[tauri::command]
-fn start_gcal_oauth_server(app_handle: tauri::AppHandle) -> Result<u16, String> {
-    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
-    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+fn open_notification_link(app: tauri::AppHandle, url: String) {
+    open_link(&app, &url);
+}
 
-    std::thread::spawn(move || {
-        for _ in 0..10 {
-            if let Ok((mut stream, _)) = listener.accept() {
-                let request = read_request(&mut stream);
+/// 완료/미루기는 바로 처리하고, 열기는 창을 띄워 `noah://task/{id}`로 넘긴다. 결과는 `notification-action`으로
+fn handle_notification_action(
+    app_handle: &tauri::AppHandle,
+    reminder: &Reminder,
+    action: ReminderAction,
+) -> Result<NotificationActionEvent, String> {
+    let db = app_handle.state::<Arc<LocalDb>>();
+    let reminders = app_handle.state::<Arc<ReminderScheduler>>();
+    let event = notification_actions::apply(&db, &reminders, reminder, action, &chrono::Local::now())
+        .map_err(|e| e.to_string())?;
+    if action == ReminderAction::Open {
+        let url = format!("{}://task/{}", deep_link::SCHEME, urlencoding::encode(&reminder.task_id));
+        open_link(app_handle, &url);
+    }
+    let _ = app_handle.emit("notification-action", &event);
+    Ok(event)
+}
 
-                let first_line = request.lines().next().unwrap_or("");
-                let method = first_line.split_whitespace().next().unwrap_or("");
-                let path = first_line.split_whitespace().nth(1).unwrap_or("/");
+/// 웹뷰가 받은 알림 버튼(모바일 `onAction`, 인앱 알림)을 처리한다
+#[tauri::command]
+fn notification_action(
+    app: tauri::AppHandle,
+    action: ReminderAction,
+    reminder: Reminder,
+) -> Result<NotificationActionEvent, String> {
+    handle_notification_action(&app, &reminder, action)
+}
 
-                if method == "OPTIONS" {
-                    send_response(&mut stream, "204 No Content", "text/plain", "");
-                    continue;
-                }
+/// 반복 규칙(RRULE/EXDATE/RDATE 줄)을 `range` 안에서 펼친다. `tz`는 IANA 이름 (없으면 시간대 없는 현지 시각)
+#[tauri::command]
+fn expand_recurrence(
+    rule: String,
+    start: String,
+    tz: Option<String>,
+    range: recurrence::Range,
+) -> Result<Vec<Occurrence>, String> {
+    let recurrence = Recurrence::parse(&rule, &start, tz.as_deref()).map_err(|e| e.to_string())?;
+    recurrence.expand(&range).map_err(|e| e.to_string())
+}
+
+/// `after`(없으면 지금) 다음 회차. 끝난 반복이면 `None`
+#[tauri::command]
+fn next_occurrence(
+    rule: String,
+    start: String,
+    tz: Option<String>,
+    after: Option<String>,
+) -> Result<Option<Occurrence>, String> {
+    let recurrence = Recurrence::parse(&rule, &start, tz.as_deref()).map_err(|e| e.to_string())?;
+    let after = after.unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+    recurrence.next_after(&after).map_err(|e| e.to_string())
+}
 
-                if method == "POST" && path.starts_with("/gcal-callback") {
-                    let body = if let Some(pos) = request.find("\r\n\r\n") {
-                        request[pos + 4..].to_string()
-                    } else {
-                        String::new()
-                    };
-                    send_response(&mut stream, "200 OK", "application/json", SUCCESS_HTML);
-                    let _ = app_handle.emit("gcal-oauth-callback", body);
-                    break;
-                } else if path == "/favicon.ico" {
-                    send_response(&mut stream, "204 No Content", "text/plain", "");
-                } else {
-                    send_response(&mut stream, "200 OK", "text/html", GCAL_HTML);
+/// 반복 할 일의 `range` 안 회차 (건너뛴 회차 빼고, 옮긴 회차는 옮긴 날에). 시간대 없는 현지 날짜로 센다
+#[tauri::command]
+fn expand_task_occurrences(
+    db: Db<'_>,
+    uid: String,
+    task_id: String,
+    range: recurrence::Range,
+) -> Result<Vec<Instance>, String> {
+    let task: TaskData = db
+        .get(&uid, &task_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("not_found: tasks/{}", task_id))?;
+    let Some(rule) = &task.recurrence_rule else {
+        return Ok(Vec::new());
+    };
+    let Some(day) = recurrence::task_day(&task) else {
+        return Ok(Vec::new());
+    };
+    let start = task.recurrence_id.clone().unwrap_or_else(|| day.format("%Y-%m-%d").to_string());
+    let text = recurrence::rule_text(rule).map_err(|e| e.to_string())?;
+    let series = Recurrence::parse(&text, &start, None)
+        .and_then(|r| r.with_overrides(&rule.overrides))
+        .map_err(|e| e.to_string())?;
+    series.expand_instances(&range).map_err(|e| e.to_string())
+}
+
+/// 반복 할 일의 한 회차(`recurrence_id`, 원래 날짜)를 건너뛰거나 옮기거나 이름을 바꾼다. `value`가 없으면 예외를 지운다.
+/// 이 할 일 자신의 회차였으면 예약한 알림도 지우거나 옮긴다
+#[tauri::command]
+fn override_occurrence(
+    db: Db<'_>,
+    reminders: Reminders<'_>,
+    uid: String,
+    task_id: String,
+    recurrence_id: String,
+    value: Option<OccurrenceOverride>,
+) -> Result<OverrideOutcome, String> {
+    let outcome = db.override_occurrence(&uid, &task_id, &recurrence_id, value).map_err(|e| e.to_string())?;
+    match &outcome {
+        OverrideOutcome::Skipped { .. } => {
+            reminders.cancel(&task_id).map_err(|e| e.to_string())?;
+        }
+        OverrideOutcome::Updated { task } => {
+            let scheduled = reminders.list().map_err(|e| e.to_string())?.into_iter().find(|r| r.task_id == task_id);
+            if let Some(scheduled) = scheduled {
+                match task.reminder.as_deref().and_then(|r| recurrence::reminder_ms(r, &chrono::Local)) {
+                    Some(fire_at) if fire_at != scheduled.fire_at => {
+                        let payload = scheduled.payload;
+                        reminders.schedule(&task_id, scheduled.uid, fire_at, payload).map_err(|e| e.to_string())?;
+                    }
+                    Some(_) => {}
+                    None => {
+                        reminders.cancel(&task_id).map_err(|e| e.to_string())?;
+                    }
                 }
             }
         }
+        OverrideOutcome::Recorded { .. } => {}
+    }
+    Ok(outcome)
+}
+
+type DigestState<'a> = tauri::State<'a, Arc<AgendaDigest>>;
+
+/// 일정 요약 미리보기 (`date`가 없으면 오늘)
+#[tauri::command]
+fn get_agenda(db: Db<'_>, uid: String, date: Option<String>) -> Result<Agenda, String> {
+    let date = match date {
+        Some(date) => chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| e.to_string())?,
+        None => chrono::Local::now().date_naive(),
+    };
+    agenda::build(&db, &uid, date, &chrono::Local).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_agenda_settings(digest: DigestState<'_>) -> AgendaSettings {
+    digest.settings()
+}
+
+#[tauri::command]
+fn set_agenda_settings(digest: DigestState<'_>, settings: AgendaSettings) -> Result<(), String> {
+    digest.set_settings(settings).map_err(|e| e.to_string())
+}
+
+/// 웹뷰가 읽거나 저장한 타임박스를 일정 요약용으로 남긴다
+#[tauri::command]
+fn cache_timebox(
+    digest: DigestState<'_>,
+    uid: String,
+    date: String,
+    slots: std::collections::BTreeMap<String, String>,
+) -> Result<(), String> {
+    digest.cache_timebox(&uid, &date, &slots).map_err(|e| e.to_string())
+}
+
+type DownloadsState<'a> = tauri::State<'a, Arc<Downloads>>;
+
+/// yt-dlp 사이드카를 띄우고 출력을 줄 단위 `ChildEvent`로 옮긴다
+fn launch_yt_dlp(app_handle: &tauri::AppHandle, args: &[String]) -> Result<ChildProcess, String> {
+    let command = app_handle.shell().sidecar("yt-dlp").map_err(|e| e.to_string())?;
+    let (mut rx, child) = command.args(args).spawn().map_err(|e| e.to_string())?;
+    let (tx, events) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let line = |bytes: Vec<u8>| String::from_utf8_lossy(&bytes).trim_end().to_string();
+        while let Some(event) = rx.blocking_recv() {
+            let event = match event {
+                CommandEvent::Stdout(bytes) => ChildEvent::Stdout(line(bytes)),
+                CommandEvent::Stderr(bytes) => ChildEvent::Stderr(line(bytes)),
+                CommandEvent::Error(e) => ChildEvent::Error(e),
+                CommandEvent::Terminated(payload) => ChildEvent::Exit(payload.code),
+                _ => continue,
+            };
+            if matches!(&event, ChildEvent::Stdout(l) | ChildEvent::Stderr(l) if l.is_empty()) {
+                continue;
+            }
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
     });
+    let kill = move || {
+        if let Err(e) = child.kill() {
+            eprintln!("yt-dlp 종료 실패: {}", e);
+        }
+    };
+    Ok(ChildProcess { events, kill: Box::new(kill) })
+}
+
+/// 받기 전에 영상 정보 (yt-dlp `--dump-json`). 웹뷰는 yt-dlp를 직접 띄울 수 없다
+#[tauri::command]
+async fn probe_download(app_handle: tauri::AppHandle, url: String) -> Result<serde_json::Value, String> {
+    let args = downloads::probe_args(&url).map_err(|e| e.to_string())?;
+    let command = app_handle.shell().sidecar("yt-dlp").map_err(|e| e.to_string())?;
+    let output = command.args(args).output().await.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if !stderr.is_empty() {
+            return Err(stderr);
+        }
+        return Err(match output.status.code() {
+            Some(code) => format!("yt-dlp exited with code {}", code),
+            None => "yt-dlp was terminated".to_string(),
+        });
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())
+}
+
+/// 대기열 끝에 넣는다. 진행은 `download-progress`로 온다
+#[tauri::command]
+fn enqueue_download(downloads: DownloadsState<'_>, request: DownloadRequest) -> Result<DownloadJob, String> {
+    downloads.enqueue(request).map_err(|e| e.to_string())
+}
+
+/// 넣은 순서대로 (끝난 작업 포함)
+#[tauri::command]
+fn list_downloads(downloads: DownloadsState<'_>) -> Result<Vec<DownloadJob>, String> {
+    downloads.list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn pause_download(downloads: DownloadsState<'_>, id: String) -> Result<DownloadJob, String> {
+    downloads.pause(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn resume_download(downloads: DownloadsState<'_>, id: String) -> Result<DownloadJob, String> {
+    downloads.resume(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn cancel_download(downloads: DownloadsState<'_>, id: String) -> Result<DownloadJob, String> {
+    downloads.cancel(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn retry_download(downloads: DownloadsState<'_>, id: String) -> Result<DownloadJob, String> {
+    downloads.retry(&id).map_err(|e| e.to_string())
+}
+
+/// 목록에서 지운다 (받은 파일은 그대로)
+#[tauri::command]
+fn remove_download(downloads: DownloadsState<'_>, id: String) -> Result<bool, String> {
+    downloads.remove(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_download_settings(downloads: DownloadsState<'_>) -> DownloadSettings {
+    downloads.settings()
+}
+
+#[tauri::command]
+fn set_download_settings(downloads: DownloadsState<'_>, settings: DownloadSettings) -> Result<(), String> {
+    downloads.set_settings(settings).map_err(|e| e.to_string())
+}
+
+type BackupState<'a> = tauri::State<'a, Arc<Backups>>;
+
+/// 지금 백업한다 (보관 규칙으로 지워지지 않는 수동 백업)
+#[tauri::command(async)]
+fn create_backup(backups: BackupState<'_>) -> Result<BackupInfo, String> {
+    backups.create(BackupKind::Manual).map_err(|e| e.to_string())
+}
+
+/// 백업 폴더의 백업들, 최근 것부터
+#[tauri::command]
+fn list_backups(backups: BackupState<'_>) -> Result<Vec<BackupInfo>, String> {
+    backups.list().map_err(|e| e.to_string())
+}
+
+/// 백업을 끝까지 풀어 체크섬을 확인한다. 암호화된 백업은 `passphrase` 또는 저장된 암호로
+#[tauri::command(async)]
+fn verify_backup(backups: BackupState<'_>, id: String, passphrase: Option<String>) -> Result<BackupInfo, String> {
+    backups.verify(&id, passphrase.as_deref()).map_err(|e| e.to_string())
+}
+
+/// 계정 `uid`를 백업 시점으로 되돌린다 (`missing`/`revert`/`exact`). 직전 상태는 따로 백업된다
+#[tauri::command(async)]
+fn restore_backup(
+    backups: BackupState<'_>,
+    id: String,
+    uid: String,
+    mode: RestoreMode,
+    passphrase: Option<String>,
+) -> Result<RestoreSummary, String> {
+    backups.restore(&id, &uid, passphrase.as_deref(), mode).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupStatusResponse {
+    #[serde(flatten)]
+    status: BackupStatus,
+    /// 로그인 시 자동 실행 여부. 앱이 꺼져 있으면 예약 백업도 돌지 않는다 (모바일은 `None`)
+    autostart: Option<bool>,
+}
+
+#[tauri::command]
+fn backup_status(app: tauri::AppHandle, backups: BackupState<'_>) -> BackupStatusResponse {
+    #[cfg(not(target_os = "android"))]
+    let autostart = {
+        use tauri_plugin_autostart::ManagerExt;
+        app.autolaunch().is_enabled().ok()
+    };
+    #[cfg(target_os = "android")]
+    let autostart = {
+        let _ = app;
+        None
+    };
+    BackupStatusResponse { status: backups.status(), autostart }
+}
 
-    Ok(port)
+/// 예약/보관/폴더/암호화 설정. `passphrase`를 주면 백업 암호를 바꾼다
+#[tauri::command]
+fn set_backup_settings(
+    backups: BackupState<'_>,
+    settings: BackupSettings,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    backups.set_settings(settings, passphrase).map_err(|e| e.to_string())
+}
+
+/// 예약 백업 결과를 `backup-status`로 알리고, 실패는 시스템 알림으로도
+fn report_backup(app_handle: &tauri::AppHandle, event: &BackupEvent) {
+    if let BackupEvent::Failed(error) = event {
+        app_handle.state::<Arc<Notifier>>().notify(Notification {
+            source: NotificationSource::Backup,
+            title: "백업 실패".into(),
+            body: error.clone(),
+            reminder: None,
+            link: None,
+        });
+    }
+    if let Some(backups) = app_handle.try_state::<Arc<Backups>>() {
+        let _ = app_handle.emit("backup-status", backups.status());
+    }
+}
+
+/// 두 번째 실행의 인자를 `second-instance` 이벤트로 넘기고, 딥링크는 라우팅한 뒤 메인 창을 앞으로
+#[cfg(desktop)]
+fn handle_second_instance(app_handle: &tauri::AppHandle, launch: ForwardedLaunch) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    for url in deep_link::links_in_args(launch.args.iter().skip(1)) {
+        dispatch_deep_link(app_handle, &url, DeepLinkSource::SecondInstance);
+    }
+    let _ = app_handle.emit("second-instance", launch);
 }
 
 #[tauri::command]
@@ -351,6 +1045,28 @@ fn open_folder(path: String) -> Result<(), String> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 이미 실행 중이면 인자만 넘기고 종료. 잠금을 못 잡으면 그냥 실행한다
+    #[cfg(desktop)]
+    let instance = {
+        let launch = ForwardedLaunch {
+            args: std::env::args().collect(),
+            cwd: std::env::current_dir().ok().map(|d| d.to_string_lossy().into_owned()),
+        };
+        match single_instance::acquire(&single_instance::default_lock_path("noah.firstb.noah"), launch) {
+            Ok(single_instance::Startup::Forwarded) => return,
+            Ok(single_instance::Startup::Primary(guard)) => Some(guard),
+            // 이미 떠 있는 인스턴스가 바쁠 뿐이다. 두 번째 주 인스턴스를 띄우지 않는다
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                eprintln!("실행 중인 인스턴스가 답하지 않음: {}", e);
+                return;
+            }
+            Err(e) => {
+                eprintln!("단일 인스턴스 확인 실패: {}", e);
+                None
+            }
+        }
+    };
+
     let builder = tauri::Builder::default()
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_dialog::init())
@@ -360,7 +1076,222 @@ pub fn run() {
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_notification::init())
-        .invoke_handler(tauri::generate_handler![start_oauth_server, start_gcal_oauth_server, open_folder]);
+        .manage(Arc::new(OAuthSessions::default()))
+        .manage(Arc::new(TokenRefresher::default()))
+        .manage(Arc::new(DeepLinks::default()))
+        .setup(move |app| {
+            #[cfg(desktop)]
+            if let Some(mut guard) = instance {
+                let handle = app.handle().clone();
+                guard.listen(move |launch| handle_second_instance(&handle, launch));
+                app.manage(guard);
+            }
+
+            // 설정 폴더의 oauth-providers.json으로 프로바이더 추가/덮어쓰기
+            let mut registry = ProviderRegistry::builtin();
+            if let Ok(dir) = app.path().app_config_dir() {
+                if let Err(e) = registry.merge_file(&dir.join("oauth-providers.json")) {
+                    eprintln!("oauth-providers.json 무시: {}", e);
+                }
+            }
+            app.manage(Arc::new(registry));
+
+            // 루프백 페이지 문구/색상/템플릿 덮어쓰기 (oauth-pages.json, oauth-page.html)
+            let mut pages = PageTemplates::builtin();
+            if let Ok(dir) = app.path().app_config_dir() {
+                if let Err(e) = pages.load_overrides(&dir) {
+                    eprintln!("OAuth 페이지 설정 무시: {}", e);
+                }
+            }
+            app.manage(Arc::new(pages));
+
+            let vault = Arc::new(TokenVault::open(&app.path().app_data_dir()?)?);
+            app.manage(Arc::clone(&vault));
+
+            // 로컬 DB + outbox를 Firestore로 내보내는 동기화 스레드
+            let local_db = Arc::new(LocalDb::open(&app.path().app_data_dir()?)?);
+            let status_handle = app.handle().clone();
+            let sync_engine = Arc::new(SyncEngine::new(Arc::clone(&local_db), move |status| {
+                let _ = status_handle.emit("sync-status", status);
+            }));
+            let change_handle = app.handle().clone();
+            let weak_sync = Arc::downgrade(&sync_engine);
+            local_db.on_change(move |uid, collection| {
+                let _ = change_handle.emit("local-db-changed", LocalDbChangedEvent { uid: uid.to_string(), collection });
+                if let Some(sync) = weak_sync.upgrade() {
+                    sync.notify();
+                }
+            });
+
+            // 모든 알림이 거치는 정책 (notifications.json)
+            let delivery_handle = app.handle().clone();
+            let notifier = Arc::new(Notifier::new(app.path().app_config_dir()?.join("notifications.json"), move |delivery| {
+                deliver_notification(&delivery_handle, delivery)
+            }));
+
+            // 예약 백업 (설정은 backup.json, 기본 폴더는 앱 데이터 폴더의 backups)
+            let backup_handle = app.handle().clone();
+            let backups = Arc::new(Backups::new(
+                Arc::clone(&local_db),
+                Arc::clone(&vault),
+                app.path().app_config_dir()?.join("backup.json"),
+                app.path().app_data_dir()?.join("backups"),
+                move |event| report_backup(&backup_handle, event),
+            ));
+
+            // 할 일 알림 (지나간 알림은 바로 울린다)
+            let reminder_handle = app.handle().clone();
+            let reminders = Arc::new(ReminderScheduler::new(Arc::clone(&local_db), move |fired| {
+                fire_reminders(&reminder_handle, fired)
+            }));
+
+            // 아침 일정 요약 (agenda.json). 로그인 시 자동 실행으로 창 없이 켜져도 보낸다
+            let digest_handle = app.handle().clone();
+            let digest = Arc::new(AgendaDigest::new(
+                Arc::clone(&local_db),
+                app.path().app_config_dir()?.join("agenda.json"),
+                move |agenda| {
+                    digest_handle.state::<Arc<Notifier>>().notify(Notification {
+                        source: NotificationSource::Agenda,
+                        title: agenda.title(),
+                        body: agenda.body(),
+                        reminder: None,
+                        link: Some(agenda::MY_DAY_LINK.into()),
+                    });
+                },
+            ));
+
+            // 미디어 다운로드 대기열 (downloads.json). 페이지를 떠나도 받는다
+            let launch_handle = app.handle().clone();
+            let progress_handle = app.handle().clone();
+            let downloads = Arc::new(Downloads::new(
+                Arc::clone(&local_db),
+                app.path().app_config_dir()?.join("downloads.json"),
+                move |args: &[String]| launch_yt_dlp(&launch_handle, args),
+                move |event| {
+                    let _ = progress_handle.emit("download-progress", event);
+                },
+            ));
+
+            // 스레드의 콜백이 서로를 `state`로 찾으므로 모두 등록한 뒤에 띄운다
+            app.manage(local_db);
+            app.manage(Arc::clone(&sync_engine));
+            app.manage(Arc::clone(&notifier));
+            app.manage(Arc::clone(&backups));
+            app.manage(Arc::clone(&reminders));
+            app.manage(Arc::clone(&digest));
+            app.manage(Arc::clone(&downloads));
+            sync_engine.spawn();
+            notifier.spawn();
+            backups.spawn();
+            reminders.spawn();
+            digest.spawn();
+            downloads.spawn();
+
+            // 설치 없이 실행한 AppImage/개발 빌드에서도 noah:// 가 이 실행 파일로 오도록
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            if let Err(e) = app.deep_link().register_all() {
+                eprintln!("noah:// 스킴 등록 실패: {}", e);
+            }
+
+            // 콜드 스타트: 플러그인이 잡은 URL + 실행 인자 (대기열이 중복을 거른다)
+            let handle = app.handle().clone();
+            let mut cold: Vec<String> = app
+                .deep_link()
+                .get_current()
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|url| url.to_string())
+                .collect();
+            cold.extend(deep_link::links_in_args(std::env::args().skip(1)));
+            for url in cold {
+                dispatch_deep_link(&handle, &url, DeepLinkSource::ColdStart);
+            }
+
+            let running = handle.clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    dispatch_deep_link(&running, url.as_str(), DeepLinkSource::Running);
+                }
+            });
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            start_oauth,
+            list_oauth_providers,
+            cancel_oauth_session,
+            list_oauth_sessions,
+            token_store_put,
+            token_store_get,
+            token_store_delete,
+            token_store_list,
+            get_valid_gcal_token,
+            take_pending_deep_links,
+            get_tasks,
+            get_my_day_tasks,
+            add_task,
+            update_task,
+            delete_task,
+            get_notes,
+            add_note,
+            update_note,
+            delete_note,
+            get_lists,
+            add_list,
+            update_list,
+            delete_list,
+            get_folders,
+            add_folder,
+            update_folder,
+            delete_folder,
+            get_mindmaps,
+            add_mindmap,
+            update_mindmap,
+            delete_mindmap,
+            local_db_replace,
+            search_everything,
+            list_conflicts,
+            resolve_conflict,
+            sync_set_credentials,
+            sync_status,
+            sync_retry,
+            sync_pending,
+            schedule_reminder,
+            cancel_reminder,
+            list_reminders,
+            notification_action,
+            open_notification_link,
+            notify,
+            get_notification_policy,
+            set_notification_policy,
+            get_agenda,
+            get_agenda_settings,
+            set_agenda_settings,
+            cache_timebox,
+            probe_download,
+            enqueue_download,
+            list_downloads,
+            pause_download,
+            resume_download,
+            cancel_download,
+            retry_download,
+            remove_download,
+            get_download_settings,
+            set_download_settings,
+            expand_recurrence,
+            next_occurrence,
+            expand_task_occurrences,
+            override_occurrence,
+            create_backup,
+            list_backups,
+            verify_backup,
+            restore_backup,
+            backup_status,
+            set_backup_settings,
+            open_folder
+        ]);
 
     #[cfg(not(target_os = "android"))]
     let builder = builder
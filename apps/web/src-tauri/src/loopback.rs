@@ -0,0 +1,1078 @@
+//! 127.0.0.1 전용 HTTP/1.1 서버.
+//!
+//! OAuth 리다이렉트/콜백을 받기 위한 용도로만 쓰이므로 기능은 작게 유지하되,
+//! 메시지 경계(Content-Length / chunked), keep-alive, 헤더 대소문자,
+//! 타임아웃과 크기 제한은 정확하게 처리한다.
+
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 서버 전체 및 연결 단위 제한값
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// 요청 라인 + 헤더 최대 바이트
+    pub max_head: usize,
+    /// 본문 최대 바이트 (chunked 디코딩 후 기준)
+    pub max_body: usize,
+    /// 소켓 read/write 한 번에 허용하는 대기 시간
+    pub io_timeout: Duration,
+    /// 첫 바이트부터 요청 하나를 다 받기까지의 시간 (조금씩 흘려 보내는 연결 방지)
+    pub request_timeout: Duration,
+    /// keep-alive 연결에서 다음 요청을 기다리는 시간
+    pub idle_timeout: Duration,
+    /// 서버 수명. 지나면 콜백을 받지 못했더라도 리스너를 닫는다
+    pub lifetime: Duration,
+    /// 동시에 처리하는 연결 수. 넘치면 받자마자 닫는다
+    pub max_connections: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_head: 16 * 1024,
+            max_body: 1024 * 1024,
+            io_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(15),
+            idle_timeout: Duration::from_secs(5),
+            lifetime: Duration::from_secs(300),
+            max_connections: 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// 퍼센트 디코딩된 경로 (쿼리 제외)
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    /// 수신 순서 그대로. 조회는 `header()`로 대소문자 무시
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    finish: bool,
+}
+
+impl Response {
+    pub fn new(status: u16) -> Self {
+        Self { status, headers: Vec::new(), body: Vec::new(), finish: false }
+    }
+
+    pub fn html(body: impl Into<String>) -> Self {
+        Self::new(200)
+            .with_header("Content-Type", "text/html; charset=utf-8")
+            .with_body(body.into().into_bytes())
+    }
+
+    pub fn json(body: impl Into<String>) -> Self {
+        Self::new(200)
+            .with_header("Content-Type", "application/json; charset=utf-8")
+            .with_body(body.into().into_bytes())
+    }
+
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        Self::new(status)
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+            .with_body(body.into().into_bytes())
+    }
+
+    pub fn no_content() -> Self {
+        Self::new(204)
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// 이 응답을 보낸 뒤 서버를 종료한다 (콜백 수신 완료 등)
+    pub fn finish(mut self) -> Self {
+        self.finish = true;
+        self
+    }
+
+    pub fn is_finish(&self) -> bool {
+        self.finish
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(name))
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        200 => "OK",
+        204 => "No Content",
+        302 => "Found",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        505 => "HTTP Version Not Supported",
+        _ => "",
+    }
+}
+
+pub type Handler = Box<dyn FnMut(&Request) -> Response + Send>;
+
+struct Route {
+    method: String,
+    path: String,
+    /// 핸들러마다 따로 잠가서, 느린 핸들러가 다른 경로의 요청을 막지 않게 한다
+    handler: Mutex<Handler>,
+}
+
+fn call(handler: &Mutex<Handler>, req: &Request) -> Response {
+    match handler.lock() {
+        Ok(mut handler) => handler(req),
+        Err(_) => Response::text(500, "internal error"),
+    }
+}
+
+/// 경로는 쿼리를 제외하고 정확히 일치해야 한다
+pub struct Router {
+    routes: Vec<Route>,
+    fallback: Option<Mutex<Handler>>,
+    default_headers: Vec<(String, String)>,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self { routes: Vec::new(), fallback: None, default_headers: Vec::new() }
+    }
+
+    pub fn route<F>(mut self, method: &str, path: &str, handler: F) -> Self
+    where
+        F: FnMut(&Request) -> Response + Send + 'static,
+    {
+        self.routes.push(Route {
+            method: method.to_ascii_uppercase(),
+            path: path.to_string(),
+            handler: Mutex::new(Box::new(handler)),
+        });
+        self
+    }
+
+    pub fn get<F>(self, path: &str, handler: F) -> Self
+    where
+        F: FnMut(&Request) -> Response + Send + 'static,
+    {
+        self.route("GET", path, handler)
+    }
+
+    pub fn post<F>(self, path: &str, handler: F) -> Self
+    where
+        F: FnMut(&Request) -> Response + Send + 'static,
+    {
+        self.route("POST", path, handler)
+    }
+
+    /// 어떤 라우트에도 걸리지 않은 요청 처리. 없으면 404
+    pub fn fallback<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(&Request) -> Response + Send + 'static,
+    {
+        self.fallback = Some(Mutex::new(Box::new(handler)));
+        self
+    }
+
+    /// 모든 응답에 붙는 헤더 (핸들러가 같은 이름을 지정하면 그쪽이 우선)
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.default_headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn dispatch(&self, req: &Request) -> Response {
+        let mut response = self.dispatch_inner(req);
+        for (k, v) in &self.default_headers {
+            if !response.has_header(k) {
+                response.headers.push((k.clone(), v.clone()));
+            }
+        }
+        response
+    }
+
+    fn dispatch_inner(&self, req: &Request) -> Response {
+        let path_matches: Vec<usize> = self
+            .routes
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.path == req.path)
+            .map(|(i, _)| i)
+            .collect();
+
+        if let Some(&i) = path_matches.iter().find(|&&i| self.routes[i].method == req.method) {
+            return call(&self.routes[i].handler, req);
+        }
+        // HEAD는 GET 핸들러 결과에서 본문만 뺀다 (Content-Length는 유지)
+        if req.method == "HEAD" {
+            if let Some(&i) = path_matches.iter().find(|&&i| self.routes[i].method == "GET") {
+                return call(&self.routes[i].handler, req);
+            }
+        }
+        if !path_matches.is_empty() {
+            let mut allow: Vec<&str> =
+                path_matches.iter().map(|&i| self.routes[i].method.as_str()).collect();
+            allow.push("OPTIONS");
+            let allow = allow.join(", ");
+            if req.method == "OPTIONS" {
+                return Response::no_content().with_header("Allow", &allow);
+            }
+            return Response::text(405, "method not allowed").with_header("Allow", &allow);
+        }
+        if req.method == "OPTIONS" {
+            return Response::no_content();
+        }
+        match &self.fallback {
+            Some(handler) => call(handler, req),
+            None => Response::text(404, "not found"),
+        }
+    }
+}
+
+/// `serve()` 종료 사유
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServeOutcome {
+    /// 핸들러가 `Response::finish()`를 반환함
+    Finished,
+    /// `Limits::lifetime` 초과
+    Expired,
+    /// `ShutdownHandle::shutdown()` 호출
+    Cancelled,
+}
+
+/// 다른 스레드에서 `serve()`를 멈추기 위한 핸들
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle(Arc<AtomicBool>);
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+pub struct LoopbackServer {
+    listener: TcpListener,
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl LoopbackServer {
+    /// 127.0.0.1의 임의 포트에 바인딩
+    pub fn bind() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        Ok(Self { listener, addr, shutdown: Arc::new(AtomicBool::new(false)) })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle(Arc::clone(&self.shutdown))
+    }
+
+    /// 종료될 때까지 블로킹. 연결마다 스레드를 하나씩 쓴다
+    /// (브라우저가 미리 열어 두는 유휴 연결이 다른 요청을 막지 않도록).
+    /// 스레드 수는 `Limits::max_connections`로 묶는다.
+    pub fn serve(self, router: Router, limits: Limits) -> ServeOutcome {
+        let router = Arc::new(router);
+        let active = Arc::new(AtomicUsize::new(0));
+        let finished = Arc::new(AtomicBool::new(false));
+        let limits = Arc::new(limits);
+        let deadline = Instant::now() + limits.lifetime;
+
+        if self.listener.set_nonblocking(true).is_err() {
+            return ServeOutcome::Expired;
+        }
+
+        loop {
+            if finished.load(Ordering::SeqCst) {
+                return ServeOutcome::Finished;
+            }
+            if self.shutdown.load(Ordering::SeqCst) {
+                return ServeOutcome::Cancelled;
+            }
+            if Instant::now() >= deadline {
+                // 열려 있는 keep-alive 연결도 더는 요청을 처리하지 않도록
+                self.shutdown.store(true, Ordering::SeqCst);
+                return ServeOutcome::Expired;
+            }
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if active.load(Ordering::SeqCst) >= limits.max_connections {
+                        let _ = stream.shutdown(Shutdown::Both);
+                        continue;
+                    }
+                    let slot = ConnectionSlot::take(&active);
+                    let router = Arc::clone(&router);
+                    let finished = Arc::clone(&finished);
+                    let shutdown = Arc::clone(&self.shutdown);
+                    let limits = Arc::clone(&limits);
+                    std::thread::spawn(move || {
+                        let _slot = slot;
+                        handle_connection(stream, &router, &finished, &shutdown, &limits);
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                Err(_) => std::thread::sleep(Duration::from_millis(20)),
+            }
+        }
+    }
+}
+
+/// 처리 중인 연결 수. 스레드가 끝나면 (패닉이어도) 돌려준다
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn take(active: &Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(active))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 요청 파싱 실패 시 돌려줄 상태 코드
+#[derive(Debug)]
+enum ParseError {
+    /// 연결이 닫혔거나 타임아웃 — 응답 없이 종료
+    Closed,
+    Status(u16, &'static str),
+}
+
+impl From<io::Error> for ParseError {
+    fn from(_: io::Error) -> Self {
+        ParseError::Closed
+    }
+}
+
+struct Conn {
+    stream: TcpStream,
+    buf: Vec<u8>,
+    /// 지금 읽는 요청을 다 받아야 하는 시각
+    deadline: Option<Instant>,
+    io_timeout: Duration,
+}
+
+impl Conn {
+    /// 버퍼에 데이터를 더 읽어 온다. EOF면 Closed, 요청 기한을 넘기면 408
+    fn fill(&mut self) -> Result<(), ParseError> {
+        let timed_out = ParseError::Status(408, "request timeout");
+        if let Some(deadline) = self.deadline {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Err(timed_out);
+            }
+            let _ = self.stream.set_read_timeout(Some(left.min(self.io_timeout)));
+        }
+        let mut tmp = [0u8; 8192];
+        let n = match self.stream.read(&mut tmp) {
+            Ok(n) => n,
+            Err(_) if self.deadline.is_some_and(|d| Instant::now() >= d) => return Err(timed_out),
+            Err(e) => return Err(e.into()),
+        };
+        if n == 0 {
+            return Err(ParseError::Closed);
+        }
+        self.buf.extend_from_slice(&tmp[..n]);
+        Ok(())
+    }
+
+    fn read_line(&mut self, max: usize) -> Result<Vec<u8>, ParseError> {
+        loop {
+            if let Some(pos) = find(&self.buf, b"\r\n") {
+                let line = self.buf[..pos].to_vec();
+                self.buf.drain(..pos + 2);
+                return Ok(line);
+            }
+            if self.buf.len() > max {
+                return Err(ParseError::Status(400, "line too long"));
+            }
+            self.fill()?;
+        }
+    }
+
+    fn read_exact_body(&mut self, len: usize) -> Result<Vec<u8>, ParseError> {
+        while self.buf.len() < len {
+            self.fill()?;
+        }
+        Ok(self.buf.drain(..len).collect())
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    router: &Router,
+    finished: &AtomicBool,
+    shutdown: &AtomicBool,
+    limits: &Limits,
+) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_write_timeout(Some(limits.io_timeout));
+    let mut conn = Conn { stream, buf: Vec::new(), deadline: None, io_timeout: limits.io_timeout };
+    let mut first = true;
+
+    loop {
+        // 첫 요청은 io_timeout, 이후 keep-alive 대기는 idle_timeout
+        let wait = if first { limits.io_timeout } else { limits.idle_timeout };
+        let _ = conn.stream.set_read_timeout(Some(wait));
+        conn.deadline = None;
+        if conn.buf.is_empty() && conn.fill().is_err() {
+            break;
+        }
+        let _ = conn.stream.set_read_timeout(Some(limits.io_timeout));
+        conn.deadline = Some(Instant::now() + limits.request_timeout);
+        first = false;
+
+        let parsed = read_request(&mut conn, limits);
+        conn.deadline = None;
+        let (req, keep_alive) = match parsed {
+            Ok(parsed) => parsed,
+            Err(ParseError::Closed) => break,
+            Err(ParseError::Status(status, msg)) => {
+                let resp = Response::text(status, msg);
+                let _ = write_response(&mut conn.stream, &resp, false, false);
+                break;
+            }
+        };
+
+        // 서버가 이미 끝났다면 (다른 연결에서 finish, 취소, 수명 초과) 핸들러를 부르지 않는다
+        if finished.load(Ordering::SeqCst) || shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+        let resp = router.dispatch(&req);
+        let finish = resp.is_finish();
+        let keep_alive = keep_alive && !finish && !finished.load(Ordering::SeqCst);
+        let head_only = req.method == "HEAD";
+        let written = write_response(&mut conn.stream, &resp, keep_alive, head_only);
+        if finish {
+            finished.store(true, Ordering::SeqCst);
+        }
+        if written.is_err() || !keep_alive {
+            break;
+        }
+    }
+    let _ = conn.stream.shutdown(Shutdown::Both);
+}
+
+/// 요청 하나를 읽는다. 반환값의 bool은 keep-alive 여부
+fn read_request(conn: &mut Conn, limits: &Limits) -> Result<(Request, bool), ParseError> {
+    // ── 헤더 블록 ──
+    let head_end = loop {
+        if let Some(pos) = find(&conn.buf, b"\r\n\r\n") {
+            break pos;
+        }
+        if conn.buf.len() > limits.max_head {
+            return Err(ParseError::Status(431, "request header too large"));
+        }
+        conn.fill()?;
+    };
+    if head_end > limits.max_head {
+        return Err(ParseError::Status(431, "request header too large"));
+    }
+    let head: Vec<u8> = conn.buf.drain(..head_end + 4).collect();
+    let head = std::str::from_utf8(&head[..head_end])
+        .map_err(|_| ParseError::Status(400, "invalid header encoding"))?;
+
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or("");
+    let mut parts = request_line.split(' ');
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(m), Some(t), Some(v), None) if !m.is_empty() && !t.is_empty() => (m, t, v),
+        _ => return Err(ParseError::Status(400, "malformed request line")),
+    };
+    if !method.bytes().all(|b| b.is_ascii_uppercase()) {
+        return Err(ParseError::Status(400, "malformed method"));
+    }
+    let http11 = match version {
+        "HTTP/1.1" => true,
+        "HTTP/1.0" => false,
+        _ => return Err(ParseError::Status(505, "unsupported http version")),
+    };
+
+    let mut headers = Vec::new();
+    for line in lines {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or(ParseError::Status(400, "malformed header"))?;
+        if name.is_empty() || name.bytes().any(|b| b.is_ascii_whitespace()) {
+            return Err(ParseError::Status(400, "malformed header"));
+        }
+        headers.push((name.to_string(), value.trim().to_string()));
+    }
+
+    let header = |name: &str| -> Vec<&str> {
+        headers
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    };
+
+    let connection = header("connection").join(",").to_ascii_lowercase();
+    let keep_alive = if http11 {
+        !connection.split(',').any(|t| t.trim() == "close")
+    } else {
+        connection.split(',').any(|t| t.trim() == "keep-alive")
+    };
+
+    let chunked = header("transfer-encoding")
+        .iter()
+        .flat_map(|v| v.split(','))
+        .map(|t| t.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let content_length = header("content-length");
+
+    if header("expect").iter().any(|v| v.eq_ignore_ascii_case("100-continue"))
+        && (!chunked.is_empty() || !content_length.is_empty())
+    {
+        let _ = conn.stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n");
+    }
+
+    let body = if !chunked.is_empty() {
+        // chunked 외의 전송 코딩은 지원하지 않음. CL과 함께 오면 요청 스머글링 방지를 위해 거부
+        if chunked.last().map(String::as_str) != Some("chunked")
+            || chunked.iter().any(|t| t != "chunked")
+        {
+            return Err(ParseError::Status(501, "unsupported transfer-encoding"));
+        }
+        if !content_length.is_empty() {
+            return Err(ParseError::Status(400, "both content-length and transfer-encoding"));
+        }
+        read_chunked(conn, limits)?
+    } else if !content_length.is_empty() {
+        let first = content_length[0];
+        if content_length.iter().any(|v| *v != first) {
+            return Err(ParseError::Status(400, "conflicting content-length"));
+        }
+        let len: usize = first
+            .parse()
+            .map_err(|_| ParseError::Status(400, "invalid content-length"))?;
+        if len > limits.max_body {
+            return Err(ParseError::Status(413, "payload too large"));
+        }
+        conn.read_exact_body(len)?
+    } else {
+        Vec::new()
+    };
+
+    let (raw_path, raw_query) = target.split_once('?').unwrap_or((target, ""));
+    let path = percent_decode(raw_path, false);
+    let query = parse_query(raw_query);
+
+    Ok((
+        Request { method: method.to_string(), path, query, headers, body },
+        keep_alive,
+    ))
+}
+
+fn read_chunked(conn: &mut Conn, limits: &Limits) -> Result<Vec<u8>, ParseError> {
+    let mut body = Vec::new();
+    loop {
+        let line = conn.read_line(1024)?;
+        let line = std::str::from_utf8(&line)
+            .map_err(|_| ParseError::Status(400, "invalid chunk size"))?;
+        let size_str = line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_str, 16)
+            .map_err(|_| ParseError::Status(400, "invalid chunk size"))?;
+        if size == 0 {
+            // trailer 필드는 읽고 버린다
+            let mut trailer_bytes = 0;
+            loop {
+                let trailer = conn.read_line(limits.max_head)?;
+                if trailer.is_empty() {
+                    return Ok(body);
+                }
+                trailer_bytes += trailer.len();
+                if trailer_bytes > limits.max_head {
+                    return Err(ParseError::Status(431, "trailer too large"));
+                }
+            }
+        }
+        if body.len() + size > limits.max_body {
+            return Err(ParseError::Status(413, "payload too large"));
+        }
+        let chunk = conn.read_exact_body(size)?;
+        body.extend_from_slice(&chunk);
+        if conn.read_line(2)?.is_empty() {
+            continue;
+        }
+        return Err(ParseError::Status(400, "malformed chunk"));
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    resp: &Response,
+    keep_alive: bool,
+    head_only: bool,
+) -> io::Result<()> {
+    let mut out = format!("HTTP/1.1 {} {}\r\n", resp.status, reason_phrase(resp.status));
+    for (k, v) in &resp.headers {
+        if k.eq_ignore_ascii_case("content-length") || k.eq_ignore_ascii_case("connection") {
+            continue;
+        }
+        out.push_str(k);
+        out.push_str(": ");
+        out.push_str(v);
+        out.push_str("\r\n");
+    }
+    // 1xx/204/304는 본문과 Content-Length를 보내지 않는다
+    let bodyless = resp.status < 200 || resp.status == 204 || resp.status == 304;
+    if !bodyless {
+        out.push_str(&format!("Content-Length: {}\r\n", resp.body.len()));
+    }
+    out.push_str(if keep_alive { "Connection: keep-alive\r\n" } else { "Connection: close\r\n" });
+    out.push_str("\r\n");
+
+    let mut bytes = out.into_bytes();
+    if !bodyless && !head_only {
+        bytes.extend_from_slice(&resp.body);
+    }
+    stream.write_all(&bytes)?;
+    stream.flush()
+}
+
+fn percent_decode(s: &str, plus_as_space: bool) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' if plus_as_space => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub fn parse_query(raw: &str) -> Vec<(String, String)> {
+    raw.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(k, true), percent_decode(v, true))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn spawn(router: Router, limits: Limits) -> (u16, mpsc::Receiver<ServeOutcome>) {
+        let server = LoopbackServer::bind().unwrap();
+        let port = server.port();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(server.serve(router, limits));
+        });
+        (port, rx)
+    }
+
+    fn echo_router() -> Router {
+        Router::new()
+            .get("/hello", |req| {
+                let name = req.query_param("name").unwrap_or("world").to_string();
+                let agent = req.header("x-agent").unwrap_or("").to_string();
+                Response::text(200, format!("hello {} ({})", name, agent))
+            })
+            .post("/echo", |req| Response::new(200).with_body(req.body.clone()))
+            .post("/done", |_| Response::json(r#"{"ok":true}"#).finish())
+    }
+
+    /// 파이프라이닝된 응답을 나눠 읽을 수 있도록 남은 바이트를 보관하는 테스트 클라이언트
+    struct Client {
+        stream: TcpStream,
+        buf: Vec<u8>,
+    }
+
+    impl Client {
+        fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+            self.stream.write_all(bytes)
+        }
+
+        fn fill(&mut self) -> usize {
+            let mut tmp = [0u8; 4096];
+            let n = self.stream.read(&mut tmp).unwrap();
+            self.buf.extend_from_slice(&tmp[..n]);
+            n
+        }
+
+        /// 서버가 연결을 닫을 때까지 남은 바이트를 모두 읽는다
+        fn read_rest(&mut self) -> Vec<u8> {
+            while self.fill() > 0 {}
+            std::mem::take(&mut self.buf)
+        }
+    }
+
+    fn connect(port: u16) -> Client {
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        Client { stream, buf: Vec::new() }
+    }
+
+    type Head = (u16, Vec<(String, String)>);
+
+    /// 상태 줄과 헤더만 읽는다 (HEAD 응답용)
+    fn read_head(client: &mut Client) -> Head {
+        let head_end = loop {
+            if let Some(pos) = find(&client.buf, b"\r\n\r\n") {
+                break pos;
+            }
+            assert!(client.fill() > 0, "connection closed before headers");
+        };
+        let head = String::from_utf8(client.buf[..head_end].to_vec()).unwrap();
+        client.buf.drain(..head_end + 4);
+        let mut lines = head.split("\r\n");
+        let status: u16 = lines.next().unwrap().split(' ').nth(1).unwrap().parse().unwrap();
+        let headers = lines
+            .map(|l| {
+                let (k, v) = l.split_once(':').unwrap();
+                (k.to_string(), v.trim().to_string())
+            })
+            .collect();
+        (status, headers)
+    }
+
+    /// 응답 하나를 Content-Length 기준으로 읽는다
+    fn read_response(client: &mut Client) -> (u16, Vec<(String, String)>, Vec<u8>) {
+        let (status, headers) = read_head(client);
+        let len = header(&headers, "content-length").map(|v| v.parse::<usize>().unwrap()).unwrap_or(0);
+        while client.buf.len() < len {
+            assert!(client.fill() > 0, "connection closed before body");
+        }
+        let body = client.buf.drain(..len).collect();
+        (status, headers, body)
+    }
+
+    fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn get_with_query_and_mixed_case_headers() {
+        let (port, _) = spawn(echo_router(), Limits::default());
+        let mut s = connect(port);
+        s.write_all(b"GET /hello?name=%ED%95%9C+%EA%B8%80&x=1 HTTP/1.1\r\nHost: x\r\nX-AGENT: raw\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let (status, headers, body) = read_response(&mut s);
+        assert_eq!(status, 200);
+        assert_eq!(String::from_utf8(body).unwrap(), "hello 한 글 (raw)");
+        assert_eq!(header(&headers, "connection"), Some("close"));
+    }
+
+    #[test]
+    fn content_length_counts_bytes_not_chars() {
+        let router = Router::new().get("/ko", |_| Response::html("로그인 완료"));
+        let (port, _) = spawn(router, Limits::default());
+        let mut s = connect(port);
+        s.write_all(b"GET /ko HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+        let (_, headers, body) = read_response(&mut s);
+        assert_eq!(header(&headers, "content-length"), Some("16"));
+        assert_eq!(body, "로그인 완료".as_bytes());
+    }
+
+    #[test]
+    fn binary_body_split_across_writes() {
+        let (port, _) = spawn(echo_router(), Limits::default());
+        let mut s = connect(port);
+        let payload: Vec<u8> = (0..=255u8).cycle().take(70_000).collect();
+        s.write_all(format!("POST /echo HTTP/1.1\r\ncontent-length: {}\r\n\r\n", payload.len()).as_bytes())
+            .unwrap();
+        for chunk in payload.chunks(10_000) {
+            s.write_all(chunk).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let (status, _, body) = read_response(&mut s);
+        assert_eq!(status, 200);
+        assert_eq!(body, payload);
+    }
+
+    #[test]
+    fn chunked_body_with_extensions_and_trailer() {
+        let (port, _) = spawn(echo_router(), Limits::default());
+        let mut s = connect(port);
+        s.write_all(
+            b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nX-Trailer: y\r\n\r\n",
+        )
+        .unwrap();
+        let (status, _, body) = read_response(&mut s);
+        assert_eq!(status, 200);
+        assert_eq!(body, b"hello world");
+    }
+
+    #[test]
+    fn keep_alive_and_pipelined_requests() {
+        let (port, _) = spawn(echo_router(), Limits::default());
+        let mut s = connect(port);
+        s.write_all(b"POST /echo HTTP/1.1\r\nContent-Length: 3\r\n\r\nonePOST /echo HTTP/1.1\r\nContent-Length: 3\r\n\r\ntwo")
+            .unwrap();
+        let (_, h1, b1) = read_response(&mut s);
+        let (_, _, b2) = read_response(&mut s);
+        assert_eq!(header(&h1, "connection"), Some("keep-alive"));
+        assert_eq!((b1.as_slice(), b2.as_slice()), (&b"one"[..], &b"two"[..]));
+
+        s.write_all(b"GET /hello HTTP/1.1\r\n\r\n").unwrap();
+        let (status, _, body) = read_response(&mut s);
+        assert_eq!(status, 200);
+        assert_eq!(body, b"hello world ()");
+    }
+
+    #[test]
+    fn http10_closes_by_default() {
+        let (port, _) = spawn(echo_router(), Limits::default());
+        let mut s = connect(port);
+        s.write_all(b"GET /hello HTTP/1.0\r\n\r\n").unwrap();
+        let (_, headers, _) = read_response(&mut s);
+        assert_eq!(header(&headers, "connection"), Some("close"));
+        assert!(s.read_rest().is_empty());
+    }
+
+    #[test]
+    fn rejects_oversized_body_and_head() {
+        let limits = Limits { max_body: 8, max_head: 256, ..Limits::default() };
+        let (port, _) = spawn(echo_router(), limits);
+
+        let mut s = connect(port);
+        s.write_all(b"POST /echo HTTP/1.1\r\nContent-Length: 9\r\n\r\n123456789").unwrap();
+        assert_eq!(read_response(&mut s).0, 413);
+
+        let mut s = connect(port);
+        s.write_all(b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n12345\r\n5\r\n67890\r\n0\r\n\r\n")
+            .unwrap();
+        assert_eq!(read_response(&mut s).0, 413);
+
+        let mut s = connect(port);
+        let big = format!("GET /hello HTTP/1.1\r\nX-Pad: {}\r\n\r\n", "a".repeat(400));
+        s.write_all(big.as_bytes()).unwrap();
+        assert_eq!(read_response(&mut s).0, 431);
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let (port, _) = spawn(echo_router(), Limits::default());
+        for raw in [
+            &b"GARBAGE\r\n\r\n"[..],
+            b"GET /hello HTTP/1.1\r\nno-colon\r\n\r\n",
+            b"POST /echo HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 3\r\n\r\nabc",
+            b"POST /echo HTTP/1.1\r\nContent-Length: x\r\n\r\n",
+            b"POST /echo HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        ] {
+            let mut s = connect(port);
+            s.write_all(raw).unwrap();
+            assert_eq!(read_response(&mut s).0, 400, "{:?}", String::from_utf8_lossy(raw));
+        }
+    }
+
+    #[test]
+    fn routing_statuses() {
+        let (port, _) = spawn(echo_router(), Limits::default());
+        let mut s = connect(port);
+        s.write_all(b"GET /missing HTTP/1.1\r\n\r\nGET /echo HTTP/1.1\r\n\r\nOPTIONS /echo HTTP/1.1\r\n\r\nHEAD /hello HTTP/1.1\r\n\r\n")
+            .unwrap();
+        assert_eq!(read_response(&mut s).0, 404);
+        let (status, headers, _) = read_response(&mut s);
+        assert_eq!(status, 405);
+        assert_eq!(header(&headers, "allow"), Some("POST, OPTIONS"));
+        assert_eq!(read_response(&mut s).0, 204);
+
+        // HEAD: Content-Length는 GET과 같고 본문은 없다
+        let (status, headers) = read_head(&mut s);
+        assert_eq!(status, 200);
+        assert_eq!(header(&headers, "content-length"), Some("14"));
+        assert!(s.buf.is_empty());
+    }
+
+    #[test]
+    fn default_headers_are_applied() {
+        let router = echo_router().default_header("Cache-Control", "no-store");
+        let (port, _) = spawn(router, Limits::default());
+        let mut s = connect(port);
+        s.write_all(b"GET /hello HTTP/1.1\r\n\r\n").unwrap();
+        let (_, headers, _) = read_response(&mut s);
+        assert_eq!(header(&headers, "cache-control"), Some("no-store"));
+    }
+
+    #[test]
+    fn finish_stops_server() {
+        let (port, rx) = spawn(echo_router(), Limits::default());
+        let mut s = connect(port);
+        s.write_all(b"POST /done HTTP/1.1\r\nContent-Length: 0\r\n\r\n").unwrap();
+        let (status, headers, body) = read_response(&mut s);
+        assert_eq!(status, 200);
+        assert_eq!(body, br#"{"ok":true}"#);
+        assert_eq!(header(&headers, "connection"), Some("close"));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(2)).unwrap(), ServeOutcome::Finished);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(TcpStream::connect(("127.0.0.1", port)).is_err());
+    }
+
+    #[test]
+    fn lifetime_expires_without_callback() {
+        let limits = Limits { lifetime: Duration::from_millis(200), ..Limits::default() };
+        let (_, rx) = spawn(echo_router(), limits);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(2)).unwrap(), ServeOutcome::Expired);
+    }
+
+    #[test]
+    fn shutdown_handle_cancels_server() {
+        let server = LoopbackServer::bind().unwrap();
+        let port = server.port();
+        let handle = server.shutdown_handle();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(server.serve(echo_router(), Limits::default()));
+        });
+        let mut s = connect(port);
+        s.write_all(b"GET /hello HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(read_response(&mut s).0, 200);
+
+        handle.shutdown();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(2)).unwrap(), ServeOutcome::Cancelled);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(TcpStream::connect(("127.0.0.1", port)).is_err());
+    }
+
+    #[test]
+    fn idle_connection_does_not_block_others() {
+        let limits = Limits { io_timeout: Duration::from_millis(300), ..Limits::default() };
+        let (port, _) = spawn(echo_router(), limits);
+        let mut idle = connect(port);
+        let mut s = connect(port);
+        s.write_all(b"GET /hello HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(read_response(&mut s).0, 200);
+
+        // 아무것도 보내지 않은 연결은 타임아웃 후 닫힌다
+        assert!(idle.read_rest().is_empty());
+    }
+
+    #[test]
+    fn slow_request_times_out_as_a_whole() {
+        let limits = Limits {
+            io_timeout: Duration::from_secs(2),
+            request_timeout: Duration::from_millis(300),
+            ..Limits::default()
+        };
+        let (port, _) = spawn(echo_router(), limits);
+        let mut s = connect(port);
+        let started = Instant::now();
+        s.write_all(b"GET /hello HTTP/1.1\r\n").unwrap();
+        // 읽기 한 번의 타임아웃보다 짧은 간격으로 조금씩 보낸다
+        for byte in b"X-A" {
+            std::thread::sleep(Duration::from_millis(60));
+            s.write_all(&[*byte]).unwrap();
+        }
+        assert_eq!(read_response(&mut s).0, 408);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn slow_handler_does_not_block_other_routes() {
+        let (release, wait) = mpsc::channel::<()>();
+        let wait = Mutex::new(wait);
+        let router = echo_router().get("/slow", move |_| {
+            let _ = wait.lock().unwrap().recv_timeout(Duration::from_secs(5));
+            Response::no_content()
+        });
+        let (port, _) = spawn(router, Limits::default());
+        let mut slow = connect(port);
+        slow.write_all(b"GET /slow HTTP/1.1\r\n\r\n").unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+
+        let mut s = connect(port);
+        s.stream.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        s.write_all(b"GET /hello HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(read_response(&mut s).0, 200);
+        release.send(()).unwrap();
+        assert_eq!(read_response(&mut slow).0, 204);
+    }
+
+    #[test]
+    fn connections_over_the_limit_are_closed() {
+        let limits = Limits { max_connections: 2, ..Limits::default() };
+        let (port, _) = spawn(echo_router(), limits);
+        let mut held = vec![connect(port), connect(port)];
+        std::thread::sleep(Duration::from_millis(100));
+
+        let mut extra = connect(port);
+        let started = Instant::now();
+        assert!(extra.read_rest().is_empty());
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // 자리가 나면 다시 받는다
+        held[0].write_all(b"GET /hello HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+        assert_eq!(read_response(&mut held[0]).0, 200);
+        held.remove(0);
+        std::thread::sleep(Duration::from_millis(100));
+        let mut s = connect(port);
+        s.write_all(b"GET /hello HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(read_response(&mut s).0, 200);
+    }
+}
@@ -0,0 +1,673 @@
+//! 여러 기기/공유 목록에서 같은 문서를 동시에 고쳤을 때의 병합.
+//!
+//! - 할 일: 필드마다 HLC(hybrid logical clock)를 `fieldClocks`에 기록하고 필드 단위 last-writer-wins.
+//!   시계 없이 고친 쪽(웹 클라이언트)은 기준본과 값이 달라졌으면 문서의 `updatedAt`을 시계로 본다.
+//! - 노트: `blocks`를 블록 id 기준으로 3-way 병합한다. 나머지 필드는 필드 단위 3-way, 양쪽이 다르게 고쳤으면
+//!   `updatedAt`이 늦은 쪽. 같은 블록을 양쪽에서 다르게 고친 경우는 로컬 쪽을 남기고 충돌로 기록한다.
+//!
+//! 기준본(base)은 마지막으로 받은 원격 문서로, `shadows` 테이블에 둔다.
+
+use crate::storage::{new_document_id, Collection, StorageError};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+
+/// 문서 필드 이름. 값은 `{필드: Hlc 문자열}`
+pub const FIELD_CLOCKS: &str = "fieldClocks";
+
+/// 병합 대상이 아닌 공통 필드
+const META_FIELDS: &[&str] = &["id", "createdAt", "updatedAt", FIELD_CLOCKS];
+
+/// (물리 시각 ms, 같은 ms 안의 순번, 기기) 순으로 비교한다
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hlc {
+    pub wall: i64,
+    pub counter: u32,
+    pub node: String,
+}
+
+impl Hlc {
+    /// 시계 없이 고친 값에 쓰는 시계 (같은 시각이면 시계가 있는 쪽이 이긴다)
+    fn at(wall: i64) -> Hlc {
+        Hlc { wall, counter: 0, node: String::new() }
+    }
+
+    /// `wall:counter:node` (문자열 비교 순서 = 시계 순서)
+    pub fn parse(s: &str) -> Option<Hlc> {
+        let mut parts = s.splitn(3, ':');
+        let wall = parts.next()?.parse().ok()?;
+        let counter = u32::from_str_radix(parts.next()?, 16).ok()?;
+        Some(Hlc { wall, counter, node: parts.next()?.to_string() })
+    }
+}
+
+impl fmt::Display for Hlc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:013}:{:04x}:{}", self.wall, self.counter, self.node)
+    }
+}
+
+/// 이 기기의 시계. 원격에서 본 시계보다 항상 뒤의 값을 낸다
+pub struct HlcClock {
+    node: String,
+    last: Mutex<(i64, u32)>,
+}
+
+impl HlcClock {
+    pub fn new(node: String) -> Self {
+        HlcClock { node, last: Mutex::new((0, 0)) }
+    }
+
+    pub fn now(&self) -> Hlc {
+        self.tick(crate::token_store::now_ms() as i64)
+    }
+
+    fn tick(&self, wall_now: i64) -> Hlc {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        *last = if wall_now > last.0 { (wall_now, 0) } else { (last.0, last.1 + 1) };
+        Hlc { wall: last.0, counter: last.1, node: self.node.clone() }
+    }
+
+    pub fn observe(&self, remote: &Hlc) {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        *last = (*last).max((remote.wall, remote.counter));
+    }
+}
+
+/// 기기 id. 처음 한 번 만들어 `meta`에 둔다
+pub(crate) fn node_id(conn: &Connection) -> Result<String, StorageError> {
+    let existing: Option<String> =
+        conn.query_row("SELECT value FROM meta WHERE key = 'node_id'", [], |row| row.get(0)).optional()?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    let id = new_document_id()[..10].to_string();
+    conn.execute("INSERT INTO meta (key, value) VALUES ('node_id', ?1)", [&id])?;
+    Ok(id)
+}
+
+pub fn field_clocks(doc: &Map<String, Value>) -> BTreeMap<String, Hlc> {
+    let Some(Value::Object(clocks)) = doc.get(FIELD_CLOCKS) else {
+        return BTreeMap::new();
+    };
+    clocks.iter().filter_map(|(k, v)| Some((k.clone(), Hlc::parse(v.as_str()?)?))).collect()
+}
+
+/// `fields`의 시계를 `clock`으로 올린다
+pub fn stamp<'a>(doc: &mut Map<String, Value>, fields: impl IntoIterator<Item = &'a str>, clock: &Hlc) {
+    let mut clocks = match doc.remove(FIELD_CLOCKS) {
+        Some(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    for field in fields.into_iter().filter(|f| !META_FIELDS.contains(f)) {
+        clocks.insert(field.to_string(), Value::String(clock.to_string()));
+    }
+    doc.insert(FIELD_CLOCKS.into(), Value::Object(clocks));
+}
+
+fn field<'a>(doc: &'a Map<String, Value>, key: &str) -> &'a Value {
+    doc.get(key).unwrap_or(&Value::Null)
+}
+
+fn updated_at(doc: &Map<String, Value>) -> i64 {
+    doc.get("updatedAt").and_then(Value::as_i64).unwrap_or(0)
+}
+
+fn merge_keys<'a>(docs: &[&'a Map<String, Value>]) -> Vec<&'a str> {
+    let mut keys: Vec<&str> = docs.iter().flat_map(|d| d.keys().map(String::as_str)).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys.retain(|k| !META_FIELDS.contains(k));
+    keys
+}
+
+/// 병합 결과의 공통 필드: 로컬 id/createdAt, 늦은 쪽 updatedAt
+fn merged_meta(local: &Map<String, Value>, remote: &Map<String, Value>) -> Map<String, Value> {
+    let mut merged = Map::new();
+    for key in ["id", "createdAt"] {
+        if let Some(v) = local.get(key).or_else(|| remote.get(key)) {
+            merged.insert(key.into(), v.clone());
+        }
+    }
+    if local.contains_key("updatedAt") || remote.contains_key("updatedAt") {
+        merged.insert("updatedAt".into(), Value::from(updated_at(local).max(updated_at(remote))));
+    }
+    merged
+}
+
+/// 할 일 필드 단위 LWW. 같은 시계면 로컬 쪽
+pub fn merge_task(
+    base: Option<&Map<String, Value>>,
+    local: &Map<String, Value>,
+    remote: &Map<String, Value>,
+) -> Map<String, Value> {
+    let base_clocks = base.map(field_clocks).unwrap_or_default();
+    let (local_clocks, remote_clocks) = (field_clocks(local), field_clocks(remote));
+
+    // 이 쪽에서 본 필드의 시계. 기준본과 같은 값이면 이 쪽은 고치지 않은 것
+    let effective = |doc: &Map<String, Value>, clocks: &BTreeMap<String, Hlc>, key: &str| -> Option<Hlc> {
+        let clock = clocks.get(key).cloned();
+        let Some(base) = base else {
+            return clock.or_else(|| Some(Hlc::at(updated_at(doc))));
+        };
+        if field(doc, key) == field(base, key) {
+            clock
+        } else if clock.as_ref() <= base_clocks.get(key) {
+            // 시계를 올리지 않고 값만 바꾼 쓰기
+            Some(Hlc::at(updated_at(doc)))
+        } else {
+            clock
+        }
+    };
+
+    let mut merged = merged_meta(local, remote);
+    let mut clocks = Map::new();
+    for key in merge_keys(&[local, remote]) {
+        let (lc, rc) = (effective(local, &local_clocks, key), effective(remote, &remote_clocks, key));
+        let (value, clock) = if field(local, key) == field(remote, key) {
+            (field(local, key), lc.max(rc))
+        } else if rc > lc {
+            (field(remote, key), rc)
+        } else {
+            (field(local, key), lc)
+        };
+        merged.insert(key.to_string(), value.clone());
+        if let Some(clock) = clock {
+            clocks.insert(key.to_string(), Value::String(clock.to_string()));
+        }
+    }
+    merged.insert(FIELD_CLOCKS.into(), Value::Object(clocks));
+    merged
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// 양쪽에서 같은 블록을 다르게 고침
+    BothEdited,
+    /// 로컬에서는 지웠는데 원격에서 고침 (고친 블록을 남겨 둔다)
+    DeletedLocally,
+    /// 원격에서는 지웠는데 로컬에서 고침 (고친 블록을 남겨 둔다)
+    DeletedRemotely,
+}
+
+impl ConflictKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ConflictKind::BothEdited => "both_edited",
+            ConflictKind::DeletedLocally => "deleted_locally",
+            ConflictKind::DeletedRemotely => "deleted_remotely",
+        }
+    }
+
+    fn parse(s: &str) -> Option<ConflictKind> {
+        serde_json::from_value(Value::String(s.to_string())).ok()
+    }
+}
+
+/// 자동으로 합치지 못한 블록 하나. 없는 쪽(지운 쪽)은 `None`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockConflict {
+    pub block_id: String,
+    pub kind: ConflictKind,
+    pub base: Option<Value>,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+}
+
+fn block_id(block: &Value) -> Option<&str> {
+    block.get("id").and_then(Value::as_str)
+}
+
+fn block_ids(blocks: &[Value]) -> Vec<&str> {
+    blocks.iter().filter_map(block_id).collect()
+}
+
+fn by_id(blocks: &[Value]) -> HashMap<&str, &Value> {
+    blocks.iter().filter_map(|b| Some((block_id(b)?, b))).collect()
+}
+
+/// 블록 안의 필드 단위 3-way. 같은 필드를 양쪽에서 다르게 고쳤으면 `None`
+fn merge_block(base: &Value, local: &Value, remote: &Value) -> Option<Value> {
+    let (Value::Object(b), Value::Object(l), Value::Object(r)) = (base, local, remote) else {
+        return None;
+    };
+    let mut merged = Map::new();
+    for key in merge_keys(&[l, r]).into_iter().chain(["id"]) {
+        let (bv, lv, rv) = (field(b, key), field(l, key), field(r, key));
+        let value = if lv == rv || rv == bv {
+            lv
+        } else if lv == bv {
+            rv
+        } else {
+            return None;
+        };
+        if !value.is_null() || (l.contains_key(key) && r.contains_key(key)) {
+            merged.insert(key.to_string(), value.clone());
+        }
+    }
+    Some(Value::Object(merged))
+}
+
+/// 공통 블록의 상대 순서가 기준본과 다른가
+fn reordered(base: &[&str], side: &[&str]) -> bool {
+    let side_set: HashSet<&str> = side.iter().copied().collect();
+    let base_set: HashSet<&str> = base.iter().copied().collect();
+    let a = base.iter().filter(|id| side_set.contains(*id));
+    let b = side.iter().filter(|id| base_set.contains(*id));
+    !a.eq(b)
+}
+
+/// 블록 순서: 한쪽만 순서를 바꿨으면 그쪽, 아니면 로컬 순서를 따르고 다른 쪽에만 있는 블록은
+/// 그쪽에서 바로 앞에 있던 블록 뒤에 끼운다
+fn merge_order<'a>(base: &[&'a str], local: &[&'a str], remote: &[&'a str], keep: &HashSet<&str>) -> Vec<&'a str> {
+    let (primary, secondary) =
+        if !reordered(base, local) && reordered(base, remote) { (remote, local) } else { (local, remote) };
+    let mut order: Vec<&str> = primary.iter().copied().filter(|id| keep.contains(id)).collect();
+    let mut anchor: Option<&str> = None;
+    for id in secondary.iter().copied() {
+        if keep.contains(id) && !order.contains(&id) {
+            let at = anchor.and_then(|a| order.iter().position(|x| *x == a)).map_or(0, |i| i + 1);
+            order.insert(at, id);
+        }
+        if order.contains(&id) {
+            anchor = Some(id);
+        }
+    }
+    order
+}
+
+/// 블록 id 기준 3-way 병합. 기준본이 없으면 양쪽에 있는 블록이 다를 때만 충돌
+pub fn merge_blocks(base: Option<&[Value]>, local: &[Value], remote: &[Value]) -> (Vec<Value>, Vec<BlockConflict>) {
+    let (base_ids, local_ids, remote_ids) = (block_ids(base.unwrap_or_default()), block_ids(local), block_ids(remote));
+    let (b, l, r) = (by_id(base.unwrap_or_default()), by_id(local), by_id(remote));
+
+    let mut chosen: HashMap<&str, Value> = HashMap::new();
+    let mut conflicts = Vec::new();
+    let mut all: Vec<&str> = local_ids.iter().chain(&remote_ids).chain(&base_ids).copied().collect();
+    let mut seen = HashSet::new();
+    all.retain(|id| seen.insert(*id));
+
+    for id in all {
+        let (bv, lv, rv) = (b.get(id).copied(), l.get(id).copied(), r.get(id).copied());
+        let conflict = |kind| BlockConflict {
+            block_id: id.to_string(),
+            kind,
+            base: bv.cloned(),
+            local: lv.cloned(),
+            remote: rv.cloned(),
+        };
+        let value = if lv == rv {
+            lv.cloned()
+        } else if base.is_some() && lv == bv {
+            rv.cloned()
+        } else if base.is_some() && rv == bv {
+            lv.cloned()
+        } else {
+            match (lv, rv) {
+                (Some(lv), Some(rv)) => match bv.and_then(|bv| merge_block(bv, lv, rv)) {
+                    Some(merged) => Some(merged),
+                    None => {
+                        conflicts.push(conflict(ConflictKind::BothEdited));
+                        Some(lv.clone())
+                    }
+                },
+                (None, Some(rv)) => {
+                    conflicts.push(conflict(ConflictKind::DeletedLocally));
+                    Some(rv.clone())
+                }
+                (Some(lv), None) => {
+                    conflicts.push(conflict(ConflictKind::DeletedRemotely));
+                    Some(lv.clone())
+                }
+                (None, None) => None,
+            }
+        };
+        if let Some(value) = value {
+            chosen.insert(id, value);
+        }
+    }
+
+    let keep: HashSet<&str> = chosen.keys().copied().collect();
+    let order = merge_order(&base_ids, &local_ids, &remote_ids, &keep);
+    let blocks = order.into_iter().filter_map(|id| chosen.remove(id)).collect();
+    (blocks, conflicts)
+}
+
+/// 노트 병합. 블록 외 필드는 3-way, 양쪽이 다르게 고쳤으면 `updatedAt`이 늦은 쪽 (같으면 로컬)
+pub fn merge_note(
+    base: Option<&Map<String, Value>>,
+    local: &Map<String, Value>,
+    remote: &Map<String, Value>,
+) -> (Map<String, Value>, Vec<BlockConflict>) {
+    let remote_newer = updated_at(remote) > updated_at(local);
+    let mut merged = merged_meta(local, remote);
+    let mut conflicts = Vec::new();
+    for key in merge_keys(&[local, remote]) {
+        let (lv, rv) = (field(local, key), field(remote, key));
+        if key == "blocks" {
+            let blocks = |v: &Value| v.as_array().map(Vec::as_slice).unwrap_or_default().to_vec();
+            let base_blocks = base.map(|b| blocks(field(b, key)));
+            let (merged_blocks, found) = merge_blocks(base_blocks.as_deref(), &blocks(lv), &blocks(rv));
+            merged.insert(key.into(), Value::Array(merged_blocks));
+            conflicts = found;
+            continue;
+        }
+        let bv = base.map(|b| field(b, key));
+        let value = if lv == rv || bv == Some(rv) {
+            lv
+        } else if bv == Some(lv) || remote_newer {
+            rv
+        } else {
+            lv
+        };
+        if local.contains_key(key) || remote.contains_key(key) {
+            merged.insert(key.to_string(), value.clone());
+        }
+    }
+    (merged, conflicts)
+}
+
+/// 컬렉션에 맞는 병합. 병합하지 않는 컬렉션(목록/폴더/마인드맵)은 `None` — 로컬 변경을 그대로 둔다
+pub(crate) fn merge_document(
+    collection: Collection,
+    base: Option<&Map<String, Value>>,
+    local: &Map<String, Value>,
+    remote: &Map<String, Value>,
+) -> Option<(Map<String, Value>, Vec<BlockConflict>)> {
+    match collection {
+        Collection::Tasks => Some((merge_task(base, local, remote), Vec::new())),
+        Collection::Notes => Some(merge_note(base, local, remote)),
+        Collection::Lists | Collection::Folders | Collection::Mindmaps => None,
+    }
+}
+
+/// 기준본을 남겨 두는 컬렉션
+pub(crate) fn keeps_shadow(collection: Collection) -> bool {
+    matches!(collection, Collection::Tasks | Collection::Notes)
+}
+
+pub(crate) fn shadow(
+    conn: &Connection,
+    uid: &str,
+    collection: Collection,
+    id: &str,
+) -> Result<Option<Map<String, Value>>, StorageError> {
+    let doc: Option<String> = conn
+        .prepare_cached("SELECT doc FROM shadows WHERE uid = ?1 AND collection = ?2 AND id = ?3")?
+        .query_row(params![uid, collection.table(), id], |row| row.get(0))
+        .optional()?;
+    Ok(doc.and_then(|d| serde_json::from_str(&d).ok()))
+}
+
+/// 원격 스냅샷 전체를 기준본으로
+pub(crate) fn replace_shadows<'a>(
+    conn: &Connection,
+    uid: &str,
+    collection: Collection,
+    docs: impl IntoIterator<Item = (&'a str, &'a Map<String, Value>)>,
+) -> Result<(), StorageError> {
+    conn.execute("DELETE FROM shadows WHERE uid = ?1 AND collection = ?2", [uid, collection.table()])?;
+    let mut stmt = conn.prepare_cached("INSERT INTO shadows (uid, collection, id, doc) VALUES (?1, ?2, ?3, ?4)")?;
+    for (id, doc) in docs {
+        stmt.execute(params![uid, collection.table(), id, Value::Object(doc.clone()).to_string()])?;
+    }
+    Ok(())
+}
+
+/// 사용자가 골라야 하는 노트 블록 충돌
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conflict {
+    pub id: String,
+    pub note_id: String,
+    pub block_id: String,
+    pub kind: ConflictKind,
+    pub base: Option<Value>,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+    pub created_at: i64,
+}
+
+/// `resolve_conflict`에서 고르는 쪽. `custom`의 값이 null이면 블록을 지운다
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "choice", content = "block", rename_all = "snake_case")]
+pub enum Resolution {
+    Local,
+    Remote,
+    Custom(Value),
+}
+
+impl Conflict {
+    /// 고른 블록 (지우기로 했으면 `None`)
+    pub fn chosen(&self, resolution: &Resolution) -> Option<Value> {
+        match resolution {
+            Resolution::Local => self.local.clone(),
+            Resolution::Remote => self.remote.clone(),
+            Resolution::Custom(Value::Null) => None,
+            Resolution::Custom(block) => Some(block.clone()),
+        }
+    }
+}
+
+const SELECT_CONFLICT: &str = "SELECT id, note_id, block_id, kind, base, local, remote, created_at FROM conflicts";
+
+fn row_to_conflict(row: &Row<'_>) -> rusqlite::Result<Conflict> {
+    let kind: String = row.get(3)?;
+    let json = |i: usize| -> rusqlite::Result<Option<Value>> {
+        Ok(row.get::<_, Option<String>>(i)?.and_then(|s| serde_json::from_str(&s).ok()))
+    };
+    Ok(Conflict {
+        id: row.get(0)?,
+        note_id: row.get(1)?,
+        block_id: row.get(2)?,
+        kind: ConflictKind::parse(&kind)
+            .ok_or_else(|| rusqlite::Error::InvalidColumnType(3, "kind".into(), rusqlite::types::Type::Text))?,
+        base: json(4)?,
+        local: json(5)?,
+        remote: json(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+/// 같은 블록에 남아 있던 충돌은 새 것으로 바꾼다
+pub(crate) fn record_conflict(
+    conn: &Connection,
+    uid: &str,
+    note_id: &str,
+    conflict: &BlockConflict,
+    now: i64,
+) -> Result<(), StorageError> {
+    let json = |v: &Option<Value>| v.as_ref().map(Value::to_string);
+    conn.prepare_cached(
+        "INSERT INTO conflicts (id, uid, note_id, block_id, kind, base, local, remote, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT (uid, note_id, block_id) DO UPDATE SET id = excluded.id, kind = excluded.kind,
+           base = excluded.base, local = excluded.local, remote = excluded.remote, created_at = excluded.created_at",
+    )?
+    .execute(params![
+        new_document_id(),
+        uid,
+        note_id,
+        conflict.block_id,
+        conflict.kind.as_str(),
+        json(&conflict.base),
+        json(&conflict.local),
+        json(&conflict.remote),
+        now
+    ])?;
+    Ok(())
+}
+
+pub(crate) fn list_conflicts(conn: &Connection, uid: &str) -> Result<Vec<Conflict>, StorageError> {
+    let sql = format!(
+        "{} WHERE uid = ?1 AND note_id IN (SELECT id FROM notes WHERE uid = ?1) ORDER BY created_at, id",
+        SELECT_CONFLICT
+    );
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let rows = stmt.query_map([uid], row_to_conflict)?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+pub(crate) fn get_conflict(conn: &Connection, uid: &str, id: &str) -> Result<Option<Conflict>, StorageError> {
+    let sql = format!("{} WHERE uid = ?1 AND id = ?2", SELECT_CONFLICT);
+    Ok(conn.prepare_cached(&sql)?.query_row([uid, id], row_to_conflict).optional()?)
+}
+
+pub(crate) fn remove_conflict(conn: &Connection, uid: &str, id: &str) -> Result<(), StorageError> {
+    conn.prepare_cached("DELETE FROM conflicts WHERE uid = ?1 AND id = ?2")?.execute([uid, id])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn obj(v: Value) -> Map<String, Value> {
+        v.as_object().cloned().unwrap()
+    }
+
+    fn hlc(wall: i64, node: &str) -> String {
+        Hlc { wall, counter: 0, node: node.into() }.to_string()
+    }
+
+    #[test]
+    fn hlc_orders_and_round_trips() {
+        let clock = HlcClock::new("a".into());
+        let first = clock.tick(100);
+        let second = clock.tick(100);
+        let behind = clock.tick(50);
+        assert!(first < second && second < behind);
+        assert_eq!(behind.wall, 100);
+
+        clock.observe(&Hlc { wall: 500, counter: 7, node: "b".into() });
+        let next = clock.tick(200);
+        assert_eq!((next.wall, next.counter), (500, 8));
+        assert_eq!(Hlc::parse(&next.to_string()), Some(next.clone()));
+        // 문자열 순서도 같다
+        assert!(first.to_string() < next.to_string());
+    }
+
+    #[test]
+    fn task_fields_take_the_later_clock() {
+        let base = obj(json!({
+            "title": "a", "status": "todo", "updatedAt": 100,
+            "fieldClocks": { "title": hlc(100, "x"), "status": hlc(100, "x") },
+        }));
+        let local = obj(json!({
+            "title": "local", "status": "todo", "updatedAt": 300,
+            "fieldClocks": { "title": hlc(300, "l"), "status": hlc(100, "x") },
+        }));
+        let remote = obj(json!({
+            "title": "remote", "status": "completed", "updatedAt": 200,
+            "fieldClocks": { "title": hlc(200, "r"), "status": hlc(200, "r") },
+        }));
+        let merged = merge_task(Some(&base), &local, &remote);
+        assert_eq!(merged["title"], "local");
+        assert_eq!(merged["status"], "completed");
+        assert_eq!(merged["updatedAt"], 300);
+        let clocks = field_clocks(&merged);
+        assert_eq!(clocks["title"].to_string(), hlc(300, "l"));
+        assert_eq!(clocks["status"].to_string(), hlc(200, "r"));
+    }
+
+    #[test]
+    fn clockless_remote_edit_uses_updated_at() {
+        // 웹에서 `fieldClocks`를 건드리지 않고 memo만 바꿈
+        let clocks = json!({ "memo": hlc(100, "x"), "title": hlc(100, "x") });
+        let base = obj(json!({ "title": "t", "memo": "old", "updatedAt": 100, "fieldClocks": clocks }));
+        let remote = obj(json!({ "title": "t", "memo": "web", "updatedAt": 400, "fieldClocks": clocks }));
+        let local = obj(json!({
+            "title": "mine", "memo": "old", "updatedAt": 300,
+            "fieldClocks": { "memo": hlc(100, "x"), "title": hlc(300, "l") },
+        }));
+        let merged = merge_task(Some(&base), &local, &remote);
+        assert_eq!(merged["memo"], "web");
+        assert_eq!(merged["title"], "mine");
+
+        // 로컬이 더 늦게 고쳤으면 로컬
+        let local = obj(json!({
+            "title": "t", "memo": "mine", "updatedAt": 500,
+            "fieldClocks": { "memo": hlc(500, "l"), "title": hlc(100, "x") },
+        }));
+        assert_eq!(merge_task(Some(&base), &local, &remote)["memo"], "mine");
+    }
+
+    fn block(id: &str, content: &str) -> Value {
+        json!({ "id": id, "type": "text", "content": content })
+    }
+
+    #[test]
+    fn blocks_merge_independent_edits() {
+        let base = vec![block("a", "1"), block("b", "2"), block("c", "3")];
+        // 로컬: b 수정, 끝에 d 추가 / 원격: a 삭제, b 뒤에 e 추가, c 체크
+        let local = vec![block("a", "1"), block("b", "2!"), block("c", "3"), block("d", "4")];
+        let mut checked = block("c", "3");
+        checked["checked"] = json!(true);
+        let remote = vec![block("b", "2"), block("e", "5"), checked.clone()];
+
+        let (merged, conflicts) = merge_blocks(Some(&base), &local, &remote);
+        assert!(conflicts.is_empty());
+        assert_eq!(block_ids(&merged), ["b", "e", "c", "d"]);
+        assert_eq!(merged[0]["content"], "2!");
+        assert_eq!(merged[2], checked);
+    }
+
+    #[test]
+    fn blocks_merge_fields_within_a_block() {
+        let base = vec![json!({ "id": "a", "type": "todo", "content": "x", "checked": false })];
+        let local = vec![json!({ "id": "a", "type": "todo", "content": "x", "checked": true })];
+        let remote = vec![json!({ "id": "a", "type": "todo", "content": "y", "checked": false })];
+        let (merged, conflicts) = merge_blocks(Some(&base), &local, &remote);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged, vec![json!({ "id": "a", "type": "todo", "content": "y", "checked": true })]);
+    }
+
+    #[test]
+    fn conflicting_block_edits_keep_local_and_are_reported() {
+        let base = vec![block("a", "1"), block("b", "2")];
+        let local = vec![block("a", "local"), block("b", "2")];
+        let remote = vec![block("a", "remote")];
+        let (merged, conflicts) = merge_blocks(Some(&base), &local, &remote);
+        // b는 원격에서만 지웠으므로 지운다
+        assert_eq!(merged, vec![block("a", "local")]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::BothEdited);
+        assert_eq!(conflicts[0].remote, Some(block("a", "remote")));
+
+        // 한쪽은 지우고 한쪽은 고침 → 고친 블록을 남긴다
+        let local = vec![block("b", "2")];
+        let remote = vec![block("a", "edited"), block("b", "2")];
+        let (merged, conflicts) = merge_blocks(Some(&base), &local, &remote);
+        assert_eq!(merged, remote);
+        assert_eq!(conflicts[0].kind, ConflictKind::DeletedLocally);
+        assert_eq!(conflicts[0].local, None);
+    }
+
+    #[test]
+    fn remote_reorder_is_kept() {
+        let base = vec![block("a", "1"), block("b", "2"), block("c", "3")];
+        let local = vec![block("a", "1!"), block("b", "2"), block("c", "3")];
+        let remote = vec![block("c", "3"), block("a", "1"), block("b", "2")];
+        let (merged, _) = merge_blocks(Some(&base), &local, &remote);
+        assert_eq!(block_ids(&merged), ["c", "a", "b"]);
+        assert_eq!(merged[1]["content"], "1!");
+    }
+
+    #[test]
+    fn note_fields_merge_three_way() {
+        let base = obj(json!({ "title": "t", "pinned": false, "blocks": [block("a", "1")], "updatedAt": 100 }));
+        let local = obj(json!({ "title": "t", "pinned": true, "blocks": [block("a", "1")], "updatedAt": 200 }));
+        let remote = obj(json!({ "title": "new", "pinned": false, "blocks": [block("a", "2")], "updatedAt": 150 }));
+        let (merged, conflicts) = merge_note(Some(&base), &local, &remote);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged["title"], "new");
+        assert_eq!(merged["pinned"], true);
+        assert_eq!(merged["blocks"], json!([block("a", "2")]));
+        assert_eq!(merged["updatedAt"], 200);
+    }
+}
@@ -0,0 +1,190 @@
+//! 할 일 알림의 버튼: 완료, 10분 뒤, 내일 다시, 열기.
+//!
+//! 완료와 미루기는 창을 열지 않고 여기서 로컬 DB와 알림 큐(`reminders`)를 고친다 (완료는 outbox를 거쳐 원격에도 간다).
+//! 열기는 `noah://task/{id}` 딥링크로 넘긴다. 버튼을 띄우고 눌린 것을 받는 방법은 플랫폼마다 다르다:
+//! - Linux: notify-rust(XDG)로 직접 띄우고 눌린 버튼을 받는다 (본문을 누르면 열기)
+//! - 모바일: 웹뷰가 알림 플러그인에 `task-reminder` action type을 등록하고, `onAction`을 `notification_action` 명령으로 넘긴다
+//! - Windows/macOS: 알림 플러그인이 버튼을 지원하지 않아 버튼 없이 뜬다. 인앱 알림의 버튼이 같은 명령을 쓴다
+
+use crate::models::TaskData;
+use crate::reminders::{Reminder, ReminderScheduler};
+use crate::storage::{LocalDb, StorageError};
+use chrono::{DateTime, Duration, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// 알림 플러그인의 action type id (모바일)
+pub const ACTION_TYPE_ID: &str = "task-reminder";
+const SNOOZE_MINUTES: i64 = 10;
+/// "내일 다시"는 다음 날 이 시각(현지)에
+const TOMORROW_HOUR: u32 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderAction {
+    Complete,
+    #[serde(rename = "snooze_10m")]
+    Snooze10Min,
+    SnoozeTomorrow,
+    Open,
+}
+
+impl ReminderAction {
+    /// 버튼 순서
+    pub const ALL: [ReminderAction; 4] =
+        [ReminderAction::Complete, ReminderAction::Snooze10Min, ReminderAction::SnoozeTomorrow, ReminderAction::Open];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            ReminderAction::Complete => "complete",
+            ReminderAction::Snooze10Min => "snooze_10m",
+            ReminderAction::SnoozeTomorrow => "snooze_tomorrow",
+            ReminderAction::Open => "open",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReminderAction::Complete => "완료",
+            ReminderAction::Snooze10Min => "10분 뒤",
+            ReminderAction::SnoozeTomorrow => "내일 다시",
+            ReminderAction::Open => "열기",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<ReminderAction> {
+        ReminderAction::ALL.into_iter().find(|a| a.id() == id)
+    }
+
+    /// 미룬 알림을 울릴 시각 (epoch ms). 미루기가 아니면 `None`
+    pub fn snooze_until<Tz: TimeZone>(self, now: &DateTime<Tz>) -> Option<i64> {
+        match self {
+            ReminderAction::Snooze10Min => Some((now.clone() + Duration::minutes(SNOOZE_MINUTES)).timestamp_millis()),
+            ReminderAction::SnoozeTomorrow => {
+                let tomorrow = now.date_naive().succ_opt()?.and_hms_opt(TOMORROW_HOUR, 0, 0)?;
+                // 서머타임 전환으로 없는 시각이면 한 시간 뒤로
+                let at = now.timezone().from_local_datetime(&tomorrow).earliest().or_else(|| {
+                    now.timezone().from_local_datetime(&(tomorrow + Duration::hours(1))).earliest()
+                })?;
+                Some(at.timestamp_millis())
+            }
+            ReminderAction::Complete | ReminderAction::Open => None,
+        }
+    }
+}
+
+/// `notification-action` 이벤트 페이로드
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationActionEvent {
+    pub action: ReminderAction,
+    pub task_id: String,
+    pub uid: Option<String>,
+    /// 미뤘으면 다시 울릴 시각
+    pub snoozed_until: Option<i64>,
+    /// 할 일을 완료로 바꿈
+    pub completed: bool,
+}
+
+/// 눌린 버튼을 처리한다. 열기는 여기서 할 일이 없다 (창을 띄우는 것은 호출한 쪽)
+pub fn apply<Tz: TimeZone>(
+    db: &LocalDb,
+    reminders: &ReminderScheduler,
+    reminder: &Reminder,
+    action: ReminderAction,
+    now: &DateTime<Tz>,
+) -> Result<NotificationActionEvent, StorageError> {
+    let mut event = NotificationActionEvent {
+        action,
+        task_id: reminder.task_id.clone(),
+        uid: reminder.uid.clone(),
+        snoozed_until: None,
+        completed: false,
+    };
+    match action {
+        ReminderAction::Complete => {
+            let uid = reminder.uid.as_deref().ok_or_else(|| StorageError::Invalid("reminder without uid".into()))?;
+            let completed_date = now.date_naive().format("%Y-%m-%d").to_string();
+            db.update::<TaskData>(uid, &reminder.task_id, &json!({ "status": "completed", "completedDate": completed_date }))?;
+            reminders.cancel(&reminder.task_id)?;
+            event.completed = true;
+        }
+        ReminderAction::Snooze10Min | ReminderAction::SnoozeTomorrow => {
+            let at = action.snooze_until(now).ok_or_else(|| StorageError::Invalid("cannot compute snooze time".into()))?;
+            reminders.schedule(&reminder.task_id, reminder.uid.clone(), at, reminder.payload.clone())?;
+            event.snoozed_until = Some(at);
+        }
+        ReminderAction::Open => {}
+    }
+    Ok(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TaskStatus;
+    use crate::reminders::ReminderPayload;
+    use chrono::{FixedOffset, Utc};
+    use std::sync::Arc;
+
+    fn setup() -> (Arc<LocalDb>, ReminderScheduler, Reminder) {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let task: TaskData = serde_json::from_value(json!({
+            "title": "보고서",
+            "status": "todo",
+            "priority": "high",
+            "listId": "inbox",
+        }))
+        .unwrap();
+        let task_id = db.insert("u1", &task).unwrap().id.unwrap();
+        let scheduler = ReminderScheduler::new(Arc::clone(&db), |_: &[_]| {});
+        let reminder = Reminder {
+            task_id,
+            uid: Some("u1".into()),
+            fire_at: 0,
+            payload: ReminderPayload { title: "AI Todo 알림".into(), body: "⏰ 보고서".into() },
+            created_at: 0,
+        };
+        (db, scheduler, reminder)
+    }
+
+    #[test]
+    fn action_ids_round_trip() {
+        for action in ReminderAction::ALL {
+            assert_eq!(ReminderAction::parse(action.id()), Some(action));
+            assert_eq!(serde_json::to_value(action).unwrap(), json!(action.id()));
+        }
+        assert_eq!(ReminderAction::parse("default"), None);
+    }
+
+    #[test]
+    fn snooze_until_tomorrow_morning_in_local_time() {
+        let kst = FixedOffset::east_opt(9 * 3600).unwrap();
+        // 2026-03-10 23:30 KST
+        let now = kst.with_ymd_and_hms(2026, 3, 10, 23, 30, 0).unwrap();
+        let tomorrow = ReminderAction::SnoozeTomorrow.snooze_until(&now).unwrap();
+        assert_eq!(tomorrow, kst.with_ymd_and_hms(2026, 3, 11, 9, 0, 0).unwrap().timestamp_millis());
+        assert_eq!(ReminderAction::Snooze10Min.snooze_until(&now), Some(now.timestamp_millis() + 600_000));
+        assert_eq!(ReminderAction::Open.snooze_until(&now), None);
+    }
+
+    #[test]
+    fn complete_updates_the_task_and_snooze_reschedules() {
+        let (db, scheduler, reminder) = setup();
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 8, 0, 0).unwrap();
+
+        let event = apply(&db, &scheduler, &reminder, ReminderAction::Snooze10Min, &now).unwrap();
+        assert_eq!(event.snoozed_until, Some(now.timestamp_millis() + 600_000));
+        assert_eq!(scheduler.list().unwrap()[0].payload, reminder.payload);
+
+        let event = apply(&db, &scheduler, &reminder, ReminderAction::Complete, &now).unwrap();
+        assert!(event.completed);
+        let task = db.get::<TaskData>("u1", &reminder.task_id).unwrap().unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert_eq!(task.completed_date.as_deref(), Some("2026-03-10"));
+        assert!(scheduler.list().unwrap().is_empty());
+
+        let orphan = Reminder { uid: None, ..reminder };
+        assert!(apply(&db, &scheduler, &orphan, ReminderAction::Complete, &now).is_err());
+    }
+}
@@ -0,0 +1,595 @@
+//! 알림 정책: 요일별 방해 금지 시간, 동시에 온 알림 묶기, 출처별 빈도 제한.
+//!
+//! 모든 알림(할 일 알림, 타임박스, 뽀모도로, 캘린더, 일정 요약, 백업)은 `Notifier::notify`(웹뷰는 `notify` 명령)를 거친다.
+//! 들어온 알림은 `collapse_window_ms` 동안 모았다가 출처별로 하나씩 내보내고, 둘 이상이면 요약 하나로 묶는다.
+//! 방해 금지 시간에는 미룰 수 있는 출처(할 일 알림, 일정 요약, 백업)는 끝날 때까지 미루고 나머지는 버린다.
+//! 출처별 빈도 제한은 토큰 버킷이다 (`max`개까지 연달아, 이후 `per_minutes`분에 `max`개 꼴). 넘치면 역시 미루거나 버린다.
+//! 설정은 설정 폴더의 `notifications.json`.
+
+use crate::reminders::Reminder;
+use crate::token_store::write_atomic;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// 요약에 적는 알림 수
+const SUMMARY_LINES: usize = 5;
+/// 알림이 없어도 이만큼마다 깨어 방해 금지 시간이 끝났는지 본다
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSource {
+    Reminder,
+    Timebox,
+    Pomodoro,
+    Calendar,
+    Agenda,
+    Backup,
+    Other,
+}
+
+impl NotificationSource {
+    pub const ALL: [NotificationSource; 7] = [
+        NotificationSource::Reminder,
+        NotificationSource::Timebox,
+        NotificationSource::Pomodoro,
+        NotificationSource::Calendar,
+        NotificationSource::Agenda,
+        NotificationSource::Backup,
+        NotificationSource::Other,
+    ];
+
+    /// 요약 제목
+    fn label(self) -> &'static str {
+        match self {
+            NotificationSource::Reminder => "할 일 알림",
+            NotificationSource::Timebox => "타임박스",
+            NotificationSource::Pomodoro => "뽀모도로",
+            NotificationSource::Calendar => "일정",
+            NotificationSource::Agenda => "오늘의 일정",
+            NotificationSource::Backup => "백업",
+            NotificationSource::Other => "알림",
+        }
+    }
+
+    /// 방해 금지/빈도 제한에 걸리면 미룬다. 아니면 버린다 (지나면 의미 없는 알림)
+    fn deferrable(self) -> bool {
+        matches!(self, NotificationSource::Reminder | NotificationSource::Agenda | NotificationSource::Backup)
+    }
+
+    fn default_limit(self) -> RateLimit {
+        let (max, per_minutes) = match self {
+            NotificationSource::Reminder => (6, 10),
+            NotificationSource::Timebox => (3, 10),
+            NotificationSource::Pomodoro => (4, 10),
+            NotificationSource::Calendar => (2, 60),
+            NotificationSource::Agenda => (2, 60),
+            NotificationSource::Backup => (2, 60),
+            NotificationSource::Other => (5, 10),
+        };
+        RateLimit { max, per_minutes }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl From<Weekday> for Day {
+    fn from(day: Weekday) -> Self {
+        match day {
+            Weekday::Mon => Day::Mon,
+            Weekday::Tue => Day::Tue,
+            Weekday::Wed => Day::Wed,
+            Weekday::Thu => Day::Thu,
+            Weekday::Fri => Day::Fri,
+            Weekday::Sat => Day::Sat,
+            Weekday::Sun => Day::Sun,
+        }
+    }
+}
+
+/// `days`의 `start`부터 `end`까지 (`"HH:MM"`, 현지 시각). `end`가 `start`보다 이르면 다음 날 `end`까지,
+/// 같으면 하루 종일
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub days: Vec<Day>,
+    pub start: String,
+    pub end: String,
+}
+
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M").ok()
+}
+
+impl QuietHours {
+    /// `day`에 시작하는 구간
+    fn span_on(&self, day: NaiveDate) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        if !self.days.contains(&Day::from(day.weekday())) {
+            return None;
+        }
+        let (start, end) = (parse_time(&self.start)?, parse_time(&self.end)?);
+        let end_day = if end > start { day } else { day.succ_opt()? };
+        Some((day.and_time(start), end_day.and_time(end)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub max: u32,
+    pub per_minutes: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PolicySettings {
+    pub quiet_hours: Vec<QuietHours>,
+    /// 없는 출처는 기본값
+    pub rate_limits: BTreeMap<NotificationSource, RateLimit>,
+    /// 이 안에 온 같은 출처의 알림은 하나로 묶는다
+    pub collapse_window_ms: u64,
+}
+
+impl Default for PolicySettings {
+    fn default() -> Self {
+        Self {
+            quiet_hours: Vec::new(),
+            rate_limits: NotificationSource::ALL.into_iter().map(|s| (s, s.default_limit())).collect(),
+            collapse_window_ms: 2_000,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PolicyError {
+    Io(io::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::Io(e) => write!(f, "notification settings i/o error: {}", e),
+            PolicyError::Invalid(msg) => write!(f, "invalid notification settings: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+impl From<io::Error> for PolicyError {
+    fn from(e: io::Error) -> Self {
+        PolicyError::Io(e)
+    }
+}
+
+impl PolicySettings {
+    pub fn validate(&self) -> Result<(), PolicyError> {
+        for quiet in &self.quiet_hours {
+            for time in [&quiet.start, &quiet.end] {
+                if parse_time(time).is_none() {
+                    return Err(PolicyError::Invalid(format!("quiet hours time must be HH:MM: {}", time)));
+                }
+            }
+        }
+        for (source, limit) in &self.rate_limits {
+            if limit.max == 0 || limit.per_minutes == 0 {
+                return Err(PolicyError::Invalid(format!("rate limit for {:?} must be positive", source)));
+            }
+        }
+        if self.collapse_window_ms > 60_000 {
+            return Err(PolicyError::Invalid("collapseWindowMs must be at most 60000".into()));
+        }
+        Ok(())
+    }
+
+    fn limit(&self, source: NotificationSource) -> RateLimit {
+        self.rate_limits.get(&source).copied().unwrap_or_else(|| source.default_limit())
+    }
+
+    /// `now`이 방해 금지 시간이면 끝나는 시각 (이어진 구간은 끝까지)
+    pub fn quiet_until<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let mut at = now.naive_local();
+        let mut until = None;
+        // 요일마다 이어 붙인 구간도 일주일 안에는 끝난다
+        for _ in 0..8 {
+            let day = at.date();
+            let end = self
+                .quiet_hours
+                .iter()
+                .flat_map(|q| [day.pred_opt().and_then(|d| q.span_on(d)), q.span_on(day)])
+                .flatten()
+                .filter(|(start, end)| *start <= at && at < *end)
+                .map(|(_, end)| end)
+                .max();
+            match end {
+                Some(end) => {
+                    at = end;
+                    until = Some(end);
+                }
+                None => break,
+            }
+        }
+        let until = until?;
+        let tz = now.timezone();
+        // 서머타임으로 없는 시각이면 한 시간 뒤
+        tz.from_local_datetime(&until)
+            .earliest()
+            .or_else(|| tz.from_local_datetime(&(until + chrono::Duration::hours(1))).earliest())
+    }
+}
+
+/// 알림 하나
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub source: NotificationSource,
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    /// 할 일 알림이면 버튼(완료/미루기/열기)을 붙인다
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminder: Option<Reminder>,
+    /// 누르면 열 딥 링크 (예: `noah://my-day`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+/// 실제로 띄울 알림. `items`가 둘 이상이면 요약
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    pub source: NotificationSource,
+    pub title: String,
+    pub body: String,
+    pub items: Vec<Notification>,
+}
+
+impl Delivery {
+    fn collapse(source: NotificationSource, items: Vec<Notification>) -> Self {
+        if let [only] = items.as_slice() {
+            return Delivery { source, title: only.title.clone(), body: only.body.clone(), items };
+        }
+        let mut lines: Vec<&str> = items
+            .iter()
+            .take(SUMMARY_LINES)
+            .map(|n| if n.body.is_empty() { n.title.as_str() } else { n.body.as_str() })
+            .collect();
+        let more = items.len().saturating_sub(SUMMARY_LINES);
+        let more_line = format!("외 {}개", more);
+        if more > 0 {
+            lines.push(&more_line);
+        }
+        Delivery { source, title: format!("{} {}개", source.label(), items.len()), body: lines.join("\n"), items }
+    }
+}
+
+/// `notify` 결과
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    /// 묶음 시간이 지나면 나간다
+    Queued,
+    /// 방해 금지 시간이 끝나면 나간다 (epoch ms)
+    Deferred { until: i64 },
+    /// 방해 금지 시간이라 버림
+    Suppressed,
+}
+
+/// 토큰 하나 = `period`. 1ms마다 `max`씩 쌓인다 (정수로 계산하려고)
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    credit: i64,
+    updated: i64,
+}
+
+struct Pending {
+    ready_at: i64,
+    notification: Notification,
+}
+
+/// 정책의 상태 (시각을 받아 계산만 한다)
+pub struct Policy {
+    settings: PolicySettings,
+    buckets: HashMap<NotificationSource, Bucket>,
+    pending: Vec<Pending>,
+}
+
+impl Policy {
+    pub fn new(settings: PolicySettings) -> Self {
+        Self { settings, buckets: HashMap::new(), pending: Vec::new() }
+    }
+
+    pub fn settings(&self) -> &PolicySettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: PolicySettings) {
+        self.settings = settings;
+        self.buckets.clear();
+    }
+
+    pub fn submit<Tz: TimeZone>(&mut self, notification: Notification, now: &DateTime<Tz>) -> Outcome {
+        let now_ms = now.timestamp_millis();
+        let (ready_at, outcome) = match self.settings.quiet_until(now) {
+            Some(end) if notification.source.deferrable() => {
+                let until = end.timestamp_millis();
+                (until, Outcome::Deferred { until })
+            }
+            Some(_) => return Outcome::Suppressed,
+            None => (now_ms + self.settings.collapse_window_ms as i64, Outcome::Queued),
+        };
+        self.pending.push(Pending { ready_at, notification });
+        outcome
+    }
+
+    /// 출처의 토큰을 하나 쓴다. 없으면 다음 토큰이 생기는 시각
+    fn take_token(&mut self, source: NotificationSource, now: i64) -> Result<(), i64> {
+        let limit = self.settings.limit(source);
+        let (max, period) = (limit.max as i64, limit.per_minutes as i64 * 60_000);
+        let bucket = self.buckets.entry(source).or_insert(Bucket { credit: period * max, updated: now });
+        bucket.credit = (bucket.credit + (now - bucket.updated).max(0) * max).min(period * max);
+        bucket.updated = now;
+        if bucket.credit >= period {
+            bucket.credit -= period;
+            Ok(())
+        } else {
+            Err(now + (period - bucket.credit + max - 1) / max)
+        }
+    }
+
+    /// 때가 된 알림을 출처별로 묶어 돌려주고, 다음에 볼 시각을 알려 준다
+    pub fn poll<Tz: TimeZone>(&mut self, now: &DateTime<Tz>) -> (Vec<Delivery>, Option<i64>) {
+        let now_ms = now.timestamp_millis();
+        let (ready, waiting): (Vec<Pending>, Vec<Pending>) =
+            std::mem::take(&mut self.pending).into_iter().partition(|p| p.ready_at <= now_ms);
+        self.pending = waiting;
+
+        // 기다리는 사이 방해 금지 시간이 됐으면
+        let quiet_until = self.settings.quiet_until(now).map(|end| end.timestamp_millis());
+        let mut groups: Vec<(NotificationSource, Vec<Notification>)> = Vec::new();
+        for pending in ready {
+            let source = pending.notification.source;
+            match groups.iter_mut().find(|(s, _)| *s == source) {
+                Some((_, items)) => items.push(pending.notification),
+                None => groups.push((source, vec![pending.notification])),
+            }
+        }
+
+        let mut deliveries = Vec::new();
+        for (source, items) in groups {
+            let retry_at = match quiet_until {
+                Some(until) => Err(until),
+                None => self.take_token(source, now_ms),
+            };
+            match retry_at {
+                Ok(()) => deliveries.push(Delivery::collapse(source, items)),
+                Err(at) if source.deferrable() => {
+                    self.pending.extend(items.into_iter().map(|notification| Pending { ready_at: at, notification }));
+                }
+                Err(_) => eprintln!("알림 {}개 버림 ({:?}: 방해 금지/빈도 제한)", items.len(), source),
+            }
+        }
+        (deliveries, self.pending.iter().map(|p| p.ready_at).min())
+    }
+}
+
+type DeliveryListener = Box<dyn Fn(&Delivery) + Send + Sync>;
+
+/// 정책을 적용해 알림을 내보내는 스레드
+pub struct Notifier {
+    settings_path: PathBuf,
+    policy: Mutex<(Policy, bool)>,
+    signal: Condvar,
+    deliver: DeliveryListener,
+}
+
+impl Notifier {
+    /// `settings_path`(notifications.json)가 없거나 읽을 수 없으면 기본 설정
+    pub fn new<F>(settings_path: PathBuf, deliver: F) -> Self
+    where
+        F: Fn(&Delivery) + Send + Sync + 'static,
+    {
+        let settings = match std::fs::read(&settings_path) {
+            Ok(bytes) => serde_json::from_slice::<PolicySettings>(&bytes)
+                .map_err(|e| e.to_string())
+                .and_then(|s| s.validate().map(|_| s).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    eprintln!("{} 무시: {}", settings_path.display(), e);
+                    PolicySettings::default()
+                }),
+            Err(_) => PolicySettings::default(),
+        };
+        Self {
+            settings_path,
+            policy: Mutex::new((Policy::new(settings), false)),
+            signal: Condvar::new(),
+            deliver: Box::new(deliver),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, (Policy, bool)> {
+        self.policy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wake(&self, mut guard: std::sync::MutexGuard<'_, (Policy, bool)>) {
+        guard.1 = true;
+        drop(guard);
+        self.signal.notify_all();
+    }
+
+    pub fn notify(&self, notification: Notification) -> Outcome {
+        let mut guard = self.lock();
+        let outcome = guard.0.submit(notification, &Local::now());
+        self.wake(guard);
+        outcome
+    }
+
+    /// 지금 방해 금지 시간이면 끝나는 시각 (epoch ms)
+    pub fn quiet_until(&self) -> Option<i64> {
+        self.lock().0.settings().quiet_until(&Local::now()).map(|end| end.timestamp_millis())
+    }
+
+    pub fn settings(&self) -> PolicySettings {
+        self.lock().0.settings().clone()
+    }
+
+    pub fn set_settings(&self, settings: PolicySettings) -> Result<(), PolicyError> {
+        settings.validate()?;
+        if let Some(dir) = self.settings_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec_pretty(&settings).expect("notification settings serialize");
+        write_atomic(&self.settings_path, &json, false)?;
+        let mut guard = self.lock();
+        guard.0.set_settings(settings);
+        self.wake(guard);
+        Ok(())
+    }
+
+    /// 알림 스레드를 띄운다
+    pub fn spawn(self: &std::sync::Arc<Self>) {
+        let notifier = std::sync::Arc::clone(self);
+        std::thread::spawn(move || loop {
+            let (deliveries, next) = notifier.lock().0.poll(&Local::now());
+            for delivery in &deliveries {
+                (notifier.deliver)(delivery);
+            }
+            let now = Local::now().timestamp_millis();
+            let timeout = next.map_or(MAX_WAIT, |at| Duration::from_millis((at - now).max(0) as u64).min(MAX_WAIT));
+            let mut guard = notifier.lock();
+            if !guard.1 {
+                guard = notifier.signal.wait_timeout(guard, timeout).unwrap_or_else(|e| e.into_inner()).0;
+            }
+            guard.1 = false;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, TimeZone};
+
+    fn kst() -> FixedOffset {
+        FixedOffset::east_opt(9 * 3600).unwrap()
+    }
+
+    fn at(day: u32, h: u32, m: u32) -> DateTime<FixedOffset> {
+        // 2026-03-02는 월요일
+        kst().with_ymd_and_hms(2026, 3, day, h, m, 0).unwrap()
+    }
+
+    fn note(source: NotificationSource, body: &str) -> Notification {
+        Notification { source, title: "AI Todo 알림".into(), body: body.into(), reminder: None, link: None }
+    }
+
+    fn settings(quiet: Vec<QuietHours>) -> PolicySettings {
+        PolicySettings { quiet_hours: quiet, ..Default::default() }
+    }
+
+    fn weeknights() -> QuietHours {
+        QuietHours {
+            days: vec![Day::Mon, Day::Tue, Day::Wed, Day::Thu, Day::Fri],
+            start: "22:30".into(),
+            end: "07:00".into(),
+        }
+    }
+
+    #[test]
+    fn quiet_hours_follow_weekdays_and_cross_midnight() {
+        let weekend = QuietHours { days: vec![Day::Sat, Day::Sun], start: "00:00".into(), end: "00:00".into() };
+        let s = settings(vec![weeknights(), weekend]);
+        // 월 23:00 → 화 07:00, 화 06:59 → 화 07:00
+        assert_eq!(s.quiet_until(&at(2, 23, 0)), Some(at(3, 7, 0)));
+        assert_eq!(s.quiet_until(&at(3, 6, 59)), Some(at(3, 7, 0)));
+        assert_eq!(s.quiet_until(&at(3, 7, 0)), None);
+        assert_eq!(s.quiet_until(&at(2, 22, 0)), None);
+        // 금요일 밤부터 주말 내내 이어지고 월요일 0시에 끝난다 (일요일 밤은 평일 구간이 아니다)
+        assert_eq!(s.quiet_until(&at(6, 23, 0)), Some(at(9, 0, 0)));
+        // 일요일 0시 → 다음 날 0시
+        assert_eq!(s.quiet_until(&at(8, 12, 0)), Some(at(9, 0, 0)));
+        assert!(PolicySettings { quiet_hours: vec![QuietHours { start: "25:00".into(), ..weeknights() }], ..Default::default() }
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn simultaneous_notifications_are_collapsed_per_source() {
+        let mut policy = Policy::new(PolicySettings::default());
+        let now = at(2, 9, 0);
+        for i in 0..7 {
+            assert_eq!(policy.submit(note(NotificationSource::Reminder, &format!("⏰ 할 일 {}", i)), &now), Outcome::Queued);
+        }
+        policy.submit(note(NotificationSource::Timebox, "⏰ 09:00 — 회의"), &now);
+
+        // 묶음 시간 전에는 아무것도 나가지 않는다
+        let (deliveries, next) = policy.poll(&now);
+        assert!(deliveries.is_empty());
+        assert_eq!(next, Some(now.timestamp_millis() + 2_000));
+
+        let (deliveries, next) = policy.poll(&(now + chrono::Duration::seconds(2)));
+        assert_eq!(next, None);
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].title, "할 일 알림 7개");
+        assert_eq!(deliveries[0].body.lines().count(), 6);
+        assert!(deliveries[0].body.ends_with("외 2개"));
+        assert_eq!((deliveries[1].title.as_str(), deliveries[1].body.as_str()), ("AI Todo 알림", "⏰ 09:00 — 회의"));
+    }
+
+    #[test]
+    fn quiet_hours_defer_reminders_and_drop_ephemeral_sources() {
+        let mut policy = Policy::new(settings(vec![weeknights()]));
+        let night = at(2, 23, 0);
+        let morning = at(3, 7, 0).timestamp_millis();
+        assert_eq!(policy.submit(note(NotificationSource::Reminder, "a"), &night), Outcome::Deferred { until: morning });
+        assert_eq!(policy.submit(note(NotificationSource::Pomodoro, "b"), &night), Outcome::Suppressed);
+        assert_eq!(policy.poll(&night), (vec![], Some(morning)));
+
+        let (deliveries, _) = policy.poll(&at(3, 7, 0));
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].items[0].body, "a");
+    }
+
+    #[test]
+    fn rate_limit_holds_reminders_and_drops_the_rest() {
+        let mut limits = PolicySettings::default();
+        limits.rate_limits.insert(NotificationSource::Reminder, RateLimit { max: 1, per_minutes: 10 });
+        limits.rate_limits.insert(NotificationSource::Timebox, RateLimit { max: 1, per_minutes: 10 });
+        limits.collapse_window_ms = 0;
+        let mut policy = Policy::new(limits);
+        let now = at(2, 9, 0);
+
+        policy.submit(note(NotificationSource::Reminder, "1"), &now);
+        policy.submit(note(NotificationSource::Timebox, "1"), &now);
+        assert_eq!(policy.poll(&now).0.len(), 2);
+
+        let later = now + chrono::Duration::minutes(1);
+        policy.submit(note(NotificationSource::Reminder, "2"), &later);
+        policy.submit(note(NotificationSource::Timebox, "2"), &later);
+        let (deliveries, next) = policy.poll(&later);
+        assert!(deliveries.is_empty());
+        // 토큰은 10분에 하나: 처음 쓴 뒤 10분
+        assert_eq!(next, Some((now + chrono::Duration::minutes(10)).timestamp_millis()));
+
+        let (deliveries, next) = policy.poll(&(now + chrono::Duration::minutes(10)));
+        assert_eq!(next, None);
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!((deliveries[0].source, deliveries[0].body.as_str()), (NotificationSource::Reminder, "2"));
+    }
+}
@@ -0,0 +1,938 @@
+//! RFC 8252 loopback 리다이렉트 + PKCE(RFC 7636) 인가 코드 흐름.
+//!
+//! 브라우저는 인가 서버로 곧장 이동하고, 루프백 리스너는 `?code=` 리다이렉트만 받는다.
+//! 코드 교환은 Rust에서 하므로 Firebase 설정이나 토큰이 쿼리스트링/페이지에 실리지 않는다.
+
+use crate::loopback::{Limits, LoopbackServer, Request, Response, Router};
+use crate::oauth_pages::LocalizedPages;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri_plugin_http::reqwest;
+
+pub const GOOGLE_AUTHORIZE_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+pub const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+/// 인가 서버/클라이언트 설정. 웹뷰에서 넘길 때는 camelCase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthClientConfig {
+    pub client_id: String,
+    /// Google 데스크톱 클라이언트는 비밀이 아닌 client_secret을 요구한다
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[serde(default = "default_authorize_url")]
+    pub authorize_url: String,
+    #[serde(default = "default_token_url")]
+    pub token_url: String,
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+    #[serde(default = "default_redirect_path")]
+    pub redirect_path: String,
+    /// 인가 요청에 덧붙일 파라미터 (prompt, access_type 등)
+    #[serde(default)]
+    pub extra_params: Vec<(String, String)>,
+    #[serde(default)]
+    pub client_auth: ClientAuth,
+    #[serde(default)]
+    pub token_format: TokenFormat,
+}
+
+/// 토큰 엔드포인트에 클라이언트 자격을 보내는 방식 (RFC 6749 §2.3.1)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientAuth {
+    /// client_id/client_secret을 본문에
+    #[default]
+    RequestBody,
+    /// HTTP Basic (Notion 등)
+    Basic,
+}
+
+/// 토큰 요청 본문 형식. 표준은 form, Notion은 JSON을 받는다
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenFormat {
+    #[default]
+    Form,
+    Json,
+}
+
+fn default_authorize_url() -> String {
+    GOOGLE_AUTHORIZE_URL.to_string()
+}
+
+fn default_token_url() -> String {
+    GOOGLE_TOKEN_URL.to_string()
+}
+
+fn default_scopes() -> Vec<String> {
+    vec!["openid".into(), "email".into(), "profile".into()]
+}
+
+fn default_redirect_path() -> String {
+    "/callback".to_string()
+}
+
+impl OAuthClientConfig {
+    /// Google 로그인 기본값
+    pub fn google(client_id: impl Into<String>) -> Self {
+        Self {
+            client_id: client_id.into(),
+            client_secret: None,
+            authorize_url: default_authorize_url(),
+            token_url: default_token_url(),
+            scopes: default_scopes(),
+            redirect_path: default_redirect_path(),
+            extra_params: Vec::new(),
+            client_auth: ClientAuth::RequestBody,
+            token_format: TokenFormat::Form,
+        }
+    }
+}
+
+/// 토큰 엔드포인트 응답 (RFC 6749 §5.1)
+#[derive(Debug, Deserialize)]
+struct TokenEndpointResponse {
+    access_token: String,
+    #[serde(default)]
+    token_type: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    id_token: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// 토큰 엔드포인트 오류 응답 (RFC 6749 §5.2)
+#[derive(Debug, Deserialize)]
+struct TokenEndpointError {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// 웹뷰로 전달되는 토큰 묶음
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub token_type: String,
+    /// 만료 시각 (unix ms). 서버가 expires_in을 주지 않으면 None
+    pub expires_at: Option<u64>,
+    pub refresh_token: Option<String>,
+    pub id_token: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OAuthError {
+    /// 인가 서버가 `?error=`로 돌려보냄 (사용자가 거부 등)
+    Denied { error: String, description: Option<String> },
+    /// 리다이렉트에 code가 없음
+    MissingCode,
+    /// 토큰 엔드포인트 연결 실패
+    Transport(String),
+    /// 토큰 엔드포인트가 오류 응답
+    TokenEndpoint { status: u16, error: String, description: Option<String> },
+    /// 토큰 엔드포인트 응답을 해석할 수 없음
+    InvalidResponse(String),
+    /// 필요한 토큰(id_token 등)이 응답에 없음
+    MissingToken(&'static str),
+}
+
+/// 웹뷰가 분기할 수 있는 안정적인 오류 코드
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthErrorReason {
+    AccessDenied,
+    MissingCode,
+    Network,
+    TokenExchangeFailed,
+    InvalidResponse,
+    MissingToken,
+}
+
+impl OAuthError {
+    pub fn reason(&self) -> OAuthErrorReason {
+        match self {
+            OAuthError::Denied { .. } => OAuthErrorReason::AccessDenied,
+            OAuthError::MissingCode => OAuthErrorReason::MissingCode,
+            OAuthError::Transport(_) => OAuthErrorReason::Network,
+            OAuthError::TokenEndpoint { .. } => OAuthErrorReason::TokenExchangeFailed,
+            OAuthError::InvalidResponse(_) => OAuthErrorReason::InvalidResponse,
+            OAuthError::MissingToken(_) => OAuthErrorReason::MissingToken,
+        }
+    }
+
+    /// refresh_token이 만료/철회됨 — 다시 로그인해야 한다
+    pub fn is_invalid_grant(&self) -> bool {
+        matches!(self, OAuthError::TokenEndpoint { error, .. } if error == "invalid_grant")
+    }
+}
+
+/// `oauth-error` 이벤트 페이로드
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthErrorEvent {
+    /// 프로바이더 id (`google`, `google-calendar` 등)
+    pub provider: String,
+    pub reason: OAuthErrorReason,
+    pub message: String,
+}
+
+impl OAuthErrorEvent {
+    pub fn new(provider: &str, error: &OAuthError) -> Self {
+        Self { provider: provider.to_string(), reason: error.reason(), message: error.to_string() }
+    }
+}
+
+impl fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OAuthError::Denied { error, description } => match description {
+                Some(d) => write!(f, "authorization denied: {} ({})", error, d),
+                None => write!(f, "authorization denied: {}", error),
+            },
+            OAuthError::MissingCode => write!(f, "redirect did not include an authorization code"),
+            OAuthError::Transport(e) => write!(f, "token endpoint unreachable: {}", e),
+            OAuthError::TokenEndpoint { status, error, description } => match description {
+                Some(d) => write!(f, "token endpoint returned {} {}: {}", status, error, d),
+                None => write!(f, "token endpoint returned {} {}", status, error),
+            },
+            OAuthError::InvalidResponse(e) => write!(f, "invalid token response: {}", e),
+            OAuthError::MissingToken(name) => write!(f, "token response did not include {}", name),
+        }
+    }
+}
+
+impl std::error::Error for OAuthError {}
+
+/// id_token(JWT)의 페이로드 중 로그인에 쓰는 클레임
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    picture: Option<String>,
+}
+
+/// `oauth-callback` 이벤트 페이로드 (Google 로그인 사용자)
+///
+/// 웹뷰는 `idToken`/`accessToken`으로 `signInWithCredential`만 하면 된다.
+/// Google refresh_token은 웹뷰로 보내지 않는다.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleSignIn {
+    pub uid: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    pub id_token: String,
+    pub access_token: String,
+    pub expires_at: Option<u64>,
+}
+
+impl GoogleSignIn {
+    /// 토큰 엔드포인트에서 TLS로 직접 받은 id_token이므로 서명 검증 없이 클레임만 읽는다
+    /// (OpenID Connect Core §3.1.3.7)
+    pub fn from_tokens(tokens: &OAuthTokens) -> Result<Self, OAuthError> {
+        let id_token = tokens.id_token.as_deref().ok_or(OAuthError::MissingToken("id_token"))?;
+        let payload = id_token
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| OAuthError::InvalidResponse("id_token is not a JWT".into()))?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .map_err(|e| OAuthError::InvalidResponse(format!("id_token payload: {}", e)))?;
+        let claims: IdTokenClaims = serde_json::from_slice(&bytes)
+            .map_err(|e| OAuthError::InvalidResponse(format!("id_token claims: {}", e)))?;
+        if claims.sub.is_empty() {
+            return Err(OAuthError::InvalidResponse("id_token has empty sub".into()));
+        }
+        Ok(Self {
+            uid: claims.sub,
+            email: claims.email,
+            email_verified: claims.email_verified,
+            display_name: claims.name,
+            photo_url: claims.picture,
+            id_token: id_token.to_string(),
+            access_token: tokens.access_token.clone(),
+            expires_at: tokens.expires_at,
+        })
+    }
+}
+
+/// `gcal-oauth-callback` 이벤트 페이로드. refresh_token은 웹뷰로 보내지 않는다
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarToken {
+    pub access_token: String,
+    pub expires_at: Option<u64>,
+}
+
+impl CalendarToken {
+    pub fn from_tokens(tokens: &OAuthTokens) -> Result<Self, OAuthError> {
+        if tokens.access_token.is_empty() {
+            return Err(OAuthError::MissingToken("access_token"));
+        }
+        if tokens.access_token.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(OAuthError::InvalidResponse("access_token contains whitespace".into()));
+        }
+        Ok(Self { access_token: tokens.access_token.clone(), expires_at: tokens.expires_at })
+    }
+}
+
+/// 암호학적 난수 `bytes`바이트를 base64url(패딩 없음)로
+pub fn random_token(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    getrandom::getrandom(&mut buf).expect("OS random source unavailable");
+    URL_SAFE_NO_PAD.encode(buf)
+}
+
+/// 루프백 콜백 보호.
+///
+/// 같은 PC의 다른 프로세스나 브라우저 탭도 포트만 알면 콜백을 보낼 수 있으므로,
+/// Host/Origin이 이 리스너를 가리키는지 확인하고 세션마다 발급한 nonce를 한 번만 받아들인다.
+pub struct CallbackGuard {
+    port: u16,
+    nonce: String,
+    used: AtomicBool,
+}
+
+impl CallbackGuard {
+    pub fn new(port: u16) -> Self {
+        Self::with_nonce(port, random_token(24))
+    }
+
+    /// OAuth `state`처럼 이미 만들어 둔 값을 nonce로 쓸 때
+    pub fn with_nonce(port: u16, nonce: String) -> Self {
+        Self { port, nonce, used: AtomicBool::new(false) }
+    }
+
+    /// 서빙하는 페이지에 심을 값
+    pub fn nonce(&self) -> &str {
+        &self.nonce
+    }
+
+    /// Host가 이 리스너이고, Origin이 있다면 같은 출처인지 (DNS rebinding / 교차 출처 요청 차단)
+    pub fn is_local_request(&self, req: &Request) -> bool {
+        let allowed = [format!("127.0.0.1:{}", self.port), format!("localhost:{}", self.port)];
+        let host_ok = req.header("host").is_some_and(|h| allowed.iter().any(|a| a == h));
+        let origin_ok = match req.header("origin") {
+            None => true,
+            Some(origin) => allowed.iter().any(|a| origin == format!("http://{}", a)),
+        };
+        host_ok && origin_ok
+    }
+
+    /// nonce가 맞으면 처음 한 번만 true. 이후 같은 값으로 와도 거부한다
+    pub fn redeem(&self, req: &Request, presented: Option<&str>) -> bool {
+        if !self.is_local_request(req) {
+            return false;
+        }
+        match presented {
+            Some(p) if constant_time_eq(p.as_bytes(), self.nonce.as_bytes()) => {
+                !self.used.swap(true, Ordering::SeqCst)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn new() -> Self {
+        // 32바이트 → 43자. RFC 7636 §4.1 허용 범위(43..=128)
+        Self::from_verifier(random_token(32))
+    }
+
+    pub fn from_verifier(verifier: String) -> Self {
+        let challenge = s256_challenge(&verifier);
+        Self { verifier, challenge }
+    }
+}
+
+impl Default for Pkce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn s256_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+pub fn build_authorize_url(
+    config: &OAuthClientConfig,
+    redirect_uri: &str,
+    state: &str,
+    challenge: &str,
+) -> String {
+    let scope = config.scopes.join(" ");
+    let mut params: Vec<(&str, &str)> = vec![
+        ("response_type", "code"),
+        ("client_id", &config.client_id),
+        ("redirect_uri", redirect_uri),
+        ("state", state),
+        ("code_challenge", challenge),
+        ("code_challenge_method", "S256"),
+    ];
+    // Notion처럼 scope 개념이 없는 프로바이더는 빈 scope를 거부한다
+    if !scope.is_empty() {
+        params.push(("scope", &scope));
+    }
+    for (k, v) in &config.extra_params {
+        params.push((k, v));
+    }
+    let query: Vec<String> = params
+        .iter()
+        .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+        .collect();
+    let sep = if config.authorize_url.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", config.authorize_url, sep, query.join("&"))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+/// 토큰 엔드포인트에 POST (블로킹 — tokio 런타임 밖의 스레드에서 호출).
+/// 클라이언트 자격(client_id/client_secret)은 `config.client_auth`에 따라 붙인다
+fn post_token_request(
+    config: &OAuthClientConfig,
+    params: &[(&str, &str)],
+) -> Result<OAuthTokens, OAuthError> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| OAuthError::Transport(e.to_string()))?;
+
+    let mut params = params.to_vec();
+    let mut request = client.post(&config.token_url).header("Accept", "application/json");
+    match config.client_auth {
+        ClientAuth::RequestBody => {
+            params.push(("client_id", config.client_id.as_str()));
+            if let Some(secret) = config.client_secret.as_deref() {
+                params.push(("client_secret", secret));
+            }
+        }
+        ClientAuth::Basic => {
+            request = request.basic_auth(&config.client_id, config.client_secret.as_deref());
+        }
+    }
+    request = match config.token_format {
+        TokenFormat::Form => request.form(&params),
+        TokenFormat::Json => {
+            let body: serde_json::Map<String, serde_json::Value> = params
+                .iter()
+                .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
+                .collect();
+            request
+                .header("Content-Type", "application/json")
+                .body(serde_json::Value::Object(body).to_string())
+        }
+    };
+    let resp = request.send().map_err(|e| OAuthError::Transport(e.to_string()))?;
+    let status = resp.status().as_u16();
+    let text = resp.text().map_err(|e| OAuthError::Transport(e.to_string()))?;
+
+    if !(200..300).contains(&status) {
+        let (error, description) = match serde_json::from_str::<TokenEndpointError>(&text) {
+            Ok(e) => (e.error, e.error_description),
+            Err(_) => ("http_error".to_string(), None),
+        };
+        return Err(OAuthError::TokenEndpoint { status, error, description });
+    }
+
+    // GitHub는 실패도 200 + {"error": ...}로 돌려준다
+    let raw: TokenEndpointResponse = match serde_json::from_str(&text) {
+        Ok(raw) => raw,
+        Err(e) => {
+            return Err(match serde_json::from_str::<TokenEndpointError>(&text) {
+                Ok(err) => OAuthError::TokenEndpoint {
+                    status,
+                    error: err.error,
+                    description: err.error_description,
+                },
+                Err(_) => OAuthError::InvalidResponse(e.to_string()),
+            })
+        }
+    };
+    Ok(OAuthTokens {
+        access_token: raw.access_token,
+        token_type: raw.token_type.unwrap_or_else(|| "Bearer".to_string()),
+        expires_at: raw.expires_in.map(|s| now_ms() + s * 1000),
+        refresh_token: raw.refresh_token,
+        id_token: raw.id_token,
+        scope: raw.scope,
+    })
+}
+
+pub fn exchange_code(
+    config: &OAuthClientConfig,
+    code: &str,
+    verifier: &str,
+    redirect_uri: &str,
+) -> Result<OAuthTokens, OAuthError> {
+    let params = [
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+        ("code_verifier", verifier),
+    ];
+    post_token_request(config, &params)
+}
+
+/// refresh_token으로 새 access token 발급 (RFC 6749 §6).
+/// 응답에 refresh_token이 없으면 기존 것을 계속 쓴다 (Google은 보통 돌려주지 않는다)
+pub fn refresh_access_token(config: &OAuthClientConfig, refresh_token: &str) -> Result<OAuthTokens, OAuthError> {
+    let params = [("grant_type", "refresh_token"), ("refresh_token", refresh_token)];
+    post_token_request(config, &params)
+}
+
+/// 시작된 루프백 흐름. 브라우저로 `login_url`(또는 `authorize_url`)을 열면 된다
+pub struct LoopbackFlow {
+    pub port: u16,
+    pub redirect_uri: String,
+    pub authorize_url: String,
+    /// 인가 URL로 302 리다이렉트하는 루프백 주소
+    pub login_url: String,
+}
+
+/// 바인딩까지 끝났지만 아직 서빙 전인 흐름. 서빙 스레드와 수명 관리는 호출 측이 맡는다
+pub struct PreparedFlow {
+    pub flow: LoopbackFlow,
+    pub server: LoopbackServer,
+    pub router: Router,
+}
+
+/// 루프백 리스너를 띄우고 별도 스레드에서 리다이렉트를 기다린다.
+/// 코드 교환까지 끝나면 `on_result`가 정확히 한 번 호출된다 (수명 초과/취소 시에는 호출되지 않음).
+pub fn start_loopback_flow<F>(
+    config: OAuthClientConfig,
+    pages: LocalizedPages,
+    limits: Limits,
+    on_result: F,
+) -> std::io::Result<LoopbackFlow>
+where
+    F: FnOnce(Result<OAuthTokens, OAuthError>) + Send + 'static,
+{
+    let PreparedFlow { flow, server, router } = prepare_loopback_flow(config, pages, on_result)?;
+    std::thread::spawn(move || server.serve(router, limits));
+    Ok(flow)
+}
+
+pub fn prepare_loopback_flow<F>(
+    config: OAuthClientConfig,
+    pages: LocalizedPages,
+    on_result: F,
+) -> std::io::Result<PreparedFlow>
+where
+    F: FnOnce(Result<OAuthTokens, OAuthError>) + Send + 'static,
+{
+    let server = LoopbackServer::bind()?;
+    let port = server.port();
+    let redirect_uri = format!("http://127.0.0.1:{}{}", port, config.redirect_path);
+    let pkce = Pkce::new();
+    let state = random_token(16);
+    let authorize_url = build_authorize_url(&config, &redirect_uri, &state, &pkce.challenge);
+
+    let redirect_path = config.redirect_path.clone();
+    let exchange_redirect_uri = redirect_uri.clone();
+    let location = authorize_url.clone();
+    let mut on_result = Some(on_result);
+    // state가 곧 이 세션의 1회용 nonce
+    let guard = Arc::new(CallbackGuard::with_nonce(port, state));
+    let login_guard = Arc::clone(&guard);
+
+    let router = Router::new()
+        .default_header("Cache-Control", "no-store")
+        .get("/login", move |req| {
+            if !login_guard.is_local_request(req) {
+                return Response::text(403, "forbidden");
+            }
+            Response::new(302).with_header("Location", &location)
+        })
+        .get("/favicon.ico", |_| Response::no_content())
+        .get(&redirect_path, move |req: &Request| {
+            // state가 다르거나 이미 쓰인 경우: 이 세션의 리다이렉트가 아니다. 거부하고 계속 대기
+            if !guard.redeem(req, req.query_param("state")) {
+                return Response::text(403, "forbidden");
+            }
+            let result = match (req.query_param("error"), req.query_param("code")) {
+                (Some(error), _) => Err(OAuthError::Denied {
+                    error: error.to_string(),
+                    description: req.query_param("error_description").map(str::to_string),
+                }),
+                (None, Some(code)) if !code.is_empty() => {
+                    exchange_code(&config, code, &pkce.verifier, &exchange_redirect_uri)
+                }
+                _ => Err(OAuthError::MissingCode),
+            };
+            let page = if result.is_ok() { pages.done() } else { pages.failed() };
+            if let Some(cb) = on_result.take() {
+                cb(result);
+            }
+            page.finish()
+        });
+
+    let flow = LoopbackFlow {
+        port,
+        redirect_uri,
+        authorize_url,
+        login_url: format!("http://127.0.0.1:{}/login", port),
+    };
+    Ok(PreparedFlow { flow, server, router })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loopback::parse_query;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+
+    fn parse_form(body: &[u8]) -> Vec<(String, String)> {
+        parse_query(&String::from_utf8_lossy(body))
+    }
+
+    fn http_get(port: u16, target: &str) -> (u16, String, String) {
+        let mut s = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        s.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        write!(s, "GET {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: close\r\n\r\n", target, port)
+            .unwrap();
+        let mut raw = String::new();
+        s.read_to_string(&mut raw).unwrap();
+        let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, head.to_string(), body.to_string())
+    }
+
+    fn location(head: &str) -> String {
+        head.lines()
+            .find_map(|l| l.strip_prefix("Location: "))
+            .unwrap()
+            .to_string()
+    }
+
+    fn query_of(url: &str) -> Vec<(String, String)> {
+        parse_query(url.split_once('?').unwrap().1)
+    }
+
+    fn param(q: &[(String, String)], k: &str) -> String {
+        q.iter().find(|(n, _)| n == k).map(|(_, v)| v.clone()).unwrap()
+    }
+
+    /// 코드 교환 요청을 기록하고 PKCE를 검증하는 mock 토큰 엔드포인트
+    fn mock_token_endpoint(
+        expected_challenge: Arc<Mutex<Option<String>>>,
+    ) -> (String, mpsc::Receiver<Vec<(String, String)>>) {
+        let server = LoopbackServer::bind().unwrap();
+        let port = server.port();
+        let (tx, rx) = mpsc::channel();
+        let router = Router::new().post("/token", move |req| {
+            let form = parse_form(&req.body);
+            let _ = tx.send(form.clone());
+            let verifier = param(&form, "code_verifier");
+            let expected = expected_challenge.lock().unwrap().clone();
+            if expected.as_deref() != Some(s256_challenge(&verifier).as_str()) {
+                return Response::new(400)
+                    .with_header("Content-Type", "application/json")
+                    .with_body(br#"{"error":"invalid_grant","error_description":"PKCE verification failed"}"#.to_vec());
+            }
+            if param(&form, "code") != "good-code" {
+                return Response::new(400)
+                    .with_header("Content-Type", "application/json")
+                    .with_body(br#"{"error":"invalid_grant"}"#.to_vec());
+            }
+            Response::json(
+                r#"{"access_token":"at-1","token_type":"Bearer","expires_in":3600,"refresh_token":"rt-1","id_token":"id-1","scope":"openid email"}"#,
+            )
+        });
+        std::thread::spawn(move || server.serve(router, Limits::default()));
+        (format!("http://127.0.0.1:{}/token", port), rx)
+    }
+
+    fn start(
+        token_url: String,
+    ) -> (LoopbackFlow, mpsc::Receiver<Result<OAuthTokens, OAuthError>>) {
+        let mut config = OAuthClientConfig::google("client-123");
+        config.authorize_url = "https://auth.example/authorize".into();
+        config.token_url = token_url;
+        config.client_secret = Some("not-so-secret".into());
+        let (tx, rx) = mpsc::channel();
+        let flow = start_loopback_flow(config, LocalizedPages::default(), Limits::default(), move |r| {
+            let _ = tx.send(r);
+        })
+        .unwrap();
+        (flow, rx)
+    }
+
+    #[test]
+    fn s256_matches_rfc7636_appendix_b() {
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".into());
+        assert_eq!(pkce.challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+        let fresh = Pkce::new();
+        assert_eq!(fresh.verifier.len(), 43);
+        assert_ne!(fresh.verifier, Pkce::new().verifier);
+    }
+
+    #[test]
+    fn authorize_url_carries_pkce_and_state() {
+        let mut config = OAuthClientConfig::google("cid");
+        config.extra_params.push(("prompt".into(), "consent".into()));
+        let url = build_authorize_url(&config, "http://127.0.0.1:5/callback", "st", "ch");
+        assert!(url.starts_with(GOOGLE_AUTHORIZE_URL));
+        let q = query_of(&url);
+        assert_eq!(param(&q, "response_type"), "code");
+        assert_eq!(param(&q, "redirect_uri"), "http://127.0.0.1:5/callback");
+        assert_eq!(param(&q, "scope"), "openid email profile");
+        assert_eq!(param(&q, "code_challenge_method"), "S256");
+        assert_eq!(param(&q, "prompt"), "consent");
+    }
+
+    #[test]
+    fn full_flow_against_mock_token_endpoint() {
+        let challenge = Arc::new(Mutex::new(None));
+        let (token_url, requests) = mock_token_endpoint(Arc::clone(&challenge));
+        let (flow, results) = start(token_url);
+
+        let (status, head, _) = http_get(flow.port, "/login");
+        assert_eq!(status, 302);
+        let auth = location(&head);
+        assert_eq!(auth, flow.authorize_url);
+        let q = query_of(&auth);
+        assert_eq!(param(&q, "client_id"), "client-123");
+        assert_eq!(param(&q, "redirect_uri"), flow.redirect_uri);
+        *challenge.lock().unwrap() = Some(param(&q, "code_challenge"));
+        let state = param(&q, "state");
+
+        // 다른 state로 온 요청은 거부하고 계속 대기한다
+        let (status, _, _) = http_get(flow.port, "/callback?code=good-code&state=forged");
+        assert_eq!(status, 403);
+        assert!(results.try_recv().is_err());
+
+        let (status, _, body) =
+            http_get(flow.port, &format!("/callback?code=good-code&state={}", state));
+        assert_eq!(status, 200);
+        assert!(body.contains("로그인 완료"));
+
+        let tokens = results.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        assert!(results.try_recv().is_err());
+        assert_eq!(tokens.access_token, "at-1");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("rt-1"));
+        assert_eq!(tokens.id_token.as_deref(), Some("id-1"));
+        assert!(tokens.expires_at.unwrap() > now_ms());
+
+        let form = requests.recv().unwrap();
+        assert_eq!(param(&form, "grant_type"), "authorization_code");
+        assert_eq!(param(&form, "redirect_uri"), flow.redirect_uri);
+        assert_eq!(param(&form, "client_secret"), "not-so-secret");
+    }
+
+    fn request(headers: &[(&str, &str)]) -> Request {
+        Request {
+            method: "POST".into(),
+            path: "/gcal-callback".into(),
+            query: Vec::new(),
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn callback_guard_checks_host_origin_and_is_one_shot() {
+        let guard = CallbackGuard::new(4321);
+        let nonce = guard.nonce().to_string();
+
+        let foreign_host = request(&[("Host", "evil.example:4321")]);
+        assert!(!guard.redeem(&foreign_host, Some(&nonce)));
+        let foreign_origin = request(&[("Host", "127.0.0.1:4321"), ("Origin", "https://evil.example")]);
+        assert!(!guard.redeem(&foreign_origin, Some(&nonce)));
+
+        let local = request(&[("host", "localhost:4321"), ("Origin", "http://localhost:4321")]);
+        assert!(!guard.redeem(&local, None));
+        assert!(!guard.redeem(&local, Some("wrong")));
+        assert!(guard.redeem(&local, Some(&nonce)));
+        assert!(!guard.redeem(&local, Some(&nonce)));
+    }
+
+    #[test]
+    fn replayed_redirect_is_rejected() {
+        let (flow, results) = start("http://127.0.0.1:9/unused".into());
+        let state = param(&query_of(&flow.authorize_url), "state");
+        let mut s = TcpStream::connect(("127.0.0.1", flow.port)).unwrap();
+        s.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        // keep-alive 연결에 같은 리다이렉트를 두 번 보내도 콜백은 한 번뿐이다
+        let target = format!("/callback?error=access_denied&state={}", state);
+        let req = format!("GET {0} HTTP/1.1\r\nHost: 127.0.0.1:{1}\r\n\r\nGET {0} HTTP/1.1\r\nHost: 127.0.0.1:{1}\r\n\r\n", target, flow.port);
+        s.write_all(req.as_bytes()).unwrap();
+        let mut raw = String::new();
+        s.read_to_string(&mut raw).unwrap();
+        assert_eq!(raw.matches("HTTP/1.1 ").count(), 1);
+        assert!(results.recv_timeout(Duration::from_secs(5)).unwrap().is_err());
+        assert!(results.try_recv().is_err());
+    }
+
+    fn jwt(claims: &str) -> String {
+        format!("eyJhbGciOiJSUzI1NiJ9.{}.sig", URL_SAFE_NO_PAD.encode(claims))
+    }
+
+    fn tokens_with_id(id_token: Option<String>) -> OAuthTokens {
+        OAuthTokens {
+            access_token: "at".into(),
+            token_type: "Bearer".into(),
+            expires_at: Some(1),
+            refresh_token: Some("rt".into()),
+            id_token,
+            scope: None,
+        }
+    }
+
+    #[test]
+    fn sign_in_payload_from_id_token_claims() {
+        let id = jwt(r#"{"sub":"1234","email":"a@b.c","email_verified":true,"name":"홍길동","picture":"https://p"}"#);
+        let user = GoogleSignIn::from_tokens(&tokens_with_id(Some(id.clone()))).unwrap();
+        assert_eq!(user.uid, "1234");
+        assert_eq!(user.display_name.as_deref(), Some("홍길동"));
+        assert!(user.email_verified);
+        assert_eq!(user.id_token, id);
+
+        let json = serde_json::to_value(&user).unwrap();
+        assert_eq!(json["photoUrl"], "https://p");
+        assert!(json.get("refreshToken").is_none());
+
+        let missing = GoogleSignIn::from_tokens(&tokens_with_id(None)).unwrap_err();
+        assert_eq!(missing.reason(), OAuthErrorReason::MissingToken);
+        let garbage = GoogleSignIn::from_tokens(&tokens_with_id(Some("nope".into()))).unwrap_err();
+        assert_eq!(garbage.reason(), OAuthErrorReason::InvalidResponse);
+        let no_sub = GoogleSignIn::from_tokens(&tokens_with_id(Some(jwt(r#"{"email":"x"}"#)))).unwrap_err();
+        assert_eq!(no_sub.reason(), OAuthErrorReason::InvalidResponse);
+    }
+
+    #[test]
+    fn calendar_token_keeps_refresh_token_native() {
+        let token = CalendarToken::from_tokens(&tokens_with_id(None)).unwrap();
+        assert_eq!(
+            serde_json::to_value(&token).unwrap(),
+            serde_json::json!({ "accessToken": "at", "expiresAt": 1 })
+        );
+
+        let mut blank = tokens_with_id(None);
+        blank.access_token = "a b".into();
+        assert_eq!(CalendarToken::from_tokens(&blank).unwrap_err().reason(), OAuthErrorReason::InvalidResponse);
+        blank.access_token.clear();
+        let err = CalendarToken::from_tokens(&blank).unwrap_err();
+        assert_eq!(
+            serde_json::to_value(OAuthErrorEvent::new("google-calendar", &err)).unwrap(),
+            serde_json::json!({
+                "provider": "google-calendar",
+                "reason": "missing_token",
+                "message": "token response did not include access_token",
+            })
+        );
+    }
+
+    #[test]
+    fn basic_auth_json_token_request() {
+        let server = LoopbackServer::bind().unwrap();
+        let port = server.port();
+        let (tx, rx) = mpsc::channel();
+        let router = Router::new().post("/token", move |req| {
+            let _ = tx.send((
+                req.header("authorization").unwrap_or("").to_string(),
+                req.header("content-type").unwrap_or("").to_string(),
+                String::from_utf8_lossy(&req.body).into_owned(),
+            ));
+            // GitHub 스타일: 200 + error 본문
+            Response::json(r#"{"error":"bad_verification_code","error_description":"expired"}"#)
+        });
+        std::thread::spawn(move || server.serve(router, Limits::default()));
+
+        let mut config = OAuthClientConfig::google("notion-id");
+        config.client_secret = Some("notion-secret".into());
+        config.token_url = format!("http://127.0.0.1:{}/token", port);
+        config.client_auth = ClientAuth::Basic;
+        config.token_format = TokenFormat::Json;
+        let err = exchange_code(&config, "c", "v", "http://127.0.0.1:1/notion-callback").unwrap_err();
+        assert_eq!(
+            err,
+            OAuthError::TokenEndpoint {
+                status: 200,
+                error: "bad_verification_code".into(),
+                description: Some("expired".into()),
+            }
+        );
+
+        let (auth, content_type, body) = rx.recv().unwrap();
+        assert_eq!(auth, format!("Basic {}", base64::engine::general_purpose::STANDARD.encode("notion-id:notion-secret")));
+        assert_eq!(content_type, "application/json");
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["grant_type"], "authorization_code");
+        assert!(body.get("client_secret").is_none());
+    }
+
+    #[test]
+    fn token_endpoint_error_is_reported() {
+        let challenge = Arc::new(Mutex::new(None));
+        let (token_url, _requests) = mock_token_endpoint(Arc::clone(&challenge));
+        let (flow, results) = start(token_url);
+        let q = query_of(&flow.authorize_url);
+        *challenge.lock().unwrap() = Some(param(&q, "code_challenge"));
+
+        let (status, _, body) = http_get(
+            flow.port,
+            &format!("/callback?code=stale-code&state={}", param(&q, "state")),
+        );
+        assert_eq!(status, 200);
+        assert!(body.contains("로그인 실패"));
+        match results.recv_timeout(Duration::from_secs(5)).unwrap() {
+            Err(OAuthError::TokenEndpoint { status, error, .. }) => {
+                assert_eq!((status, error.as_str()), (400, "invalid_grant"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn denied_authorization_skips_exchange() {
+        let (flow, results) = start("http://127.0.0.1:9/unused".into());
+        let state = param(&query_of(&flow.authorize_url), "state");
+        http_get(
+            flow.port,
+            &format!("/callback?error=access_denied&error_description=user+cancelled&state={}", state),
+        );
+        assert_eq!(
+            results.recv_timeout(Duration::from_secs(5)).unwrap(),
+            Err(OAuthError::Denied {
+                error: "access_denied".into(),
+                description: Some("user cancelled".into()),
+            })
+        );
+    }
+}
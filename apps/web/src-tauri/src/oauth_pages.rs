@@ -0,0 +1,371 @@
+//! 루프백 리스너가 브라우저에 보여주는 완료/실패 페이지.
+//!
+//! 기본 템플릿과 문구는 `templates/`에 있고, 앱 설정 폴더의 `oauth-pages.json`(문구/색상 일부 덮어쓰기)과
+//! `oauth-page.html`(템플릿 교체)로 다시 빌드하지 않고 바꿀 수 있다.
+//! 모든 페이지는 인라인 `<style>`의 sha256 해시와 `<script>`용 응답별 nonce만 허용하는 CSP와 함께 나간다.
+
+use crate::loopback::Response;
+use crate::oauth::random_token;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const DEFAULT_TEMPLATE: &str = include_str!("../templates/oauth-page.html");
+const DEFAULT_CONFIG: &str = include_str!("../templates/oauth-pages.json");
+
+/// 앱 기본 언어 (`lib/i18n-context.tsx`와 같음)
+pub const DEFAULT_LOCALE: &str = "ko";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageTheme {
+    pub background: String,
+    pub card: String,
+    pub border: String,
+    pub text: String,
+    pub accent: String,
+    pub success: String,
+    pub error: String,
+    pub muted: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageStrings {
+    pub title: String,
+    pub done: String,
+    pub done_hint: String,
+    pub failed: String,
+    pub failed_hint: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageConfig {
+    pub brand: String,
+    pub theme: PageTheme,
+    /// 언어 코드(ko, en, ...) → 문구
+    pub strings: BTreeMap<String, PageStrings>,
+}
+
+/// `patch`의 값으로 `base`를 덮어쓴다. 객체는 키 단위로 재귀
+fn merge_json(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(&key) {
+                    Some(slot) => merge_json(slot, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+/// CSS에 그대로 들어가므로 색상 표기에 쓰이는 문자만 허용
+fn is_css_color(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 64
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || "#(),.% ".contains(c))
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `{{key}}`를 치환. 모르는 키는 빈 문자열
+fn fill(template: &str, vars: &BTreeMap<&str, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                if let Some(value) = vars.get(after[..end].trim()) {
+                    out.push_str(value);
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 렌더링된 문서의 `<style>` 블록 내용마다 CSP 해시 소스
+fn style_hashes(html: &str) -> Vec<String> {
+    let mut hashes = Vec::new();
+    let mut rest = html;
+    while let Some(open) = rest.find("<style") {
+        let Some(body_start) = rest[open..].find('>').map(|i| open + i + 1) else { break };
+        let Some(close) = rest[body_start..].find("</style>").map(|i| body_start + i) else { break };
+        let digest = Sha256::digest(&rest.as_bytes()[body_start..close]);
+        hashes.push(format!("'sha256-{}'", STANDARD.encode(digest)));
+        rest = &rest[close..];
+    }
+    hashes
+}
+
+fn content_security_policy(html: &str, nonce: &str) -> String {
+    let styles = style_hashes(html);
+    let style_src = if styles.is_empty() { "'none'".to_string() } else { styles.join(" ") };
+    format!(
+        "default-src 'none'; style-src {}; script-src 'nonce-{}'; img-src data:; base-uri 'none'; form-action 'none'; frame-ancestors 'none'",
+        style_src, nonce
+    )
+}
+
+pub struct PageTemplates {
+    config: PageConfig,
+    template: Arc<str>,
+}
+
+impl Default for PageTemplates {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl PageTemplates {
+    pub fn builtin() -> Self {
+        let config = serde_json::from_str(DEFAULT_CONFIG).expect("templates/oauth-pages.json is valid");
+        Self { config, template: Arc::from(DEFAULT_TEMPLATE) }
+    }
+
+    pub fn config(&self) -> &PageConfig {
+        &self.config
+    }
+
+    /// 설정 폴더의 `oauth-pages.json`(부분 덮어쓰기)과 `oauth-page.html`(템플릿 교체)을 적용.
+    /// 없는 파일은 건너뛴다
+    pub fn load_overrides(&mut self, dir: &Path) -> Result<(), String> {
+        let read = |name: &str| match std::fs::read_to_string(dir.join(name)) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("{}: {}", name, e)),
+        };
+        if let Some(text) = read("oauth-pages.json")? {
+            let patch: serde_json::Value =
+                serde_json::from_str(&text).map_err(|e| format!("oauth-pages.json: {}", e))?;
+            let mut merged = serde_json::to_value(&self.config).map_err(|e| e.to_string())?;
+            merge_json(&mut merged, patch);
+            self.config = serde_json::from_value(merged).map_err(|e| format!("oauth-pages.json: {}", e))?;
+        }
+        if let Some(text) = read("oauth-page.html")? {
+            self.template = Arc::from(text);
+        }
+        Ok(())
+    }
+
+    /// `en-US`, `pt_BR`처럼 지역이 붙어도 앞부분으로 찾는다. 없는 언어면 기본 언어
+    pub fn localized(&self, locale: Option<&str>) -> LocalizedPages {
+        let requested = locale
+            .and_then(|tag| tag.split(['-', '_']).next())
+            .map(|lang| lang.trim().to_ascii_lowercase())
+            .filter(|lang| self.config.strings.contains_key(lang));
+        let lang = requested.unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+        let strings = self
+            .config
+            .strings
+            .get(&lang)
+            .or_else(|| self.config.strings.values().next())
+            .cloned()
+            .expect("at least one locale in oauth-pages.json");
+        LocalizedPages {
+            lang,
+            brand: self.config.brand.clone(),
+            theme: self.config.theme.clone(),
+            strings,
+            template: Arc::clone(&self.template),
+        }
+    }
+}
+
+/// 한 세션에서 쓸 언어가 정해진 페이지 묶음
+#[derive(Debug, Clone)]
+pub struct LocalizedPages {
+    lang: String,
+    brand: String,
+    theme: PageTheme,
+    strings: PageStrings,
+    template: Arc<str>,
+}
+
+impl Default for LocalizedPages {
+    fn default() -> Self {
+        PageTemplates::builtin().localized(None)
+    }
+}
+
+impl LocalizedPages {
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    pub fn done(&self) -> Response {
+        self.render(true)
+    }
+
+    pub fn failed(&self) -> Response {
+        self.render(false)
+    }
+
+    fn render(&self, ok: bool) -> Response {
+        let nonce = random_token(16);
+        let (status, message, hint) = if ok {
+            ("success", &self.strings.done, &self.strings.done_hint)
+        } else {
+            ("error", &self.strings.failed, &self.strings.failed_hint)
+        };
+        let mut vars: BTreeMap<&str, String> = BTreeMap::new();
+        vars.insert("lang", escape_html(&self.lang));
+        vars.insert("title", escape_html(&self.strings.title));
+        vars.insert("brand", escape_html(&self.brand));
+        vars.insert("message", escape_html(message));
+        vars.insert("hint", escape_html(hint));
+        vars.insert("status", status.to_string());
+        vars.insert("autoClose", ok.to_string());
+        vars.insert("nonce", nonce.clone());
+        let theme = &self.theme;
+        for (key, value) in [
+            ("color.background", &theme.background),
+            ("color.card", &theme.card),
+            ("color.border", &theme.border),
+            ("color.text", &theme.text),
+            ("color.accent", &theme.accent),
+            ("color.success", &theme.success),
+            ("color.error", &theme.error),
+            ("color.muted", &theme.muted),
+        ] {
+            let value = if is_css_color(value) { value.clone() } else { "inherit".to_string() };
+            vars.insert(key, value);
+        }
+
+        let html = fill(&self.template, &vars);
+        let csp = content_security_policy(&html, &nonce);
+        Response::html(html)
+            .with_header("Content-Security-Policy", &csp)
+            .with_header("X-Content-Type-Options", "nosniff")
+            .with_header("Referrer-Policy", "no-referrer")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header<'a>(resp: &'a Response, name: &str) -> &'a str {
+        resp.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .unwrap()
+    }
+
+    fn body(resp: &Response) -> String {
+        String::from_utf8(resp.body.clone()).unwrap()
+    }
+
+    #[test]
+    fn builtin_has_every_app_language() {
+        let templates = PageTemplates::builtin();
+        for lang in ["ko", "en", "ja", "es", "pt", "fr"] {
+            assert_eq!(templates.localized(Some(lang)).lang(), lang);
+        }
+        assert_eq!(templates.localized(Some("pt-BR")).lang(), "pt");
+        assert_eq!(templates.localized(Some("en_GB")).lang(), "en");
+        assert_eq!(templates.localized(Some("de")).lang(), DEFAULT_LOCALE);
+        assert_eq!(templates.localized(None).lang(), DEFAULT_LOCALE);
+    }
+
+    #[test]
+    fn pages_are_localized() {
+        let templates = PageTemplates::builtin();
+        let html = body(&templates.localized(Some("fr")).failed());
+        assert!(html.contains(r#"<html lang="fr">"#));
+        assert!(html.contains("Échec de la connexion"));
+        assert!(html.contains(r#"data-autoclose="false""#));
+        let html = body(&templates.localized(Some("ko")).done());
+        assert!(html.contains("로그인 완료"));
+        assert!(html.contains(r#"data-autoclose="true""#));
+        assert!(!html.contains("{{"));
+    }
+
+    #[test]
+    fn csp_pins_inline_style_hash_and_script_nonce() {
+        let resp = LocalizedPages::default().done();
+        let html = body(&resp);
+        let csp = header(&resp, "Content-Security-Policy");
+
+        let style_start = html.find("<style>").unwrap() + "<style>".len();
+        let style_end = html.find("</style>").unwrap();
+        let digest = STANDARD.encode(Sha256::digest(&html.as_bytes()[style_start..style_end]));
+        assert!(csp.contains(&format!("style-src 'sha256-{}'", digest)));
+
+        let nonce = csp.split("'nonce-").nth(1).unwrap().split('\'').next().unwrap();
+        assert!(html.contains(&format!(r#"<script nonce="{}">"#, nonce)));
+        assert!(csp.starts_with("default-src 'none'"));
+        assert!(!csp.contains("unsafe-inline"));
+
+        // 응답마다 nonce가 다르다
+        let other = LocalizedPages::default().done();
+        assert_ne!(header(&other, "Content-Security-Policy"), csp);
+    }
+
+    #[test]
+    fn overrides_merge_without_recompiling() {
+        let dir = std::env::temp_dir().join(format!("noah-pages-{}", random_token(6)));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("oauth-pages.json"),
+            r##"{"brand":"Acme <Tasks>","theme":{"accent":"#123456","muted":"red;} body{display:none"},
+                "strings":{"en":{"done":"All set"},
+                           "de":{"title":"Anmelden","done":"Fertig","doneHint":"Fenster schließen",
+                                 "failed":"Fehler","failedHint":"Erneut versuchen"}}}"##,
+        )
+        .unwrap();
+
+        let mut templates = PageTemplates::builtin();
+        templates.load_overrides(&dir).unwrap();
+        let html = body(&templates.localized(Some("en")).done());
+        assert!(html.contains("All set"));
+        // 덮어쓰지 않은 문구는 기본값 유지
+        assert!(html.contains("You can close this window"));
+        assert!(html.contains("Acme &lt;Tasks&gt;"));
+        assert!(html.contains("color: #123456"));
+        // CSS를 깨는 값은 버린다
+        assert!(!html.contains("display:none"));
+        assert_eq!(templates.localized(Some("de-AT")).lang(), "de");
+
+        std::fs::write(dir.join("oauth-page.html"), "<p>{{message}}</p>").unwrap();
+        templates.load_overrides(&dir).unwrap();
+        let resp = templates.localized(Some("de")).done();
+        assert_eq!(body(&resp), "<p>Fertig</p>");
+        assert!(header(&resp, "Content-Security-Policy").contains("style-src 'none'"));
+
+        std::fs::write(dir.join("oauth-pages.json"), "{").unwrap();
+        assert!(templates.load_overrides(&dir).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
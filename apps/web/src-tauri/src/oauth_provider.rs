@@ -0,0 +1,266 @@
+//! OAuth 프로바이더 레지스트리.
+//!
+//! 프로바이더마다 다른 것은 URL, scope, 리다이렉트 경로, 이벤트 이름, 웹뷰로 보낼 페이로드 모양뿐이다.
+//! 리스너/PKCE/코드 교환은 `oauth::prepare_loopback_flow` 하나를 공유한다.
+//! 내장 목록 외에 앱 설정 폴더의 `oauth-providers.json`으로 추가·덮어쓰기할 수 있다.
+
+use crate::oauth::{
+    CalendarToken, ClientAuth, GoogleSignIn, OAuthClientConfig, OAuthError, OAuthTokens, TokenFormat,
+    GOOGLE_AUTHORIZE_URL, GOOGLE_TOKEN_URL,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// 콜백 이벤트에 실을 페이로드
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadKind {
+    /// id_token 클레임 + Firebase `signInWithCredential`용 토큰
+    GoogleSignIn,
+    /// access_token과 만료 시각만
+    AccessToken,
+    /// 토큰 응답 (refresh_token은 보관소에만 있고 빠진다)
+    Tokens,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthProvider {
+    pub id: String,
+    pub authorize_url: String,
+    pub token_url: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub redirect_path: String,
+    /// 성공 시 emit할 이벤트 이름
+    pub event: String,
+    #[serde(default)]
+    pub extra_params: Vec<(String, String)>,
+    #[serde(default)]
+    pub client_auth: ClientAuth,
+    #[serde(default)]
+    pub token_format: TokenFormat,
+    pub payload: PayloadKind,
+}
+
+impl OAuthProvider {
+    /// 프로바이더 설정 + 앱의 클라이언트 자격 → 루프백 흐름 설정
+    pub fn client_config(&self, client_id: String, client_secret: Option<String>) -> OAuthClientConfig {
+        OAuthClientConfig {
+            client_id,
+            client_secret,
+            authorize_url: self.authorize_url.clone(),
+            token_url: self.token_url.clone(),
+            scopes: self.scopes.clone(),
+            redirect_path: self.redirect_path.clone(),
+            extra_params: self.extra_params.clone(),
+            client_auth: self.client_auth,
+            token_format: self.token_format,
+        }
+    }
+
+    /// 토큰 응답을 이 프로바이더의 이벤트 페이로드로
+    pub fn payload(&self, tokens: &OAuthTokens) -> Result<serde_json::Value, OAuthError> {
+        let value = match self.payload {
+            PayloadKind::GoogleSignIn => serde_json::to_value(GoogleSignIn::from_tokens(tokens)?),
+            PayloadKind::AccessToken => serde_json::to_value(CalendarToken::from_tokens(tokens)?),
+            PayloadKind::Tokens => serde_json::to_value(OAuthTokens { refresh_token: None, ..tokens.clone() }),
+        };
+        value.map_err(|e| OAuthError::InvalidResponse(e.to_string()))
+    }
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+fn builtin() -> Vec<OAuthProvider> {
+    vec![
+        OAuthProvider {
+            id: "google".into(),
+            authorize_url: GOOGLE_AUTHORIZE_URL.into(),
+            token_url: GOOGLE_TOKEN_URL.into(),
+            scopes: strings(&["openid", "email", "profile"]),
+            redirect_path: "/callback".into(),
+            event: "oauth-callback".into(),
+            extra_params: vec![("prompt".into(), "select_account".into())],
+            client_auth: ClientAuth::RequestBody,
+            token_format: TokenFormat::Form,
+            payload: PayloadKind::GoogleSignIn,
+        },
+        OAuthProvider {
+            id: "google-calendar".into(),
+            authorize_url: GOOGLE_AUTHORIZE_URL.into(),
+            token_url: GOOGLE_TOKEN_URL.into(),
+            scopes: strings(&["openid", "email", "https://www.googleapis.com/auth/calendar.events.readonly"]),
+            redirect_path: "/gcal-callback".into(),
+            event: "gcal-oauth-callback".into(),
+            // refresh_token을 받기 위해 offline + consent
+            extra_params: vec![
+                ("access_type".into(), "offline".into()),
+                ("prompt".into(), "consent".into()),
+            ],
+            client_auth: ClientAuth::RequestBody,
+            token_format: TokenFormat::Form,
+            payload: PayloadKind::AccessToken,
+        },
+        OAuthProvider {
+            id: "microsoft-todo".into(),
+            authorize_url: "https://login.microsoftonline.com/common/oauth2/v2.0/authorize".into(),
+            token_url: "https://login.microsoftonline.com/common/oauth2/v2.0/token".into(),
+            scopes: strings(&["offline_access", "User.Read", "Tasks.ReadWrite"]),
+            redirect_path: "/microsoft-callback".into(),
+            event: "microsoft-todo-oauth-callback".into(),
+            extra_params: Vec::new(),
+            client_auth: ClientAuth::RequestBody,
+            token_format: TokenFormat::Form,
+            payload: PayloadKind::Tokens,
+        },
+        OAuthProvider {
+            id: "notion".into(),
+            authorize_url: "https://api.notion.com/v1/oauth/authorize".into(),
+            token_url: "https://api.notion.com/v1/oauth/token".into(),
+            scopes: Vec::new(),
+            redirect_path: "/notion-callback".into(),
+            event: "notion-oauth-callback".into(),
+            extra_params: vec![("owner".into(), "user".into())],
+            client_auth: ClientAuth::Basic,
+            token_format: TokenFormat::Json,
+            payload: PayloadKind::Tokens,
+        },
+        OAuthProvider {
+            id: "github".into(),
+            authorize_url: "https://github.com/login/oauth/authorize".into(),
+            token_url: "https://github.com/login/oauth/access_token".into(),
+            scopes: strings(&["read:user", "repo"]),
+            redirect_path: "/github-callback".into(),
+            event: "github-oauth-callback".into(),
+            extra_params: Vec::new(),
+            client_auth: ClientAuth::RequestBody,
+            token_format: TokenFormat::Form,
+            payload: PayloadKind::Tokens,
+        },
+    ]
+}
+
+pub struct ProviderRegistry {
+    providers: BTreeMap<String, OAuthProvider>,
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ProviderRegistry {
+    pub fn builtin() -> Self {
+        let mut registry = Self { providers: BTreeMap::new() };
+        for provider in builtin() {
+            registry.register(provider);
+        }
+        registry
+    }
+
+    /// 같은 id가 있으면 덮어쓴다
+    pub fn register(&mut self, provider: OAuthProvider) {
+        self.providers.insert(provider.id.clone(), provider);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&OAuthProvider> {
+        self.providers.get(id)
+    }
+
+    pub fn list(&self) -> Vec<OAuthProvider> {
+        self.providers.values().cloned().collect()
+    }
+
+    /// `[OAuthProvider, ...]` JSON 파일을 합친다. 파일이 없으면 아무것도 하지 않는다
+    pub fn merge_file(&mut self, path: &Path) -> Result<usize, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.to_string()),
+        };
+        let providers: Vec<OAuthProvider> =
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let count = providers.len();
+        for provider in providers {
+            self.register(provider);
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_providers_are_registered() {
+        let registry = ProviderRegistry::builtin();
+        for id in ["google", "google-calendar", "microsoft-todo", "notion", "github"] {
+            let provider = registry.get(id).unwrap_or_else(|| panic!("missing {}", id));
+            assert!(provider.redirect_path.starts_with('/'));
+            assert!(!provider.event.is_empty());
+        }
+        let notion = registry.get("notion").unwrap().client_config("id".into(), Some("secret".into()));
+        assert_eq!(notion.client_auth, ClientAuth::Basic);
+        assert_eq!(notion.token_format, TokenFormat::Json);
+        assert!(notion.scopes.is_empty());
+    }
+
+    #[test]
+    fn config_file_adds_and_overrides_providers() {
+        let dir = std::env::temp_dir().join(format!("noah-providers-{}", crate::oauth::random_token(6)));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("oauth-providers.json");
+        std::fs::write(
+            &path,
+            r#"[
+              {"id":"github","authorizeUrl":"https://ghe.example/login/oauth/authorize",
+               "tokenUrl":"https://ghe.example/login/oauth/access_token","scopes":["repo"],
+               "redirectPath":"/github-callback","event":"github-oauth-callback","payload":"tokens"},
+              {"id":"todoist","authorizeUrl":"https://todoist.com/oauth/authorize",
+               "tokenUrl":"https://todoist.com/oauth/access_token","redirectPath":"/todoist-callback",
+               "event":"todoist-oauth-callback","payload":"access_token"}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut registry = ProviderRegistry::builtin();
+        assert_eq!(registry.merge_file(&path).unwrap(), 2);
+        assert_eq!(registry.get("github").unwrap().authorize_url, "https://ghe.example/login/oauth/authorize");
+        let todoist = registry.get("todoist").unwrap();
+        assert_eq!(todoist.client_auth, ClientAuth::RequestBody);
+        assert_eq!(todoist.payload, PayloadKind::AccessToken);
+        assert_eq!(registry.merge_file(&dir.join("missing.json")).unwrap(), 0);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(registry.merge_file(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn payload_shapes_follow_provider_kind() {
+        let tokens = OAuthTokens {
+            access_token: "at".into(),
+            token_type: "bearer".into(),
+            expires_at: Some(1_000),
+            refresh_token: Some("rt".into()),
+            id_token: None,
+            scope: None,
+        };
+        let registry = ProviderRegistry::builtin();
+        let github = registry.get("github").unwrap().payload(&tokens).unwrap();
+        assert_eq!(github["accessToken"], "at");
+        // refresh_token은 웹뷰로 나가지 않는다
+        assert!(github["refreshToken"].is_null());
+        let calendar = registry.get("google-calendar").unwrap().payload(&tokens).unwrap();
+        assert_eq!(calendar["accessToken"], "at");
+        assert!(calendar.get("refreshToken").is_none());
+        // id_token 없이는 로그인 페이로드를 만들 수 없다
+        assert!(registry.get("google").unwrap().payload(&tokens).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! 진행 중인 OAuth 루프백 세션 목록.
+//!
+//! 세션마다 id와 서버 측 마감 시각을 두고, 마감이 지나거나 취소되면 리스너 스레드와 포트를 정리한다.
+
+use crate::loopback::{Limits, LoopbackServer, Router, ServeOutcome, ShutdownHandle};
+use crate::oauth::random_token;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthSessionInfo {
+    pub id: String,
+    /// 프로바이더 id (`oauth_provider` 레지스트리 키)
+    pub provider: String,
+    pub port: u16,
+    /// unix ms
+    pub started_at: u64,
+    /// unix ms. 이 시각이 지나면 `oauth-timeout`
+    pub expires_at: u64,
+}
+
+/// `oauth-timeout` 이벤트 페이로드
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthTimeoutEvent {
+    pub session_id: String,
+    pub provider: String,
+}
+
+struct Entry {
+    info: OAuthSessionInfo,
+    shutdown: ShutdownHandle,
+}
+
+#[derive(Default)]
+pub struct OAuthSessions {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+impl OAuthSessions {
+    /// 세션을 등록하고 서빙 스레드를 띄운다. 서버가 어떤 이유로든 끝나면
+    /// 목록에서 빠진 뒤 `on_exit`이 호출된다.
+    pub fn spawn<F>(
+        self: &Arc<Self>,
+        provider: &str,
+        server: LoopbackServer,
+        router: Router,
+        limits: Limits,
+        on_exit: F,
+    ) -> OAuthSessionInfo
+    where
+        F: FnOnce(&OAuthSessionInfo, ServeOutcome) + Send + 'static,
+    {
+        let started_at = now_ms();
+        let info = OAuthSessionInfo {
+            id: random_token(12),
+            provider: provider.to_string(),
+            port: server.port(),
+            started_at,
+            expires_at: started_at + limits.lifetime.as_millis() as u64,
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                info.id.clone(),
+                Entry { info: info.clone(), shutdown: server.shutdown_handle() },
+            );
+        }
+
+        let sessions = Arc::clone(self);
+        let exit_info = info.clone();
+        std::thread::spawn(move || {
+            let outcome = server.serve(router, limits);
+            if let Ok(mut entries) = sessions.entries.lock() {
+                entries.remove(&exit_info.id);
+            }
+            on_exit(&exit_info, outcome);
+        });
+        info
+    }
+
+    /// 세션을 즉시 종료한다. 없는 id면 false
+    pub fn cancel(&self, id: &str) -> bool {
+        let entry = match self.entries.lock() {
+            Ok(mut entries) => entries.remove(id),
+            Err(_) => None,
+        };
+        match entry {
+            Some(entry) => {
+                entry.shutdown.shutdown();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 같은 프로바이더의 기존 세션을 모두 종료 (로그인 버튼을 다시 누른 경우 등)
+    pub fn cancel_provider(&self, provider: &str) -> usize {
+        let ids: Vec<String> = self
+            .list()
+            .into_iter()
+            .filter(|info| info.provider == provider)
+            .map(|info| info.id)
+            .collect();
+        ids.iter().filter(|id| self.cancel(id)).count()
+    }
+
+    /// 시작 시각 순
+    pub fn list(&self) -> Vec<OAuthSessionInfo> {
+        let mut list: Vec<OAuthSessionInfo> = match self.entries.lock() {
+            Ok(entries) => entries.values().map(|e| e.info.clone()).collect(),
+            Err(_) => Vec::new(),
+        };
+        list.sort_by_key(|info| info.started_at);
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loopback::Response;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::time::Duration;
+
+    /// 띄운 세션과 `on_exit`이 받은 것
+    struct Session {
+        info: OAuthSessionInfo,
+        exit: Receiver<(String, ServeOutcome)>,
+    }
+
+    impl Session {
+        fn outcome(&self) -> ServeOutcome {
+            let (id, outcome) = self.exit.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(id, self.info.id);
+            outcome
+        }
+
+        fn listening(&self) -> bool {
+            TcpStream::connect(("127.0.0.1", self.info.port)).is_ok()
+        }
+    }
+
+    fn spawn(sessions: &Arc<OAuthSessions>, provider: &str, router: Router, limits: Limits) -> Session {
+        let (tx, rx) = channel();
+        let server = LoopbackServer::bind().unwrap();
+        let info = sessions.spawn(provider, server, router, limits, move |info, outcome| {
+            let _ = tx.send((info.id.clone(), outcome));
+        });
+        Session { info, exit: rx }
+    }
+
+    fn ids(sessions: &OAuthSessions) -> Vec<String> {
+        sessions.list().into_iter().map(|info| info.id).collect()
+    }
+
+    #[test]
+    fn cancel_stops_the_listener_and_forgets_the_session() {
+        let sessions = Arc::new(OAuthSessions::default());
+        let a = spawn(&sessions, "google", Router::new(), Limits::default());
+        let b = spawn(&sessions, "google", Router::new(), Limits::default());
+        let c = spawn(&sessions, "github", Router::new(), Limits::default());
+        let mut live = ids(&sessions);
+        live.sort();
+        let mut expected = vec![a.info.id.clone(), b.info.id.clone(), c.info.id.clone()];
+        expected.sort();
+        assert_eq!(live, expected);
+        assert_eq!(a.info.expires_at - a.info.started_at, Limits::default().lifetime.as_millis() as u64);
+        assert!(a.listening());
+
+        assert!(sessions.cancel(&a.info.id));
+        assert_eq!(a.outcome(), ServeOutcome::Cancelled);
+        assert!(!a.listening());
+        assert!(!sessions.cancel(&a.info.id));
+
+        // 같은 프로바이더의 남은 세션만
+        assert_eq!(sessions.cancel_provider("google"), 1);
+        assert_eq!(b.outcome(), ServeOutcome::Cancelled);
+        assert_eq!(ids(&sessions), [c.info.id.as_str()]);
+        assert!(c.listening());
+        assert_eq!(sessions.cancel_provider("google"), 0);
+        assert!(sessions.cancel(&c.info.id));
+        assert_eq!(c.outcome(), ServeOutcome::Cancelled);
+    }
+
+    #[test]
+    fn finished_and_expired_sessions_remove_themselves() {
+        let sessions = Arc::new(OAuthSessions::default());
+        let router = Router::new().get("/callback", |_| Response::text(200, "ok").finish());
+        let done = spawn(&sessions, "google", router, Limits::default());
+        let mut stream = TcpStream::connect(("127.0.0.1", done.info.port)).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        stream.write_all(b"GET /callback?code=x HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert_eq!(done.outcome(), ServeOutcome::Finished);
+        assert!(ids(&sessions).is_empty());
+
+        let limits = Limits { lifetime: Duration::from_millis(100), ..Limits::default() };
+        let expired = spawn(&sessions, "notion", Router::new(), limits);
+        assert_eq!(expired.info.expires_at - expired.info.started_at, 100);
+        assert_eq!(ids(&sessions), [expired.info.id.as_str()]);
+        assert_eq!(expired.outcome(), ServeOutcome::Expired);
+        assert!(ids(&sessions).is_empty());
+        assert!(!sessions.cancel(&expired.info.id));
+    }
+}
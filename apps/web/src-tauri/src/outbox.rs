@@ -0,0 +1,156 @@
+//! 로컬 변경 기록 (outbox).
+//!
+//! 로컬 DB에 쓰는 트랜잭션 안에서 같이 기록하므로, 로컬에 반영된 변경은 반드시 outbox에도 남는다.
+//! `sync`가 `seq` 순서대로 꺼내 Firestore에 반영하고 지운다.
+//! 다시 시도해도 소용없는 실패(권한, 잘못된 문서 등)는 `failed`로 표시해 큐를 막지 않게 한다.
+
+use crate::storage::{new_document_id, Collection, StorageError};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    /// 문서 전체를 쓴다 (`setDoc`)
+    Set,
+    /// `fields`의 최상위 필드만 바꾼다 (`updateDoc`)
+    Update,
+    Delete,
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Set => "set",
+            Op::Update => "update",
+            Op::Delete => "delete",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Op> {
+        match s {
+            "set" => Some(Op::Set),
+            "update" => Some(Op::Update),
+            "delete" => Some(Op::Delete),
+            _ => None,
+        }
+    }
+}
+
+fn parse_collection(s: &str) -> Option<Collection> {
+    serde_json::from_value(Value::String(s.to_string())).ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mutation {
+    pub seq: i64,
+    pub id: String,
+    pub uid: String,
+    pub collection: Collection,
+    pub doc_id: String,
+    pub op: Op,
+    /// Set: 문서 전체, Update: 바꾼 필드만, Delete: 없음
+    pub fields: Option<Value>,
+    pub created_at: i64,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub failed: bool,
+}
+
+const SELECT: &str =
+    "SELECT seq, id, uid, collection, doc_id, op, fields, created_at, attempts, last_error, failed FROM outbox";
+
+fn row_to_mutation(row: &Row<'_>) -> rusqlite::Result<Mutation> {
+    let collection: String = row.get(3)?;
+    let op: String = row.get(5)?;
+    let fields: Option<String> = row.get(6)?;
+    let bad = |what: &str| rusqlite::Error::InvalidColumnType(0, what.to_string(), rusqlite::types::Type::Text);
+    Ok(Mutation {
+        seq: row.get(0)?,
+        id: row.get(1)?,
+        uid: row.get(2)?,
+        collection: parse_collection(&collection).ok_or_else(|| bad("collection"))?,
+        doc_id: row.get(4)?,
+        op: Op::parse(&op).ok_or_else(|| bad("op"))?,
+        fields: fields.and_then(|f| serde_json::from_str(&f).ok()),
+        created_at: row.get(7)?,
+        attempts: row.get(8)?,
+        last_error: row.get(9)?,
+        failed: row.get::<_, i64>(10)? != 0,
+    })
+}
+
+/// 변경 하나를 기록하고 그 id를 돌려준다. 로컬 쓰기와 같은 트랜잭션에서 호출할 것
+pub(crate) fn enqueue(
+    conn: &Connection,
+    uid: &str,
+    collection: Collection,
+    doc_id: &str,
+    op: Op,
+    fields: Option<&Value>,
+    now: i64,
+) -> Result<String, StorageError> {
+    let id = new_document_id();
+    conn.prepare_cached(
+        "INSERT INTO outbox (id, uid, collection, doc_id, op, fields, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?
+    .execute(params![id, uid, collection.table(), doc_id, op.as_str(), fields.map(|f| f.to_string()), now])?;
+    Ok(id)
+}
+
+/// 아직 원격에 반영되지 않은 변경이 있는 문서들 (원격 스냅샷으로 덮어쓰면 안 되는 것)
+pub(crate) fn pending_doc_ids(conn: &Connection, uid: &str, collection: Collection) -> Result<HashSet<String>, StorageError> {
+    let mut stmt = conn.prepare_cached("SELECT DISTINCT doc_id FROM outbox WHERE uid = ?1 AND collection = ?2")?;
+    let ids = stmt.query_map(params![uid, collection.table()], |row| row.get(0))?;
+    Ok(ids.collect::<Result<_, _>>()?)
+}
+
+/// 다음에 보낼 변경 (실패로 표시된 것은 건너뜀)
+pub(crate) fn head(conn: &Connection, uid: &str) -> Result<Option<Mutation>, StorageError> {
+    let sql = format!("{} WHERE uid = ?1 AND failed = 0 ORDER BY seq LIMIT 1", SELECT);
+    Ok(conn.prepare_cached(&sql)?.query_row([uid], row_to_mutation).optional()?)
+}
+
+/// 반영 완료
+pub(crate) fn ack(conn: &Connection, id: &str) -> Result<(), StorageError> {
+    conn.prepare_cached("DELETE FROM outbox WHERE id = ?1")?.execute([id])?;
+    Ok(())
+}
+
+/// 문서에 남은 변경을 모두 버린다 (병합 결과로 다시 기록할 때)
+pub(crate) fn discard(conn: &Connection, uid: &str, collection: Collection, doc_id: &str) -> Result<(), StorageError> {
+    conn.prepare_cached("DELETE FROM outbox WHERE uid = ?1 AND collection = ?2 AND doc_id = ?3")?
+        .execute(params![uid, collection.table(), doc_id])?;
+    Ok(())
+}
+
+/// 실패 기록. `permanent`면 재시도 대상에서 뺀다
+pub(crate) fn record_failure(conn: &Connection, id: &str, error: &str, permanent: bool) -> Result<(), StorageError> {
+    conn.prepare_cached("UPDATE outbox SET attempts = attempts + 1, last_error = ?2, failed = ?3 WHERE id = ?1")?
+        .execute(params![id, error, permanent as i64])?;
+    Ok(())
+}
+
+/// 실패로 표시된 변경을 다시 큐에 넣는다. 되살린 개수
+pub(crate) fn retry_failed(conn: &Connection, uid: &str) -> Result<usize, StorageError> {
+    Ok(conn.prepare_cached("UPDATE outbox SET failed = 0 WHERE uid = ?1 AND failed = 1")?.execute([uid])?)
+}
+
+pub(crate) fn list(conn: &Connection, uid: &str) -> Result<Vec<Mutation>, StorageError> {
+    let sql = format!("{} WHERE uid = ?1 ORDER BY seq", SELECT);
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let rows = stmt.query_map([uid], row_to_mutation)?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// (대기 중, 실패) 개수
+pub(crate) fn counts(conn: &Connection, uid: &str) -> Result<(usize, usize), StorageError> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FILTER (WHERE failed = 0), COUNT(*) FILTER (WHERE failed = 1) FROM outbox WHERE uid = ?1",
+        [uid],
+        |row| Ok((row.get::<_, i64>(0)? as usize, row.get::<_, i64>(1)? as usize)),
+    )?)
+}
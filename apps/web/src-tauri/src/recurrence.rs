@@ -0,0 +1,1442 @@
+//! 반복 규칙 (RFC 5545 RRULE, EXDATE, RDATE).
+//!
+//! 규칙은 iCalendar 줄 그대로 받는다 (`RRULE:FREQ=MONTHLY;BYDAY=-1FR` 또는 값만, 그리고 `EXDATE`/`RDATE` 줄).
+//! 반복은 시작 시각(DTSTART)의 현지 벽시계 시각으로 계산하고, 시간대가 있으면 그때 UTC로 바꾼다.
+//! 서머타임으로 없는 시각(봄 앞당김)은 RFC대로 앞당기기 전 오프셋으로 해석해 뒤로 밀리고,
+//! 두 번 있는 시각(가을 되돌림)은 앞의 것이다. HOURLY 이하도 벽시계로 세므로 되돌림 때 한 번 빠질 수 있다.
+//! DTSTART가 규칙에 맞지 않으면 결과에 넣지 않는다 (python-dateutil과 같음).
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, SecondsFormat, TimeZone,
+    Timelike, Weekday,
+};
+use crate::models::{Frequency, OccurrenceOverride, RecurrenceRule, SubTask, TaskData, TaskPriority, TaskStatus};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// 그레고리력은 400년마다 되풀이되므로 그동안 회차가 없으면 없는 규칙이다 (2월 30일 등)
+const CYCLE_YEARS: i32 = 400;
+/// 하루 미만 반복에서 멈추는 한도
+const MAX_PERIODS: u32 = 1_000_000;
+/// `expand` 결과 기본/최대 개수
+const DEFAULT_LIMIT: usize = 1000;
+const MAX_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecurrenceError {
+    Invalid(String),
+    UnknownTimeZone(String),
+}
+
+impl fmt::Display for RecurrenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecurrenceError::Invalid(msg) => write!(f, "invalid recurrence: {}", msg),
+            RecurrenceError::UnknownTimeZone(tz) => write!(f, "unknown time zone: {}", tz),
+        }
+    }
+}
+
+impl std::error::Error for RecurrenceError {}
+
+fn invalid(msg: impl Into<String>) -> RecurrenceError {
+    RecurrenceError::Invalid(msg.into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Freq {
+    Yearly,
+    Monthly,
+    Weekly,
+    Daily,
+    Hourly,
+    Minutely,
+    Secondly,
+}
+
+impl Freq {
+    fn name(self) -> &'static str {
+        match self {
+            Freq::Yearly => "YEARLY",
+            Freq::Monthly => "MONTHLY",
+            Freq::Weekly => "WEEKLY",
+            Freq::Daily => "DAILY",
+            Freq::Hourly => "HOURLY",
+            Freq::Minutely => "MINUTELY",
+            Freq::Secondly => "SECONDLY",
+        }
+    }
+}
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+fn parse_weekday(s: &str) -> Result<Weekday, RecurrenceError> {
+    WEEKDAYS.iter().find(|(name, _)| *name == s).map(|(_, day)| *day).ok_or_else(|| invalid(format!("weekday {}", s)))
+}
+
+fn weekday_name(day: Weekday) -> &'static str {
+    WEEKDAYS[day.num_days_from_monday() as usize].0
+}
+
+/// BYDAY 한 항목. `n`이 0이면 그 요일 전부, 아니면 달/해에서 n번째 (음수는 끝에서)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeekdayNum {
+    pub n: i32,
+    pub weekday: Weekday,
+}
+
+impl fmt::Display for WeekdayNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.n != 0 {
+            write!(f, "{}", self.n)?;
+        }
+        f.write_str(weekday_name(self.weekday))
+    }
+}
+
+/// iCalendar 날짜/시각 값 (`20260310`, `20260310T090000`, `...Z`, ISO 8601도 받는다)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Date(NaiveDate),
+    /// 시간대 없는 현지 시각
+    Local(NaiveDateTime),
+    Utc(NaiveDateTime),
+    Offset(DateTime<FixedOffset>),
+}
+
+impl FromStr for Value {
+    type Err = RecurrenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y%m%d").or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d")) {
+            return Ok(Value::Date(date));
+        }
+        if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+            return Ok(if at.offset().local_minus_utc() == 0 && s.ends_with(['Z', 'z']) {
+                Value::Utc(at.naive_utc())
+            } else {
+                Value::Offset(at)
+            });
+        }
+        let (naive, utc) = match s.strip_suffix(['Z', 'z']) {
+            Some(rest) => (rest, true),
+            None => (s, false),
+        };
+        let at = ["%Y%m%dT%H%M%S", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(naive, format).ok())
+            .ok_or_else(|| invalid(format!("date-time {}", s)))?;
+        Ok(if utc { Value::Utc(at) } else { Value::Local(at) })
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Date(date) => write!(f, "{}", date.format("%Y%m%d")),
+            Value::Local(at) => write!(f, "{}", at.format("%Y%m%dT%H%M%S")),
+            Value::Utc(at) => write!(f, "{}", at.format("%Y%m%dT%H%M%SZ")),
+            Value::Offset(at) => write!(f, "{}", at.naive_utc().format("%Y%m%dT%H%M%SZ")),
+        }
+    }
+}
+
+/// RRULE 값
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub freq: Freq,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<Value>,
+    pub by_month: Vec<u32>,
+    pub by_week_no: Vec<i32>,
+    pub by_year_day: Vec<i32>,
+    pub by_month_day: Vec<i32>,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_hour: Vec<u32>,
+    pub by_minute: Vec<u32>,
+    pub by_second: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub wkst: Weekday,
+}
+
+impl RRule {
+    pub fn new(freq: Freq) -> Self {
+        RRule {
+            freq,
+            interval: 1,
+            count: None,
+            until: None,
+            by_month: Vec::new(),
+            by_week_no: Vec::new(),
+            by_year_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_day: Vec::new(),
+            by_hour: Vec::new(),
+            by_minute: Vec::new(),
+            by_second: Vec::new(),
+            by_set_pos: Vec::new(),
+            wkst: Weekday::Mon,
+        }
+    }
+
+    /// RFC 5545 3.3.10의 MUST/MUST NOT
+    fn validate(&self) -> Result<(), RecurrenceError> {
+        if self.interval == 0 {
+            return Err(invalid("INTERVAL must be positive"));
+        }
+        if self.count.is_some() && self.until.is_some() {
+            return Err(invalid("COUNT and UNTIL are exclusive"));
+        }
+        if !self.by_week_no.is_empty() && self.freq != Freq::Yearly {
+            return Err(invalid("BYWEEKNO is only valid with FREQ=YEARLY"));
+        }
+        if !self.by_year_day.is_empty() && matches!(self.freq, Freq::Monthly | Freq::Weekly | Freq::Daily) {
+            return Err(invalid("BYYEARDAY is not valid with FREQ=MONTHLY, WEEKLY or DAILY"));
+        }
+        if !self.by_month_day.is_empty() && self.freq == Freq::Weekly {
+            return Err(invalid("BYMONTHDAY is not valid with FREQ=WEEKLY"));
+        }
+        let nth_allowed = self.freq == Freq::Monthly || (self.freq == Freq::Yearly && self.by_week_no.is_empty());
+        if !nth_allowed && self.by_day.iter().any(|d| d.n != 0) {
+            return Err(invalid("numbered BYDAY needs FREQ=MONTHLY or YEARLY without BYWEEKNO"));
+        }
+        let no_other_by = self.by_month.is_empty()
+            && self.by_week_no.is_empty()
+            && self.by_year_day.is_empty()
+            && self.by_month_day.is_empty()
+            && self.by_day.is_empty()
+            && self.by_hour.is_empty()
+            && self.by_minute.is_empty()
+            && self.by_second.is_empty();
+        if !self.by_set_pos.is_empty() && no_other_by {
+            return Err(invalid("BYSETPOS needs another BYxxx rule part"));
+        }
+        Ok(())
+    }
+}
+
+fn parse_list<T: FromStr>(key: &str, value: &str, ok: impl Fn(&T) -> bool) -> Result<Vec<T>, RecurrenceError> {
+    value
+        .split(',')
+        .map(|item| item.trim().parse::<T>().ok().filter(&ok).ok_or_else(|| invalid(format!("{}={}", key, value))))
+        .collect()
+}
+
+fn signed_in(max: i32) -> impl Fn(&i32) -> bool {
+    move |n| *n != 0 && n.abs() <= max
+}
+
+impl FromStr for RRule {
+    type Err = RecurrenceError;
+
+    /// `RRULE:` 접두사는 있어도 된다. 키는 대소문자를 가리지 않는다
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = match s.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &s[6..],
+            _ => s,
+        };
+        let mut freq = None;
+        let mut rule = RRule::new(Freq::Daily);
+        for part in s.split(';').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| invalid(format!("rule part {}", part)))?;
+            let key = key.trim().to_ascii_uppercase();
+            let value = value.trim().to_ascii_uppercase();
+            match key.as_str() {
+                "FREQ" => {
+                    let parsed = [
+                        Freq::Yearly,
+                        Freq::Monthly,
+                        Freq::Weekly,
+                        Freq::Daily,
+                        Freq::Hourly,
+                        Freq::Minutely,
+                        Freq::Secondly,
+                    ]
+                    .into_iter()
+                    .find(|f| f.name() == value);
+                    freq = Some(parsed.ok_or_else(|| invalid(format!("FREQ={}", value)))?);
+                }
+                "INTERVAL" => rule.interval = value.parse().map_err(|_| invalid(format!("INTERVAL={}", value)))?,
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid(format!("COUNT={}", value)))?),
+                "UNTIL" => rule.until = Some(value.parse()?),
+                "BYMONTH" => rule.by_month = parse_list(&key, &value, |m| (1..=12).contains(m))?,
+                "BYWEEKNO" => rule.by_week_no = parse_list(&key, &value, signed_in(53))?,
+                "BYYEARDAY" => rule.by_year_day = parse_list(&key, &value, signed_in(366))?,
+                "BYMONTHDAY" => rule.by_month_day = parse_list(&key, &value, signed_in(31))?,
+                "BYHOUR" => rule.by_hour = parse_list(&key, &value, |h| *h < 24)?,
+                "BYMINUTE" => rule.by_minute = parse_list(&key, &value, |m| *m < 60)?,
+                "BYSECOND" => rule.by_second = parse_list(&key, &value, |s| *s <= 60)?,
+                "BYSETPOS" => rule.by_set_pos = parse_list(&key, &value, signed_in(366))?,
+                "WKST" => rule.wkst = parse_weekday(&value)?,
+                "BYDAY" => {
+                    let bad = || invalid(format!("BYDAY={}", value));
+                    rule.by_day = value
+                        .split(',')
+                        .map(|item| {
+                            let item = item.trim();
+                            let (n, day) = item.split_at(item.len().checked_sub(2).ok_or_else(bad)?);
+                            let n = match n {
+                                "" => 0,
+                                n => n.parse().ok().filter(signed_in(53)).ok_or_else(bad)?,
+                            };
+                            Ok(WeekdayNum { n, weekday: parse_weekday(day)? })
+                        })
+                        .collect::<Result<_, RecurrenceError>>()?;
+                }
+                key if key.starts_with("X-") => {}
+                _ => return Err(invalid(format!("unknown rule part {}", key))),
+            }
+        }
+        rule.freq = freq.ok_or_else(|| invalid("FREQ is required"))?;
+        rule.validate()?;
+        Ok(rule)
+    }
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, key: &str, items: &[T]) -> fmt::Result {
+    if items.is_empty() {
+        return Ok(());
+    }
+    let items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+    write!(f, ";{}={}", key, items.join(","))
+}
+
+impl fmt::Display for RRule {
+    /// `RRULE:` 없이 값만. 순서는 FREQ, INTERVAL, COUNT/UNTIL, 큰 단위 BYxxx부터
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.freq.name())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = &self.until {
+            write!(f, ";UNTIL={}", until)?;
+        }
+        write_list(f, "BYMONTH", &self.by_month)?;
+        write_list(f, "BYWEEKNO", &self.by_week_no)?;
+        write_list(f, "BYYEARDAY", &self.by_year_day)?;
+        write_list(f, "BYMONTHDAY", &self.by_month_day)?;
+        write_list(f, "BYDAY", &self.by_day)?;
+        write_list(f, "BYHOUR", &self.by_hour)?;
+        write_list(f, "BYMINUTE", &self.by_minute)?;
+        write_list(f, "BYSECOND", &self.by_second)?;
+        write_list(f, "BYSETPOS", &self.by_set_pos)?;
+        if self.wkst != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_name(self.wkst))?;
+        }
+        Ok(())
+    }
+}
+
+/// 반복을 계산하는 시간대. `Floating`은 시간대 없는 현지 시각
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Floating,
+    Tz(Tz),
+}
+
+impl Zone {
+    pub fn parse(name: Option<&str>) -> Result<Zone, RecurrenceError> {
+        match name.map(str::trim).filter(|n| !n.is_empty()) {
+            None => Ok(Zone::Floating),
+            Some(name) => {
+                name.parse::<Tz>().map(Zone::Tz).map_err(|_| RecurrenceError::UnknownTimeZone(name.to_string()))
+            }
+        }
+    }
+
+    /// 벽시계 시각 → 회차. 없는 시각은 앞당기기 전 오프셋으로, 두 번 있으면 앞의 것
+    fn resolve(self, local: NaiveDateTime, all_day: bool) -> Occurrence {
+        let offset = match self {
+            Zone::Floating => None,
+            Zone::Tz(tz) => Some(match tz.from_local_datetime(&local).earliest() {
+                Some(at) => at.offset().fix(),
+                None => tz.offset_from_utc_datetime(&(local - Duration::days(1))).fix(),
+            }),
+        };
+        let mut occurrence = Occurrence { local, offset, all_day };
+        if let (Zone::Tz(tz), Some(offset)) = (self, offset) {
+            // 앞당겨진 시각은 실제 벽시계로 (02:30 → 03:30)
+            let at = tz.from_utc_datetime(&(local - Duration::seconds(offset.local_minus_utc() as i64)));
+            occurrence.local = at.naive_local();
+            occurrence.offset = Some(at.offset().fix());
+        }
+        occurrence
+    }
+
+    /// 정렬/비교용 시각 (시간대가 있으면 UTC)
+    fn key_of(self, value: &Value) -> NaiveDateTime {
+        match (self, value) {
+            (_, Value::Date(date)) => self.resolve(date.and_time(NaiveTime::MIN), false).key(),
+            (_, Value::Local(at)) => self.resolve(*at, false).key(),
+            (Zone::Floating, Value::Utc(at)) => *at,
+            (Zone::Floating, Value::Offset(at)) => at.naive_local(),
+            (Zone::Tz(_), Value::Utc(at)) => *at,
+            (Zone::Tz(_), Value::Offset(at)) => at.naive_utc(),
+        }
+    }
+
+    /// `self` 시간대의 값을 `target` 벽시계 시각으로 (EXDATE;TZID=...)
+    fn local_in(self, value: &Value, target: Zone) -> NaiveDateTime {
+        match (self, value) {
+            (Zone::Tz(_), Value::Local(at)) if self != target => {
+                let utc = self.resolve(*at, false).key();
+                target.local_of(&Value::Utc(utc))
+            }
+            _ => target.local_of(value),
+        }
+    }
+
+    /// 값을 이 시간대의 벽시계 시각으로
+    fn local_of(self, value: &Value) -> NaiveDateTime {
+        match (self, value) {
+            (_, Value::Date(date)) => date.and_time(NaiveTime::MIN),
+            (_, Value::Local(at)) => *at,
+            (Zone::Floating, Value::Utc(at)) => *at,
+            (Zone::Floating, Value::Offset(at)) => at.naive_local(),
+            (Zone::Tz(tz), Value::Utc(at)) => tz.from_utc_datetime(at).naive_local(),
+            (Zone::Tz(tz), Value::Offset(at)) => at.with_timezone(&tz).naive_local(),
+        }
+    }
+}
+
+/// 회차 하나. 하루 종일이면 `YYYY-MM-DD`, 시간대가 없으면 오프셋 없는 ISO, 있으면 RFC 3339로 직렬화
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence {
+    pub local: NaiveDateTime,
+    pub offset: Option<FixedOffset>,
+    pub all_day: bool,
+}
+
+impl Occurrence {
+    fn key(&self) -> NaiveDateTime {
+        match self.offset {
+            Some(offset) => self.local - Duration::seconds(offset.local_minus_utc() as i64),
+            None => self.local,
+        }
+    }
+}
+
+impl fmt::Display for Occurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.offset {
+            _ if self.all_day => write!(f, "{}", self.local.format("%Y-%m-%d")),
+            None => write!(f, "{}", self.local.format("%Y-%m-%dT%H:%M:%S")),
+            Some(offset) => match offset.from_local_datetime(&self.local).single() {
+                Some(at) => f.write_str(&at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                None => write!(f, "{}", self.local.format("%Y-%m-%dT%H:%M:%S")),
+            },
+        }
+    }
+}
+
+impl Serialize for Occurrence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// 펼칠 구간 `[from, to)`. 값은 `Value`와 같은 형식
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Range {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExDate {
+    At(NaiveDateTime),
+    /// 날짜만 준 EXDATE는 그날의 회차 전부
+    Day(NaiveDate),
+}
+
+/// 시작 시각 + RRULE + EXDATE/RDATE (+ 회차별 예외)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recurrence {
+    pub start: NaiveDateTime,
+    pub all_day: bool,
+    pub zone: Zone,
+    pub rule: Option<RRule>,
+    rdates: Vec<Occurrence>,
+    exdates: Vec<ExDate>,
+    /// 원래 날짜별 예외 (RECURRENCE-ID)
+    overrides: BTreeMap<NaiveDate, OccurrenceOverride>,
+}
+
+/// 예외를 적용한 회차. `recurrence_id`는 원래 회차, `start`는 옮겼으면 옮긴 날의 같은 시각
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instance {
+    pub recurrence_id: Occurrence,
+    pub start: Occurrence,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskPriority>,
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (y, m) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(y, m, 1).and_then(|d| d.pred_opt()).map_or(31, |d| d.day())
+}
+
+fn days_in_year(year: i32) -> u32 {
+    if NaiveDate::from_ymd_opt(year, 2, 29).is_some() { 366 } else { 365 }
+}
+
+fn week_start(date: NaiveDate, wkst: Weekday) -> NaiveDate {
+    let back = (7 + date.weekday().num_days_from_monday() - wkst.num_days_from_monday()) % 7;
+    date - Duration::days(back as i64)
+}
+
+/// 1주차 첫날: 4일 이상 그해에 든 첫 주
+fn first_week(year: i32, wkst: Weekday) -> NaiveDate {
+    let jan1 = NaiveDate::from_ymd_opt(year, 1, 1).expect("january 1st");
+    let start = week_start(jan1, wkst);
+    if (jan1 - start).num_days() <= 3 { start } else { start + Duration::days(7) }
+}
+
+/// (주차, 그해 주 수). 앞뒤 해에 속한 주는 그 해 기준
+fn week_no(date: NaiveDate, wkst: Weekday) -> (i32, i32) {
+    let mut year = date.year();
+    if date < first_week(year, wkst) {
+        year -= 1;
+    } else if date >= first_week(year + 1, wkst) {
+        year += 1;
+    }
+    let first = first_week(year, wkst);
+    let weeks = ((first_week(year + 1, wkst) - first).num_days() / 7) as i32;
+    (((date - first).num_days() / 7) as i32 + 1, weeks)
+}
+
+fn matches_signed(list: &[i32], n: u32, len: u32) -> bool {
+    list.iter().any(|&v| if v > 0 { v as u32 == n } else { (len as i32 + v + 1) as u32 == n })
+}
+
+/// 규칙 하나의 회차를 차례로 (정렬됨, EXDATE/RDATE 전)
+struct RuleIter<'a> {
+    rule: RRule,
+    recurrence: &'a Recurrence,
+    period: u32,
+    buffer: std::collections::VecDeque<NaiveDateTime>,
+    emitted: u32,
+    /// 마지막 회차가 나온 해
+    last_year: i32,
+    done: bool,
+}
+
+impl<'a> RuleIter<'a> {
+    fn new(recurrence: &'a Recurrence, rule: &RRule) -> Self {
+        let mut rule = rule.clone();
+        let start = recurrence.start;
+        // 규칙에 없는 부분은 DTSTART에서 (RFC 5545 3.3.10 끝부분)
+        if rule.by_week_no.is_empty()
+            && rule.by_year_day.is_empty()
+            && rule.by_month_day.is_empty()
+            && rule.by_day.is_empty()
+        {
+            match rule.freq {
+                Freq::Yearly => {
+                    if rule.by_month.is_empty() {
+                        rule.by_month = vec![start.month()];
+                    }
+                    rule.by_month_day = vec![start.day() as i32];
+                }
+                Freq::Monthly => rule.by_month_day = vec![start.day() as i32],
+                Freq::Weekly => rule.by_day = vec![WeekdayNum { n: 0, weekday: start.weekday() }],
+                _ => {}
+            }
+        }
+        if rule.by_hour.is_empty() && rule.freq < Freq::Hourly {
+            rule.by_hour = vec![start.hour()];
+        }
+        if rule.by_minute.is_empty() && rule.freq < Freq::Minutely {
+            rule.by_minute = vec![start.minute()];
+        }
+        if rule.by_second.is_empty() && rule.freq < Freq::Secondly {
+            rule.by_second = vec![start.second()];
+        }
+        RuleIter {
+            rule,
+            recurrence,
+            period: 0,
+            buffer: Default::default(),
+            emitted: 0,
+            last_year: start.year(),
+            done: false,
+        }
+    }
+
+    /// `period`번째 구간의 날짜들과 (하루 미만 반복이면) 그 구간의 시각
+    fn period_days(&self, k: u32) -> Option<(Vec<NaiveDate>, Option<NaiveDateTime>)> {
+        let start = self.recurrence.start;
+        let step = k.checked_mul(self.rule.interval)?;
+        Some(match self.rule.freq {
+            Freq::Yearly => {
+                let year = start.year().checked_add(i32::try_from(step).ok()?)?;
+                let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                (first.iter_days().take(days_in_year(year) as usize).collect(), None)
+            }
+            Freq::Monthly => {
+                let months = (start.year() as i64) * 12 + start.month0() as i64 + step as i64;
+                let (year, month) = (i32::try_from(months.div_euclid(12)).ok()?, months.rem_euclid(12) as u32 + 1);
+                let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+                (first.iter_days().take(days_in_month(year, month) as usize).collect(), None)
+            }
+            Freq::Weekly => {
+                let first = week_start(start.date(), self.rule.wkst).checked_add_signed(Duration::weeks(step as i64))?;
+                (first.iter_days().take(7).collect(), None)
+            }
+            Freq::Daily => (vec![start.date().checked_add_signed(Duration::days(step as i64))?], None),
+            Freq::Hourly | Freq::Minutely | Freq::Secondly => {
+                let unit = match self.rule.freq {
+                    Freq::Hourly => Duration::hours(1),
+                    Freq::Minutely => Duration::minutes(1),
+                    _ => Duration::seconds(1),
+                };
+                let truncated = match self.rule.freq {
+                    Freq::Hourly => start.date().and_hms_opt(start.hour(), 0, 0)?,
+                    Freq::Minutely => start.date().and_hms_opt(start.hour(), start.minute(), 0)?,
+                    _ => start.with_nanosecond(0)?,
+                };
+                let at = truncated.checked_add_signed(unit * i32::try_from(step).ok()?)?;
+                (vec![at.date()], Some(at))
+            }
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let rule = &self.rule;
+        if !rule.by_month.is_empty() && !rule.by_month.contains(&date.month()) {
+            return false;
+        }
+        if !rule.by_week_no.is_empty() {
+            let (week, weeks) = week_no(date, rule.wkst);
+            if !matches_signed(&rule.by_week_no, week as u32, weeks as u32) {
+                return false;
+            }
+        }
+        let year_len = days_in_year(date.year());
+        if !rule.by_year_day.is_empty() && !matches_signed(&rule.by_year_day, date.ordinal(), year_len) {
+            return false;
+        }
+        let month_len = days_in_month(date.year(), date.month());
+        if !rule.by_month_day.is_empty() && !matches_signed(&rule.by_month_day, date.day(), month_len) {
+            return false;
+        }
+        if !rule.by_day.is_empty() {
+            // n번째는 해(BYMONTH 없는 YEARLY) 또는 달 안에서
+            let in_year = rule.freq == Freq::Yearly && rule.by_month.is_empty();
+            let (index, len) =
+                if in_year { (date.ordinal(), year_len) } else { (date.day(), month_len) };
+            let nth = (index as i32 - 1) / 7 + 1;
+            let nth_back = -((len as i32 - index as i32) / 7 + 1);
+            let found = rule
+                .by_day
+                .iter()
+                .any(|d| d.weekday == date.weekday() && (d.n == 0 || d.n == nth || d.n == nth_back));
+            if !found {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn times(&self, at: Option<NaiveDateTime>) -> Vec<NaiveTime> {
+        let rule = &self.rule;
+        let keep = |list: &[u32], v: u32| list.is_empty() || list.contains(&v);
+        let (hours, minutes, seconds) = match (rule.freq, at) {
+            (Freq::Hourly, Some(at)) if keep(&rule.by_hour, at.hour()) => {
+                (vec![at.hour()], rule.by_minute.clone(), rule.by_second.clone())
+            }
+            (Freq::Minutely, Some(at)) if keep(&rule.by_hour, at.hour()) && keep(&rule.by_minute, at.minute()) => {
+                (vec![at.hour()], vec![at.minute()], rule.by_second.clone())
+            }
+            (Freq::Secondly, Some(at))
+                if keep(&rule.by_hour, at.hour())
+                    && keep(&rule.by_minute, at.minute())
+                    && keep(&rule.by_second, at.second()) =>
+            {
+                (vec![at.hour()], vec![at.minute()], vec![at.second()])
+            }
+            (_, Some(_)) => return Vec::new(),
+            (_, None) => (rule.by_hour.clone(), rule.by_minute.clone(), rule.by_second.clone()),
+        };
+        let mut times = Vec::new();
+        for &h in &hours {
+            for &m in &minutes {
+                for &s in &seconds {
+                    // 윤초(60)는 59초로
+                    times.extend(NaiveTime::from_hms_opt(h, m, s.min(59)));
+                }
+            }
+        }
+        times.sort();
+        times.dedup();
+        times
+    }
+
+    /// 구간 하나의 회차 (BYSETPOS까지)
+    fn candidates(&self, days: &[NaiveDate], at: Option<NaiveDateTime>) -> Vec<NaiveDateTime> {
+        let times = self.times(at);
+        let mut set: Vec<NaiveDateTime> = days
+            .iter()
+            .filter(|d| self.day_matches(**d))
+            .flat_map(|d| times.iter().map(move |t| d.and_time(*t)))
+            .collect();
+        if !self.rule.by_set_pos.is_empty() {
+            let len = set.len() as i32;
+            let mut picked: Vec<NaiveDateTime> = self
+                .rule
+                .by_set_pos
+                .iter()
+                .filter_map(|&pos| {
+                    let index = if pos > 0 { pos - 1 } else { len + pos };
+                    (0..len).contains(&index).then(|| set[index as usize])
+                })
+                .collect();
+            picked.sort();
+            picked.dedup();
+            set = picked;
+        }
+        set
+    }
+
+    fn past_until(&self, local: NaiveDateTime) -> bool {
+        let recurrence = self.recurrence;
+        match &self.rule.until {
+            None => false,
+            Some(Value::Date(date)) => local.date() > *date,
+            Some(Value::Local(until)) => local > *until,
+            Some(until) => recurrence.zone.resolve(local, false).key() > recurrence.zone.key_of(until),
+        }
+    }
+
+    fn fill(&mut self) {
+        while self.buffer.is_empty() && !self.done {
+            if self.period >= MAX_PERIODS {
+                self.done = true;
+                return;
+            }
+            let Some((days, at)) = self.period_days(self.period) else {
+                self.done = true;
+                return;
+            };
+            self.period += 1;
+            if days.first().is_some_and(|d| d.year() - self.last_year > CYCLE_YEARS) {
+                self.done = true;
+                return;
+            }
+            // UNTIL을 지난 구간이면 끝 (구간은 시간순)
+            if days.first().is_some_and(|d| self.past_until(at.unwrap_or_else(|| d.and_time(NaiveTime::MIN)))) {
+                self.done = true;
+                return;
+            }
+            // 하루 미만 반복: 날짜가 안 맞으면 다음 날 첫 구간으로 건너뛴다
+            if let (Some(at), [day]) = (at, days.as_slice()) {
+                if !self.day_matches(*day) {
+                    let next_day = day.succ_opt().map(|d| d.and_time(NaiveTime::MIN));
+                    let step = self.period_days(self.period).and_then(|(_, next)| Some((next? - at).num_seconds()));
+                    if let (Some(next_day), Some(step)) = (next_day, step.filter(|s| *s > 0)) {
+                        let skip = ((next_day - at).num_seconds() + step - 1) / step - 1;
+                        self.period = self.period.saturating_add(u32::try_from(skip.max(0)).unwrap_or(u32::MAX));
+                    }
+                    continue;
+                }
+            }
+            let start = self.recurrence.start;
+            self.buffer.extend(self.candidates(&days, at).into_iter().filter(|c| *c >= start));
+            if let Some(last) = self.buffer.back() {
+                self.last_year = last.year();
+            }
+        }
+    }
+}
+
+impl Iterator for RuleIter<'_> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        if self.rule.count.is_some_and(|count| self.emitted >= count) {
+            return None;
+        }
+        self.fill();
+        let next = self.buffer.pop_front()?;
+        if self.past_until(next) {
+            self.done = true;
+            self.buffer.clear();
+            return None;
+        }
+        self.emitted += 1;
+        Some(next)
+    }
+}
+
+impl Recurrence {
+    /// `text`: iCalendar 줄들(RRULE, EXDATE, RDATE) 또는 RRULE 값 하나. `start`는 `Value` 형식,
+    /// `tz`는 IANA 이름 (없으면 시간대 없는 현지 시각)
+    pub fn parse(text: &str, start: &str, tz: Option<&str>) -> Result<Self, RecurrenceError> {
+        let zone = Zone::parse(tz)?;
+        let (start, all_day) = match start.parse::<Value>()? {
+            Value::Date(date) => (date.and_time(NaiveTime::MIN), true),
+            value => (zone.local_of(&value), false),
+        };
+        let mut recurrence = Recurrence {
+            start,
+            all_day,
+            zone,
+            rule: None,
+            rdates: Vec::new(),
+            exdates: Vec::new(),
+            overrides: BTreeMap::new(),
+        };
+        // 접힌 줄(RFC 5545 3.1)을 편다
+        let unfolded = text.replace("\r\n", "\n").replace("\n ", "").replace("\n\t", "");
+        for line in unfolded.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let Some((head, value)) = line.split_once(':') else {
+                recurrence.set_rule(line.parse()?)?;
+                continue;
+            };
+            let mut params = head.split(';');
+            let name = params.next().unwrap_or_default().to_ascii_uppercase();
+            let mut line_zone = zone;
+            for param in params {
+                match param.split_once('=').map(|(k, v)| (k.to_ascii_uppercase(), v)) {
+                    Some((k, v)) if k == "TZID" => line_zone = Zone::parse(Some(v.trim_matches('"')))?,
+                    Some((k, v)) if k == "VALUE" && v.eq_ignore_ascii_case("PERIOD") => {
+                        return Err(invalid("RDATE periods are not supported"));
+                    }
+                    _ => {}
+                }
+            }
+            match name.as_str() {
+                "RRULE" => recurrence.set_rule(value.parse()?)?,
+                "EXDATE" => {
+                    for value in value.split(',') {
+                        let exdate = match value.parse::<Value>()? {
+                            Value::Date(date) => ExDate::Day(date),
+                            value => ExDate::At(zone.resolve(line_zone.local_in(&value, zone), false).key()),
+                        };
+                        recurrence.exdates.push(exdate);
+                    }
+                }
+                "RDATE" => {
+                    for value in value.split(',') {
+                        let local = match value.parse::<Value>()? {
+                            Value::Date(date) => date.and_time(if all_day { NaiveTime::MIN } else { start.time() }),
+                            value => line_zone.local_in(&value, zone),
+                        };
+                        recurrence.rdates.push(zone.resolve(local, all_day));
+                    }
+                }
+                "DTSTART" | "EXRULE" => return Err(invalid(format!("{} is not supported here", name))),
+                _ if name.starts_with("X-") => {}
+                _ => return Err(invalid(format!("unknown property {}", name))),
+            }
+        }
+        recurrence.rdates.sort_by_key(Occurrence::key);
+        Ok(recurrence)
+    }
+
+    fn set_rule(&mut self, rule: RRule) -> Result<(), RecurrenceError> {
+        if self.rule.is_some() {
+            return Err(invalid("only one RRULE is supported"));
+        }
+        if self.all_day && rule.freq > Freq::Daily {
+            return Err(invalid("a date-only start needs FREQ=DAILY or longer"));
+        }
+        self.rule = Some(rule);
+        Ok(())
+    }
+
+    /// 회차별 예외를 붙인다. 키와 `start`는 `YYYY-MM-DD`
+    pub fn with_overrides(mut self, overrides: &BTreeMap<String, OccurrenceOverride>) -> Result<Self, RecurrenceError> {
+        for (key, value) in overrides {
+            let date = parse_day(key).ok_or_else(|| invalid(format!("override date {}", key)))?;
+            if let Some(start) = value.start.as_deref() {
+                parse_day(start).ok_or_else(|| invalid(format!("override start {}", start)))?;
+            }
+            self.overrides.insert(date, value.clone());
+        }
+        Ok(self)
+    }
+
+    fn excluded(&self, occurrence: &Occurrence) -> bool {
+        let skipped = self.overrides.get(&occurrence.local.date()).is_some_and(|o| o.skipped);
+        skipped
+            || self.exdates.iter().any(|ex| match ex {
+                ExDate::At(key) => occurrence.key() == *key,
+                ExDate::Day(date) => occurrence.local.date() == *date,
+            })
+    }
+
+    fn instance(&self, occurrence: Occurrence) -> Instance {
+        let Some(ov) = self.overrides.get(&occurrence.local.date()) else {
+            return Instance { recurrence_id: occurrence, start: occurrence, title: None, priority: None };
+        };
+        let start = match ov.start.as_deref().and_then(parse_day) {
+            Some(date) => self.zone.resolve(date.and_time(occurrence.local.time()), self.all_day),
+            None => occurrence,
+        };
+        Instance { recurrence_id: occurrence, start, title: ov.title.clone(), priority: ov.priority }
+    }
+
+    /// 모든 회차를 시간순으로 (RRULE + RDATE, EXDATE와 건너뛴 회차 제외, 중복 없음). 옮긴 회차는 원래 자리에
+    pub fn iter(&self) -> impl Iterator<Item = Occurrence> + '_ {
+        let rule = self.rule.as_ref().map(|rule| RuleIter::new(self, rule));
+        // RRULE이 없으면 DTSTART 하나
+        let single = self.rule.is_none().then(|| self.zone.resolve(self.start, self.all_day));
+        let rule_occurrences = rule
+            .into_iter()
+            .flatten()
+            .map(|local| self.zone.resolve(local, self.all_day))
+            .chain(single);
+        let mut rdates = self.rdates.iter().copied().peekable();
+        let mut rule_occurrences = rule_occurrences.peekable();
+        let mut last: Option<NaiveDateTime> = None;
+        std::iter::from_fn(move || loop {
+            let next = match (rule_occurrences.peek(), rdates.peek()) {
+                (Some(a), Some(b)) if b.key() < a.key() => rdates.next(),
+                (Some(_), _) => rule_occurrences.next(),
+                (None, _) => rdates.next(),
+            }?;
+            if last == Some(next.key()) || self.excluded(&next) {
+                continue;
+            }
+            last = Some(next.key());
+            return Some(next);
+        })
+    }
+
+    /// `[from, to)` 안의 회차 (최대 `limit`개, 기본 1000)
+    pub fn expand(&self, range: &Range) -> Result<Vec<Occurrence>, RecurrenceError> {
+        let from = self.zone.key_of(&range.from.parse()?);
+        let to = self.zone.key_of(&range.to.parse()?);
+        let limit = range.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        Ok(self.iter().skip_while(|o| o.key() < from).take_while(|o| o.key() < to).take(limit).collect())
+    }
+
+    /// 예외를 적용해 `[from, to)`에 오는 회차, 옮긴 날짜순. 구간 밖에서 옮겨 온 회차도 넣는다
+    pub fn expand_instances(&self, range: &Range) -> Result<Vec<Instance>, RecurrenceError> {
+        let from = self.zone.key_of(&range.from.parse()?);
+        let to = self.zone.key_of(&range.to.parse()?);
+        let limit = range.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let inside = |o: &Occurrence| o.key() >= from && o.key() < to;
+        let mut instances: Vec<Instance> = self
+            .iter()
+            .skip_while(|o| o.key() < from)
+            .take_while(|o| o.key() < to)
+            .map(|o| self.instance(o))
+            .filter(|i| inside(&i.start))
+            .take(limit)
+            .collect();
+        for (date, ov) in &self.overrides {
+            if ov.skipped || ov.start.is_none() {
+                continue;
+            }
+            let original = self.iter().find(|o| o.local.date() >= *date).filter(|o| o.local.date() == *date);
+            let Some(original) = original.filter(|o| !inside(o)) else {
+                continue;
+            };
+            let instance = self.instance(original);
+            if inside(&instance.start) {
+                instances.push(instance);
+            }
+        }
+        instances.sort_by_key(|i| i.start.key());
+        instances.truncate(limit);
+        Ok(instances)
+    }
+
+    /// `after` 다음(같은 시각 제외) 회차
+    pub fn next_after(&self, after: &str) -> Result<Option<Occurrence>, RecurrenceError> {
+        let value: Value = after.parse()?;
+        // 하루 종일 반복에 날짜를 주면 그날 다음
+        let after = match value {
+            Value::Date(date) if self.all_day => self.zone.key_of(&Value::Date(date)),
+            Value::Date(date) => self.zone.key_of(&Value::Local(date.and_time(NaiveTime::MIN))) - Duration::seconds(1),
+            value => self.zone.key_of(&value),
+        };
+        Ok(self.iter().find(|o| o.key() > after))
+    }
+}
+
+/// 할 일의 `recurrence_rule`을 규칙 줄로. `rrule`이 있으면 그대로, 없으면 freq/interval/until/byweekday/bymonthday로
+pub fn rule_text(rule: &RecurrenceRule) -> Result<String, RecurrenceError> {
+    if let Some(text) = rule.rrule.as_deref().filter(|t| !t.trim().is_empty()) {
+        return Ok(text.to_string());
+    }
+    let mut rrule = RRule::new(match rule.freq {
+        Frequency::Daily => Freq::Daily,
+        Frequency::Weekly => Freq::Weekly,
+        Frequency::Monthly => Freq::Monthly,
+        Frequency::Yearly => Freq::Yearly,
+    });
+    rrule.interval = rule.interval.unwrap_or(1).max(1);
+    if let Some(until) = rule.until.as_deref().filter(|u| !u.trim().is_empty()) {
+        rrule.until = Some(until.parse()?);
+    }
+    for day in rule.byweekday.iter().flatten() {
+        let (_, weekday) = WEEKDAYS.get(*day as usize).ok_or_else(|| invalid(format!("byweekday {}", day)))?;
+        rrule.by_day.push(WeekdayNum { n: 0, weekday: *weekday });
+    }
+    if let Some(day) = rule.bymonthday {
+        rrule.by_month_day = vec![day as i32];
+    }
+    rrule.validate()?;
+    Ok(rrule.to_string())
+}
+
+/// 다음 회차가 가질 규칙. COUNT는 그 앞의 회차(EXDATE로 뺀 것 포함) `used`개를 뺀 수로
+fn remaining_rule(text: &str, used: u32) -> Result<String, RecurrenceError> {
+    let mut lines = Vec::new();
+    for line in text.replace("\r\n", "\n").replace("\n ", "").replace("\n\t", "").lines() {
+        let trimmed = line.trim();
+        let rule = match trimmed.split_once(':') {
+            None if !trimmed.is_empty() => Some(("", trimmed)),
+            Some((head, value)) if head.eq_ignore_ascii_case("RRULE") => Some(("RRULE:", value)),
+            _ => None,
+        };
+        match rule {
+            Some((prefix, value)) => {
+                let mut rule: RRule = value.parse()?;
+                rule.count = rule.count.map(|count| count.saturating_sub(used).max(1));
+                lines.push(format!("{}{}", prefix, rule));
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+fn parse_day(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// 알림 시각을 `tz`의 벽시계 기준으로 `days`만큼 옮긴다 (형식은 그대로)
+pub fn shift_reminder<Z: TimeZone>(reminder: &str, days: Duration, tz: &Z) -> Option<String> {
+    match reminder.parse::<Value>().ok()? {
+        Value::Offset(at) => {
+            let local = at.with_timezone(tz).naive_local() + days;
+            let at = tz.from_local_datetime(&local).earliest()?;
+            Some(at.with_timezone(&chrono::Utc).format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        }
+        Value::Utc(at) => {
+            let local = tz.from_utc_datetime(&at).naive_local() + days;
+            let at = tz.from_local_datetime(&local).earliest()?;
+            Some(at.with_timezone(&chrono::Utc).format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        }
+        Value::Local(at) => Some((at + days).format("%Y-%m-%dT%H:%M:%S").to_string()),
+        Value::Date(date) => Some((date + days).format("%Y-%m-%d").to_string()),
+    }
+}
+
+/// 알림 시각을 epoch ms로. 오프셋이 없으면 `tz`의 현지 시각, 날짜만 있으면 그날 0시
+pub fn reminder_ms<Z: TimeZone>(reminder: &str, tz: &Z) -> Option<i64> {
+    let at = match reminder.parse::<Value>().ok()? {
+        Value::Offset(at) => return Some(at.timestamp_millis()),
+        Value::Utc(at) => return Some(at.and_utc().timestamp_millis()),
+        Value::Local(at) => at,
+        Value::Date(date) => date.and_time(NaiveTime::MIN),
+    };
+    tz.from_local_datetime(&at).earliest().map(|at| at.timestamp_millis())
+}
+
+/// 할 일이 실제로 놓인 날짜: 마감일, 없으면 만든 날(My Day), 그것도 없으면 완료한 날
+pub fn task_day(task: &TaskData) -> Option<NaiveDate> {
+    [&task.due_date, &task.created_date, &task.completed_date].into_iter().flatten().find_map(|date| parse_day(date))
+}
+
+/// 날짜 부분만 바꾼다 (시각/오프셋은 그대로)
+pub fn with_day(value: &str, day: NaiveDate) -> String {
+    format!("{}{}", day.format("%Y-%m-%d"), value.get(10..).unwrap_or_default())
+}
+
+/// 완료한 반복 할 일의 다음 회차 (저장 전). 기준은 이 회차의 원래 날짜(`recurrence_id`), 없으면 `task_day`.
+/// id는 반복의 첫 할 일 id + 원래 날짜라서 같은 회차를 다시 만들면 같은 id다.
+/// 건너뛴 회차는 넘기고, 옮긴 회차는 옮긴 날로, 제목/우선순위 예외도 적용한다.
+/// 하위 할 일은 체크를 풀고, 알림은 날짜 간격을 `tz`의 벽시계 시각으로 유지한다
+pub fn next_instance<Z: TimeZone>(task: &TaskData, tz: &Z) -> Result<Option<TaskData>, RecurrenceError> {
+    let (Some(rule), Some(id)) = (&task.recurrence_rule, task.id.as_deref()) else {
+        return Ok(None);
+    };
+    let Some(day) = task_day(task) else {
+        return Ok(None);
+    };
+    let anchor = task.recurrence_id.as_deref().and_then(parse_day).unwrap_or(day);
+    let text = rule_text(rule)?;
+    let start = anchor.format("%Y-%m-%d").to_string();
+    let recurrence = Recurrence::parse(&text, &start, None)?.with_overrides(&rule.overrides)?;
+    let Some(next) = recurrence.next_after(&start)? else {
+        return Ok(None);
+    };
+    let used = recurrence.rule.as_ref().map_or(0, |rule| {
+        RuleIter::new(&recurrence, rule).take_while(|at| at.date() < next.local.date()).count() as u32
+    });
+    let next_rule = remaining_rule(&text, used)?;
+    let next_date = next.local.date();
+    let instance = recurrence.instance(next);
+    let next_day = instance.start.local.date();
+    let days = next_day - day;
+
+    let due_date = task.due_date.as_deref().filter(|due| parse_day(due).is_some()).map(|due| with_day(due, next_day));
+    let reminder = task.reminder.as_deref().and_then(|reminder| shift_reminder(reminder, days, tz));
+    let series = task.recurrence_parent_id.clone().unwrap_or_else(|| id.to_string());
+    let title = rule.series_title.clone().unwrap_or_else(|| task.title.clone());
+    let priority = rule.series_priority.unwrap_or(task.priority);
+    let mut recurrence_rule = rule.clone();
+    recurrence_rule.series_title = instance.title.is_some().then(|| title.clone());
+    recurrence_rule.series_priority = instance.priority.is_some().then_some(priority);
+    if recurrence_rule.rrule.is_some() {
+        recurrence_rule.rrule = Some(next_rule);
+    }
+    // 지난 회차의 예외는 더 쓰지 않는다
+    recurrence_rule.overrides.retain(|key, _| parse_day(key).is_none_or(|date| date >= next_date));
+    Ok(Some(TaskData {
+        id: Some(format!("{}-{}", series, next_date.format("%Y%m%d"))),
+        title: instance.title.unwrap_or(title),
+        status: TaskStatus::Todo,
+        priority: instance.priority.unwrap_or(priority),
+        starred: task.starred,
+        list_id: task.list_id.clone(),
+        due_date,
+        my_day: task.my_day,
+        sub_tasks: task.sub_tasks.iter().map(|s| SubTask { completed: false, ..s.clone() }).collect(),
+        reminder,
+        memo: task.memo.clone(),
+        // 첨부 파일은 회차마다 따로 지워지므로 옮기지 않는다
+        attachments: Vec::new(),
+        tags: task.tags.clone(),
+        order: task.order,
+        completed_date: None,
+        created_date: Some(next_day.format("%Y-%m-%d").to_string()),
+        linked_note_ids: task.linked_note_ids.clone(),
+        recurrence_rule: Some(recurrence_rule),
+        recurrence_parent_id: Some(series),
+        recurrence_id: Some(next_date.format("%Y-%m-%d").to_string()),
+        field_clocks: None,
+        created_at: None,
+        updated_at: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(rule: &str, start: &str, tz: Option<&str>, from: &str, to: &str) -> Vec<String> {
+        let recurrence = Recurrence::parse(rule, start, tz).unwrap();
+        let range = Range { from: from.into(), to: to.into(), limit: None };
+        recurrence.expand(&range).unwrap().iter().map(|o| o.to_string()).collect()
+    }
+
+    fn all(rule: &str, start: &str) -> Vec<String> {
+        let recurrence = Recurrence::parse(rule, start, Some("America/New_York")).unwrap();
+        recurrence.iter().take(50).map(|o| o.local.format("%Y-%m-%d").to_string()).collect()
+    }
+
+    #[test]
+    fn rfc5545_examples() {
+        // 3.8.5.3의 예제들 (DTSTART;TZID=America/New_York)
+        assert_eq!(all("FREQ=DAILY;COUNT=3", "19970902T090000"), ["1997-09-02", "1997-09-03", "1997-09-04"]);
+        assert_eq!(
+            all("RRULE:FREQ=WEEKLY;INTERVAL=2;WKST=SU;BYDAY=MO,WE,FR;COUNT=10", "19970901T090000"),
+            [
+                "1997-09-01", "1997-09-03", "1997-09-05", "1997-09-15", "1997-09-17", "1997-09-19", "1997-09-29",
+                "1997-10-01", "1997-10-03", "1997-10-13"
+            ]
+        );
+        assert_eq!(
+            all("FREQ=MONTHLY;COUNT=6;BYDAY=1FR", "19970905T090000"),
+            ["1997-09-05", "1997-10-03", "1997-11-07", "1997-12-05", "1998-01-02", "1998-02-06"]
+        );
+        // 달의 마지막 평일
+        assert_eq!(
+            all("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1;COUNT=4", "19970929T090000"),
+            ["1997-09-30", "1997-10-31", "1997-11-28", "1997-12-31"]
+        );
+        // 20주차 월요일
+        assert_eq!(
+            all("FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO;COUNT=3", "19970512T090000"),
+            ["1997-05-12", "1998-05-11", "1999-05-17"]
+        );
+        // 13일의 금요일 (DTSTART는 규칙에 맞지 않아 빠진다)
+        assert_eq!(
+            all("FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13;COUNT=3", "19970902T090000"),
+            ["1998-02-13", "1998-03-13", "1998-11-13"]
+        );
+        assert_eq!(
+            all("FREQ=YEARLY;BYMONTH=11;BYDAY=4TH;UNTIL=20001231", "19971127T090000"),
+            ["1997-11-27", "1998-11-26", "1999-11-25", "2000-11-23"]
+        );
+        assert_eq!(
+            all("FREQ=YEARLY;INTERVAL=3;COUNT=4;BYYEARDAY=1,100,200", "19970101T090000"),
+            ["1997-01-01", "1997-04-10", "1997-07-19", "2000-01-01"]
+        );
+    }
+
+    #[test]
+    fn last_friday_and_skipped_month_days() {
+        assert_eq!(
+            expand("FREQ=MONTHLY;BYDAY=-1FR", "2026-01-30T18:00:00", None, "2026-01-01", "2026-05-01"),
+            ["2026-01-30T18:00:00", "2026-02-27T18:00:00", "2026-03-27T18:00:00", "2026-04-24T18:00:00"]
+        );
+        // 31일이 없는 달은 건너뛴다
+        assert_eq!(
+            expand("FREQ=MONTHLY;COUNT=3", "2026-01-31", None, "2026-01-01", "2027-01-01"),
+            ["2026-01-31", "2026-03-31", "2026-05-31"]
+        );
+        assert_eq!(
+            expand("FREQ=MONTHLY;BYMONTHDAY=-1;COUNT=3", "2028-01-31", None, "2028-01-01", "2029-01-01"),
+            ["2028-01-31", "2028-02-29", "2028-03-31"]
+        );
+    }
+
+    #[test]
+    fn time_zones_and_dst() {
+        // 2026-03-08 02:00 EST → EDT
+        assert_eq!(
+            expand("FREQ=DAILY;COUNT=3", "2026-03-07T09:00:00", Some("America/New_York"), "2026-03-01", "2026-04-01"),
+            ["2026-03-07T09:00:00-05:00", "2026-03-08T09:00:00-04:00", "2026-03-09T09:00:00-04:00"]
+        );
+        // 없는 02:30은 03:30 EDT로
+        assert_eq!(
+            expand("FREQ=DAILY;COUNT=3", "2026-03-07T02:30:00", Some("America/New_York"), "2026-03-01", "2026-04-01"),
+            ["2026-03-07T02:30:00-05:00", "2026-03-08T03:30:00-04:00", "2026-03-09T02:30:00-04:00"]
+        );
+        // 두 번 있는 01:30은 앞의 것 (EDT)
+        assert_eq!(
+            expand("FREQ=WEEKLY;COUNT=2", "2026-10-25T01:30:00", Some("America/New_York"), "2026-10-01", "2026-12-01"),
+            ["2026-10-25T01:30:00-04:00", "2026-11-01T01:30:00-04:00"]
+        );
+        // UNTIL(UTC)은 시각으로 비교한다: 09:00 KST = 00:00Z
+        assert_eq!(
+            expand("FREQ=DAILY;UNTIL=20260311T000000Z", "20260309T090000", Some("Asia/Seoul"), "20260301", "20260401"),
+            ["2026-03-09T09:00:00+09:00", "2026-03-10T09:00:00+09:00", "2026-03-11T09:00:00+09:00"]
+        );
+        let unknown = Recurrence::parse("FREQ=DAILY", "2026-03-09", Some("Mars/Base"));
+        assert!(matches!(unknown, Err(RecurrenceError::UnknownTimeZone(_))));
+    }
+
+    #[test]
+    fn exdate_rdate_and_next_occurrence() {
+        let rule = "RRULE:FREQ=WEEKLY;BYDAY=TU\nEXDATE;TZID=Asia/Seoul:20260317T090000\nEXDATE:20260324\n\
+                    RDATE:20260319T000000Z";
+        let recurrence = Recurrence::parse(rule, "2026-03-10T09:00:00", Some("Asia/Seoul")).unwrap();
+        let range = Range { from: "2026-03-01".into(), to: "2026-04-01".into(), limit: None };
+        let got: Vec<String> = recurrence.expand(&range).unwrap().iter().map(|o| o.to_string()).collect();
+        assert_eq!(got, ["2026-03-10T09:00:00+09:00", "2026-03-19T09:00:00+09:00", "2026-03-31T09:00:00+09:00"]);
+        let next = recurrence.next_after("2026-03-10T09:00:00+09:00").unwrap().unwrap();
+        assert_eq!(next.to_string(), "2026-03-19T09:00:00+09:00");
+        assert_eq!(recurrence.next_after("2026-03-10").unwrap().unwrap().to_string(), "2026-03-10T09:00:00+09:00");
+
+        // 끝나는 규칙과 맞는 날이 없는 규칙
+        let done = Recurrence::parse("FREQ=DAILY;COUNT=2", "2026-03-10", None).unwrap();
+        assert_eq!(done.next_after("2026-03-11").unwrap(), None);
+        let never = Recurrence::parse("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30", "2026-01-01", None).unwrap();
+        assert_eq!(never.next_after("2026-01-01").unwrap(), None);
+    }
+
+    #[test]
+    fn sub_daily_rules() {
+        assert_eq!(
+            expand("FREQ=HOURLY;INTERVAL=3;BYDAY=SA", "2026-03-13T21:00:00", None, "2026-03-13", "2026-03-15"),
+            [
+                "2026-03-14T00:00:00", "2026-03-14T03:00:00", "2026-03-14T06:00:00", "2026-03-14T09:00:00",
+                "2026-03-14T12:00:00", "2026-03-14T15:00:00", "2026-03-14T18:00:00", "2026-03-14T21:00:00"
+            ]
+        );
+        assert_eq!(
+            expand("FREQ=MINUTELY;INTERVAL=20;BYHOUR=9,10;COUNT=4", "2026-03-10T09:00", None, "20260310", "20260312"),
+            ["2026-03-10T09:00:00", "2026-03-10T09:20:00", "2026-03-10T09:40:00", "2026-03-10T10:00:00"]
+        );
+    }
+
+    #[test]
+    fn parse_and_serialize() {
+        let rule: RRule = "rrule:freq=monthly;byday=-1fr,2MO;interval=2;until=20261231T235959Z;wkst=su".parse()
+            .unwrap();
+        let by_day = [WeekdayNum { n: -1, weekday: Weekday::Fri }, WeekdayNum { n: 2, weekday: Weekday::Mon }];
+        assert_eq!(rule.by_day, by_day);
+        assert_eq!(rule.to_string(), "FREQ=MONTHLY;INTERVAL=2;UNTIL=20261231T235959Z;BYDAY=-1FR,2MO;WKST=SU");
+        assert_eq!(rule.to_string().parse::<RRule>().unwrap(), rule);
+
+        for bad in [
+            "INTERVAL=2",
+            "FREQ=DAILY;COUNT=2;UNTIL=20260101",
+            "FREQ=MONTHLY;BYWEEKNO=1",
+            "FREQ=WEEKLY;BYMONTHDAY=1",
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=DAILY;BYSETPOS=1",
+            "FREQ=DAILY;BYHOUR=24",
+            "FREQ=DAILY;BYMONTHDAY=0",
+            "FREQ=FORTNIGHTLY",
+            "FREQ=DAILY;FOO=1",
+        ] {
+            assert!(bad.parse::<RRule>().is_err(), "{}", bad);
+        }
+        assert!(Recurrence::parse("FREQ=HOURLY", "2026-03-10", None).is_err());
+    }
+
+    #[test]
+    fn next_instance_copies_the_task_and_shifts_dates() {
+        let task: TaskData = serde_json::from_value(serde_json::json!({
+            "id": "t1",
+            "title": "주간 보고",
+            "status": "completed",
+            "priority": "high",
+            "listId": "work",
+            "dueDate": "2026-03-06",
+            "reminder": "2026-03-06T00:00:00.000Z",
+            "subTasks": [{ "id": "s1", "title": "초안", "completed": true }],
+            "attachments": [{ "id": "a1", "name": "a.pdf", "size": 1, "type": "text/plain", "addedAt": "2026-03-01" }],
+            "tags": ["work"],
+            "linkedNoteIds": ["n1"],
+            "completedDate": "2026-03-06",
+            "recurrence_rule": { "freq": "weekly", "interval": 1, "byweekday": [4] },
+        }))
+        .unwrap();
+        // 뉴욕 기준 19:00 알림은 서머타임이 시작돼도 19:00
+        let ny: Tz = "America/New_York".parse().unwrap();
+        let next = next_instance(&task, &ny).unwrap().unwrap();
+        assert_eq!(next.id.as_deref(), Some("t1-20260313"));
+        assert_eq!(next.status, TaskStatus::Todo);
+        assert_eq!(next.due_date.as_deref(), Some("2026-03-13"));
+        assert_eq!(next.created_date.as_deref(), Some("2026-03-13"));
+        assert_eq!(next.reminder.as_deref(), Some("2026-03-12T23:00:00.000Z"));
+        assert_eq!(next.sub_tasks[0], SubTask { id: "s1".into(), title: "초안".into(), completed: false });
+        assert!(next.attachments.is_empty() && next.completed_date.is_none());
+        assert_eq!((&next.tags, &next.linked_note_ids), (&task.tags, &task.linked_note_ids));
+        assert_eq!(next.list_id, task.list_id);
+        assert_eq!(next.recurrence_parent_id.as_deref(), Some("t1"));
+
+        // 다음 회차에서 또 만들어도 반복의 첫 할 일 id를 쓴다
+        let rrule = RecurrenceRule {
+            rrule: Some("RRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3\nEXDATE:20260424".into()),
+            ..task.recurrence_rule.clone().unwrap()
+        };
+        let second = TaskData {
+            id: Some("t1-20260327".into()),
+            due_date: Some("2026-03-27T18:00:00+09:00".into()),
+            reminder: None,
+            recurrence_rule: Some(rrule),
+            recurrence_parent_id: Some("t1".into()),
+            ..task.clone()
+        };
+        let third = next_instance(&second, &ny).unwrap().unwrap();
+        assert_eq!(third.id.as_deref(), Some("t1-20260529"));
+        assert_eq!(third.due_date.as_deref(), Some("2026-05-29T18:00:00+09:00"));
+        let rule = third.recurrence_rule.as_ref().unwrap();
+        assert_eq!(rule.rrule.as_deref(), Some("RRULE:FREQ=MONTHLY;COUNT=1;BYDAY=-1FR\nEXDATE:20260424"));
+        // EXDATE로 뺀 4월도 COUNT에 든다. 이제 끝났다
+        assert_eq!(next_instance(&third, &ny).unwrap(), None);
+        assert_eq!(next_instance(&TaskData { recurrence_rule: None, ..task }, &ny).unwrap(), None);
+    }
+
+    fn overrides(value: serde_json::Value) -> BTreeMap<String, OccurrenceOverride> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn overrides_skip_move_and_rename_occurrences() {
+        let ov = overrides(serde_json::json!({
+            "2026-03-03": { "skipped": true },
+            "2026-03-04": { "start": "2026-03-06", "title": "미룬 회의" },
+            "2026-03-10": { "start": "2026-03-05", "priority": "urgent" },
+            "2026-03-06": { "start": "2026-04-01" },
+        }));
+        let recurrence = Recurrence::parse("FREQ=DAILY", "2026-03-01T09:00:00", Some("Asia/Seoul"))
+            .unwrap()
+            .with_overrides(&ov)
+            .unwrap();
+        let range = Range { from: "2026-03-01".into(), to: "2026-03-08".into(), limit: None };
+        let instances = recurrence.expand_instances(&range).unwrap();
+        let view: Vec<(String, String)> =
+            instances.iter().map(|i| (i.recurrence_id.to_string(), i.start.to_string())).collect();
+        let at = |day: &str| format!("2026-03-{}T09:00:00+09:00", day);
+        let pair = |a: &str, b: &str| (at(a), at(b));
+        assert_eq!(
+            view,
+            vec![
+                pair("01", "01"),
+                pair("02", "02"),
+                // 3일은 건너뛰고 10일이 5일로, 4일이 6일로 오며 6일은 4월로 나간다
+                pair("05", "05"),
+                pair("10", "05"),
+                pair("04", "06"),
+                pair("07", "07"),
+            ]
+        );
+        assert_eq!(instances[3].priority, Some(TaskPriority::Urgent));
+        assert_eq!(instances[4].title.as_deref(), Some("미룬 회의"));
+        // 건너뛴 회차는 펼치기/다음 회차에도 없다
+        assert!(!recurrence.expand(&range).unwrap().iter().any(|o| o.local.day() == 3));
+        assert_eq!(recurrence.next_after("2026-03-02T10:00:00+09:00").unwrap().unwrap().local.day(), 4);
+        assert_eq!(reminder_ms("2026-03-02T07:00:00.000Z", &chrono::Utc), Some(1_772_434_800_000));
+        assert_eq!(reminder_ms("2026-03-02T16:00:00", &"Asia/Seoul".parse::<Tz>().unwrap()), Some(1_772_434_800_000));
+        let bad = overrides(serde_json::json!({ "3월 3일": { "skipped": true } }));
+        assert!(Recurrence::parse("FREQ=DAILY", "2026-03-01", None).unwrap().with_overrides(&bad).is_err());
+    }
+
+    #[test]
+    fn next_instance_applies_overrides() {
+        let task: TaskData = serde_json::from_value(serde_json::json!({
+            "id": "t1",
+            "title": "운동",
+            "status": "completed",
+            "priority": "low",
+            "listId": "home",
+            "dueDate": "2026-03-02",
+            "reminder": "2026-03-02T07:00:00",
+            "recurrence_rule": {
+                "freq": "daily",
+                "overrides": {
+                    "2026-03-01": { "skipped": true },
+                    "2026-03-03": { "skipped": true },
+                    "2026-03-04": { "start": "2026-03-07", "title": "긴 운동", "priority": "high" },
+                },
+            },
+        }))
+        .unwrap();
+        let next = next_instance(&task, &chrono::Utc).unwrap().unwrap();
+        // id와 recurrence_id는 원래 날짜, 날짜/알림은 옮긴 날
+        assert_eq!(next.id.as_deref(), Some("t1-20260304"));
+        assert_eq!(next.recurrence_id.as_deref(), Some("2026-03-04"));
+        assert_eq!(next.due_date.as_deref(), Some("2026-03-07"));
+        assert_eq!(next.created_date.as_deref(), Some("2026-03-07"));
+        assert_eq!(next.reminder.as_deref(), Some("2026-03-07T07:00:00"));
+        assert_eq!((next.title.as_str(), next.priority), ("긴 운동", TaskPriority::High));
+        // 지난 회차의 예외는 버린다
+        let rule = next.recurrence_rule.as_ref().unwrap();
+        assert_eq!(rule.overrides.keys().collect::<Vec<_>>(), vec!["2026-03-04"]);
+
+        // 옮긴 회차를 끝내면 원래 날짜 다음 회차로, 제목은 반복의 것
+        assert_eq!((rule.series_title.as_deref(), rule.series_priority), (Some("운동"), Some(TaskPriority::Low)));
+        let after = next_instance(&next, &chrono::Utc).unwrap().unwrap();
+        assert_eq!(after.id.as_deref(), Some("t1-20260305"));
+        assert_eq!(after.due_date.as_deref(), Some("2026-03-05"));
+        assert_eq!(after.reminder.as_deref(), Some("2026-03-05T07:00:00"));
+        assert_eq!((after.title.as_str(), after.priority), ("운동", TaskPriority::Low));
+        let rule = after.recurrence_rule.as_ref().unwrap();
+        assert!(rule.overrides.is_empty() && rule.series_title.is_none() && rule.series_priority.is_none());
+    }
+}
@@ -0,0 +1,264 @@
+//! 할 일 알림 예약.
+//!
+//! 웹뷰의 `setTimeout` 대신 로컬 DB의 `reminders` 테이블(할 일 id, 울릴 시각, 내용)에 두고 Rust 스레드가 울린다.
+//! 창을 새로 고치거나 앱을 다시 켜도 남아 있고, 기간 제한도 없다.
+//! 잠자기나 앱 종료 중에 지나간 알림은 깨어나거나 켜자마자 울린다(`missed`). 잠자기 동안 단조 시계가
+//! 멈추는 OS가 있어 `MAX_WAIT`보다 오래 자지 않고 벽시계를 다시 본다.
+
+use crate::storage::{LocalDb, StorageError};
+use crate::token_store::now_ms;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// 한 번에 기다리는 최대 시간 (잠자기에서 깨어난 것을 이 안에 알아챈다)
+const MAX_WAIT: Duration = Duration::from_secs(30);
+/// 이보다 늦게 울리면 놓친 알림으로 본다
+const MISSED_AFTER_MS: i64 = 60_000;
+
+/// 알림에 보일 내용
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderPayload {
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    /// 할 일 하나에 알림 하나. 다시 예약하면 덮어쓴다
+    pub task_id: String,
+    /// 할 일의 주인 (알림에서 할 일을 고칠 때 필요)
+    #[serde(default)]
+    pub uid: Option<String>,
+    /// epoch ms
+    pub fire_at: i64,
+    pub payload: ReminderPayload,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// 울린 알림
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FiredReminder {
+    #[serde(flatten)]
+    pub reminder: Reminder,
+    pub fired_at: i64,
+    /// 제때 울리지 못함 (잠자기, 앱 종료)
+    pub missed: bool,
+}
+
+const SELECT: &str = "SELECT task_id, uid, fire_at, payload, created_at FROM reminders";
+
+fn row_to_reminder(row: &Row<'_>) -> rusqlite::Result<Reminder> {
+    let payload: String = row.get(3)?;
+    Ok(Reminder {
+        task_id: row.get(0)?,
+        uid: row.get(1)?,
+        fire_at: row.get(2)?,
+        payload: serde_json::from_str(&payload).unwrap_or_default(),
+        created_at: row.get(4)?,
+    })
+}
+
+pub(crate) fn upsert(conn: &Connection, reminder: &Reminder) -> Result<(), StorageError> {
+    let payload = serde_json::to_string(&reminder.payload).expect("reminder payload serializes");
+    conn.prepare_cached(
+        "INSERT INTO reminders (task_id, uid, fire_at, payload, created_at) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (task_id) DO UPDATE SET uid = ?2, fire_at = ?3, payload = ?4, created_at = ?5",
+    )?
+    .execute(params![reminder.task_id, reminder.uid, reminder.fire_at, payload, reminder.created_at])?;
+    Ok(())
+}
+
+pub(crate) fn remove(conn: &Connection, task_id: &str) -> Result<bool, StorageError> {
+    Ok(conn.prepare_cached("DELETE FROM reminders WHERE task_id = ?1")?.execute([task_id])? > 0)
+}
+
+/// 할 일이 없어진 알림을 지운다 (다른 기기에서 지운 할 일이 스냅샷으로 빠졌을 때)
+pub(crate) fn prune(conn: &Connection, uid: &str) -> Result<usize, StorageError> {
+    let sql = "DELETE FROM reminders WHERE (uid = ?1 OR uid IS NULL) AND task_id NOT IN (SELECT id FROM tasks)";
+    Ok(conn.prepare_cached(sql)?.execute([uid])?)
+}
+
+/// 울릴 순서대로
+pub(crate) fn list(conn: &Connection) -> Result<Vec<Reminder>, StorageError> {
+    let mut stmt = conn.prepare_cached(&format!("{} ORDER BY fire_at, task_id", SELECT))?;
+    let rows = stmt.query_map([], row_to_reminder)?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+pub(crate) fn next_fire_at(conn: &Connection) -> Result<Option<i64>, StorageError> {
+    Ok(conn.query_row("SELECT MIN(fire_at) FROM reminders", [], |row| row.get(0)).optional()?.flatten())
+}
+
+/// 때가 된 알림을 꺼낸다 (큐에서 지운다)
+pub(crate) fn take_due(conn: &Connection, now: i64) -> Result<Vec<FiredReminder>, StorageError> {
+    let tx = conn.unchecked_transaction()?;
+    let due: Vec<Reminder> = {
+        let mut stmt = tx.prepare_cached(&format!("{} WHERE fire_at <= ?1 ORDER BY fire_at, task_id", SELECT))?;
+        let rows = stmt.query_map([now], row_to_reminder)?;
+        rows.collect::<Result<_, _>>()?
+    };
+    tx.prepare_cached("DELETE FROM reminders WHERE fire_at <= ?1")?.execute([now])?;
+    tx.commit()?;
+    Ok(due
+        .into_iter()
+        .map(|reminder| {
+            let missed = now - reminder.fire_at > MISSED_AFTER_MS;
+            FiredReminder { reminder, fired_at: now, missed }
+        })
+        .collect())
+}
+
+type FireListener = Box<dyn Fn(&[FiredReminder]) + Send + Sync>;
+
+pub struct ReminderScheduler {
+    db: Arc<LocalDb>,
+    wake: Mutex<bool>,
+    signal: Condvar,
+    on_fire: FireListener,
+}
+
+impl ReminderScheduler {
+    /// `on_fire`는 한 번에 울릴 알림들을 받는다 (놓친 알림이 한꺼번에 올 수 있다)
+    pub fn new<F>(db: Arc<LocalDb>, on_fire: F) -> Self
+    where
+        F: Fn(&[FiredReminder]) + Send + Sync + 'static,
+    {
+        Self { db, wake: Mutex::new(false), signal: Condvar::new(), on_fire: Box::new(on_fire) }
+    }
+
+    fn notify(&self) {
+        *self.wake.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.signal.notify_all();
+    }
+
+    /// `task_id`의 알림을 (다시) 예약한다. 이미 지난 시각이면 바로 울린다
+    pub fn schedule(
+        &self,
+        task_id: &str,
+        uid: Option<String>,
+        fire_at: i64,
+        payload: ReminderPayload,
+    ) -> Result<Reminder, StorageError> {
+        if task_id.is_empty() {
+            return Err(StorageError::Invalid("reminder without task id".into()));
+        }
+        let reminder = Reminder { task_id: task_id.to_string(), uid, fire_at, payload, created_at: now_ms() as i64 };
+        upsert(&self.db.conn(), &reminder)?;
+        self.notify();
+        Ok(reminder)
+    }
+
+    pub fn cancel(&self, task_id: &str) -> Result<bool, StorageError> {
+        let removed = remove(&self.db.conn(), task_id)?;
+        if removed {
+            self.notify();
+        }
+        Ok(removed)
+    }
+
+    pub fn list(&self) -> Result<Vec<Reminder>, StorageError> {
+        list(&self.db.conn())
+    }
+
+    /// 때가 된 알림을 울리고 다음 알림 시각을 돌려준다
+    pub fn run_due(&self, now: i64) -> Result<Option<i64>, StorageError> {
+        let (fired, next) = {
+            let conn = self.db.conn();
+            (take_due(&conn, now)?, next_fire_at(&conn)?)
+        };
+        if !fired.is_empty() {
+            (self.on_fire)(&fired);
+        }
+        Ok(next)
+    }
+
+    fn wait(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let mut wake = self.wake.lock().unwrap_or_else(|e| e.into_inner());
+        while !*wake {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            wake = self.signal.wait_timeout(wake, deadline - now).unwrap_or_else(|e| e.into_inner()).0;
+        }
+        *wake = false;
+    }
+
+    /// 알림 스레드를 띄운다. 켜자마자 지나간 알림부터 울린다
+    pub fn spawn(self: &Arc<Self>) {
+        let scheduler = Arc::clone(self);
+        std::thread::spawn(move || loop {
+            let now = now_ms() as i64;
+            let timeout = match scheduler.run_due(now) {
+                Ok(Some(next)) => Duration::from_millis((next - now).max(0) as u64).min(MAX_WAIT),
+                Ok(None) => MAX_WAIT,
+                Err(e) => {
+                    eprintln!("알림 예약 확인 실패: {}", e);
+                    MAX_WAIT
+                }
+            };
+            scheduler.wait(timeout);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(title: &str) -> ReminderPayload {
+        ReminderPayload { title: title.into(), body: String::new() }
+    }
+
+    fn scheduler() -> (ReminderScheduler, Arc<Mutex<Vec<FiredReminder>>>) {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&fired);
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let scheduler = ReminderScheduler::new(db, move |batch: &[FiredReminder]| sink.lock().unwrap().extend_from_slice(batch));
+        (scheduler, fired)
+    }
+
+    #[test]
+    fn rescheduling_replaces_and_cancel_removes() {
+        let (scheduler, _) = scheduler();
+        scheduler.schedule("t1", Some("u1".into()), 5_000, payload("a")).unwrap();
+        scheduler.schedule("t2", None, 3_000, payload("b")).unwrap();
+        scheduler.schedule("t1", Some("u1".into()), 1_000, payload("a2")).unwrap();
+
+        let list = scheduler.list().unwrap();
+        assert_eq!(list.iter().map(|r| (r.task_id.as_str(), r.fire_at)).collect::<Vec<_>>(), [("t1", 1_000), ("t2", 3_000)]);
+        assert_eq!(list[0].payload.title, "a2");
+        assert!(scheduler.cancel("t1").unwrap());
+        assert!(!scheduler.cancel("t1").unwrap());
+        assert_eq!(scheduler.list().unwrap().len(), 1);
+        assert!(scheduler.schedule("", None, 0, payload("x")).is_err());
+    }
+
+    #[test]
+    fn due_reminders_fire_once_and_missed_ones_are_flagged() {
+        let (scheduler, fired) = scheduler();
+        // 3일 전에 울렸어야 하는 알림 (앱이 꺼져 있었음), 방금 된 알림, 아직인 알림
+        let now = 1_800_000_000_000;
+        scheduler.schedule("old", None, now - 3 * 86_400_000, payload("old")).unwrap();
+        scheduler.schedule("now", None, now - 1_000, payload("now")).unwrap();
+        scheduler.schedule("later", None, now + 60 * 86_400_000, payload("later")).unwrap();
+
+        assert_eq!(scheduler.run_due(now).unwrap(), Some(now + 60 * 86_400_000));
+        let batch: Vec<(String, bool)> =
+            fired.lock().unwrap().iter().map(|f| (f.reminder.task_id.clone(), f.missed)).collect();
+        assert_eq!(batch, [("old".to_string(), true), ("now".to_string(), false)]);
+
+        // 다시 확인해도 같은 알림은 울리지 않는다
+        assert_eq!(scheduler.run_due(now + 1).unwrap(), Some(now + 60 * 86_400_000));
+        assert_eq!(fired.lock().unwrap().len(), 2);
+        assert_eq!(scheduler.list().unwrap().len(), 1);
+    }
+}
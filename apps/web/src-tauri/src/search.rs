@@ -0,0 +1,606 @@
+//! 할 일/노트/마인드맵 전문 검색 (SQLite FTS5).
+//!
+//! 색인은 `search_entries`(원문 한 조각 = 한 행)와 이를 내용으로 쓰는 FTS5 테이블 `search_index`로 이뤄진다.
+//! 토큰은 여기서 직접 만든다: 라틴 문자 등은 소문자 단어, 한글/한자/가나는 띄어쓰기와 조사에 상관없이
+//! 찾을 수 있게 글자 bigram (+ 마지막 글자 unigram). 자모로 풀린 한글(NFD)은 음절로 합쳐서 다룬다.
+//! 문서가 로컬 DB에 쓰일 때마다 `storage`가 같은 트랜잭션에서 색인을 고친다.
+//!
+//! 검색은 단어 앞부분 일치가 기본이고, 결과가 모자라면 오타를 허용한 단어로 한 번 더 찾는다.
+
+use crate::storage::{Collection, StorageError};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+pub const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+/// 스니펫 길이 (글자)
+const SNIPPET_CHARS: usize = 80;
+/// 스니펫에서 첫 일치 앞에 보여 줄 글자 수
+const SNIPPET_LEAD: usize = 24;
+/// 오타 허용 결과의 점수 배율
+const FUZZY_PENALTY: f64 = 0.5;
+
+/// 색인하는 조각의 종류
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Title,
+    Memo,
+    Tags,
+    /// 노트 블록 (`subId` = 블록 id)
+    Block,
+    /// 마인드맵 노드 (`subId` = 노드 id)
+    Node,
+}
+
+impl Field {
+    fn as_str(self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::Memo => "memo",
+            Field::Tags => "tags",
+            Field::Block => "block",
+            Field::Node => "node",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Field> {
+        [Field::Title, Field::Memo, Field::Tags, Field::Block, Field::Node].into_iter().find(|f| f.as_str() == s)
+    }
+
+    fn weight(self) -> f64 {
+        match self {
+            Field::Title => 3.0,
+            Field::Tags => 2.0,
+            Field::Memo | Field::Block | Field::Node => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlight: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub collection: Collection,
+    pub id: String,
+    /// 문서 제목 (할 일/노트/마인드맵의 title)
+    pub title: String,
+    /// 가장 잘 맞은 조각
+    pub field: Field,
+    pub sub_id: Option<String>,
+    pub snippet: Vec<SnippetPart>,
+    pub score: f64,
+    /// 오타 허용으로 찾은 결과
+    pub fuzzy: bool,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF     // 한글 자모
+        | 0x3040..=0x30FF   // 히라가나, 가타카나
+        | 0x3130..=0x318F   // 한글 호환 자모
+        | 0x3400..=0x4DBF   // 한자 확장 A
+        | 0x4E00..=0x9FFF   // 한자
+        | 0xAC00..=0xD7A3   // 한글 음절
+        | 0xF900..=0xFAFF)  // 호환 한자
+}
+
+/// `chars[i..]`의 첫 글자. 초성+중성(+종성) 자모는 음절 하나로 합친다. (글자, 쓴 개수)
+fn compose_at(chars: &[char], i: usize) -> (char, usize) {
+    let code = |j: usize| chars.get(j).map(|c| *c as u32);
+    let (Some(l @ 0x1100..=0x1112), Some(v @ 0x1161..=0x1175)) = (code(i), code(i + 1)) else {
+        return (chars[i], 1);
+    };
+    let mut syllable = 0xAC00 + ((l - 0x1100) * 21 + (v - 0x1161)) * 28;
+    let mut used = 2;
+    if let Some(t @ 0x11A8..=0x11C2) = code(i + 2) {
+        syllable += t - 0x11A7;
+        used = 3;
+    }
+    (char::from_u32(syllable).unwrap_or(chars[i]), used)
+}
+
+/// 같은 종류 글자가 이어진 덩어리. `chars`는 (소문자로 바꾼 글자, 원문에서의 글자 위치)
+#[derive(Debug)]
+struct Word {
+    chars: Vec<(char, usize)>,
+    end: usize,
+    cjk: bool,
+}
+
+impl Word {
+    fn text(&self) -> String {
+        self.chars.iter().map(|(c, _)| c).collect()
+    }
+
+    /// 이 단어의 글자 `from..to`가 원문에서 차지하는 범위
+    fn span(&self, from: usize, to: usize) -> (usize, usize) {
+        let end = self.chars.get(to).map_or(self.end, |(_, at)| *at);
+        (self.chars[from].1, end)
+    }
+}
+
+fn words(text: &str) -> Vec<Word> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words: Vec<Word> = Vec::new();
+    let mut current: Option<Word> = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let (c, used) = compose_at(&chars, i);
+        let cjk = is_cjk(c);
+        if !cjk && !c.is_alphanumeric() {
+            words.extend(current.take());
+        } else {
+            if current.as_ref().is_some_and(|w| w.cjk != cjk) {
+                words.extend(current.take());
+            }
+            let word = current.get_or_insert_with(|| Word { chars: Vec::new(), end: i, cjk });
+            word.chars.extend(c.to_lowercase().map(|lower| (lower, i)));
+            word.end = i + used;
+        }
+        i += used;
+    }
+    words.extend(current);
+    words
+}
+
+/// 색인에 넣는 토큰 (공백으로 구분)
+pub fn index_tokens(text: &str) -> String {
+    let mut tokens = Vec::new();
+    for word in words(text) {
+        let chars: Vec<char> = word.chars.iter().map(|(c, _)| *c).collect();
+        if !word.cjk {
+            tokens.push(chars.into_iter().collect::<String>());
+            continue;
+        }
+        tokens.extend(chars.windows(2).map(|pair| pair.iter().collect::<String>()));
+        if let Some(last) = chars.last() {
+            tokens.push(last.to_string());
+        }
+    }
+    tokens.join(" ")
+}
+
+/// 검색어 한 덩어리. 라틴 단어는 앞부분 일치, 한중일 글자 덩어리는 bigram 구(phrase)
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    text: String,
+    cjk: bool,
+    /// 오타를 허용해 찾은 비슷한 색인 단어
+    alternatives: Vec<String>,
+}
+
+fn parse_query(query: &str) -> Vec<Term> {
+    words(query).into_iter().map(|w| Term { text: w.text(), cjk: w.cjk, alternatives: Vec::new() }).collect()
+}
+
+/// FTS5 MATCH 식. 토큰은 영숫자/한중일 글자뿐이라 따옴표 안에 그대로 넣어도 된다
+fn match_expression(terms: &[Term], fuzzy: bool) -> String {
+    let groups: Vec<String> = terms
+        .iter()
+        .map(|term| {
+            let chars: Vec<char> = term.text.chars().collect();
+            let exact = if term.cjk && chars.len() > 1 {
+                let bigrams: Vec<String> = chars.windows(2).map(|p| p.iter().collect()).collect();
+                format!("\"{}\"", bigrams.join(" "))
+            } else {
+                format!("\"{}\"*", term.text)
+            };
+            if !fuzzy || term.alternatives.is_empty() {
+                return exact;
+            }
+            let alternatives: Vec<String> = term.alternatives.iter().map(|a| format!("\"{}\"", a)).collect();
+            format!("({} OR {})", exact, alternatives.join(" OR "))
+        })
+        .collect();
+    groups.join(" AND ")
+}
+
+/// 단어 앞부분(또는 전체)과의 편집 거리
+fn prefix_distance(term: &str, candidate: &str) -> usize {
+    let n = term.chars().count();
+    let head: String = candidate.chars().take(n).collect();
+    strsim::levenshtein(term, candidate).min(strsim::levenshtein(term, &head))
+}
+
+/// 색인에 있는 단어 중 `term`과 비슷한 것 (이미 앞부분 일치로 찾히는 것은 뺀다)
+fn fuzzy_alternatives(conn: &Connection, term: &str) -> Result<Vec<String>, StorageError> {
+    let n = term.chars().count();
+    if n < 4 {
+        return Ok(Vec::new());
+    }
+    let max_distance = if n >= 8 { 2 } else { 1 };
+    let mut stmt = conn.prepare_cached("SELECT term FROM search_vocab WHERE length(term) >= ?1")?;
+    let candidates = stmt.query_map([(n - max_distance) as i64], |row| row.get::<_, String>(0))?;
+    let mut scored = Vec::new();
+    for candidate in candidates {
+        let candidate = candidate?;
+        if candidate.starts_with(term) {
+            continue;
+        }
+        let distance = prefix_distance(term, &candidate);
+        if distance <= max_distance {
+            scored.push((distance, candidate));
+        }
+    }
+    scored.sort();
+    Ok(scored.into_iter().take(5).map(|(_, c)| c).collect())
+}
+
+/// 원문에서 검색어와 맞는 범위 (글자 위치)
+fn highlights(text: &str, terms: &[Term]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    for word in words(text) {
+        let chars: Vec<char> = word.chars.iter().map(|(c, _)| *c).collect();
+        for term in terms.iter().filter(|t| t.cjk == word.cjk) {
+            let needle: Vec<char> = term.text.chars().collect();
+            if word.cjk {
+                let mut i = 0;
+                while i + needle.len() <= chars.len() {
+                    if chars[i..i + needle.len()] == needle[..] {
+                        ranges.push(word.span(i, i + needle.len()));
+                        i += needle.len();
+                    } else {
+                        i += 1;
+                    }
+                }
+            } else if chars.starts_with(&needle) {
+                ranges.push(word.span(0, needle.len()));
+            } else if term.alternatives.iter().any(|a| *a == word.text()) {
+                ranges.push(word.span(0, chars.len()));
+            }
+        }
+    }
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 첫 일치 주변을 잘라 일치 부분을 표시한다. 줄바꿈은 공백으로
+fn snippet(text: &str, ranges: &[(usize, usize)]) -> Vec<SnippetPart> {
+    let chars: Vec<char> = text.chars().map(|c| if c.is_whitespace() { ' ' } else { c }).collect();
+    let mut start = ranges.first().map_or(0, |r| r.0.saturating_sub(SNIPPET_LEAD));
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+    start = start.min(end.saturating_sub(SNIPPET_CHARS));
+
+    let mut parts: Vec<SnippetPart> = Vec::new();
+    let mut push = |text: String, highlight: bool| {
+        if text.is_empty() {
+            return;
+        }
+        match parts.last_mut() {
+            Some(last) if last.highlight == highlight => last.text.push_str(&text),
+            _ => parts.push(SnippetPart { text, highlight }),
+        }
+    };
+    if start > 0 {
+        push("…".into(), false);
+    }
+    let mut at = start;
+    for &(from, to) in ranges {
+        let (from, to) = (from.clamp(start, end), to.clamp(start, end));
+        if from >= to {
+            continue;
+        }
+        push(chars[at..from].iter().collect(), false);
+        push(chars[from..to].iter().collect(), true);
+        at = to;
+    }
+    push(chars[at..end].iter().collect(), false);
+    if end < chars.len() {
+        push("…".into(), false);
+    }
+    parts
+}
+
+struct Entry {
+    field: Field,
+    sub_id: Option<String>,
+    text: String,
+}
+
+fn text_of<'a>(fields: &'a Map<String, Value>, key: &str) -> &'a str {
+    fields.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+/// 문서에서 색인할 조각들. 휴지통의 노트는 색인하지 않는다
+fn entries(collection: Collection, fields: &Map<String, Value>) -> Vec<Entry> {
+    let entry = |field, sub_id: Option<&str>, text: &str| Entry {
+        field,
+        sub_id: sub_id.map(str::to_string),
+        text: text.to_string(),
+    };
+    let items = |key: &str| fields.get(key).and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    let mut entries = vec![entry(Field::Title, None, text_of(fields, "title"))];
+    match collection {
+        Collection::Tasks => {
+            entries.push(entry(Field::Memo, None, text_of(fields, "memo")));
+            let tags: Vec<&str> = items("tags").iter().filter_map(Value::as_str).collect();
+            entries.push(entry(Field::Tags, None, &tags.join(" ")));
+        }
+        Collection::Notes => {
+            if fields.get("deleted").and_then(Value::as_bool) == Some(true) {
+                return Vec::new();
+            }
+            for block in items("blocks") {
+                let id = block.get("id").and_then(Value::as_str);
+                entries.push(entry(Field::Block, id, block.get("content").and_then(Value::as_str).unwrap_or_default()));
+            }
+        }
+        Collection::Mindmaps => {
+            for node in items("nodes") {
+                let id = node.get("id").and_then(Value::as_str);
+                entries.push(entry(Field::Node, id, node.get("text").and_then(Value::as_str).unwrap_or_default()));
+            }
+        }
+        Collection::Lists | Collection::Folders => return Vec::new(),
+    }
+    entries.retain(|e| !e.text.trim().is_empty());
+    entries
+}
+
+/// 색인하는 컬렉션
+pub(crate) fn indexes(collection: Collection) -> bool {
+    matches!(collection, Collection::Tasks | Collection::Notes | Collection::Mindmaps)
+}
+
+/// 문서 하나를 다시 색인한다. 색인할 내용이 전과 같으면 아무것도 하지 않는다
+pub(crate) fn index_doc(
+    conn: &Connection,
+    uid: &str,
+    collection: Collection,
+    id: &str,
+    fields: &Map<String, Value>,
+) -> Result<(), StorageError> {
+    let entries = entries(collection, fields);
+    let source: Vec<Value> =
+        entries.iter().map(|e| serde_json::json!([e.field.as_str(), e.sub_id, e.text])).collect();
+    let source = Value::Array(source).to_string();
+    let previous: Option<String> = conn
+        .prepare_cached("SELECT source FROM search_docs WHERE uid = ?1 AND collection = ?2 AND doc_id = ?3")?
+        .query_row(params![uid, collection.table(), id], |row| row.get(0))
+        .optional()?;
+    if previous.as_deref() == Some(source.as_str()) {
+        return Ok(());
+    }
+    remove_doc(conn, collection, uid, id)?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut insert = conn.prepare_cached(
+        "INSERT INTO search_entries (uid, collection, doc_id, field, sub_id, text, tokens) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for e in &entries {
+        insert.execute(params![uid, collection.table(), id, e.field.as_str(), e.sub_id, e.text, index_tokens(&e.text)])?;
+    }
+    conn.prepare_cached("INSERT INTO search_docs (uid, collection, doc_id, title, source) VALUES (?1, ?2, ?3, ?4, ?5)")?
+        .execute(params![uid, collection.table(), id, text_of(fields, "title"), source])?;
+    Ok(())
+}
+
+pub(crate) fn remove_doc(conn: &Connection, collection: Collection, uid: &str, id: &str) -> Result<(), StorageError> {
+    let args = params![uid, collection.table(), id];
+    conn.prepare_cached("DELETE FROM search_entries WHERE uid = ?1 AND collection = ?2 AND doc_id = ?3")?.execute(args)?;
+    conn.prepare_cached("DELETE FROM search_docs WHERE uid = ?1 AND collection = ?2 AND doc_id = ?3")?.execute(args)?;
+    Ok(())
+}
+
+/// 로컬 테이블에서 사라진 문서의 색인을 지운다 (스냅샷으로 통째로 바꾼 뒤)
+pub(crate) fn prune(conn: &Connection, uid: &str, collection: Collection) -> Result<(), StorageError> {
+    for table in ["search_entries", "search_docs"] {
+        conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE uid = ?1 AND collection = ?2 AND doc_id NOT IN (SELECT id FROM {} WHERE uid = ?1)",
+                table,
+                collection.table()
+            ),
+            [uid, collection.table()],
+        )?;
+    }
+    Ok(())
+}
+
+struct Row {
+    collection: Collection,
+    id: String,
+    field: Field,
+    sub_id: Option<String>,
+    text: String,
+    rank: f64,
+}
+
+fn run_query(conn: &Connection, uid: &str, expression: &str, limit: usize) -> Result<Vec<Row>, StorageError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT e.collection, e.doc_id, e.field, e.sub_id, e.text, bm25(search_index)
+         FROM search_index JOIN search_entries e ON e.id = search_index.rowid
+         WHERE search_index MATCH ?1 AND e.uid = ?2
+         ORDER BY bm25(search_index) LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![expression, uid, limit as i64], |row| {
+        Ok((row.get::<_, String>(0)?, row.get(1)?, row.get::<_, String>(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        let (collection, id, field, sub_id, text, rank) = row?;
+        let collection = serde_json::from_value(Value::String(collection)).ok();
+        if let (Some(collection), Some(field)) = (collection, Field::parse(&field)) {
+            out.push(Row { collection, id, field, sub_id, text, rank });
+        }
+    }
+    Ok(out)
+}
+
+/// 문서마다 가장 잘 맞은 조각 하나씩, 점수 순
+fn collect_hits(
+    conn: &Connection,
+    uid: &str,
+    rows: Vec<Row>,
+    terms: &[Term],
+    fuzzy: bool,
+    hits: &mut HashMap<(Collection, String), SearchHit>,
+) -> Result<(), StorageError> {
+    let mut title = conn.prepare_cached("SELECT title FROM search_docs WHERE uid = ?1 AND collection = ?2 AND doc_id = ?3")?;
+    for row in rows {
+        // bm25는 작을수록 잘 맞는다
+        let score = -row.rank * row.field.weight() * if fuzzy { FUZZY_PENALTY } else { 1.0 };
+        let key = (row.collection, row.id.clone());
+        if hits.get(&key).is_some_and(|hit| hit.score >= score) {
+            continue;
+        }
+        let doc_title: Option<String> =
+            title.query_row(params![uid, row.collection.table(), row.id], |r| r.get(0)).optional()?;
+        hits.insert(key, SearchHit {
+            collection: row.collection,
+            id: row.id,
+            title: doc_title.unwrap_or_default(),
+            field: row.field,
+            sub_id: row.sub_id,
+            snippet: snippet(&row.text, &highlights(&row.text, terms)),
+            score,
+            fuzzy,
+        });
+    }
+    Ok(())
+}
+
+/// 할 일/노트/마인드맵을 한꺼번에 찾는다. 점수 높은 순
+pub fn search(conn: &Connection, uid: &str, query: &str, limit: usize) -> Result<Vec<SearchHit>, StorageError> {
+    let limit = limit.clamp(1, MAX_LIMIT);
+    let mut terms = parse_query(query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    // 한 문서에 맞는 조각이 여럿일 수 있으니 넉넉히 가져와 문서별로 묶는다
+    let fetch = limit * 4;
+    let mut hits = HashMap::new();
+    let rows = run_query(conn, uid, &match_expression(&terms, false), fetch)?;
+    collect_hits(conn, uid, rows, &terms, false, &mut hits)?;
+
+    if hits.len() < limit {
+        for term in terms.iter_mut().filter(|t| !t.cjk) {
+            term.alternatives = fuzzy_alternatives(conn, &term.text)?;
+        }
+        if terms.iter().any(|t| !t.alternatives.is_empty()) {
+            let rows = run_query(conn, uid, &match_expression(&terms, true), fetch)?;
+            let rows = rows.into_iter().filter(|r| !hits.contains_key(&(r.collection, r.id.clone()))).collect();
+            collect_hits(conn, uid, rows, &terms, true, &mut hits)?;
+        }
+    }
+
+    let mut hits: Vec<SearchHit> = hits.into_values().collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(parts: &[SnippetPart]) -> Vec<(&str, bool)> {
+        parts.iter().map(|p| (p.text.as_str(), p.highlight)).collect()
+    }
+
+    #[test]
+    fn tokenizes_words_and_cjk_bigrams() {
+        assert_eq!(index_tokens("Weekly 회의록을 정리"), "weekly 회의 의록 록을 을 정리 리");
+        assert_eq!(index_tokens("東京タワー, x2"), "東京 京タ タワ ワー ー x2");
+        // 자모로 풀린 한글도 음절로
+        assert_eq!(index_tokens("\u{1112}\u{1161}\u{11AB}\u{1100}\u{1173}\u{11AF}"), "한글 글");
+    }
+
+    #[test]
+    fn builds_match_expressions() {
+        let mut terms = parse_query("회의록 meet 회");
+        assert_eq!(match_expression(&terms, false), r#""회의 의록" AND "meet"* AND "회"*"#);
+        terms[1].alternatives = vec!["meat".into()];
+        assert_eq!(match_expression(&terms, true), r#""회의 의록" AND ("meet"* OR "meat") AND "회"*"#);
+    }
+
+    #[test]
+    fn highlights_prefixes_and_cjk_substrings() {
+        let terms = parse_query("회의 MEET");
+        let text = "어제 회의록: Meeting notes";
+        let ranges = highlights(text, &terms);
+        assert_eq!(ranges, vec![(3, 5), (8, 12)]);
+        assert_eq!(
+            texts(&snippet(text, &ranges)),
+            [("어제 ", false), ("회의", true), ("록: ", false), ("Meet", true), ("ing notes", false)]
+        );
+    }
+
+    #[test]
+    fn long_snippets_are_trimmed_around_the_first_match() {
+        let text = format!("{}찾는말{}", "가".repeat(100), "나".repeat(100));
+        let parts = snippet(&text, &highlights(&text, &parse_query("찾는말")));
+        assert_eq!(parts[0].text.chars().next(), Some('…'));
+        assert_eq!(parts[1], SnippetPart { text: "찾는말".into(), highlight: true });
+        assert!(parts.last().unwrap().text.ends_with('…'));
+        let shown: usize = parts.iter().map(|p| p.text.chars().count()).sum();
+        assert_eq!(shown, SNIPPET_CHARS + 2);
+    }
+
+    #[test]
+    fn prefix_distance_allows_typos_in_partial_words() {
+        assert_eq!(prefix_distance("meetn", "meeting"), 1);
+        assert_eq!(prefix_distance("recieve", "receive"), 2);
+        assert_eq!(prefix_distance("shop", "shopping"), 0);
+    }
+
+    #[test]
+    fn index_follows_local_writes() {
+        use crate::models::{NoteData, TaskData};
+        use crate::storage::LocalDb;
+        use serde_json::json;
+
+        let db = LocalDb::open_in_memory().unwrap();
+        let task: TaskData = serde_json::from_value(json!({
+            "title": "Quarterly planning", "status": "todo", "priority": "high", "listId": "inbox",
+            "memo": "예산 검토", "tags": ["work"],
+        }))
+        .unwrap();
+        let task_id = db.insert("u1", &task).unwrap().id.unwrap();
+        let note: NoteData = serde_json::from_value(json!({
+            "title": "주간 회의", "icon": "📝",
+            "blocks": [{ "id": "b1", "type": "text", "content": "다음 회의록은 금요일까지 공유" }],
+        }))
+        .unwrap();
+        let note_id = db.insert("u1", &note).unwrap().id.unwrap();
+
+        let hits = db.search("u1", "회의록", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].id.as_str(), hits[0].field, hits[0].sub_id.as_deref()), (note_id.as_str(), Field::Block, Some("b1")));
+        assert_eq!(hits[0].title, "주간 회의");
+        assert!(hits[0].snippet.iter().any(|p| p.highlight && p.text == "회의록"));
+        // 제목이 맞은 쪽이 위
+        let hits = db.search("u1", "회의", 10).unwrap();
+        assert_eq!(hits[0].field, Field::Title);
+
+        assert_eq!(db.search("u1", "quart", 10).unwrap()[0].id, task_id);
+        let fuzzy = db.search("u1", "planing", 10).unwrap();
+        assert!(fuzzy[0].fuzzy && fuzzy[0].id == task_id);
+        assert!(db.search("u2", "quart", 10).unwrap().is_empty());
+
+        db.update::<TaskData>("u1", &task_id, &json!({ "memo": "결산" })).unwrap();
+        assert!(db.search("u1", "예산", 10).unwrap().is_empty());
+        assert_eq!(db.search("u1", "결산", 10).unwrap().len(), 1);
+        // 휴지통으로 옮긴 노트와 지운 할 일은 빠진다
+        db.update::<NoteData>("u1", &note_id, &json!({ "deleted": true })).unwrap();
+        db.delete::<TaskData>("u1", &task_id).unwrap();
+        assert!(db.search("u1", "회의", 10).unwrap().is_empty());
+        assert!(db.search("u1", "quarterly", 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,343 @@
+//! 단일 인스턴스.
+//!
+//! 먼저 뜬 프로세스가 루프백 포트를 열고 잠금 파일에 `포트 + 1회용 토큰 + PID`를 기록한다.
+//! 두 번째 실행은 잠금 파일을 읽어 그 포트로 실행 인자를 넘기고 바로 종료한다.
+//! 잠금 파일은 내용을 다 쓴 임시 파일을 hard link로 붙여 만들므로, 동시에 실행돼도 한쪽만 이긴다.
+//! 주인이 죽어 남은 잠금 파일은 그 포트에 아무도 없거나 그 PID가 없으면 지우고 다시 시도한다.
+//! 주인이 살아 있는데 답이 늦을 뿐이면 (바쁨) 잠금을 건드리지 않고 `TimedOut`으로 끝난다.
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+/// 실행 인자 JSON 한 줄의 최대 크기
+const MAX_MESSAGE: u64 = 64 * 1024;
+const ACQUIRE_ATTEMPTS: usize = 5;
+
+/// 두 번째 실행이 넘겨준 정보
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardedLaunch {
+    /// 실행 파일 경로를 포함한 argv
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Message {
+    token: String,
+    #[serde(flatten)]
+    launch: ForwardedLaunch,
+}
+
+pub enum Startup {
+    /// 이 프로세스가 주 인스턴스. 앱이 끝날 때까지 들고 있어야 한다
+    Primary(InstanceGuard),
+    /// 실행 중인 인스턴스에 인자를 넘겼다. 이 프로세스는 종료하면 된다
+    Forwarded,
+}
+
+/// 주 인스턴스의 리스너와 잠금 파일. drop 시 잠금 파일을 지운다
+pub struct InstanceGuard {
+    lock_path: PathBuf,
+    token: String,
+    listener: Option<TcpListener>,
+}
+
+/// OS별 사용자 전용 위치의 잠금 파일 경로. `app_id`는 번들 식별자
+pub fn default_lock_path(app_id: &str) -> PathBuf {
+    let file = format!("{}.instance", app_id);
+    #[cfg(target_os = "linux")]
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+        return PathBuf::from(dir).join(file);
+    }
+    // /tmp는 사용자끼리 공유되므로 사용자 이름을 붙인다 (Windows/macOS 임시 폴더는 사용자별)
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default();
+    let file = if user.is_empty() { file } else { format!("{}-{}", user, file) };
+    std::env::temp_dir().join(file)
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("OS random source unavailable");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 내용이 완전히 쓰인 잠금 파일을 원자적으로 만든다. 이미 있으면 AlreadyExists
+fn create_lock(lock_path: &Path, contents: &str) -> io::Result<()> {
+    let tmp = lock_path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&tmp, contents)?;
+    let linked = std::fs::hard_link(&tmp, lock_path);
+    let _ = std::fs::remove_file(&tmp);
+    linked
+}
+
+/// 포트, 토큰, PID (PID가 없는 예전 잠금 파일도 읽는다)
+fn read_lock(lock_path: &Path) -> Option<(u16, String, Option<u32>)> {
+    let text = std::fs::read_to_string(lock_path).ok()?;
+    let mut lines = text.lines();
+    let port = lines.next()?.trim().parse().ok()?;
+    let token = lines.next()?.trim().to_string();
+    let pid = lines.next().and_then(|line| line.trim().parse().ok());
+    Some((port, token, pid))
+}
+
+/// 프로세스가 살아 있는지. 알 수 없으면 `None`
+fn process_alive(pid: u32) -> Option<bool> {
+    if cfg!(target_os = "linux") {
+        Some(Path::new("/proc").join(pid.to_string()).exists())
+    } else if cfg!(unix) {
+        let status = std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(std::process::Stdio::null())
+            .status();
+        status.ok().map(|status| status.success())
+    } else {
+        None
+    }
+}
+
+/// 실행 중인 인스턴스에 인자를 넘긴다. 응답까지 받아야 성공
+fn forward(port: u16, token: &str, launch: &ForwardedLaunch, timeout: Duration) -> io::Result<()> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let message = Message { token: token.to_string(), launch: launch.clone() };
+    let mut line = serde_json::to_string(&message).map_err(io::Error::other)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    if reply.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "running instance rejected the launch"))
+    }
+}
+
+/// 주 인스턴스가 되거나, 이미 있으면 `launch`를 넘긴다.
+/// 살아 있는 주 인스턴스가 끝내 답하지 않으면 `TimedOut` (이 프로세스는 주 인스턴스가 되면 안 된다)
+pub fn acquire(lock_path: &Path, launch: ForwardedLaunch) -> io::Result<Startup> {
+    acquire_with(lock_path, launch, IO_TIMEOUT)
+}
+
+fn acquire_with(lock_path: &Path, launch: ForwardedLaunch, timeout: Duration) -> io::Result<Startup> {
+    if let Some(dir) = lock_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut busy = false;
+    for _ in 0..ACQUIRE_ATTEMPTS {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let port = listener.local_addr()?.port();
+        let token = random_token();
+        match create_lock(lock_path, &format!("{}\n{}\n{}\n", port, token, std::process::id())) {
+            Ok(()) => {
+                return Ok(Startup::Primary(InstanceGuard {
+                    lock_path: lock_path.to_path_buf(),
+                    token,
+                    listener: Some(listener),
+                }))
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        drop(listener);
+
+        let Some((port, token, pid)) = read_lock(lock_path) else {
+            // 읽을 수 없는 잠금 파일. 지우고 다시
+            let _ = std::fs::remove_file(lock_path);
+            continue;
+        };
+        let error = match forward(port, &token, &launch, timeout) {
+            Ok(()) => return Ok(Startup::Forwarded),
+            Err(e) => e,
+        };
+        // 주인이 없는 잠금 파일 (비정상 종료): 포트에 아무도 없거나 PID가 없다. 시간 초과만으로는 지우지 않는다
+        let stale = error.kind() == io::ErrorKind::ConnectionRefused || pid.and_then(process_alive) == Some(false);
+        if stale {
+            let _ = std::fs::remove_file(lock_path);
+        } else {
+            busy = true;
+        }
+    }
+    if busy {
+        return Err(io::Error::new(io::ErrorKind::TimedOut, "the running instance did not answer"));
+    }
+    Err(io::Error::new(io::ErrorKind::WouldBlock, "could not acquire the single-instance lock"))
+}
+
+fn handle_connection(stream: TcpStream, token: &str) -> io::Result<ForwardedLaunch> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream.try_clone()?.take(MAX_MESSAGE)).read_line(&mut line)?;
+    let mut stream = stream;
+    let message: Message = match serde_json::from_str(line.trim_end()) {
+        Ok(message) => message,
+        Err(e) => {
+            let _ = stream.write_all(b"error\n");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+    };
+    if !constant_time_eq(message.token.as_bytes(), token.as_bytes()) {
+        let _ = stream.write_all(b"denied\n");
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "bad instance token"));
+    }
+    stream.write_all(b"ok\n")?;
+    Ok(message.launch)
+}
+
+impl InstanceGuard {
+    /// 두 번째 실행을 받을 스레드를 띄운다. `on_launch`는 리스너 스레드에서 호출된다
+    pub fn listen<F>(&mut self, on_launch: F)
+    where
+        F: Fn(ForwardedLaunch) + Send + 'static,
+    {
+        let Some(listener) = self.listener.take() else { return };
+        let token = self.token.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                match handle_connection(stream, &token) {
+                    Ok(launch) => on_launch(launch),
+                    Err(e) => eprintln!("single-instance: 요청 무시: {}", e),
+                }
+            }
+        });
+    }
+}
+
+impl Drop for InstanceGuard {
+    fn drop(&mut self) {
+        // 다른 인스턴스가 이미 새로 잡은 잠금이면 건드리지 않는다
+        if read_lock(&self.lock_path).is_some_and(|(_, token, _)| token == self.token) {
+            let _ = std::fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn temp_lock() -> PathBuf {
+        std::env::temp_dir().join(format!("noah-si-{}", random_token())).join("app.instance")
+    }
+
+    fn launch(args: &[&str]) -> ForwardedLaunch {
+        ForwardedLaunch { args: args.iter().map(|a| a.to_string()).collect(), cwd: Some("/home/me".into()) }
+    }
+
+    #[test]
+    fn second_launch_is_forwarded_to_primary() {
+        let lock = temp_lock();
+        let Startup::Primary(mut guard) = acquire(&lock, launch(&["noah"])).unwrap() else {
+            panic!("first launch must be primary")
+        };
+        let (tx, rx) = mpsc::channel();
+        guard.listen(move |l| {
+            let _ = tx.send(l);
+        });
+
+        let second = acquire(&lock, launch(&["noah", "noah://task/t1"])).unwrap();
+        assert!(matches!(second, Startup::Forwarded));
+        let got = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(got, launch(&["noah", "noah://task/t1"]));
+
+        drop(guard);
+        assert!(!lock.exists());
+        let _ = std::fs::remove_dir_all(lock.parent().unwrap());
+    }
+
+    #[test]
+    fn stale_lock_is_replaced() {
+        let lock = temp_lock();
+        std::fs::create_dir_all(lock.parent().unwrap()).unwrap();
+        // 닫힌 포트를 가리키는 잠금 파일
+        let dead = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = dead.local_addr().unwrap().port();
+        drop(dead);
+        std::fs::write(&lock, format!("{}\ndeadbeef\n", port)).unwrap();
+
+        let startup = acquire(&lock, launch(&["noah"])).unwrap();
+        assert!(matches!(startup, Startup::Primary(_)));
+        let (_, token, pid) = read_lock(&lock).unwrap();
+        assert_ne!(token, "deadbeef");
+        assert_eq!(pid, Some(std::process::id()));
+        drop(startup);
+        let _ = std::fs::remove_dir_all(lock.parent().unwrap());
+    }
+
+    #[test]
+    fn requests_without_the_token_are_rejected() {
+        let lock = temp_lock();
+        let Startup::Primary(mut guard) = acquire(&lock, launch(&["noah"])).unwrap() else {
+            panic!("first launch must be primary")
+        };
+        let (tx, rx) = mpsc::channel();
+        guard.listen(move |l| {
+            let _ = tx.send(l);
+        });
+        let (port, _, _) = read_lock(&lock).unwrap();
+
+        assert!(forward(port, "wrong-token", &launch(&["noah", "noah://note/n1"]), IO_TIMEOUT).is_err());
+        // 브라우저가 localhost로 보내는 요청 같은 것
+        let mut raw = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        raw.write_all(b"GET / HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n").unwrap();
+        let mut reply = String::new();
+        raw.set_read_timeout(Some(IO_TIMEOUT)).unwrap();
+        let _ = raw.read_to_string(&mut reply);
+        assert!(!reply.starts_with("ok"));
+        assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+
+        // 잠금 파일 주인은 그대로
+        assert!(matches!(acquire(&lock, launch(&["noah"])).unwrap(), Startup::Forwarded));
+        drop(guard);
+        let _ = std::fs::remove_dir_all(lock.parent().unwrap());
+    }
+
+    #[test]
+    fn busy_primary_keeps_its_lock() {
+        let lock = temp_lock();
+        std::fs::create_dir_all(lock.parent().unwrap()).unwrap();
+        // 접속은 받지만 답하지 않는 살아 있는 주인
+        let busy = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = busy.local_addr().unwrap().port();
+        std::fs::write(&lock, format!("{}\nbusy\n{}\n", port, std::process::id())).unwrap();
+
+        let err = acquire_with(&lock, launch(&["noah"]), Duration::from_millis(100)).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(read_lock(&lock).unwrap().1, "busy");
+        drop(busy);
+        let _ = std::fs::remove_dir_all(lock.parent().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lock_of_a_dead_process_is_replaced_even_if_the_port_answers() {
+        let lock = temp_lock();
+        std::fs::create_dir_all(lock.parent().unwrap()).unwrap();
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        // 포트는 다른 무언가가 다시 쓰고 있다
+        let other = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = other.local_addr().unwrap().port();
+        std::fs::write(&lock, format!("{}\nold\n{}\n", port, dead_pid)).unwrap();
+
+        let startup = acquire_with(&lock, launch(&["noah"]), Duration::from_millis(100)).unwrap();
+        assert!(matches!(startup, Startup::Primary(_)));
+        assert_ne!(read_lock(&lock).unwrap().1, "old");
+        drop(startup);
+        let _ = std::fs::remove_dir_all(lock.parent().unwrap());
+    }
+}
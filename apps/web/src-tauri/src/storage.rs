@@ -0,0 +1,1543 @@
+//! 로컬 SQLite 저장소 (앱 데이터 폴더의 `noah.db`).
+//!
+//! 테이블은 `models`의 타입을 컬럼 단위로 옮긴 것이고, 배열/객체 필드는 JSON 텍스트로 둔다.
+//! 문서는 Firestore와 같은 `users/{uid}/{collection}/{id}` 구조를 `(uid, id)` 키로 표현한다.
+//! 스키마 변경은 `MIGRATIONS`에 추가한다 (`PRAGMA user_version`으로 적용 여부를 기록).
+//! 앱에서 일어난 변경(`insert`/`update`/`delete`)은 같은 트랜잭션에서 `outbox`에도 기록된다.
+//! 원격에 아직 반영되지 않은 문서에 원격 스냅샷이 오면 `merge`로 합친다.
+//! 할 일/노트/마인드맵은 쓸 때마다 같은 트랜잭션에서 검색 색인(`search`)도 고친다.
+//! 예약된 알림(`reminders`)과 다운로드 대기열(`downloads`)도 같은 DB에 둔다.
+
+use crate::merge::{self, Conflict, HlcClock, Resolution, FIELD_CLOCKS};
+use crate::models::{FolderData, ListData, MindMapData, NoteData, OccurrenceOverride, TaskData};
+use crate::outbox::{self, Op};
+use crate::recurrence;
+use crate::reminders;
+use crate::search::{self, SearchHit};
+use crate::token_store::now_ms;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+pub const DB_FILE: &str = "noah.db";
+
+const MIGRATIONS: &[&str] = &[
+    // 1: Firestore 컬렉션 미러
+    r#"
+    CREATE TABLE tasks (
+        uid TEXT NOT NULL,
+        id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        status TEXT NOT NULL,
+        priority TEXT NOT NULL,
+        starred INTEGER NOT NULL,
+        list_id TEXT NOT NULL,
+        due_date TEXT,
+        my_day INTEGER NOT NULL,
+        sub_tasks TEXT NOT NULL,
+        reminder TEXT,
+        memo TEXT,
+        attachments TEXT NOT NULL,
+        tags TEXT NOT NULL,
+        sort_order REAL,
+        completed_date TEXT,
+        created_date TEXT,
+        linked_note_ids TEXT NOT NULL,
+        recurrence_rule TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (uid, id)
+    );
+    CREATE INDEX tasks_my_day ON tasks (uid, my_day);
+    CREATE INDEX tasks_list ON tasks (uid, list_id);
+
+    CREATE TABLE notes (
+        uid TEXT NOT NULL,
+        id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        icon TEXT NOT NULL,
+        blocks TEXT NOT NULL,
+        pinned INTEGER NOT NULL,
+        starred INTEGER NOT NULL,
+        tags TEXT NOT NULL,
+        folder_id TEXT,
+        linked_task_id TEXT,
+        linked_task_ids TEXT NOT NULL,
+        deleted INTEGER NOT NULL,
+        deleted_at TEXT,
+        original_folder_id TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (uid, id)
+    );
+    CREATE INDEX notes_folder ON notes (uid, folder_id);
+
+    CREATE TABLE lists (
+        uid TEXT NOT NULL,
+        id TEXT NOT NULL,
+        label TEXT NOT NULL,
+        color TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (uid, id)
+    );
+
+    CREATE TABLE folders (
+        uid TEXT NOT NULL,
+        id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        color TEXT NOT NULL,
+        icon TEXT NOT NULL,
+        parent_id TEXT,
+        deleted INTEGER NOT NULL,
+        deleted_at TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (uid, id)
+    );
+
+    CREATE TABLE mindmaps (
+        uid TEXT NOT NULL,
+        id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        nodes TEXT NOT NULL,
+        edges TEXT NOT NULL,
+        viewport_x REAL NOT NULL,
+        viewport_y REAL NOT NULL,
+        zoom REAL NOT NULL,
+        starred INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (uid, id)
+    );
+    "#,
+    // 2: 원격 반영 대기열
+    r#"
+    CREATE TABLE outbox (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        id TEXT NOT NULL UNIQUE,
+        uid TEXT NOT NULL,
+        collection TEXT NOT NULL,
+        doc_id TEXT NOT NULL,
+        op TEXT NOT NULL,
+        fields TEXT,
+        created_at INTEGER NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        last_error TEXT,
+        failed INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX outbox_doc ON outbox (uid, collection, doc_id);
+    "#,
+    // 3: 동시 편집 병합 (필드 시계, 기준본, 충돌)
+    r#"
+    ALTER TABLE tasks ADD COLUMN field_clocks TEXT;
+
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    CREATE TABLE shadows (
+        uid TEXT NOT NULL,
+        collection TEXT NOT NULL,
+        id TEXT NOT NULL,
+        doc TEXT NOT NULL,
+        PRIMARY KEY (uid, collection, id)
+    );
+
+    CREATE TABLE conflicts (
+        id TEXT PRIMARY KEY,
+        uid TEXT NOT NULL,
+        note_id TEXT NOT NULL,
+        block_id TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        base TEXT,
+        local TEXT,
+        remote TEXT,
+        created_at INTEGER NOT NULL,
+        UNIQUE (uid, note_id, block_id)
+    );
+    "#,
+    // 4: 전문 검색 색인 (`search`)
+    r#"
+    CREATE TABLE search_entries (
+        id INTEGER PRIMARY KEY,
+        uid TEXT NOT NULL,
+        collection TEXT NOT NULL,
+        doc_id TEXT NOT NULL,
+        field TEXT NOT NULL,
+        sub_id TEXT,
+        text TEXT NOT NULL,
+        tokens TEXT NOT NULL
+    );
+    CREATE INDEX search_entries_doc ON search_entries (uid, collection, doc_id);
+
+    CREATE VIRTUAL TABLE search_index USING fts5(
+        tokens,
+        content = 'search_entries',
+        content_rowid = 'id',
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+    CREATE VIRTUAL TABLE search_vocab USING fts5vocab(search_index, row);
+
+    CREATE TRIGGER search_entries_insert AFTER INSERT ON search_entries BEGIN
+        INSERT INTO search_index (rowid, tokens) VALUES (new.id, new.tokens);
+    END;
+    CREATE TRIGGER search_entries_delete AFTER DELETE ON search_entries BEGIN
+        INSERT INTO search_index (search_index, rowid, tokens) VALUES ('delete', old.id, old.tokens);
+    END;
+
+    CREATE TABLE search_docs (
+        uid TEXT NOT NULL,
+        collection TEXT NOT NULL,
+        doc_id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        source TEXT NOT NULL,
+        PRIMARY KEY (uid, collection, doc_id)
+    );
+    "#,
+    // 5: 알림 예약 (`reminders`)
+    r#"
+    CREATE TABLE reminders (
+        task_id TEXT PRIMARY KEY,
+        uid TEXT,
+        fire_at INTEGER NOT NULL,
+        payload TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX reminders_fire_at ON reminders (fire_at);
+    "#,
+    // 6: 아침 일정 요약에 쓰는 타임박스 (웹뷰가 넣어 둔 것, `agenda`)
+    r#"
+    CREATE TABLE timeboxes (
+        uid TEXT NOT NULL,
+        date TEXT NOT NULL,
+        slots TEXT NOT NULL,
+        PRIMARY KEY (uid, date)
+    );
+    "#,
+    // 7: 반복 할 일의 다음 회차가 가리키는 첫 할 일
+    r#"
+    ALTER TABLE tasks ADD COLUMN recurrence_parent_id TEXT;
+    "#,
+    // 8: 반복 회차의 원래 날짜 (RECURRENCE-ID)
+    r#"
+    ALTER TABLE tasks ADD COLUMN recurrence_id TEXT;
+    "#,
+    // 9: 미디어 다운로드 대기열 (`downloads`)
+    r#"
+    CREATE TABLE downloads (
+        id TEXT PRIMARY KEY,
+        request TEXT NOT NULL,
+        state TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        error TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX downloads_state ON downloads (state, created_at);
+    "#,
+    // 10: 받은 파일과 크기 (다운로드 기록)
+    r#"
+    ALTER TABLE downloads ADD COLUMN file TEXT;
+    ALTER TABLE downloads ADD COLUMN size INTEGER;
+    "#,
+];
+
+/// 이 버전부터 검색 색인이 있다. 그 전에 저장된 문서는 열 때 한 번 색인한다
+const SEARCH_SINCE: usize = 4;
+
+#[derive(Debug)]
+pub enum StorageError {
+    Sqlite(rusqlite::Error),
+    NotFound { collection: Collection, id: String },
+    /// 문서가 스키마(models 타입)에 맞지 않음
+    Invalid(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Sqlite(e) => write!(f, "local database error: {}", e),
+            StorageError::NotFound { collection, id } => write!(f, "not_found: {}/{}", collection.table(), id),
+            StorageError::Invalid(msg) => write!(f, "invalid document: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
+
+/// 로컬에 미러링하는 컬렉션
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Collection {
+    Tasks,
+    Notes,
+    Lists,
+    Folders,
+    Mindmaps,
+}
+
+impl Collection {
+    /// 테이블 이름 = Firestore 컬렉션 이름
+    pub fn table(self) -> &'static str {
+        match self {
+            Collection::Tasks => "tasks",
+            Collection::Notes => "notes",
+            Collection::Lists => "lists",
+            Collection::Folders => "folders",
+            Collection::Mindmaps => "mindmaps",
+        }
+    }
+
+    /// Firestore 문서에 `updatedAt`을 두는 컬렉션 (lists/folders는 `createdAt`만 있다)
+    pub fn has_updated_at(self) -> bool {
+        matches!(self, Collection::Tasks | Collection::Notes | Collection::Mindmaps)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Text,
+    Real,
+    Bool,
+    /// 배열/객체. JSON 텍스트로 저장
+    Json,
+}
+
+/// 테이블 컬럼 하나와 대응하는 문서 필드
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub name: &'static str,
+    pub field: &'static str,
+    pub kind: Kind,
+}
+
+const fn col(name: &'static str, field: &'static str, kind: Kind) -> Column {
+    Column { name, field, kind }
+}
+
+/// 로컬 테이블 하나에 대응하는 문서 타입. `id`/`createdAt`/`updatedAt`은 공통 컬럼이라 `COLUMNS`에 없다
+pub trait Document: Serialize + DeserializeOwned {
+    const COLLECTION: Collection;
+    const COLUMNS: &'static [Column];
+    /// 목록 조회 순서 (`lib/firestore.ts`의 쿼리와 같게)
+    const ORDER_BY: &'static str = "created_at DESC, id";
+    /// 필드마다 HLC를 `fieldClocks`에 기록하는가 (`merge::merge_task`)
+    const FIELD_CLOCKS: bool = false;
+}
+
+impl Document for TaskData {
+    const COLLECTION: Collection = Collection::Tasks;
+    const COLUMNS: &'static [Column] = &[
+        col("title", "title", Kind::Text),
+        col("status", "status", Kind::Text),
+        col("priority", "priority", Kind::Text),
+        col("starred", "starred", Kind::Bool),
+        col("list_id", "listId", Kind::Text),
+        col("due_date", "dueDate", Kind::Text),
+        col("my_day", "myDay", Kind::Bool),
+        col("sub_tasks", "subTasks", Kind::Json),
+        col("reminder", "reminder", Kind::Text),
+        col("memo", "memo", Kind::Text),
+        col("attachments", "attachments", Kind::Json),
+        col("tags", "tags", Kind::Json),
+        col("sort_order", "order", Kind::Real),
+        col("completed_date", "completedDate", Kind::Text),
+        col("created_date", "createdDate", Kind::Text),
+        col("linked_note_ids", "linkedNoteIds", Kind::Json),
+        col("recurrence_rule", "recurrence_rule", Kind::Json),
+        col("recurrence_parent_id", "recurrence_parent_id", Kind::Text),
+        col("recurrence_id", "recurrence_id", Kind::Text),
+        col("field_clocks", FIELD_CLOCKS, Kind::Json),
+    ];
+    const FIELD_CLOCKS: bool = true;
+}
+
+impl Document for NoteData {
+    const COLLECTION: Collection = Collection::Notes;
+    const COLUMNS: &'static [Column] = &[
+        col("title", "title", Kind::Text),
+        col("icon", "icon", Kind::Text),
+        col("blocks", "blocks", Kind::Json),
+        col("pinned", "pinned", Kind::Bool),
+        col("starred", "starred", Kind::Bool),
+        col("tags", "tags", Kind::Json),
+        col("folder_id", "folderId", Kind::Text),
+        col("linked_task_id", "linkedTaskId", Kind::Text),
+        col("linked_task_ids", "linkedTaskIds", Kind::Json),
+        col("deleted", "deleted", Kind::Bool),
+        col("deleted_at", "deletedAt", Kind::Text),
+        col("original_folder_id", "originalFolderId", Kind::Text),
+    ];
+}
+
+impl Document for ListData {
+    const COLLECTION: Collection = Collection::Lists;
+    const COLUMNS: &'static [Column] = &[col("label", "label", Kind::Text), col("color", "color", Kind::Text)];
+    const ORDER_BY: &'static str = "created_at, id";
+}
+
+impl Document for FolderData {
+    const COLLECTION: Collection = Collection::Folders;
+    const COLUMNS: &'static [Column] = &[
+        col("name", "name", Kind::Text),
+        col("color", "color", Kind::Text),
+        col("icon", "icon", Kind::Text),
+        col("parent_id", "parentId", Kind::Text),
+        col("deleted", "deleted", Kind::Bool),
+        col("deleted_at", "deletedAt", Kind::Text),
+    ];
+    const ORDER_BY: &'static str = "created_at, id";
+}
+
+impl Document for MindMapData {
+    const COLLECTION: Collection = Collection::Mindmaps;
+    const COLUMNS: &'static [Column] = &[
+        col("title", "title", Kind::Text),
+        col("nodes", "nodes", Kind::Json),
+        col("edges", "edges", Kind::Json),
+        col("viewport_x", "viewportX", Kind::Real),
+        col("viewport_y", "viewportY", Kind::Real),
+        col("zoom", "zoom", Kind::Real),
+        col("starred", "starred", Kind::Bool),
+    ];
+}
+
+/// Firestore 자동 ID와 같은 형식 (영숫자 20자). 나중에 그대로 원격 문서 ID로 쓴다
+pub fn new_document_id() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut id = String::with_capacity(20);
+    while id.len() < 20 {
+        let mut bytes = [0u8; 32];
+        getrandom::getrandom(&mut bytes).expect("OS random source unavailable");
+        // 62의 배수(248) 이상은 버려서 치우침 없이
+        for b in bytes.into_iter().filter(|b| *b < 248) {
+            if id.len() == 20 {
+                break;
+            }
+            id.push(ALPHABET[(b % 62) as usize] as char);
+        }
+    }
+    id
+}
+
+fn to_sql(value: Option<&Value>, column: &Column) -> Result<SqlValue, StorageError> {
+    let invalid = || StorageError::Invalid(format!("field `{}` has the wrong type", column.field));
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return Ok(SqlValue::Null);
+    };
+    Ok(match column.kind {
+        Kind::Text => SqlValue::Text(value.as_str().ok_or_else(invalid)?.to_string()),
+        Kind::Real => SqlValue::Real(value.as_f64().ok_or_else(invalid)?),
+        Kind::Bool => SqlValue::Integer(value.as_bool().ok_or_else(invalid)? as i64),
+        Kind::Json => SqlValue::Text(value.to_string()),
+    })
+}
+
+fn from_sql(value: SqlValue, kind: Kind) -> Value {
+    match (value, kind) {
+        (SqlValue::Null, _) => Value::Null,
+        (SqlValue::Text(s), Kind::Json) => serde_json::from_str(&s).unwrap_or(Value::Null),
+        (SqlValue::Text(s), _) => Value::String(s),
+        (SqlValue::Integer(n), Kind::Bool) => Value::Bool(n != 0),
+        (SqlValue::Integer(n), Kind::Real) => serde_json::Number::from_f64(n as f64).map_or(Value::Null, Value::Number),
+        (SqlValue::Integer(n), _) => Value::from(n),
+        (SqlValue::Real(f), _) => serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number),
+        (SqlValue::Blob(_), _) => Value::Null,
+    }
+}
+
+fn select_sql<T: Document>(filter: &str) -> String {
+    let columns: Vec<&str> = T::COLUMNS.iter().map(|c| c.name).collect();
+    format!(
+        "SELECT id, created_at, updated_at, {} FROM {} WHERE uid = ?1{} ORDER BY {}",
+        columns.join(", "),
+        T::COLLECTION.table(),
+        filter,
+        T::ORDER_BY
+    )
+}
+
+/// 행을 문서 JSON으로 (필드 이름은 Firestore 그대로)
+fn row_to_value<T: Document>(row: &rusqlite::Row<'_>) -> rusqlite::Result<Value> {
+    let mut doc = Map::new();
+    doc.insert("id".into(), Value::String(row.get(0)?));
+    doc.insert("createdAt".into(), Value::from(row.get::<_, i64>(1)?));
+    doc.insert("updatedAt".into(), Value::from(row.get::<_, i64>(2)?));
+    for (i, column) in T::COLUMNS.iter().enumerate() {
+        doc.insert(column.field.into(), from_sql(row.get(i + 3)?, column.kind));
+    }
+    Ok(Value::Object(doc))
+}
+
+fn from_value<T: Document>(value: Value) -> Result<T, StorageError> {
+    serde_json::from_value(value).map_err(|e| StorageError::Invalid(e.to_string()))
+}
+
+fn to_object<T: Document>(doc: &T) -> Result<Map<String, Value>, StorageError> {
+    match serde_json::to_value(doc).map_err(|e| StorageError::Invalid(e.to_string()))? {
+        Value::Object(map) => Ok(map),
+        _ => Err(StorageError::Invalid("document must be an object".into())),
+    }
+}
+
+fn to_field<T: Serialize>(value: &T) -> Result<Value, StorageError> {
+    serde_json::to_value(value).map_err(|e| StorageError::Invalid(e.to_string()))
+}
+
+pub(crate) fn query_docs<T: Document>(
+    conn: &Connection,
+    uid: &str,
+    filter: &str,
+    args: &[SqlValue],
+) -> Result<Vec<T>, StorageError> {
+    let mut stmt = conn.prepare_cached(&select_sql::<T>(filter))?;
+    let params = std::iter::once(SqlValue::Text(uid.to_string())).chain(args.iter().cloned());
+    let rows = stmt.query_map(params_from_iter(params), |row| row_to_value::<T>(row))?;
+    rows.map(|row| from_value(row?)).collect()
+}
+
+pub(crate) fn get_doc<T: Document>(conn: &Connection, uid: &str, id: &str) -> Result<Option<T>, StorageError> {
+    let mut stmt = conn.prepare_cached(&select_sql::<T>(" AND id = ?2"))?;
+    let value = stmt.query_row([uid, id], |row| row_to_value::<T>(row)).optional()?;
+    value.map(from_value).transpose()
+}
+
+/// 문서를 그대로 쓴다 (있으면 덮어씀). `fields`는 검증된 문서의 JSON
+pub(crate) fn write_doc<T: Document>(
+    conn: &Connection,
+    uid: &str,
+    id: &str,
+    fields: &Map<String, Value>,
+    created_at: i64,
+    updated_at: i64,
+) -> Result<(), StorageError> {
+    let names: Vec<&str> = T::COLUMNS.iter().map(|c| c.name).collect();
+    let placeholders: Vec<String> = (1..=names.len() + 4).map(|i| format!("?{}", i)).collect();
+    let updates: Vec<String> = names.iter().map(|n| format!("{0} = excluded.{0}", n)).collect();
+    let sql = format!(
+        "INSERT INTO {} (uid, id, created_at, updated_at, {}) VALUES ({})
+         ON CONFLICT (uid, id) DO UPDATE SET created_at = excluded.created_at, updated_at = excluded.updated_at, {}",
+        T::COLLECTION.table(),
+        names.join(", "),
+        placeholders.join(", "),
+        updates.join(", ")
+    );
+    let mut values = vec![
+        SqlValue::Text(uid.to_string()),
+        SqlValue::Text(id.to_string()),
+        SqlValue::Integer(created_at),
+        SqlValue::Integer(updated_at),
+    ];
+    for column in T::COLUMNS {
+        values.push(to_sql(fields.get(column.field), column)?);
+    }
+    conn.prepare_cached(&sql)?.execute(params_from_iter(values))?;
+    if search::indexes(T::COLLECTION) {
+        search::index_doc(conn, uid, T::COLLECTION, id, fields)?;
+    }
+    Ok(())
+}
+
+/// 문서의 `id` 필드
+pub fn document_id<T: Document>(doc: &T) -> Option<String> {
+    to_object(doc).ok()?.get("id").and_then(Value::as_str).map(str::to_string)
+}
+
+fn timestamp(fields: &Map<String, Value>, key: &str) -> Option<i64> {
+    fields.get(key).and_then(Value::as_i64)
+}
+
+/// 검증 후 저장하고 저장된 문서를 돌려준다. `createdAt`/`updatedAt`이 없으면 지금 시각
+pub(crate) fn put_doc<T: Document>(conn: &Connection, uid: &str, id: &str, doc: &T) -> Result<T, StorageError> {
+    let mut fields = to_object(doc)?;
+    let now = now_ms() as i64;
+    let created_at = timestamp(&fields, "createdAt").unwrap_or(now);
+    let updated_at = timestamp(&fields, "updatedAt").unwrap_or(now);
+    fields.insert("id".into(), Value::String(id.to_string()));
+    write_doc::<T>(conn, uid, id, &fields, created_at, updated_at)?;
+    get_doc(conn, uid, id)?.ok_or_else(|| StorageError::NotFound { collection: T::COLLECTION, id: id.into() })
+}
+
+/// `updateDoc`처럼 최상위 필드만 바꾼다. 결과가 스키마에 맞지 않으면 아무것도 쓰지 않는다
+pub(crate) fn patch_doc<T: Document>(
+    conn: &Connection,
+    uid: &str,
+    id: &str,
+    patch: &Map<String, Value>,
+    updated_at: i64,
+) -> Result<T, StorageError> {
+    let current: T =
+        get_doc(conn, uid, id)?.ok_or_else(|| StorageError::NotFound { collection: T::COLLECTION, id: id.into() })?;
+    let mut fields = to_object(&current)?;
+    for (key, value) in patch {
+        if !matches!(key.as_str(), "id" | "createdAt" | "updatedAt") {
+            fields.insert(key.clone(), value.clone());
+        }
+    }
+    let created_at = timestamp(&fields, "createdAt").unwrap_or(updated_at);
+    let merged: T = from_value(Value::Object(fields))?;
+    write_doc::<T>(conn, uid, id, &to_object(&merged)?, created_at, updated_at)?;
+    get_doc(conn, uid, id)?.ok_or_else(|| StorageError::NotFound { collection: T::COLLECTION, id: id.into() })
+}
+
+pub(crate) fn delete_doc(conn: &Connection, collection: Collection, uid: &str, id: &str) -> Result<bool, StorageError> {
+    let sql = format!("DELETE FROM {} WHERE uid = ?1 AND id = ?2", collection.table());
+    let deleted = conn.prepare_cached(&sql)?.execute([uid, id])? > 0;
+    if search::indexes(collection) {
+        search::remove_doc(conn, collection, uid, id)?;
+    }
+    // 지운 할 일의 알림이 울리면 완료 버튼이 not_found로 실패한다
+    if collection == Collection::Tasks {
+        reminders::remove(conn, id)?;
+    }
+    Ok(deleted)
+}
+
+/// 스키마를 최신으로 올리고 올리기 전 버전을 돌려준다
+fn migrate(conn: &mut Connection) -> Result<usize, StorageError> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(version)
+}
+
+/// 테이블의 모든 문서를 색인한다
+fn index_all<T: Document>(conn: &Connection) -> Result<(), StorageError> {
+    let sql = format!("SELECT DISTINCT uid FROM {}", T::COLLECTION.table());
+    let uids: Vec<String> = conn.prepare(&sql)?.query_map([], |row| row.get(0))?.collect::<Result<_, _>>()?;
+    for uid in uids {
+        for doc in query_docs::<T>(conn, &uid, "", &[])? {
+            let fields = to_object(&doc)?;
+            let id = fields.get("id").and_then(Value::as_str).unwrap_or_default();
+            search::index_doc(conn, &uid, T::COLLECTION, id, &fields)?;
+        }
+    }
+    Ok(())
+}
+
+/// `LocalDb::restore` 결과
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreCounts {
+    /// 로컬에 없어서 되살린 문서
+    pub restored: usize,
+    /// 백업 때의 내용으로 되돌린 문서
+    pub reverted: usize,
+    /// 백업에 없어서 지운 문서
+    pub removed: usize,
+}
+
+impl std::ops::AddAssign for RestoreCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.restored += other.restored;
+        self.reverted += other.reverted;
+        self.removed += other.removed;
+    }
+}
+
+/// `LocalDb::restore` 트랜잭션 안에서 컬렉션을 하나씩 되살린다
+pub struct Restorer<'a> {
+    db: &'a LocalDb,
+    tx: &'a Connection,
+    uid: &'a str,
+    revert: bool,
+    remove_others: bool,
+    now: i64,
+    counts: RestoreCounts,
+    changed: Vec<Collection>,
+}
+
+impl Restorer<'_> {
+    /// 원격에도 반영되도록 outbox에 문서 전체를 기록한다 (`createdAt`은 백업 그대로)
+    pub fn docs<T: Document>(&mut self, docs: &[T]) -> Result<RestoreCounts, StorageError> {
+        let (tx, uid, now) = (self.tx, self.uid, self.now);
+        let mut counts = RestoreCounts::default();
+        let mut kept = std::collections::HashSet::new();
+        for doc in docs {
+            let mut fields = to_object(doc)?;
+            let id = fields
+                .get("id")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| StorageError::Invalid("backup document without id".into()))?;
+            kept.insert(id.clone());
+            let current = get_doc::<T>(tx, uid, &id)?.map(|d| to_object(&d)).transpose()?;
+            match &current {
+                None => counts.restored += 1,
+                Some(current) if self.revert && !same_content(current, &fields) => counts.reverted += 1,
+                Some(_) => continue,
+            }
+            fields.insert("updatedAt".into(), Value::from(now));
+            if T::FIELD_CLOCKS {
+                fields.remove(FIELD_CLOCKS);
+                merge::stamp(&mut fields, T::COLUMNS.iter().map(|c| c.field), &self.db.clock.now());
+            }
+            let saved = to_object(&put_doc::<T>(tx, uid, &id, &from_value::<T>(Value::Object(fields))?)?)?;
+            let mut remote = saved;
+            remote.remove("id");
+            if !T::COLLECTION.has_updated_at() {
+                remote.remove("updatedAt");
+            }
+            outbox::enqueue(tx, uid, T::COLLECTION, &id, Op::Set, Some(&Value::Object(remote)), now)?;
+        }
+        if self.remove_others {
+            for doc in query_docs::<T>(tx, uid, "", &[])? {
+                let Some(id) = document_id(&doc).filter(|id| !kept.contains(id)) else { continue };
+                delete_doc(tx, T::COLLECTION, uid, &id)?;
+                outbox::enqueue(tx, uid, T::COLLECTION, &id, Op::Delete, None, now)?;
+                counts.removed += 1;
+            }
+        }
+        if counts != RestoreCounts::default() {
+            self.changed.push(T::COLLECTION);
+        }
+        self.counts += counts;
+        Ok(counts)
+    }
+}
+
+/// `override_occurrence` 결과. 네이티브 알림을 맞추는 데 쓴다
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum OverrideOutcome {
+    /// 아직 만들지 않은 회차라 규칙에만 적었다
+    Recorded { task: TaskData },
+    /// 이 할 일의 회차를 건너뛰어 지우고 다음 회차를 만들었다 (끝난 반복이면 없음)
+    Skipped { next: Option<TaskData> },
+    /// 이 할 일의 날짜/제목/우선순위를 바꿨다
+    Updated { task: TaskData },
+}
+
+/// 비교에서 빼는 필드 (쓸 때마다 바뀌는 것)
+fn same_content(a: &Map<String, Value>, b: &Map<String, Value>) -> bool {
+    let strip = |m: &Map<String, Value>| -> Map<String, Value> {
+        m.iter().filter(|(k, _)| !matches!(k.as_str(), "updatedAt" | FIELD_CLOCKS)).map(|(k, v)| (k.clone(), v.clone())).collect()
+    };
+    strip(a) == strip(b)
+}
+
+type ChangeListener = Box<dyn Fn(&str, Collection) + Send + Sync>;
+
+pub struct LocalDb {
+    conn: Mutex<Connection>,
+    on_change: Mutex<Option<ChangeListener>>,
+    clock: HlcClock,
+}
+
+impl LocalDb {
+    /// `dir/noah.db`를 열고 스키마를 최신으로 올린다
+    pub fn open(dir: &Path) -> Result<Self, StorageError> {
+        std::fs::create_dir_all(dir).map_err(|e| StorageError::Invalid(format!("{}: {}", dir.display(), e)))?;
+        let conn = Connection::open(dir.join(DB_FILE))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Self::init(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self, StorageError> {
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        let from = migrate(&mut conn)?;
+        if from > 0 && from < SEARCH_SINCE {
+            let tx = conn.transaction()?;
+            index_all::<TaskData>(&tx)?;
+            index_all::<NoteData>(&tx)?;
+            index_all::<MindMapData>(&tx)?;
+            tx.commit()?;
+        }
+        let clock = HlcClock::new(merge::node_id(&conn)?);
+        Ok(LocalDb { conn: Mutex::new(conn), on_change: Mutex::new(None), clock })
+    }
+
+    pub(crate) fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 앱에서 일어난 변경(`insert`/`update`/`delete`)이 커밋된 뒤 호출된다. 원격 스냅샷 반영에는 호출되지 않는다
+    pub fn on_change<F>(&self, listener: F)
+    where
+        F: Fn(&str, Collection) + Send + Sync + 'static,
+    {
+        *self.on_change.lock().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(listener));
+    }
+
+    fn changed(&self, uid: &str, collection: Collection) {
+        if let Some(listener) = self.on_change.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            listener(uid, collection);
+        }
+    }
+
+    pub fn list<T: Document>(&self, uid: &str) -> Result<Vec<T>, StorageError> {
+        query_docs(&self.conn(), uid, "", &[])
+    }
+
+    pub fn get<T: Document>(&self, uid: &str, id: &str) -> Result<Option<T>, StorageError> {
+        get_doc(&self.conn(), uid, id)
+    }
+
+    /// `addDoc`. 문서에 id가 없으면 새로 만든다. 생성/수정 시각은 지금
+    pub fn insert<T: Document>(&self, uid: &str, doc: &T) -> Result<T, StorageError> {
+        let saved = {
+            let mut conn = self.conn();
+            let tx = conn.transaction()?;
+            let saved = self.insert_in(&tx, uid, doc)?;
+            tx.commit()?;
+            saved
+        };
+        self.changed(uid, T::COLLECTION);
+        Ok(saved)
+    }
+
+    fn insert_in<T: Document>(&self, tx: &Connection, uid: &str, doc: &T) -> Result<T, StorageError> {
+        let mut fields = to_object(doc)?;
+        let id = fields.get("id").and_then(Value::as_str).map(str::to_string).unwrap_or_else(new_document_id);
+        fields.remove("createdAt");
+        fields.remove("updatedAt");
+        if T::FIELD_CLOCKS {
+            fields.remove(FIELD_CLOCKS);
+            merge::stamp(&mut fields, T::COLUMNS.iter().map(|c| c.field), &self.clock.now());
+        }
+        let doc = from_value::<T>(Value::Object(fields))?;
+        let saved = put_doc::<T>(tx, uid, &id, &doc)?;
+        let mut remote = to_object(&saved)?;
+        remote.remove("id");
+        if !T::COLLECTION.has_updated_at() {
+            remote.remove("updatedAt");
+        }
+        outbox::enqueue(tx, uid, T::COLLECTION, &id, Op::Set, Some(&Value::Object(remote)), now_ms() as i64)?;
+        Ok(saved)
+    }
+
+    /// `updateDoc`. `patch`는 바꿀 최상위 필드만 담은 객체.
+    /// 반복 할 일을 완료하면 다음 회차를 같은 트랜잭션에서 만든다
+    pub fn update<T: Document>(&self, uid: &str, id: &str, patch: &Value) -> Result<T, StorageError> {
+        let patch = patch.as_object().ok_or_else(|| StorageError::Invalid("updates must be an object".into()))?;
+        let updated = {
+            let mut conn = self.conn();
+            let tx = conn.transaction()?;
+            let updated = self.update_in(&tx, uid, id, patch, now_ms() as i64)?;
+            if T::COLLECTION == Collection::Tasks && patch.get("status").and_then(Value::as_str) == Some("completed") {
+                self.materialize_next(&tx, uid, id)?;
+            }
+            tx.commit()?;
+            updated
+        };
+        self.changed(uid, T::COLLECTION);
+        Ok(updated)
+    }
+
+    fn update_in<T: Document>(
+        &self,
+        tx: &Connection,
+        uid: &str,
+        id: &str,
+        patch: &Map<String, Value>,
+        now: i64,
+    ) -> Result<T, StorageError> {
+        let mut patch: Map<String, Value> =
+            patch.iter().filter(|(k, _)| k.as_str() != FIELD_CLOCKS).map(|(k, v)| (k.clone(), v.clone())).collect();
+        if T::FIELD_CLOCKS {
+            let current: T =
+                get_doc(tx, uid, id)?.ok_or_else(|| StorageError::NotFound { collection: T::COLLECTION, id: id.into() })?;
+            let mut clocks = to_object(&current)?;
+            clocks.retain(|k, _| k == FIELD_CLOCKS);
+            let fields: Vec<&str> = T::COLUMNS.iter().map(|c| c.field).filter(|f| patch.contains_key(*f)).collect();
+            merge::stamp(&mut clocks, fields, &self.clock.now());
+            patch.extend(clocks);
+        }
+        let updated: T = patch_doc(tx, uid, id, &patch, now)?;
+        // 원격에는 바꾼 필드만 (값은 검증을 거친 쪽으로). 스키마에 없는 필드는 버린다
+        let merged = to_object(&updated)?;
+        let mut changed: Map<String, Value> = patch
+            .keys()
+            .filter(|k| T::COLUMNS.iter().any(|c| c.field == k.as_str()))
+            .map(|k| (k.clone(), merged.get(k).cloned().unwrap_or(Value::Null)))
+            .collect();
+        if T::COLLECTION.has_updated_at() {
+            changed.insert("updatedAt".into(), Value::from(now));
+        }
+        outbox::enqueue(tx, uid, T::COLLECTION, id, Op::Update, Some(&Value::Object(changed)), now)?;
+        Ok(updated)
+    }
+
+    /// 완료한 반복 할 일의 다음 회차. id가 회차마다 정해져 있어서 다시 완료하거나 동기화로 같은 변경이 다시 와도
+    /// 하나만 생긴다. 규칙이 잘못됐으면 완료만 하고 넘어간다
+    fn materialize_next(&self, tx: &Connection, uid: &str, id: &str) -> Result<Option<TaskData>, StorageError> {
+        let Some(task) = get_doc::<TaskData>(tx, uid, id)? else {
+            return Ok(None);
+        };
+        let next = match recurrence::next_instance(&task, &chrono::Local) {
+            Ok(Some(next)) => next,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                eprintln!("다음 반복 할 일을 만들지 못함 ({}): {}", id, e);
+                return Ok(None);
+            }
+        };
+        let next_id = next.id.clone().unwrap_or_default();
+        match get_doc::<TaskData>(tx, uid, &next_id)? {
+            Some(existing) => Ok(Some(existing)),
+            None => self.insert_in(tx, uid, &next).map(Some),
+        }
+    }
+
+    /// 반복 할 일 `id`의 한 회차(`recurrence_id`, 원래 날짜 YYYY-MM-DD)에 예외를 두거나 `None`이면 지운다.
+    /// 예외는 `recurrence_rule.overrides`에 적어 다음 회차로 넘어가고 같이 동기화된다.
+    /// 이 할 일 자신의 회차면 바로 적용한다: 건너뛰면 다음 회차를 만들고 이 할 일은 지우며,
+    /// 옮기면 마감일/만든 날/알림을, 이름을 바꾸면 제목/우선순위를 바꾼다 (반복 본래 값은 `series_*`에 둔다)
+    pub fn override_occurrence(
+        &self,
+        uid: &str,
+        id: &str,
+        recurrence_id: &str,
+        value: Option<OccurrenceOverride>,
+    ) -> Result<OverrideOutcome, StorageError> {
+        let invalid = |e: recurrence::RecurrenceError| StorageError::Invalid(e.to_string());
+        let outcome = {
+            let mut conn = self.conn();
+            let tx = conn.transaction()?;
+            let task: TaskData = get_doc(&tx, uid, id)?
+                .ok_or_else(|| StorageError::NotFound { collection: Collection::Tasks, id: id.into() })?;
+            let not_recurring = || StorageError::Invalid(format!("task {} does not repeat on a date", id));
+            let mut rule = task.recurrence_rule.clone().ok_or_else(not_recurring)?;
+            let day = recurrence::task_day(&task).ok_or_else(not_recurring)?;
+            let own_date = task.recurrence_id.as_deref().and_then(|d| d.parse().ok()).unwrap_or(day);
+            let date: chrono::NaiveDate =
+                recurrence_id.parse().map_err(|_| StorageError::Invalid(format!("recurrence id {}", recurrence_id)))?;
+            // 이 할 일부터의 회차여야 한다
+            let text = recurrence::rule_text(&rule).map_err(invalid)?;
+            let series = recurrence::Recurrence::parse(&text, &own_date.format("%Y-%m-%d").to_string(), None)
+                .map_err(invalid)?;
+            if series.iter().find(|o| o.local.date() >= date).is_none_or(|o| o.local.date() != date) {
+                return Err(StorageError::Invalid(format!("{} is not an occurrence of task {}", recurrence_id, id)));
+            }
+            let own = date == own_date;
+            let value = value.unwrap_or_default();
+            let mut patch = Map::new();
+            if own {
+                let title = rule.series_title.take().unwrap_or_else(|| task.title.clone());
+                let next_title = value.title.clone().unwrap_or_else(|| title.clone());
+                if next_title != task.title {
+                    patch.insert("title".into(), Value::from(next_title));
+                }
+                rule.series_title = value.title.is_some().then_some(title);
+                let priority = rule.series_priority.take().unwrap_or(task.priority);
+                let next_priority = value.priority.unwrap_or(priority);
+                if next_priority != task.priority {
+                    patch.insert("priority".into(), to_field(&next_priority)?);
+                }
+                rule.series_priority = value.priority.is_some().then_some(priority);
+            }
+            if value == OccurrenceOverride::default() {
+                rule.overrides.remove(recurrence_id);
+            } else {
+                rule.overrides.insert(recurrence_id.to_string(), value.clone());
+            }
+            // 형식 검사
+            series.clone().with_overrides(&rule.overrides).map_err(invalid)?;
+            patch.insert("recurrence_rule".into(), to_field(&rule)?);
+            let now = now_ms() as i64;
+            if own && value.skipped {
+                self.update_in::<TaskData>(&tx, uid, id, &patch, now)?;
+                let next = self.materialize_next(&tx, uid, id)?;
+                delete_doc(&tx, Collection::Tasks, uid, id)?;
+                outbox::enqueue(&tx, uid, Collection::Tasks, id, Op::Delete, None, now)?;
+                tx.commit()?;
+                OverrideOutcome::Skipped { next }
+            } else if own {
+                let target = value.start.as_deref().and_then(|d| d.parse().ok()).unwrap_or(own_date);
+                if target != day {
+                    if let Some(due) = task.due_date.as_deref() {
+                        patch.insert("dueDate".into(), Value::from(recurrence::with_day(due, target)));
+                    }
+                    patch.insert("createdDate".into(), Value::from(target.format("%Y-%m-%d").to_string()));
+                    if let Some(reminder) = task.reminder.as_deref() {
+                        let shifted = recurrence::shift_reminder(reminder, target - day, &chrono::Local);
+                        patch.insert("reminder".into(), shifted.map_or(Value::Null, Value::from));
+                    }
+                }
+                if task.recurrence_id.is_none() {
+                    patch.insert("recurrence_id".into(), Value::from(own_date.format("%Y-%m-%d").to_string()));
+                }
+                let task = self.update_in::<TaskData>(&tx, uid, id, &patch, now)?;
+                tx.commit()?;
+                OverrideOutcome::Updated { task }
+            } else {
+                let task = self.update_in::<TaskData>(&tx, uid, id, &patch, now)?;
+                tx.commit()?;
+                OverrideOutcome::Recorded { task }
+            }
+        };
+        self.changed(uid, Collection::Tasks);
+        Ok(outcome)
+    }
+
+    /// 로컬에 없던 문서라도 원격 삭제는 기록한다
+    pub fn delete<T: Document>(&self, uid: &str, id: &str) -> Result<bool, StorageError> {
+        let existed = {
+            let mut conn = self.conn();
+            let tx = conn.transaction()?;
+            let existed = delete_doc(&tx, T::COLLECTION, uid, id)?;
+            outbox::enqueue(&tx, uid, T::COLLECTION, id, Op::Delete, None, now_ms() as i64)?;
+            tx.commit()?;
+            existed
+        };
+        self.changed(uid, T::COLLECTION);
+        Ok(existed)
+    }
+
+    /// 원격 스냅샷으로 컬렉션 전체를 바꾼다 (문서의 시각은 그대로 유지).
+    /// 아직 outbox에 남은 변경이 있는 문서는 로컬 쪽을 두거나, 병합하는 컬렉션이면 원격과 합친 뒤
+    /// 남은 변경을 "병합 결과와 원격의 차이"로 바꿔 둔다
+    pub fn replace_all<T: Document>(&self, uid: &str, docs: &[T]) -> Result<(), StorageError> {
+        let now = now_ms() as i64;
+        let mut requeued = false;
+        {
+            let mut conn = self.conn();
+            let tx = conn.transaction()?;
+            let pending = outbox::pending_doc_ids(&tx, uid, T::COLLECTION)?;
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE uid = ?1 AND id NOT IN (SELECT doc_id FROM outbox WHERE uid = ?1 AND collection = ?2)",
+                    T::COLLECTION.table()
+                ),
+                [uid, T::COLLECTION.table()],
+            )?;
+            let mut remotes = Vec::with_capacity(docs.len());
+            for doc in docs {
+                let id =
+                    document_id(doc).ok_or_else(|| StorageError::Invalid("snapshot document without id".into()))?;
+                let remote = to_object(doc)?;
+                if T::FIELD_CLOCKS {
+                    if let Some(latest) = merge::field_clocks(&remote).into_values().max() {
+                        self.clock.observe(&latest);
+                    }
+                }
+                if !pending.contains(&id) {
+                    put_doc(&tx, uid, &id, doc)?;
+                } else if let Some(local) = get_doc::<T>(&tx, uid, &id)? {
+                    requeued |= self.merge_pending::<T>(&tx, uid, &id, &to_object(&local)?, &remote, now)?;
+                }
+                remotes.push((id, remote));
+            }
+            if search::indexes(T::COLLECTION) {
+                search::prune(&tx, uid, T::COLLECTION)?;
+            }
+            // 다른 기기에서 지운 할 일의 알림
+            if T::COLLECTION == Collection::Tasks {
+                reminders::prune(&tx, uid)?;
+            }
+            if merge::keeps_shadow(T::COLLECTION) {
+                merge::replace_shadows(&tx, uid, T::COLLECTION, remotes.iter().map(|(id, doc)| (id.as_str(), doc)))?;
+            }
+            tx.commit()?;
+        }
+        if requeued {
+            self.changed(uid, T::COLLECTION);
+        }
+        Ok(())
+    }
+
+    /// 로컬 변경이 남은 문서에 원격 문서가 왔을 때. outbox를 바꿨으면 `true`
+    fn merge_pending<T: Document>(
+        &self,
+        tx: &Connection,
+        uid: &str,
+        id: &str,
+        local: &Map<String, Value>,
+        remote: &Map<String, Value>,
+        now: i64,
+    ) -> Result<bool, StorageError> {
+        let base = merge::shadow(tx, uid, T::COLLECTION, id)?;
+        let Some((merged, conflicts)) = merge::merge_document(T::COLLECTION, base.as_ref(), local, remote) else {
+            return Ok(false);
+        };
+        let merged: T = from_value(Value::Object(merged))?;
+        let saved = to_object(&put_doc(tx, uid, id, &merged)?)?;
+
+        let mut diff: Map<String, Value> = T::COLUMNS
+            .iter()
+            .map(|c| c.field)
+            .filter(|f| saved.get(*f).unwrap_or(&Value::Null) != remote.get(*f).unwrap_or(&Value::Null))
+            .map(|f| (f.to_string(), saved.get(f).cloned().unwrap_or(Value::Null)))
+            .collect();
+        outbox::discard(tx, uid, T::COLLECTION, id)?;
+        if !diff.is_empty() {
+            if let Some(updated_at) = saved.get("updatedAt").filter(|_| T::COLLECTION.has_updated_at()) {
+                diff.insert("updatedAt".into(), updated_at.clone());
+            }
+            outbox::enqueue(tx, uid, T::COLLECTION, id, Op::Update, Some(&Value::Object(diff)), now)?;
+        }
+        for conflict in &conflicts {
+            merge::record_conflict(tx, uid, id, conflict, now)?;
+        }
+        Ok(true)
+    }
+
+    /// 병합하지 못한 노트 블록들 (남아 있는 노트의 것만)
+    pub fn conflicts(&self, uid: &str) -> Result<Vec<Conflict>, StorageError> {
+        merge::list_conflicts(&self.conn(), uid)
+    }
+
+    /// 충돌 블록을 고른 쪽으로 바꾸고 충돌 기록을 지운다. 바뀐 노트 (노트가 이미 없으면 `None`)
+    pub fn resolve_conflict(
+        &self,
+        uid: &str,
+        conflict_id: &str,
+        resolution: &Resolution,
+    ) -> Result<Option<NoteData>, StorageError> {
+        let resolved = {
+            let mut conn = self.conn();
+            let tx = conn.transaction()?;
+            let conflict = merge::get_conflict(&tx, uid, conflict_id)?
+                .ok_or_else(|| StorageError::Invalid(format!("unknown conflict {}", conflict_id)))?;
+            merge::remove_conflict(&tx, uid, conflict_id)?;
+            let resolved = match get_doc::<NoteData>(&tx, uid, &conflict.note_id)? {
+                Some(note) => {
+                    let mut blocks = match to_object(&note)?.remove("blocks") {
+                        Some(Value::Array(blocks)) => blocks,
+                        _ => Vec::new(),
+                    };
+                    let position = blocks.iter().position(|b| b.get("id").and_then(Value::as_str) == Some(&conflict.block_id));
+                    let chosen = conflict.chosen(resolution).map(|mut block| {
+                        if let Some(fields) = block.as_object_mut() {
+                            fields.insert("id".into(), Value::String(conflict.block_id.clone()));
+                        }
+                        block
+                    });
+                    match (position, chosen) {
+                        (Some(i), Some(block)) => blocks[i] = block,
+                        (Some(i), None) => {
+                            blocks.remove(i);
+                        }
+                        (None, Some(block)) => blocks.push(block),
+                        (None, None) => {}
+                    }
+                    let mut patch = Map::new();
+                    patch.insert("blocks".into(), Value::Array(blocks));
+                    Some(self.update_in::<NoteData>(&tx, uid, &conflict.note_id, &patch, now_ms() as i64)?)
+                }
+                None => None,
+            };
+            tx.commit()?;
+            resolved
+        };
+        if resolved.is_some() {
+            self.changed(uid, Collection::Notes);
+        }
+        Ok(resolved)
+    }
+
+    /// 할 일/노트/마인드맵 전문 검색
+    pub fn search(&self, uid: &str, query: &str, limit: usize) -> Result<Vec<SearchHit>, StorageError> {
+        search::search(&self.conn(), uid, query, limit)
+    }
+
+    /// 백업의 문서들을 되살린다. `f`에서 컬렉션마다 `Restorer::docs`를 부르고, 전부 한 트랜잭션으로 적용된다
+    /// (하나라도 실패하면 아무것도 바뀌지 않는다).
+    /// `revert`면 내용이 달라진 문서도 백업 때로 되돌리고, `remove_others`면 백업에 없는 문서를 지운다
+    pub fn restore<F>(&self, uid: &str, revert: bool, remove_others: bool, f: F) -> Result<RestoreCounts, StorageError>
+    where
+        F: FnOnce(&mut Restorer<'_>) -> Result<(), StorageError>,
+    {
+        let (counts, changed) = {
+            let mut conn = self.conn();
+            let tx = conn.transaction()?;
+            let mut restorer = Restorer {
+                db: self,
+                tx: &tx,
+                uid,
+                revert,
+                remove_others,
+                now: now_ms() as i64,
+                counts: RestoreCounts::default(),
+                changed: Vec::new(),
+            };
+            f(&mut restorer)?;
+            let done = (restorer.counts, restorer.changed);
+            tx.commit()?;
+            done
+        };
+        for collection in changed {
+            self.changed(uid, collection);
+        }
+        Ok(counts)
+    }
+
+    /// `getMyDayTasks`
+    pub fn my_day_tasks(&self, uid: &str) -> Result<Vec<TaskData>, StorageError> {
+        query_docs(&self.conn(), uid, " AND my_day = 1", &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NoteBlock, SubTask, TaskPriority, TaskStatus};
+    use serde_json::json;
+
+    fn task(title: &str, my_day: bool) -> TaskData {
+        serde_json::from_value(json!({
+            "title": title,
+            "status": "todo",
+            "priority": "medium",
+            "starred": false,
+            "listId": "inbox",
+            "myDay": my_day,
+            "subTasks": [{ "id": "s1", "title": "sub", "completed": false }],
+            "tags": ["work"],
+        }))
+        .unwrap()
+    }
+
+    fn columns_of(db: &LocalDb, table: &str) -> Vec<String> {
+        let conn = db.conn();
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+        let names = stmt.query_map([], |row| row.get::<_, String>(1)).unwrap();
+        names.map(Result::unwrap).collect()
+    }
+
+    fn assert_schema<T: Document>(db: &LocalDb) {
+        let columns = columns_of(db, T::COLLECTION.table());
+        for column in T::COLUMNS {
+            assert!(columns.iter().any(|c| c == column.name), "{} missing {}", T::COLLECTION.table(), column.name);
+        }
+    }
+
+    #[test]
+    fn schema_has_every_mapped_column() {
+        let db = LocalDb::open_in_memory().unwrap();
+        assert_schema::<TaskData>(&db);
+        assert_schema::<NoteData>(&db);
+        assert_schema::<ListData>(&db);
+        assert_schema::<FolderData>(&db);
+        assert_schema::<MindMapData>(&db);
+        let version: usize = db.conn().query_row("PRAGMA user_version", [], |r| r.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn task_crud_round_trip() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let added = db.insert("u1", &task("장보기", true)).unwrap();
+        let id = added.id.clone().unwrap();
+        assert_eq!(id.len(), 20);
+        assert!(added.created_at.is_some());
+        assert_eq!(added.sub_tasks, vec![SubTask { id: "s1".into(), title: "sub".into(), completed: false }]);
+
+        let updated: TaskData =
+            db.update("u1", &id, &json!({ "status": "completed", "priority": "high", "dueDate": null, "id": "x" })).unwrap();
+        assert_eq!(updated.status, TaskStatus::Completed);
+        assert_eq!(updated.priority, TaskPriority::High);
+        assert_eq!(updated.id.as_deref(), Some(id.as_str()));
+        assert_eq!(updated.created_at, added.created_at);
+        assert_eq!(updated.tags, vec!["work".to_string()]);
+
+        // 다른 사용자의 문서는 보이지 않는다
+        assert!(db.get::<TaskData>("u2", &id).unwrap().is_none());
+        assert!(db.delete::<TaskData>("u1", &id).unwrap());
+        assert!(db.list::<TaskData>("u1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn deleting_a_task_drops_its_reminder() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let ids: Vec<String> =
+            (0..2).map(|i| db.insert("u1", &task(&format!("t{}", i), false)).unwrap().id.unwrap()).collect();
+        for id in &ids {
+            let reminder = reminders::Reminder {
+                task_id: id.clone(),
+                uid: Some("u1".into()),
+                fire_at: now_ms() as i64 + 60_000,
+                payload: Default::default(),
+                created_at: 0,
+            };
+            reminders::upsert(&db.conn(), &reminder).unwrap();
+        }
+        assert!(db.delete::<TaskData>("u1", &ids[0]).unwrap());
+        let left: Vec<String> = reminders::list(&db.conn()).unwrap().into_iter().map(|r| r.task_id).collect();
+        assert_eq!(left, [ids[1].as_str()]);
+    }
+
+    #[test]
+    fn snapshot_without_a_task_drops_its_reminder() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let mut kept = task("kept", false);
+        kept.id = Some("kept".into());
+        let mut gone = task("gone", false);
+        gone.id = Some("gone".into());
+        db.replace_all("u1", &[kept.clone(), gone]).unwrap();
+        for (id, uid) in [("kept", Some("u1")), ("gone", Some("u1")), ("other", Some("u2"))] {
+            let reminder = reminders::Reminder {
+                task_id: id.into(),
+                uid: uid.map(str::to_string),
+                fire_at: now_ms() as i64 + 60_000,
+                payload: Default::default(),
+                created_at: 0,
+            };
+            reminders::upsert(&db.conn(), &reminder).unwrap();
+        }
+
+        // 다른 기기에서 "gone"을 지웠다
+        db.replace_all("u1", &[kept]).unwrap();
+        let left: Vec<String> = reminders::list(&db.conn()).unwrap().into_iter().map(|r| r.task_id).collect();
+        // 다른 계정의 알림은 건드리지 않는다
+        assert_eq!(left, ["kept", "other"]);
+    }
+
+    #[test]
+    fn invalid_update_is_rejected_without_writing() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let id = db.insert("u1", &task("a", false)).unwrap().id.unwrap();
+        let err = db.update::<TaskData>("u1", &id, &json!({ "status": "archived" })).unwrap_err();
+        assert!(matches!(err, StorageError::Invalid(_)));
+        assert_eq!(db.get::<TaskData>("u1", &id).unwrap().unwrap().status, TaskStatus::Todo);
+        assert!(matches!(
+            db.update::<TaskData>("u1", "missing", &json!({ "title": "b" })),
+            Err(StorageError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn my_day_and_ordering() {
+        let db = LocalDb::open_in_memory().unwrap();
+        for (i, my_day) in [true, false, true].into_iter().enumerate() {
+            let mut t = task(&format!("t{}", i), my_day);
+            t.id = Some(format!("t{}", i));
+            t.created_at = Some(1_000 + i as i64);
+            put_doc(&db.conn(), "u1", &format!("t{}", i), &t).unwrap();
+        }
+        let titles: Vec<String> = db.list::<TaskData>("u1").unwrap().into_iter().map(|t| t.title).collect();
+        assert_eq!(titles, ["t2", "t1", "t0"]);
+        let my_day: Vec<String> = db.my_day_tasks("u1").unwrap().into_iter().map(|t| t.title).collect();
+        assert_eq!(my_day, ["t2", "t0"]);
+    }
+
+    #[test]
+    fn snapshot_replaces_collection_and_keeps_timestamps() {
+        let db = LocalDb::open_in_memory().unwrap();
+        // 이전 스냅샷으로 받은 문서 (로컬 변경 없음)
+        db.replace_all("u1", &[NoteData {
+            id: Some("stale".into()),
+            title: "stale".into(),
+            icon: "📝".into(),
+            blocks: vec![],
+            pinned: false,
+            starred: false,
+            tags: vec![],
+            folder_id: None,
+            linked_task_id: None,
+            linked_task_ids: vec![],
+            deleted: false,
+            deleted_at: None,
+            original_folder_id: None,
+            created_at: None,
+            updated_at: None,
+        }])
+        .unwrap();
+
+        let remote: NoteData = serde_json::from_value(json!({
+            "id": "n1", "title": "회의록", "icon": "📝", "pinned": true, "tags": [], "folderId": null,
+            "blocks": [{ "id": "b1", "type": "callout", "content": "안녕" }],
+            "createdAt": 1_700_000_000_000i64, "updatedAt": 1_700_000_100_000i64,
+        }))
+        .unwrap();
+        db.replace_all("u1", std::slice::from_ref(&remote)).unwrap();
+
+        let notes = db.list::<NoteData>("u1").unwrap();
+        assert_eq!(notes, vec![remote]);
+        assert_eq!(
+            notes[0].blocks[0],
+            NoteBlock {
+                id: "b1".into(),
+                block_type: "callout".into(),
+                content: "안녕".into(),
+                checked: None,
+                url: None,
+                children: None,
+                image_url: None,
+                image_path: None,
+            }
+        );
+    }
+
+    fn note(id: &str, blocks: Value, updated_at: i64) -> NoteData {
+        serde_json::from_value(json!({
+            "id": id, "title": "노트", "icon": "📝", "blocks": blocks,
+            "createdAt": 1_000, "updatedAt": updated_at,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn restore_is_all_or_nothing() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let saved = db.insert("u1", &task("before", false)).unwrap();
+        let id = saved.id.clone().unwrap();
+        db.update::<TaskData>("u1", &id, &json!({ "title": "after" })).unwrap();
+        let queued = outbox::list(&db.conn(), "u1").unwrap().len();
+
+        // 노트에서 실패하면 앞서 되돌린 할 일도 그대로
+        let mut broken = note("n1", json!([]), 2_000);
+        broken.id = None;
+        let result = db.restore("u1", true, true, |restorer| {
+            assert_eq!(restorer.docs(std::slice::from_ref(&saved))?.reverted, 1);
+            restorer.docs(&[broken])?;
+            Ok(())
+        });
+        assert!(matches!(result, Err(StorageError::Invalid(_))));
+        assert_eq!(db.get::<TaskData>("u1", &id).unwrap().unwrap().title, "after");
+        assert_eq!(outbox::list(&db.conn(), "u1").unwrap().len(), queued);
+
+        let counts = db.restore("u1", true, true, |restorer| restorer.docs(&[saved]).map(|_| ())).unwrap();
+        assert_eq!((counts.restored, counts.reverted, counts.removed), (0, 1, 0));
+        assert_eq!(db.get::<TaskData>("u1", &id).unwrap().unwrap().title, "before");
+    }
+
+    #[test]
+    fn pending_task_is_merged_with_snapshot() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let mut remote = task("a", false);
+        remote.id = Some("t1".into());
+        remote.created_at = Some(1_000);
+        remote.updated_at = Some(1_000);
+        db.replace_all("u1", std::slice::from_ref(&remote)).unwrap();
+
+        db.update::<TaskData>("u1", "t1", &json!({ "title": "local" })).unwrap();
+        // 다른 기기(시계 없음)가 상태만 바꿨다
+        remote.status = TaskStatus::Completed;
+        remote.updated_at = Some(2_000);
+        db.replace_all("u1", std::slice::from_ref(&remote)).unwrap();
+
+        let merged = db.get::<TaskData>("u1", "t1").unwrap().unwrap();
+        assert_eq!((merged.title.as_str(), merged.status), ("local", TaskStatus::Completed));
+        // 남은 변경은 원격과 다른 필드만
+        let pending = outbox::list(&db.conn(), "u1").unwrap();
+        assert_eq!(pending.len(), 1);
+        let fields = pending[0].fields.as_ref().unwrap().as_object().unwrap();
+        let mut keys: Vec<&str> = fields.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["fieldClocks", "title", "updatedAt"]);
+    }
+
+    #[test]
+    fn block_conflicts_are_recorded_and_resolved() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let base = json!([{ "id": "a", "type": "text", "content": "1" }, { "id": "b", "type": "text", "content": "2" }]);
+        db.replace_all("u1", &[note("n1", base, 1_000)]).unwrap();
+        db.update::<NoteData>(
+            "u1",
+            "n1",
+            &json!({ "blocks": [{ "id": "a", "type": "text", "content": "local" }, { "id": "b", "type": "text", "content": "2" }] }),
+        )
+        .unwrap();
+        let remote = json!([{ "id": "a", "type": "text", "content": "remote" }, { "id": "b", "type": "text", "content": "2!" }]);
+        db.replace_all("u1", &[note("n1", remote, 2_000)]).unwrap();
+
+        let merged = db.get::<NoteData>("u1", "n1").unwrap().unwrap();
+        let contents: Vec<&str> = merged.blocks.iter().map(|b| b.content.as_str()).collect();
+        assert_eq!(contents, ["local", "2!"]);
+        let conflicts = db.conflicts("u1").unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!((conflicts[0].note_id.as_str(), conflicts[0].block_id.as_str()), ("n1", "a"));
+
+        let resolved = db.resolve_conflict("u1", &conflicts[0].id, &Resolution::Remote).unwrap().unwrap();
+        assert_eq!(resolved.blocks[0].content, "remote");
+        assert!(db.conflicts("u1").unwrap().is_empty());
+        assert!(matches!(db.resolve_conflict("u1", &conflicts[0].id, &Resolution::Local), Err(StorageError::Invalid(_))));
+    }
+
+    #[test]
+    fn completing_a_recurring_task_creates_the_next_one_once() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let mut recurring = task("물 주기", false);
+        recurring.due_date = Some("2026-03-10".into());
+        recurring.recurrence_rule = serde_json::from_value(json!({ "freq": "daily", "interval": 3 })).unwrap();
+        let id = db.insert("u1", &recurring).unwrap().id.unwrap();
+
+        let done = json!({ "status": "completed", "completedDate": "2026-03-10" });
+        db.update::<TaskData>("u1", &id, &done).unwrap();
+        // 다시 완료해도(동기화로 같은 변경이 다시 와도) 하나
+        db.update::<TaskData>("u1", &id, &json!({ "status": "todo" })).unwrap();
+        db.update::<TaskData>("u1", &id, &done).unwrap();
+
+        let next_id = format!("{}-20260313", id);
+        let next: TaskData = db.get("u1", &next_id).unwrap().unwrap();
+        assert_eq!(db.list::<TaskData>("u1").unwrap().len(), 2);
+        assert_eq!((next.status, next.due_date.as_deref()), (TaskStatus::Todo, Some("2026-03-13")));
+        assert_eq!(next.recurrence_parent_id.as_deref(), Some(id.as_str()));
+        // 새 회차도 원격으로 나간다
+        let pending = outbox::list(&db.conn(), "u1").unwrap();
+        assert!(pending.iter().any(|m| m.doc_id == next_id && m.op == Op::Set));
+
+        // 반복이 아니면 아무것도 만들지 않는다
+        let plain = db.insert("u1", &task("한 번", false)).unwrap().id.unwrap();
+        db.update::<TaskData>("u1", &plain, &done).unwrap();
+        assert_eq!(db.list::<TaskData>("u1").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn occurrence_overrides_apply_to_this_task_and_later_ones() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let mut recurring = task("물 주기", false);
+        recurring.due_date = Some("2026-03-10".into());
+        recurring.recurrence_rule = serde_json::from_value(json!({ "freq": "daily" })).unwrap();
+        let id = db.insert("u1", &recurring).unwrap().id.unwrap();
+        let ov = |value: serde_json::Value| Some(serde_json::from_value::<OccurrenceOverride>(value).unwrap());
+
+        // 아직 없는 회차는 규칙에만 적는다
+        let moved = db.override_occurrence("u1", &id, "2026-03-12", ov(json!({ "start": "2026-03-14" }))).unwrap();
+        let OverrideOutcome::Recorded { task: recorded } = moved else { panic!("{:?}", moved) };
+        assert_eq!(recorded.recurrence_rule.unwrap().overrides.len(), 1);
+        // 지난 날짜나 회차가 아닌 날은 안 된다
+        assert!(db.override_occurrence("u1", &id, "2026-03-09", ov(json!({ "skipped": true }))).is_err());
+        assert!(db.override_occurrence("u1", &id, "3/10", None).is_err());
+
+        // 이 회차의 이름을 바꿨다가 옮기면 이름은 반복 본래 값으로 돌아온다
+        let renamed = db.override_occurrence("u1", &id, "2026-03-10", ov(json!({ "title": "큰 화분" }))).unwrap();
+        let OverrideOutcome::Updated { task: renamed } = renamed else { panic!("{:?}", renamed) };
+        assert_eq!(renamed.title, "큰 화분");
+        assert_eq!(renamed.recurrence_id.as_deref(), Some("2026-03-10"));
+        assert_eq!(renamed.recurrence_rule.unwrap().series_title.as_deref(), Some("물 주기"));
+        let later = db.override_occurrence("u1", &id, "2026-03-10", ov(json!({ "start": "2026-03-11" }))).unwrap();
+        let OverrideOutcome::Updated { task: later } = later else { panic!("{:?}", later) };
+        assert_eq!((later.title.as_str(), later.due_date.as_deref()), ("물 주기", Some("2026-03-11")));
+
+        // 이 회차를 건너뛰면 지우고 다음 회차를 만든다
+        let skipped = db.override_occurrence("u1", &id, "2026-03-10", ov(json!({ "skipped": true }))).unwrap();
+        let OverrideOutcome::Skipped { next: Some(next) } = skipped else { panic!("{:?}", skipped) };
+        assert!(db.get::<TaskData>("u1", &id).unwrap().is_none());
+        let pending = outbox::list(&db.conn(), "u1").unwrap();
+        assert!(pending.iter().any(|m| m.doc_id == id && m.op == Op::Delete));
+        let next_id = next.id.unwrap();
+        assert_eq!(next_id, format!("{}-20260311", id));
+        assert_eq!(next.due_date.as_deref(), Some("2026-03-11"));
+
+        // 옮겨 둔 12일 회차는 14일로 만들어진다
+        let done = json!({ "status": "completed", "completedDate": "2026-03-11" });
+        db.update::<TaskData>("u1", &next_id, &done).unwrap();
+        let third: TaskData = db.get("u1", &format!("{}-20260312", id)).unwrap().unwrap();
+        assert_eq!(third.due_date.as_deref(), Some("2026-03-14"));
+        assert_eq!(third.recurrence_id.as_deref(), Some("2026-03-12"));
+    }
+}
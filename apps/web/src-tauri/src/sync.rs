@@ -0,0 +1,699 @@
+//! outbox를 Firestore REST API로 내보내는 백그라운드 동기화.
+//!
+//! 변경은 기록된 순서대로 하나씩 보낸다. 네트워크 오류/5xx/429는 지수 백오프로 같은 변경을 다시 보내고,
+//! 다시 보내도 안 되는 4xx는 outbox에 실패로 남긴 채 다음 변경으로 넘어간다.
+//! 인증은 웹뷰가 넘겨주는 Firebase ID 토큰을 쓴다. 401이면 새 토큰이 올 때까지 멈춘다.
+
+use crate::outbox::{self, Mutation, Op};
+use crate::storage::{LocalDb, StorageError};
+use crate::token_store::now_ms;
+use chrono::{DateTime, SecondsFormat};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tauri_plugin_http::reqwest;
+
+pub const FIRESTORE_API: &str = "https://firestore.googleapis.com/v1";
+
+const BACKOFF_BASE: Duration = Duration::from_secs(2);
+const BACKOFF_MAX: Duration = Duration::from_secs(300);
+
+/// 문서 경로의 기준 URL (`.../documents`)
+pub fn documents_url(project_id: &str) -> String {
+    format!("{}/projects/{}/databases/(default)/documents", FIRESTORE_API, urlencoding::encode(project_id))
+}
+
+/// 로컬은 epoch ms, Firestore는 Timestamp인 필드
+const TIMESTAMP_FIELDS: &[&str] = &["createdAt", "updatedAt"];
+
+fn rfc3339(ms: i64) -> String {
+    DateTime::from_timestamp_millis(ms)
+        .unwrap_or(DateTime::UNIX_EPOCH)
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// JSON 값을 Firestore REST `Value`로
+pub fn encode_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "nullValue": null }),
+        Value::Bool(b) => json!({ "booleanValue": b }),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => json!({ "integerValue": i.to_string() }),
+            None => json!({ "doubleValue": n.as_f64() }),
+        },
+        Value::String(s) => json!({ "stringValue": s }),
+        Value::Array(items) => json!({ "arrayValue": { "values": items.iter().map(encode_value).collect::<Vec<_>>() } }),
+        Value::Object(map) => json!({ "mapValue": { "fields": encode_fields(map) } }),
+    }
+}
+
+fn encode_fields(map: &Map<String, Value>) -> Map<String, Value> {
+    map.iter().map(|(k, v)| (k.clone(), encode_value(v))).collect()
+}
+
+/// 최상위 필드 인코딩. `createdAt`/`updatedAt`은 timestampValue로
+pub fn encode_document(fields: &Map<String, Value>) -> Map<String, Value> {
+    fields
+        .iter()
+        .filter(|(k, _)| k.as_str() != "id")
+        .map(|(k, v)| {
+            let encoded = match v.as_i64() {
+                Some(ms) if TIMESTAMP_FIELDS.contains(&k.as_str()) => json!({ "timestampValue": rfc3339(ms) }),
+                _ => encode_value(v),
+            };
+            (k.clone(), encoded)
+        })
+        .collect()
+}
+
+/// updateMask용 필드 경로. 식별자 형태가 아니면 백틱으로 감싼다
+fn field_path(name: &str) -> String {
+    let simple = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if simple {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('\\', "\\\\").replace('`', "\\`"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushError {
+    /// 네트워크 오류, 5xx, 429 — 같은 변경을 나중에 다시
+    Transient(String),
+    /// ID 토큰 만료/무효 — 새 토큰을 받을 때까지 대기
+    Unauthenticated(String),
+    /// 다시 보내도 안 되는 요청 (권한, 잘못된 문서, 지워진 문서 수정)
+    Rejected(String),
+}
+
+#[derive(serde::Deserialize)]
+struct ApiErrorBody {
+    error: ApiError,
+}
+
+#[derive(serde::Deserialize)]
+struct ApiError {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    status: String,
+}
+
+fn classify(status: u16, body: &str, op: Op) -> Result<(), PushError> {
+    if (200..300).contains(&status) || (status == 404 && op == Op::Delete) {
+        return Ok(());
+    }
+    let detail = match serde_json::from_str::<ApiErrorBody>(body) {
+        Ok(b) => format!("{} {}: {}", status, b.error.status, b.error.message),
+        Err(_) => format!("HTTP {}", status),
+    };
+    Err(match status {
+        401 => PushError::Unauthenticated(detail),
+        408 | 429 | 500..=599 => PushError::Transient(detail),
+        _ => PushError::Rejected(detail),
+    })
+}
+
+pub struct FirestoreClient {
+    base_url: String,
+    http: reqwest::blocking::Client,
+}
+
+impl FirestoreClient {
+    /// `base_url`은 `.../documents`까지 (`documents_url`)
+    pub fn new(base_url: impl Into<String>) -> Result<Self, String> {
+        let http = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(20))
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self { base_url: base_url.into().trim_end_matches('/').to_string(), http })
+    }
+
+    fn document_url(&self, m: &Mutation) -> String {
+        format!(
+            "{}/users/{}/{}/{}",
+            self.base_url,
+            urlencoding::encode(&m.uid),
+            m.collection.table(),
+            urlencoding::encode(&m.doc_id)
+        )
+    }
+
+    /// 변경 하나를 반영한다 (블로킹)
+    pub fn push(&self, id_token: &str, m: &Mutation) -> Result<(), PushError> {
+        let url = self.document_url(m);
+        let request = match m.op {
+            Op::Delete => self.http.delete(&url),
+            Op::Set | Op::Update => {
+                let fields = m.fields.as_ref().and_then(Value::as_object).cloned().unwrap_or_default();
+                let mut query: Vec<String> = Vec::new();
+                if m.op == Op::Update {
+                    // 마스크에 있고 본문에 없는 필드는 지워진다 — 바꾼 필드만 정확히
+                    query.extend(
+                        fields.keys().map(|k| format!("updateMask.fieldPaths={}", urlencoding::encode(&field_path(k)))),
+                    );
+                    query.push("currentDocument.exists=true".into());
+                }
+                let url = if query.is_empty() { url } else { format!("{}?{}", url, query.join("&")) };
+                self.http
+                    .patch(url)
+                    .header("Content-Type", "application/json")
+                    .body(json!({ "fields": encode_document(&fields) }).to_string())
+            }
+        };
+        let resp = request.bearer_auth(id_token).send().map_err(|e| PushError::Transient(e.to_string()))?;
+        let status = resp.status().as_u16();
+        let body = resp.text().unwrap_or_default();
+        classify(status, &body, m.op)
+    }
+}
+
+/// 연속 실패 `failures`번째의 대기 시간. 2s, 4s, 8s ... 최대 5분, ±20% 흔들기
+pub fn backoff_delay(failures: u32) -> Duration {
+    let exp = BACKOFF_BASE.saturating_mul(1u32 << failures.saturating_sub(1).min(16));
+    let base = exp.min(BACKOFF_MAX);
+    let mut bytes = [0u8; 2];
+    let _ = getrandom::getrandom(&mut bytes);
+    let jitter = 0.8 + 0.4 * (u16::from_le_bytes(bytes) as f64 / u16::MAX as f64);
+    base.mul_f64(jitter)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// 로그인 정보가 아직 없음
+    SignedOut,
+    Idle,
+    Syncing,
+    /// 일시적 오류로 재시도 대기 중
+    Backoff,
+    /// ID 토큰이 거부됨. 새 토큰 필요
+    AuthRequired,
+}
+
+/// `sync-status` 이벤트 본문
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub state: SyncState,
+    pub pending: usize,
+    pub failed: usize,
+    pub last_error: Option<String>,
+    pub last_synced_at: Option<i64>,
+    pub next_retry_at: Option<i64>,
+}
+
+impl Default for SyncStatus {
+    fn default() -> Self {
+        Self { state: SyncState::SignedOut, pending: 0, failed: 0, last_error: None, last_synced_at: None, next_retry_at: None }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub uid: String,
+    pub id_token: String,
+    /// `documents_url(project_id)`. 테스트에서는 mock 서버 주소
+    pub base_url: String,
+}
+
+/// `run_once` 결과. 다음에 언제 돌지 정한다
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    /// 보낼 것이 없다. 새 변경이 들어오면 다시
+    Drained,
+    Retry(Duration),
+    /// 새 ID 토큰이 올 때까지 대기
+    WaitForAuth,
+}
+
+struct Inner {
+    credentials: Option<Credentials>,
+    client: Option<Arc<FirestoreClient>>,
+    status: SyncStatus,
+    failures: u32,
+    wake: bool,
+}
+
+type StatusListener = Box<dyn Fn(&SyncStatus) + Send + Sync>;
+
+pub struct SyncEngine {
+    db: Arc<LocalDb>,
+    inner: Mutex<Inner>,
+    signal: Condvar,
+    on_status: StatusListener,
+}
+
+impl SyncEngine {
+    /// `on_status`는 상태가 바뀔 때마다 호출된다 (`sync-status` 이벤트로 내보낼 곳)
+    pub fn new<F>(db: Arc<LocalDb>, on_status: F) -> Self
+    where
+        F: Fn(&SyncStatus) + Send + Sync + 'static,
+    {
+        Self {
+            db,
+            inner: Mutex::new(Inner {
+                credentials: None,
+                client: None,
+                status: SyncStatus::default(),
+                failures: 0,
+                wake: false,
+            }),
+            signal: Condvar::new(),
+            on_status: Box::new(on_status),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn status(&self) -> SyncStatus {
+        self.lock().status.clone()
+    }
+
+    /// 상태를 고치고 outbox 개수를 다시 세어, 바뀌었으면 알린다
+    fn update_status(&self, change: impl FnOnce(&mut SyncStatus)) {
+        let status = {
+            let mut inner = self.lock();
+            let before = inner.status.clone();
+            change(&mut inner.status);
+            if let Some(uid) = inner.credentials.as_ref().map(|c| c.uid.clone()) {
+                if let Ok((pending, failed)) = outbox::counts(&self.db.conn(), &uid) {
+                    inner.status.pending = pending;
+                    inner.status.failed = failed;
+                }
+            }
+            if inner.status == before {
+                return;
+            }
+            inner.status.clone()
+        };
+        (self.on_status)(&status);
+    }
+
+    /// 로그인/토큰 갱신 시. `None`이면 로그아웃 (동기화 중단)
+    pub fn set_credentials(&self, credentials: Option<Credentials>) -> Result<(), String> {
+        {
+            let mut inner = self.lock();
+            let same_target = matches!(
+                (&inner.credentials, &credentials),
+                (Some(a), Some(b)) if a.base_url == b.base_url
+            );
+            match &credentials {
+                None => inner.client = None,
+                Some(c) if !same_target || inner.client.is_none() => {
+                    inner.client = Some(Arc::new(FirestoreClient::new(c.base_url.clone())?));
+                }
+                Some(_) => {}
+            }
+            inner.credentials = credentials;
+            inner.failures = 0;
+            inner.wake = true;
+        }
+        self.signal.notify_all();
+        let signed_in = self.lock().credentials.is_some();
+        self.update_status(|s| {
+            s.state = if signed_in { SyncState::Idle } else { SyncState::SignedOut };
+            s.next_retry_at = None;
+        });
+        Ok(())
+    }
+
+    /// 로컬 변경이 기록됐을 때. 백오프 중이면 대기 시간을 지킨다
+    pub fn notify(&self) {
+        {
+            let mut inner = self.lock();
+            if inner.status.state != SyncState::Backoff {
+                inner.wake = true;
+            }
+        }
+        self.signal.notify_all();
+        self.update_status(|_| {});
+    }
+
+    /// 백오프를 무시하고 바로 다시 시도. `retry_failed`면 실패로 남은 변경도 다시 큐에
+    pub fn retry_now(&self, retry_failed: bool) -> Result<(), StorageError> {
+        {
+            let mut inner = self.lock();
+            if retry_failed {
+                if let Some(uid) = inner.credentials.as_ref().map(|c| c.uid.clone()) {
+                    outbox::retry_failed(&self.db.conn(), &uid)?;
+                }
+            }
+            inner.failures = 0;
+            inner.wake = true;
+        }
+        self.signal.notify_all();
+        self.update_status(|_| {});
+        Ok(())
+    }
+
+    /// 보낼 수 있는 만큼 보낸다
+    pub fn run_once(&self) -> Step {
+        let (credentials, client) = {
+            let inner = self.lock();
+            match (&inner.credentials, &inner.client) {
+                (Some(c), Some(client)) if !c.id_token.is_empty() => (c.clone(), Arc::clone(client)),
+                _ => return Step::WaitForAuth,
+            }
+        };
+        loop {
+            let head = outbox::head(&self.db.conn(), &credentials.uid);
+            let mutation = match head {
+                Ok(Some(m)) => m,
+                Ok(None) => {
+                    self.lock().failures = 0;
+                    self.update_status(|s| {
+                        s.state = SyncState::Idle;
+                        s.next_retry_at = None;
+                    });
+                    return Step::Drained;
+                }
+                Err(e) => return self.backoff(e.to_string()),
+            };
+            self.update_status(|s| s.state = SyncState::Syncing);
+
+            match client.push(&credentials.id_token, &mutation) {
+                Ok(()) => {
+                    if let Err(e) = outbox::ack(&self.db.conn(), &mutation.id) {
+                        return self.backoff(e.to_string());
+                    }
+                    self.lock().failures = 0;
+                    self.update_status(|s| {
+                        s.last_synced_at = Some(now_ms() as i64);
+                        s.last_error = None;
+                    });
+                }
+                Err(PushError::Rejected(msg)) => {
+                    let _ = outbox::record_failure(&self.db.conn(), &mutation.id, &msg, true);
+                    self.update_status(|s| s.last_error = Some(msg));
+                }
+                Err(PushError::Transient(msg)) => {
+                    let _ = outbox::record_failure(&self.db.conn(), &mutation.id, &msg, false);
+                    return self.backoff(msg);
+                }
+                Err(PushError::Unauthenticated(msg)) => {
+                    let _ = outbox::record_failure(&self.db.conn(), &mutation.id, &msg, false);
+                    if let Some(c) = self.lock().credentials.as_mut() {
+                        // 같은 토큰으로 다시 보내지 않도록
+                        if c.id_token == credentials.id_token {
+                            c.id_token.clear();
+                        }
+                    }
+                    self.update_status(|s| {
+                        s.state = SyncState::AuthRequired;
+                        s.last_error = Some(msg);
+                        s.next_retry_at = None;
+                    });
+                    return Step::WaitForAuth;
+                }
+            }
+        }
+    }
+
+    fn backoff(&self, error: String) -> Step {
+        let delay = {
+            let mut inner = self.lock();
+            inner.failures = inner.failures.saturating_add(1);
+            backoff_delay(inner.failures)
+        };
+        self.update_status(|s| {
+            s.state = SyncState::Backoff;
+            s.last_error = Some(error);
+            s.next_retry_at = Some(now_ms() as i64 + delay.as_millis() as i64);
+        });
+        Step::Retry(delay)
+    }
+
+    /// 깨울 때까지(또는 `timeout`까지) 대기
+    fn wait(&self, timeout: Option<Duration>) {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut inner = self.lock();
+        while !inner.wake {
+            match deadline {
+                None => inner = self.signal.wait(inner).unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    inner = self.signal.wait_timeout(inner, deadline - now).unwrap_or_else(|e| e.into_inner()).0;
+                }
+            }
+        }
+        inner.wake = false;
+    }
+
+    /// 동기화 스레드를 띄운다
+    pub fn spawn(self: &Arc<Self>) {
+        let engine = Arc::clone(self);
+        std::thread::spawn(move || loop {
+            let timeout = match engine.run_once() {
+                Step::Retry(delay) => Some(delay),
+                Step::Drained | Step::WaitForAuth => None,
+            };
+            engine.wait(timeout);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loopback::{Limits, LoopbackServer, Request, Response, Router};
+    use crate::models::TaskData;
+    use crate::storage::Collection;
+
+    struct Recorded {
+        method: String,
+        path: String,
+        query: Vec<(String, String)>,
+        auth: Option<String>,
+        body: Value,
+    }
+
+    /// Firestore 대신 응답하는 mock 서버. `respond`가 요청 순번(0부터)과 요청으로 응답을 정한다
+    fn mock_firestore<F>(respond: F) -> (String, Arc<Mutex<Vec<Recorded>>>)
+    where
+        F: Fn(usize, &Request) -> Response + Send + 'static,
+    {
+        let server = LoopbackServer::bind().unwrap();
+        let port = server.port();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&log);
+        let router = Router::new().fallback(move |req| {
+            let mut log = recorder.lock().unwrap();
+            let n = log.len();
+            log.push(Recorded {
+                method: req.method.clone(),
+                path: req.path.clone(),
+                query: req.query.clone(),
+                auth: req.header("Authorization").map(str::to_string),
+                body: serde_json::from_slice(&req.body).unwrap_or(Value::Null),
+            });
+            drop(log);
+            respond(n, req)
+        });
+        std::thread::spawn(move || server.serve(router, Limits::default()));
+        (format!("http://127.0.0.1:{}/v1/documents", port), log)
+    }
+
+    fn ok() -> Response {
+        Response::json("{}")
+    }
+
+    fn api_error(status: u16, code: &str) -> Response {
+        Response::new(status)
+            .with_header("Content-Type", "application/json")
+            .with_body(format!(r#"{{"error":{{"code":{},"message":"boom","status":"{}"}}}}"#, status, code).into_bytes())
+    }
+
+    fn task(title: &str) -> TaskData {
+        serde_json::from_value(json!({
+            "title": title, "status": "todo", "priority": "medium", "listId": "inbox", "tags": ["a"],
+        }))
+        .unwrap()
+    }
+
+    fn engine(db: &Arc<LocalDb>, base_url: &str) -> (Arc<SyncEngine>, Arc<Mutex<Vec<SyncStatus>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let engine = Arc::new(SyncEngine::new(Arc::clone(db), move |s| sink.lock().unwrap().push(s.clone())));
+        engine
+            .set_credentials(Some(Credentials {
+                uid: "u1".into(),
+                id_token: "token-1".into(),
+                base_url: base_url.to_string(),
+            }))
+            .unwrap();
+        (engine, events)
+    }
+
+    #[test]
+    fn replays_mutations_in_order() {
+        let (url, log) = mock_firestore(|_, _| ok());
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let id = db.insert("u1", &task("장보기")).unwrap().id.unwrap();
+        db.update::<TaskData>("u1", &id, &json!({ "status": "completed", "dueDate": null, "bogus": 1 })).unwrap();
+        db.delete::<TaskData>("u1", &id).unwrap();
+        let (engine, events) = engine(&db, &url);
+
+        assert_eq!(engine.run_once(), Step::Drained);
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 3);
+        let doc_path = format!("/v1/documents/users/u1/tasks/{}", id);
+
+        assert_eq!((log[0].method.as_str(), log[0].path.as_str()), ("PATCH", doc_path.as_str()));
+        assert!(log[0].query.is_empty());
+        assert_eq!(log[0].auth.as_deref(), Some("Bearer token-1"));
+        let fields = &log[0].body["fields"];
+        assert_eq!(fields["title"], json!({ "stringValue": "장보기" }));
+        assert_eq!(fields["tags"], json!({ "arrayValue": { "values": [{ "stringValue": "a" }] } }));
+        assert_eq!(fields["myDay"], json!({ "booleanValue": false }));
+        assert!(fields["createdAt"]["timestampValue"].as_str().unwrap().ends_with('Z'));
+        assert!(fields.get("id").is_none());
+
+        assert_eq!(log[1].method, "PATCH");
+        let mask: Vec<&str> =
+            log[1].query.iter().filter(|(k, _)| k == "updateMask.fieldPaths").map(|(_, v)| v.as_str()).collect();
+        assert_eq!(mask, ["dueDate", "fieldClocks", "status", "updatedAt"]);
+        assert!(log[1].query.contains(&("currentDocument.exists".into(), "true".into())));
+        assert_eq!(log[1].body["fields"]["dueDate"], json!({ "nullValue": null }));
+        assert_eq!(log[1].body["fields"]["status"], json!({ "stringValue": "completed" }));
+        let clocks = &log[1].body["fields"]["fieldClocks"]["mapValue"]["fields"];
+        assert!(clocks["status"]["stringValue"].is_string() && clocks.get("bogus").is_none());
+
+        assert_eq!((log[2].method.as_str(), log[2].path.as_str()), ("DELETE", doc_path.as_str()));
+
+        let status = engine.status();
+        assert_eq!((status.state, status.pending, status.failed), (SyncState::Idle, 0, 0));
+        assert!(status.last_synced_at.is_some());
+        // 대기 개수가 줄어드는 과정이 이벤트로 나갔다
+        let pendings: Vec<usize> = events.lock().unwrap().iter().map(|s| s.pending).collect();
+        assert_eq!(pendings.first(), Some(&3));
+        assert_eq!(pendings.last(), Some(&0));
+    }
+
+    #[test]
+    fn transient_errors_back_off_and_keep_the_mutation() {
+        let (url, log) = mock_firestore(|n, _| if n == 0 { api_error(503, "UNAVAILABLE") } else { ok() });
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        db.insert("u1", &task("a")).unwrap();
+        let (engine, _) = engine(&db, &url);
+
+        let Step::Retry(delay) = engine.run_once() else { panic!("expected a retry") };
+        assert!(delay >= Duration::from_millis(1600) && delay <= Duration::from_millis(2400));
+        let status = engine.status();
+        assert_eq!((status.state, status.pending), (SyncState::Backoff, 1));
+        assert!(status.last_error.unwrap().contains("UNAVAILABLE"));
+        assert_eq!(outbox::list(&db.conn(), "u1").unwrap()[0].attempts, 1);
+
+        assert_eq!(engine.run_once(), Step::Drained);
+        assert_eq!(log.lock().unwrap().len(), 2);
+        assert_eq!(engine.status().pending, 0);
+    }
+
+    #[test]
+    fn rejected_mutations_are_parked_and_the_queue_moves_on() {
+        let (url, log) = mock_firestore(|n, _| if n == 0 { api_error(403, "PERMISSION_DENIED") } else { ok() });
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        db.insert("u1", &task("a")).unwrap();
+        db.insert("u1", &task("b")).unwrap();
+        let (engine, _) = engine(&db, &url);
+
+        assert_eq!(engine.run_once(), Step::Drained);
+        assert_eq!(log.lock().unwrap().len(), 2);
+        let status = engine.status();
+        assert_eq!((status.pending, status.failed), (0, 1));
+        assert!(status.last_error.is_none());
+        let parked = outbox::list(&db.conn(), "u1").unwrap();
+        assert!(parked[0].failed && parked[0].last_error.as_deref().unwrap().contains("PERMISSION_DENIED"));
+
+        // 다시 큐에 넣으면 보낸다
+        engine.retry_now(true).unwrap();
+        assert_eq!(engine.run_once(), Step::Drained);
+        assert_eq!(engine.status().failed, 0);
+    }
+
+    #[test]
+    fn unauthenticated_waits_for_a_new_token() {
+        let (url, log) = mock_firestore(|_, req| {
+            if req.header("Authorization") == Some("Bearer token-1") {
+                api_error(401, "UNAUTHENTICATED")
+            } else {
+                ok()
+            }
+        });
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        db.insert("u1", &task("a")).unwrap();
+        let (engine, _) = engine(&db, &url);
+
+        assert_eq!(engine.run_once(), Step::WaitForAuth);
+        assert_eq!(engine.status().state, SyncState::AuthRequired);
+        // 같은 토큰으로는 더 보내지 않는다
+        assert_eq!(engine.run_once(), Step::WaitForAuth);
+        assert_eq!(log.lock().unwrap().len(), 1);
+
+        engine
+            .set_credentials(Some(Credentials { uid: "u1".into(), id_token: "token-2".into(), base_url: url.clone() }))
+            .unwrap();
+        assert_eq!(engine.run_once(), Step::Drained);
+        assert_eq!(engine.status().pending, 0);
+    }
+
+    #[test]
+    fn outbox_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("noah-sync-{}", crate::storage::new_document_id()));
+        {
+            let db = LocalDb::open(&dir).unwrap();
+            db.insert("u1", &task("a")).unwrap();
+        }
+        let (url, log) = mock_firestore(|_, _| ok());
+        let db = Arc::new(LocalDb::open(&dir).unwrap());
+        let (engine, _) = engine(&db, &url);
+        assert_eq!(engine.status().pending, 1);
+        assert_eq!(engine.run_once(), Step::Drained);
+        assert_eq!(log.lock().unwrap().len(), 1);
+        drop(engine);
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn snapshot_does_not_clobber_unsynced_local_changes() {
+        let db = LocalDb::open_in_memory().unwrap();
+        let local = db.insert("u1", &task("local only")).unwrap();
+        let mut remote = task("remote");
+        remote.id = Some("r1".into());
+        db.replace_all("u1", &[remote]).unwrap();
+        let titles: Vec<String> = db.list::<TaskData>("u1").unwrap().into_iter().map(|t| t.title).collect();
+        assert!(titles.contains(&"local only".to_string()) && titles.contains(&"remote".to_string()));
+        assert_eq!(outbox::pending_doc_ids(&db.conn(), "u1", Collection::Tasks).unwrap().len(), 1);
+        assert!(db.get::<TaskData>("u1", local.id.as_deref().unwrap()).unwrap().is_some());
+    }
+
+    #[test]
+    fn value_encoding_and_backoff_bounds() {
+        assert_eq!(encode_value(&json!(3)), json!({ "integerValue": "3" }));
+        assert_eq!(encode_value(&json!(1.5)), json!({ "doubleValue": 1.5 }));
+        assert_eq!(
+            encode_value(&json!({ "a": [true, null] })),
+            json!({ "mapValue": { "fields": { "a": { "arrayValue": { "values": [{ "booleanValue": true }, { "nullValue": null }] } } } } })
+        );
+        let doc = encode_document(json!({ "id": "x", "createdAt": 0, "order": 2 }).as_object().unwrap());
+        assert_eq!(doc["createdAt"], json!({ "timestampValue": "1970-01-01T00:00:00.000Z" }));
+        assert_eq!(doc["order"], json!({ "integerValue": "2" }));
+        assert!(!doc.contains_key("id"));
+        assert_eq!(field_path("recurrence_rule"), "recurrence_rule");
+        assert_eq!(field_path("my-field"), "`my-field`");
+
+        for n in 1..40 {
+            let d = backoff_delay(n);
+            assert!(d >= Duration::from_millis(1600) && d <= BACKOFF_MAX.mul_f64(1.2));
+        }
+        assert!(backoff_delay(3) >= Duration::from_millis(6400));
+    }
+}
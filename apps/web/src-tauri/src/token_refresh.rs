@@ -0,0 +1,223 @@
+//! 보관소의 access token을 만료 전에 refresh_token으로 갱신한다.
+//!
+//! 웹뷰는 항상 유효한 access token만 받는다. refresh_token은 보관소 밖으로 나가지 않는다.
+
+use crate::oauth::{self, OAuthClientConfig, OAuthError};
+use crate::token_store::{TokenEntry, TokenLookup, TokenVault};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefreshError {
+    /// 저장된 토큰이 없거나 refresh_token 없이 만료됨
+    NotConnected,
+    /// refresh_token이 만료/철회됨 (invalid_grant). 항목은 지운다
+    ReconnectRequired,
+    Failed(OAuthError),
+}
+
+impl RefreshError {
+    /// 웹뷰에 넘기는 에러 코드
+    pub fn code(&self) -> &'static str {
+        match self {
+            RefreshError::NotConnected => "not_connected",
+            RefreshError::ReconnectRequired => "reconnect_required",
+            RefreshError::Failed(_) => "refresh_failed",
+        }
+    }
+}
+
+impl fmt::Display for RefreshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefreshError::NotConnected => write!(f, "no stored credentials"),
+            RefreshError::ReconnectRequired => write!(f, "refresh token was revoked or expired"),
+            RefreshError::Failed(e) => write!(f, "token refresh failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RefreshError {}
+
+#[derive(Default)]
+pub struct TokenRefresher {
+    /// 동시에 여러 요청이 와도 갱신은 한 번만 (두 번째 호출은 갱신된 토큰을 본다)
+    in_flight: Mutex<()>,
+}
+
+impl TokenRefresher {
+    /// `min_ttl` 이상 남은 access token을 돌려준다. 필요하면 `config.token_url`로 갱신해 저장한다
+    pub fn valid_token(
+        &self,
+        vault: &TokenVault,
+        key: &str,
+        config: &OAuthClientConfig,
+        min_ttl: Duration,
+    ) -> Result<TokenEntry, RefreshError> {
+        let _guard = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = match vault.lookup(key, min_ttl) {
+            TokenLookup::Fresh(entry) => return Ok(entry),
+            TokenLookup::Missing => return Err(RefreshError::NotConnected),
+            TokenLookup::Expired(entry) => entry,
+        };
+        let refresh_token = entry.refresh_token.ok_or(RefreshError::NotConnected)?;
+
+        match oauth::refresh_access_token(config, &refresh_token) {
+            Ok(tokens) => {
+                let mut refreshed = TokenEntry::from_oauth(&tokens);
+                // scope를 다시 주지 않는 서버도 있다
+                refreshed.scope = refreshed.scope.or(entry.scope);
+                vault
+                    .put(key, refreshed)
+                    .map_err(|e| RefreshError::Failed(OAuthError::InvalidResponse(e.to_string())))?;
+                vault.get(key).ok_or(RefreshError::NotConnected)
+            }
+            Err(e) if e.is_invalid_grant() => {
+                let _ = vault.delete(key);
+                Err(RefreshError::ReconnectRequired)
+            }
+            Err(e) => Err(RefreshError::Failed(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loopback::{parse_query, Limits, LoopbackServer, Response, Router};
+    use crate::token_store::{now_ms, DEFAULT_MIN_TTL};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn temp_vault() -> (PathBuf, TokenVault) {
+        let dir = std::env::temp_dir().join(format!("noah-refresh-{}", oauth::random_token(6)));
+        let vault = TokenVault::open(&dir).unwrap();
+        (dir, vault)
+    }
+
+    /// refresh_token `rt-good`만 받아 주는 mock 토큰 엔드포인트. 호출 횟수를 센다
+    fn mock_refresh_endpoint() -> (String, Arc<AtomicUsize>) {
+        let server = LoopbackServer::bind().unwrap();
+        let port = server.port();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let router = Router::new().post("/token", move |req| {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            let form = parse_query(&String::from_utf8_lossy(&req.body));
+            let get = |k: &str| form.iter().find(|(n, _)| n == k).map(|(_, v)| v.as_str());
+            if get("grant_type") != Some("refresh_token") || get("client_id") != Some("client-123") {
+                return Response::new(400)
+                    .with_header("Content-Type", "application/json")
+                    .with_body(br#"{"error":"invalid_request"}"#.to_vec());
+            }
+            match get("refresh_token") {
+                Some("rt-good") => Response::json(format!(
+                    r#"{{"access_token":"at-{}","token_type":"Bearer","expires_in":3599}}"#,
+                    n
+                )),
+                Some("rt-flaky") => Response::new(503)
+                    .with_header("Content-Type", "application/json")
+                    .with_body(br#"{"error":"temporarily_unavailable"}"#.to_vec()),
+                _ => Response::new(400)
+                    .with_header("Content-Type", "application/json")
+                    .with_body(br#"{"error":"invalid_grant","error_description":"Token has been expired or revoked."}"#.to_vec()),
+            }
+        });
+        std::thread::spawn(move || server.serve(router, Limits::default()));
+        (format!("http://127.0.0.1:{}/token", port), calls)
+    }
+
+    fn config(token_url: &str) -> OAuthClientConfig {
+        let mut config = OAuthClientConfig::google("client-123");
+        config.client_secret = Some("not-so-secret".into());
+        config.token_url = token_url.to_string();
+        config
+    }
+
+    fn stored(value: &str, refresh_token: Option<&str>, expires_at: u64) -> TokenEntry {
+        let mut entry = TokenEntry::new(value);
+        entry.refresh_token = refresh_token.map(str::to_string);
+        entry.expires_at = Some(expires_at);
+        entry.scope = Some("calendar.events.readonly".into());
+        entry
+    }
+
+    #[test]
+    fn fresh_token_is_returned_without_refresh() {
+        let (url, calls) = mock_refresh_endpoint();
+        let (dir, vault) = temp_vault();
+        vault.put("gcal", stored("at-0", Some("rt-good"), now_ms() + 3_600_000)).unwrap();
+
+        let entry = TokenRefresher::default().valid_token(&vault, "gcal", &config(&url), DEFAULT_MIN_TTL).unwrap();
+        assert_eq!(entry.value, "at-0");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn expired_token_is_refreshed_and_stored() {
+        let (url, calls) = mock_refresh_endpoint();
+        let (dir, vault) = temp_vault();
+        // 30초 남음 → 최소 60초 기준으로는 만료
+        vault.put("gcal", stored("at-0", Some("rt-good"), now_ms() + 30_000)).unwrap();
+        let refresher = TokenRefresher::default();
+
+        let entry = refresher.valid_token(&vault, "gcal", &config(&url), DEFAULT_MIN_TTL).unwrap();
+        assert_eq!(entry.value, "at-1");
+        assert!(entry.expires_at.unwrap() > now_ms() + 3_500_000);
+        // 응답에 없던 refresh_token/scope는 유지
+        assert_eq!(entry.refresh_token.as_deref(), Some("rt-good"));
+        assert_eq!(entry.scope.as_deref(), Some("calendar.events.readonly"));
+
+        // 다시 물으면 저장된 새 토큰을 그대로
+        let again = refresher.valid_token(&vault, "gcal", &config(&url), DEFAULT_MIN_TTL).unwrap();
+        assert_eq!(again.value, "at-1");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn revoked_refresh_token_requires_reconnect() {
+        let (url, _) = mock_refresh_endpoint();
+        let (dir, vault) = temp_vault();
+        vault.put("gcal", stored("at-0", Some("rt-revoked"), 1)).unwrap();
+
+        let err = TokenRefresher::default().valid_token(&vault, "gcal", &config(&url), DEFAULT_MIN_TTL);
+        assert_eq!(err, Err(RefreshError::ReconnectRequired));
+        assert!(vault.get("gcal").is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn transient_failure_keeps_credentials() {
+        let (url, _) = mock_refresh_endpoint();
+        let (dir, vault) = temp_vault();
+        vault.put("gcal", stored("at-0", Some("rt-flaky"), 1)).unwrap();
+
+        let err = TokenRefresher::default().valid_token(&vault, "gcal", &config(&url), DEFAULT_MIN_TTL).unwrap_err();
+        assert_eq!(err.code(), "refresh_failed");
+        assert_eq!(vault.get("gcal").unwrap().refresh_token.as_deref(), Some("rt-flaky"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_or_unrefreshable_token_is_not_connected() {
+        let (url, calls) = mock_refresh_endpoint();
+        let (dir, vault) = temp_vault();
+        vault.put("no-refresh", stored("at-0", None, 1)).unwrap();
+        let refresher = TokenRefresher::default();
+
+        assert_eq!(
+            refresher.valid_token(&vault, "missing", &config(&url), DEFAULT_MIN_TTL),
+            Err(RefreshError::NotConnected)
+        );
+        assert_eq!(
+            refresher.valid_token(&vault, "no-refresh", &config(&url), DEFAULT_MIN_TTL),
+            Err(RefreshError::NotConnected)
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
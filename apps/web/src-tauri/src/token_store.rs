@@ -0,0 +1,454 @@
+//! 암호화된 토큰 보관소.
+//!
+//! 앱 데이터 폴더의 `tokens.vault` 하나에 키별 자격 증명을 JSON으로 모아 ChaCha20-Poly1305로 암호화한다.
+//! 암호화 키는 같은 폴더의 로컬 비밀(`vault.key`, 32바이트 난수)에서 HKDF-SHA256으로 유도한다.
+//! 웹뷰 저장소(localStorage/sessionStorage)에 토큰을 두지 않기 위한 것.
+
+use crate::oauth::OAuthTokens;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const VAULT_FILE: &str = "tokens.vault";
+const SECRET_FILE: &str = "vault.key";
+const MAGIC: &[u8; 8] = b"NOAHTV1\0";
+const NONCE_LEN: usize = 12;
+const KDF_SALT: &[u8] = b"noah-token-vault-v1";
+const KDF_INFO: &[u8] = b"tokens.vault chacha20poly1305";
+
+/// 만료까지 이보다 적게 남았으면 만료로 본다 (요청 도중 만료 방지)
+pub const DEFAULT_MIN_TTL: Duration = Duration::from_secs(60);
+
+/// 보관되는 자격 증명 하나. 웹뷰와 주고받을 때는 camelCase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenEntry {
+    /// access token 또는 불투명한 비밀 문자열 (Firebase 세션 blob 등)
+    pub value: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// unix ms. None이면 만료 없음
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// unix ms. put 시 보관소가 채운다
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl TokenEntry {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self { value: value.into(), refresh_token: None, expires_at: None, scope: None, updated_at: 0 }
+    }
+
+    /// 토큰 응답 그대로 (refresh_token 포함)
+    pub fn from_oauth(tokens: &OAuthTokens) -> Self {
+        Self {
+            value: tokens.access_token.clone(),
+            refresh_token: tokens.refresh_token.clone(),
+            expires_at: tokens.expires_at,
+            scope: tokens.scope.clone(),
+            updated_at: 0,
+        }
+    }
+
+    /// `min_ttl` 이상 남아 있으면 true
+    pub fn is_fresh(&self, now_ms: u64, min_ttl: Duration) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at > now_ms.saturating_add(min_ttl.as_millis() as u64),
+            None => true,
+        }
+    }
+
+    /// 웹뷰로 보낼 때는 refresh_token을 뺀다
+    pub fn without_refresh_token(mut self) -> Self {
+        self.refresh_token = None;
+        self
+    }
+}
+
+/// `token_store_list` 항목. 비밀 값은 담지 않는다
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub key: String,
+    pub expires_at: Option<u64>,
+    pub has_refresh_token: bool,
+    pub expired: bool,
+    pub updated_at: u64,
+}
+
+/// 만료를 고려한 조회 결과
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenLookup {
+    Missing,
+    Fresh(TokenEntry),
+    /// 만료됐거나 곧 만료. refresh_token이 있으면 호출 측이 갱신한다
+    Expired(TokenEntry),
+}
+
+#[derive(Debug)]
+pub enum VaultError {
+    Io(io::Error),
+    /// 파일 형식이 아니거나 키가 맞지 않음 (복호화 실패)
+    Corrupt(&'static str),
+}
+
+impl std::fmt::Display for VaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VaultError::Io(e) => write!(f, "token vault I/O error: {}", e),
+            VaultError::Corrupt(what) => write!(f, "token vault is unreadable: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for VaultError {}
+
+impl From<io::Error> for VaultError {
+    fn from(e: io::Error) -> Self {
+        VaultError::Io(e)
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut buf = [0u8; N];
+    getrandom::getrandom(&mut buf).expect("OS random source unavailable");
+    buf
+}
+
+/// 파일을 임시 이름으로 쓴 뒤 rename (쓰다 죽어도 기존 파일은 남는다)
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8], private: bool) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        if private {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        #[cfg(not(unix))]
+        let _ = private;
+        let mut file = options.open(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)
+}
+
+/// 로컬 비밀을 읽고, 없으면 만든다
+fn load_secret(dir: &Path) -> io::Result<[u8; 32]> {
+    let path = dir.join(SECRET_FILE);
+    match std::fs::read(&path) {
+        Ok(bytes) if bytes.len() == 32 => {
+            let mut secret = [0u8; 32];
+            secret.copy_from_slice(&bytes);
+            Ok(secret)
+        }
+        // 새 비밀로는 기존 보관소를 못 여니 `TokenVault::open`이 그것도 옮겨 둔다
+        Ok(_) => {
+            let aside = set_aside(&path)?;
+            eprintln!("{} 길이가 맞지 않아 {}로 옮기고 새로 만든다", SECRET_FILE, aside.display());
+            new_secret(&path)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => new_secret(&path),
+        Err(e) => Err(e),
+    }
+}
+
+fn new_secret(path: &Path) -> io::Result<[u8; 32]> {
+    let secret = random_bytes::<32>();
+    write_atomic(path, &secret, true)?;
+    Ok(secret)
+}
+
+/// 읽을 수 없는 파일을 `<이름>.corrupt-<ms>`로 옮긴다 (지우지 않는다)
+fn set_aside(path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let mut stamp = now_ms();
+    let mut aside = path.with_file_name(format!("{}.corrupt-{}", name, stamp));
+    while aside.exists() {
+        stamp += 1;
+        aside = path.with_file_name(format!("{}.corrupt-{}", name, stamp));
+    }
+    std::fs::rename(path, &aside)?;
+    Ok(aside)
+}
+
+fn derive_key(secret: &[u8]) -> Key {
+    let hk = Hkdf::<Sha256>::new(Some(KDF_SALT), secret);
+    let mut key = Key::default();
+    hk.expand(KDF_INFO, &mut key).expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+fn seal(cipher: &ChaCha20Poly1305, entries: &BTreeMap<String, TokenEntry>) -> Vec<u8> {
+    let plain = serde_json::to_vec(entries).expect("token entries serialize");
+    let nonce = random_bytes::<NONCE_LEN>();
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plain, aad: MAGIC })
+        .expect("chacha20poly1305 encryption");
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&sealed);
+    out
+}
+
+fn open_sealed(cipher: &ChaCha20Poly1305, bytes: &[u8]) -> Result<BTreeMap<String, TokenEntry>, VaultError> {
+    if bytes.len() < MAGIC.len() + NONCE_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(VaultError::Corrupt("bad header"));
+    }
+    let (nonce, sealed) = bytes[MAGIC.len()..].split_at(NONCE_LEN);
+    let plain = cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: MAGIC })
+        .map_err(|_| VaultError::Corrupt("decryption failed"))?;
+    serde_json::from_slice(&plain).map_err(|_| VaultError::Corrupt("bad payload"))
+}
+
+pub struct TokenVault {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+    entries: Mutex<BTreeMap<String, TokenEntry>>,
+}
+
+impl TokenVault {
+    /// `dir`(앱 데이터 폴더)의 보관소를 연다. 처음이면 로컬 비밀부터 만든다.
+    /// 깨졌거나 로컬 비밀이 바뀌어 못 읽으면 옮겨 두고 빈 보관소로 연다 (앱은 뜨고, 다시 연결하면 된다)
+    pub fn open(dir: &Path) -> Result<Self, VaultError> {
+        std::fs::create_dir_all(dir)?;
+        let secret = load_secret(dir)?;
+        let path = dir.join(VAULT_FILE);
+        match Self::open_with_secret(path.clone(), &secret) {
+            Err(VaultError::Corrupt(what)) => {
+                let aside = set_aside(&path)?;
+                eprintln!("토큰 보관소를 읽을 수 없어 {}로 옮기고 새로 시작: {}", aside.display(), what);
+                Self::open_with_secret(path, &secret)
+            }
+            result => result,
+        }
+    }
+
+    pub fn open_with_secret(path: PathBuf, secret: &[u8]) -> Result<Self, VaultError> {
+        let cipher = ChaCha20Poly1305::new(&derive_key(secret));
+        let entries = match std::fs::read(&path) {
+            Ok(bytes) => open_sealed(&cipher, &bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, cipher, entries: Mutex::new(entries) })
+    }
+
+    fn with_entries<R>(&self, f: impl FnOnce(&mut BTreeMap<String, TokenEntry>) -> R) -> R {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut entries)
+    }
+
+    /// 변경 후 파일 전체를 다시 봉인한다 (항목 수가 적어 단순하게)
+    fn persist(&self, entries: &BTreeMap<String, TokenEntry>) -> io::Result<()> {
+        write_atomic(&self.path, &seal(&self.cipher, entries), true)
+    }
+
+    /// 새 항목에 refresh_token이 없으면 기존 것을 유지한다
+    /// (갱신 응답이나 웹뷰가 access token만 다시 저장하는 경우)
+    pub fn put(&self, key: &str, mut entry: TokenEntry) -> io::Result<()> {
+        entry.updated_at = now_ms();
+        self.with_entries(|entries| {
+            if entry.refresh_token.is_none() {
+                entry.refresh_token = entries.get(key).and_then(|old| old.refresh_token.clone());
+            }
+            // 파일에 쓰지 못하면 메모리도 그대로 둔다
+            let mut next = entries.clone();
+            next.insert(key.to_string(), entry);
+            self.persist(&next)?;
+            *entries = next;
+            Ok(())
+        })
+    }
+
+    /// 만료 여부와 관계없이 그대로
+    pub fn get(&self, key: &str) -> Option<TokenEntry> {
+        self.with_entries(|entries| entries.get(key).cloned())
+    }
+
+    /// 만료를 고려한 조회. `min_ttl`보다 적게 남았으면 `Expired`
+    pub fn lookup(&self, key: &str, min_ttl: Duration) -> TokenLookup {
+        match self.get(key) {
+            None => TokenLookup::Missing,
+            Some(entry) if entry.is_fresh(now_ms(), min_ttl) => TokenLookup::Fresh(entry),
+            Some(entry) => TokenLookup::Expired(entry),
+        }
+    }
+
+    /// 없는 키면 false
+    pub fn delete(&self, key: &str) -> io::Result<bool> {
+        self.with_entries(|entries| {
+            let mut next = entries.clone();
+            if next.remove(key).is_none() {
+                return Ok(false);
+            }
+            self.persist(&next)?;
+            *entries = next;
+            Ok(true)
+        })
+    }
+
+    pub fn list(&self) -> Vec<TokenInfo> {
+        let now = now_ms();
+        self.with_entries(|entries| {
+            entries
+                .iter()
+                .map(|(key, entry)| TokenInfo {
+                    key: key.clone(),
+                    expires_at: entry.expires_at,
+                    has_refresh_token: entry.refresh_token.is_some(),
+                    expired: !entry.is_fresh(now, Duration::ZERO),
+                    updated_at: entry.updated_at,
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("noah-vault-{}", u64::from_le_bytes(random_bytes::<8>())));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn entries_survive_reopen_and_are_not_plaintext() {
+        let dir = temp_dir();
+        let vault = TokenVault::open(&dir).unwrap();
+        let mut entry = TokenEntry::new("ya29.secret-access-token");
+        entry.refresh_token = Some("1//refresh-secret".into());
+        vault.put("google-calendar", entry).unwrap();
+        vault.put("firebase", TokenEntry::new("{\"uid\":\"u1\"}")).unwrap();
+        drop(vault);
+
+        let raw = std::fs::read(dir.join(VAULT_FILE)).unwrap();
+        assert!(raw.starts_with(MAGIC));
+        let haystack = String::from_utf8_lossy(&raw);
+        assert!(!haystack.contains("secret-access-token"));
+        assert!(!haystack.contains("refresh-secret"));
+
+        let vault = TokenVault::open(&dir).unwrap();
+        let entry = vault.get("google-calendar").unwrap();
+        assert_eq!(entry.value, "ya29.secret-access-token");
+        assert_eq!(entry.refresh_token.as_deref(), Some("1//refresh-secret"));
+        assert!(entry.updated_at > 0);
+        assert_eq!(vault.list().len(), 2);
+
+        // access token만 다시 저장해도 refresh_token은 남는다
+        vault.put("google-calendar", TokenEntry::new("ya29.next")).unwrap();
+        let entry = vault.get("google-calendar").unwrap();
+        assert_eq!(entry.value, "ya29.next");
+        assert_eq!(entry.refresh_token.as_deref(), Some("1//refresh-secret"));
+
+        assert!(vault.delete("firebase").unwrap());
+        assert!(!vault.delete("firebase").unwrap());
+        drop(vault);
+        assert!(TokenVault::open(&dir).unwrap().get("firebase").is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn wrong_secret_or_tampering_is_rejected() {
+        let dir = temp_dir();
+        let path = dir.join(VAULT_FILE);
+        let vault = TokenVault::open_with_secret(path.clone(), b"secret-a").unwrap();
+        vault.put("k", TokenEntry::new("v")).unwrap();
+
+        assert!(matches!(
+            TokenVault::open_with_secret(path.clone(), b"secret-b"),
+            Err(VaultError::Corrupt(_))
+        ));
+
+        let mut raw = std::fs::read(&path).unwrap();
+        let last = raw.len() - 1;
+        raw[last] ^= 0x01;
+        std::fs::write(&path, &raw).unwrap();
+        assert!(matches!(TokenVault::open_with_secret(path, b"secret-a"), Err(VaultError::Corrupt(_))));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unreadable_vault_is_set_aside_and_opened_empty() {
+        let dir = temp_dir();
+        let aside = |prefix: &str| {
+            std::fs::read_dir(&dir)
+                .unwrap()
+                .filter_map(|e| e.ok()?.file_name().into_string().ok())
+                .filter(|name| name.starts_with(&format!("{}.corrupt-", prefix)))
+                .count()
+        };
+        std::fs::write(dir.join(VAULT_FILE), b"garbage, not a vault").unwrap();
+        let vault = TokenVault::open(&dir).unwrap();
+        assert!(vault.list().is_empty());
+        assert_eq!(aside(VAULT_FILE), 1);
+        vault.put("k", TokenEntry::new("v")).unwrap();
+        drop(vault);
+        assert_eq!(TokenVault::open(&dir).unwrap().get("k").unwrap().value, "v");
+
+        // 로컬 비밀이 깨지면 새 비밀로는 기존 보관소도 못 연다
+        std::fs::write(dir.join(SECRET_FILE), b"short").unwrap();
+        let vault = TokenVault::open(&dir).unwrap();
+        assert!(vault.get("k").is_none());
+        assert_eq!((aside(SECRET_FILE), aside(VAULT_FILE)), (1, 2));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_write_leaves_entries_unchanged() {
+        let dir = temp_dir();
+        let vault = TokenVault::open(&dir).unwrap();
+        vault.put("kept", TokenEntry::new("v")).unwrap();
+
+        // 임시 파일 자리에 디렉터리가 있으면 쓰기가 실패한다
+        std::fs::create_dir(dir.join(VAULT_FILE).with_extension("tmp")).unwrap();
+        assert!(vault.put("new", TokenEntry::new("v")).is_err());
+        assert!(vault.delete("kept").is_err());
+        assert!(vault.get("new").is_none());
+        assert_eq!(vault.get("kept").unwrap().value, "v");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lookup_respects_expiry_and_min_ttl() {
+        let dir = temp_dir();
+        let vault = TokenVault::open(&dir).unwrap();
+        let now = now_ms();
+
+        let mut soon = TokenEntry::new("soon");
+        soon.expires_at = Some(now + 30_000);
+        vault.put("soon", soon).unwrap();
+        let mut later = TokenEntry::new("later");
+        later.expires_at = Some(now + 3_600_000);
+        vault.put("later", later).unwrap();
+        vault.put("forever", TokenEntry::new("forever")).unwrap();
+
+        assert!(matches!(vault.lookup("soon", DEFAULT_MIN_TTL), TokenLookup::Expired(_)));
+        assert!(matches!(vault.lookup("soon", Duration::ZERO), TokenLookup::Fresh(_)));
+        assert!(matches!(vault.lookup("later", DEFAULT_MIN_TTL), TokenLookup::Fresh(_)));
+        assert!(matches!(vault.lookup("forever", DEFAULT_MIN_TTL), TokenLookup::Fresh(_)));
+        assert_eq!(vault.lookup("missing", DEFAULT_MIN_TTL), TokenLookup::Missing);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}